@@ -16,11 +16,11 @@
 
 //! Some configurable implementations as associated type for the substrate runtime.
 
-use node_primitives::Balance;
+use node_primitives::{AccountId, Balance};
 use sp_runtime::traits::{Convert, Saturating};
 use sp_runtime::{Fixed64, Perbill};
-use frame_support::{traits::{OnUnbalanced, Currency, Get}, weights::Weight};
-use crate::{Balances, System, Authorship, MaximumBlockWeight, NegativeImbalance};
+use frame_support::{traits::{OnUnbalanced, Currency, Get, VestingSchedule}, weights::Weight};
+use crate::{Balances, System, Authorship, MaximumBlockWeight, NegativeImbalance, Vesting};
 
 pub struct Author;
 impl OnUnbalanced<NegativeImbalance> for Author {
@@ -45,6 +45,19 @@ impl Convert<u128, Balance> for CurrencyToVoteHandler {
 	fn convert(x: u128) -> Balance { x * Self::factor() }
 }
 
+/// The funds of stakers still vesting, as known by the vesting module.
+pub struct StakingVestedFunds;
+
+impl pallet_staking::asset::VestedFunds<AccountId, Balance> for StakingVestedFunds {
+	fn vesting_balance(who: &AccountId) -> Balance {
+		<Vesting as VestingSchedule<AccountId>>::vesting_balance(who).unwrap_or_default()
+	}
+
+	fn burn_vesting(who: &AccountId, amount: Balance) {
+		Vesting::burn_locked(who, amount)
+	}
+}
+
 /// Convert from weight to balance via a simple coefficient multiplication
 /// The associated type C encapsulates a constant in units of balance per weight
 pub struct LinearWeightToFee<C>(sp_std::marker::PhantomData<C>);
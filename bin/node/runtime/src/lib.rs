@@ -24,7 +24,7 @@ use sp_std::prelude::*;
 use frame_support::{
 	construct_runtime, parameter_types, debug,
 	weights::Weight,
-	traits::{SplitTwoWays, Currency, Randomness},
+	traits::{SplitTwoWays, Currency, Randomness, LockIdentifier},
 };
 use sp_core::u32_trait::{_1, _2, _3, _4};
 pub use node_primitives::{AccountId, Signature};
@@ -63,12 +63,17 @@ pub use pallet_staking::StakerStatus;
 
 /// Implementations of some helper traits passed into runtime modules as associated types.
 pub mod impls;
-use impls::{CurrencyToVoteHandler, Author, LinearWeightToFee, TargetedFeeAdjustment};
+use impls::{
+	CurrencyToVoteHandler, Author, LinearWeightToFee, TargetedFeeAdjustment, StakingVestedFunds,
+};
 
 /// Constant values used within the runtime.
 pub mod constants;
 use constants::{time::*, currency::*};
 
+/// Weights of the dispatchables of the modules used within the runtime.
+mod weights;
+
 // Make the WASM binary available.
 #[cfg(feature = "std")]
 include!(concat!(env!("OUT_DIR"), "/wasm_binary.rs"));
@@ -82,7 +87,7 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	// and set impl_version to 0. If only runtime
 	// implementation changes and behavior does not, then leave spec_version as
 	// is and increment impl_version.
-	spec_version: 233,
+	spec_version: 299,
 	impl_version: 0,
 	apis: RUNTIME_API_VERSIONS,
 };
@@ -268,26 +273,69 @@ parameter_types! {
 	pub const SessionsPerEra: sp_staking::SessionIndex = 6;
 	pub const BondingDuration: pallet_staking::EraIndex = 24 * 28;
 	pub const SlashDeferDuration: pallet_staking::EraIndex = 24 * 7; // 1/4 the bonding duration.
+	pub const HistoryShrinkDelay: pallet_staking::EraIndex = 24 * 7; // one week.
 	pub const RewardCurve: &'static PiecewiseLinear<'static> = &REWARD_CURVE;
 	pub const MaxNominatorRewardedPerValidator: u32 = 64;
+	pub const MaxNominatorExposedPerValidator: u32 = 256;
+	pub const MaxNominations: u32 = 16;
+	pub const MaxUnlockingChunks: u32 = 32;
+	pub const MaxInvulnerables: u32 = 16;
+	pub const LivenessPoints: pallet_staking::RewardPoint = 20;
+	pub const FinalityPoints: pallet_staking::RewardPoint = 1;
+	pub const MaxUnresponsiveSessions: sp_staking::SessionIndex = 6; // one era.
+	pub const AuthorPoints: pallet_staking::RewardPoint = 20;
+	pub const UncleReferencePoints: pallet_staking::RewardPoint = 2;
+	pub const UnclePoints: pallet_staking::RewardPoint = 1;
+	pub const MaxAutoPayoutsPerBlock: u32 = 16;
+	pub const MaxPrunedKeysPerBlock: u32 = 512;
+	pub const MaxMigratedValuesPerBlock: u32 = 1024;
+	pub const MaxDelegators: u32 = 256;
+	pub const StakingLockId: LockIdentifier = pallet_staking::asset::STAKING_ID;
 }
 
 impl pallet_staking::Trait for Runtime {
 	type Currency = Balances;
+	type LockId = StakingLockId;
 	type Time = Timestamp;
 	type CurrencyToVote = CurrencyToVoteHandler;
 	type RewardRemainder = Treasury;
+	type UnclaimedRewards = Treasury;
 	type Event = Event;
 	type Slash = Treasury; // send the slashed funds to the treasury.
 	type Reward = (); // rewards are minted from the void
 	type SessionsPerEra = SessionsPerEra;
+	type MinimumEraDuration = ();
 	type BondingDuration = BondingDuration;
 	type SlashDeferDuration = SlashDeferDuration;
+	type HistoricalErasKept = BondingDuration;
+	type HistoryShrinkDelay = HistoryShrinkDelay;
 	/// A super-majority of the council can cancel the slash.
 	type SlashCancelOrigin = pallet_collective::EnsureProportionAtLeast<_3, _4, AccountId, CouncilCollective>;
+	type ReportOffenceOrigin = pallet_collective::EnsureProportionAtLeast<_3, _4, AccountId, CouncilCollective>;
 	type SessionInterface = Self;
-	type RewardCurve = RewardCurve;
+	type SessionKeysProvider = Self;
+	type EraPayout = pallet_staking::ConvertCurve<RewardCurve>;
+	type CandidateFilter = ();
+	type BondingRestriction = ();
+	type VestedFunds = StakingVestedFunds;
+	type StakeKeeper = ();
+	type OnStakingUpdate = ();
 	type MaxNominatorRewardedPerValidator = MaxNominatorRewardedPerValidator;
+	type MaxNominatorExposedPerValidator = MaxNominatorExposedPerValidator;
+	type MaxNominations = MaxNominations;
+	type MaxUnlockingChunks = MaxUnlockingChunks;
+	type MaxInvulnerables = MaxInvulnerables;
+	type LivenessPoints = LivenessPoints;
+	type MaxUnresponsiveSessions = MaxUnresponsiveSessions;
+	type FinalityPoints = FinalityPoints;
+	type AuthorPoints = AuthorPoints;
+	type UncleReferencePoints = UncleReferencePoints;
+	type UnclePoints = UnclePoints;
+	type MaxAutoPayoutsPerBlock = MaxAutoPayoutsPerBlock;
+	type MaxPrunedKeysPerBlock = MaxPrunedKeysPerBlock;
+	type MaxMigratedValuesPerBlock = MaxMigratedValuesPerBlock;
+	type MaxDelegators = MaxDelegators;
+	type WeightInfo = weights::pallet_staking::WeightInfo;
 }
 
 parameter_types! {
@@ -472,6 +520,8 @@ impl pallet_im_online::Trait for Runtime {
 	type SubmitTransaction = SubmitTransaction;
 	type SessionDuration = SessionDuration;
 	type ReportUnresponsiveness = Offences;
+	type OnLiveness = Staking;
+	type OnUnresponsiveness = Staking;
 }
 
 impl pallet_offences::Trait for Runtime {
@@ -818,6 +868,89 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl pallet_staking_rpc_runtime_api::StakingApi<Block, AccountId, Balance> for Runtime {
+		fn current_era() -> Option<pallet_staking::EraIndex> {
+			Staking::current_era()
+		}
+
+		fn active_era() -> Option<pallet_staking::EraIndex> {
+			Staking::active_era().map(|active_era| active_era.index)
+		}
+
+		fn ledger(stash: AccountId) -> Option<pallet_staking::StakingLedger<AccountId, Balance>> {
+			Staking::bonded(&stash).and_then(Staking::ledger)
+		}
+
+		fn check_ledger(stash: AccountId) -> Result<(), pallet_staking::LedgerCorruption> {
+			Staking::check_ledger(&stash)
+		}
+
+		fn nominations(stash: AccountId) -> Option<pallet_staking::Nominations<AccountId>> {
+			Staking::nominators(stash)
+		}
+
+		fn eras_stakers(
+			era: pallet_staking::EraIndex,
+			validator: AccountId,
+		) -> pallet_staking::Exposure<AccountId, Balance> {
+			Staking::eras_stakers(era, validator)
+		}
+
+		fn eras_stakers_overview(
+			era: pallet_staking::EraIndex,
+			validator: AccountId,
+		) -> Option<pallet_staking::ExposureOverview<Balance>> {
+			Staking::eras_stakers_overview(era, validator)
+		}
+
+		fn eras_stakers_page(
+			era: pallet_staking::EraIndex,
+			validator: AccountId,
+			page: u32,
+		) -> Option<pallet_staking::ExposurePage<AccountId, Balance>> {
+			Staking::eras_stakers_page(era, validator, page)
+		}
+
+		fn validator_prefs(
+			stash: AccountId,
+		) -> Option<pallet_staking::ValidatorPrefs<AccountId, Balance>> {
+			Staking::validator_prefs(stash)
+		}
+
+		fn simulate_slash(validator: AccountId, slash_fraction: Perbill) -> Vec<(AccountId, Balance)> {
+			Staking::simulate_slash(validator, slash_fraction)
+		}
+
+		fn pending_rewards(stash: AccountId) -> Vec<(pallet_staking::EraIndex, Balance)> {
+			Staking::pending_rewards(stash)
+		}
+
+		fn payout_info(
+			stash: AccountId,
+		) -> Vec<pallet_staking::ClaimablePayout<AccountId, Balance>> {
+			Staking::claimable_payouts(stash)
+		}
+
+		fn reward_points(
+			from: pallet_staking::EraIndex,
+			to: pallet_staking::EraIndex,
+		) -> Vec<pallet_staking::ValidatorEraPoints<AccountId>> {
+			Staking::reward_points(from, to)
+		}
+
+		fn nominators_for(validator: AccountId) -> Vec<AccountId> {
+			Staking::nominators_for(validator)
+		}
+
+		fn pending_slashes() -> Vec<pallet_staking::PendingSlash<AccountId, Balance>> {
+			Staking::pending_slashes()
+		}
+
+		fn vested_bond(stash: AccountId) -> Balance {
+			Staking::vested_bond(&stash)
+		}
+	}
+
 	impl sp_session::SessionKeys<Block> for Runtime {
 		fn generate_session_keys(seed: Option<Vec<u8>>) -> Vec<u8> {
 			SessionKeys::generate(seed)
@@ -857,6 +990,13 @@ impl_runtime_apis! {
 					steps,
 					repeat,
 				),
+				b"pallet-staking" | b"staking" => Staking::run_benchmark(
+					extrinsic,
+					lowest_range_values,
+					highest_range_values,
+					steps,
+					repeat,
+				),
 				b"pallet-timestamp" | b"timestamp" => Timestamp::run_benchmark(
 					extrinsic,
 					lowest_range_values,
@@ -910,7 +1050,7 @@ mod tests {
 
 			assert_eq!(
 				block_hooks_weight,
-				0,
+				13_120_000,
 				"This test might fail simply because the value being compared to has increased to a \
 				module declaring a new weight for a hook or call. In this case update the test and \
 				happily move on.",
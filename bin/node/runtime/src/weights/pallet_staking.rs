@@ -0,0 +1,181 @@
+// Copyright 2019-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Weights of the staking module for this runtime.
+//!
+//! Each weight is linear in the components of the benchmark of its dispatchable, see
+//! `pallet_staking::benchmarking`: a base weight plus a weight per unit of each component. The
+//! figures are estimates from the storage accesses of each dispatchable, to be replaced by the
+//! linear fit of the `extrinsic_time` of a run of the benchmarks on reference hardware:
+//!
+//! ```text
+//! substrate benchmark --chain dev --execution wasm --pallet staking --extrinsic <dispatchable> \
+//!     --steps 50 --repeat 20
+//! ```
+
+use frame_support::weights::Weight;
+
+pub struct WeightInfo;
+impl pallet_staking::WeightInfo for WeightInfo {
+	fn bond() -> Weight {
+		600_000
+	}
+	fn bond_extra() -> Weight {
+		550_000
+	}
+	fn unbond() -> Weight {
+		450_000
+	}
+	fn withdraw_unbonded(c: u32, s: u32) -> Weight {
+		450_000u32
+			.saturating_add(8_000u32.saturating_mul(c))
+			.saturating_add(25_000u32.saturating_mul(s))
+	}
+	fn validate() -> Weight {
+		750_000
+	}
+	fn validate_with_keys() -> Weight {
+		1_050_000
+	}
+	fn nominate(n: u32) -> Weight {
+		700_000u32
+			.saturating_add(60_000u32.saturating_mul(n))
+	}
+	fn chill() -> Weight {
+		500_000
+	}
+	fn set_payee() -> Weight {
+		300_000
+	}
+	fn set_auto_payout(a: u32) -> Weight {
+		450_000u32
+			.saturating_add(2_000u32.saturating_mul(a))
+	}
+	fn set_controller() -> Weight {
+		700_000
+	}
+	fn set_validator_count() -> Weight {
+		5_000
+	}
+	fn force_no_eras() -> Weight {
+		5_000
+	}
+	fn force_new_era() -> Weight {
+		5_000
+	}
+	fn set_invulnerables(i: u32) -> Weight {
+		10_000u32
+			.saturating_add(30_000u32.saturating_mul(i))
+	}
+	fn add_invulnerable(i: u32) -> Weight {
+		35_000u32
+			.saturating_add(1_000u32.saturating_mul(i))
+	}
+	fn remove_invulnerable(i: u32) -> Weight {
+		35_000u32
+			.saturating_add(1_000u32.saturating_mul(i))
+	}
+	fn force_unstake(s: u32) -> Weight {
+		800_000u32
+			.saturating_add(25_000u32.saturating_mul(s))
+	}
+	fn force_new_era_always() -> Weight {
+		5_000
+	}
+	fn set_slash_reward_decay() -> Weight {
+		5_000
+	}
+	fn set_extra_remainder_share() -> Weight {
+		5_000
+	}
+	fn set_payout_stake_floor() -> Weight {
+		5_000
+	}
+	fn set_max_payout_apr() -> Weight {
+		5_000
+	}
+	fn set_reward_curve(p: u32) -> Weight {
+		10_000u32
+			.saturating_add(1_000u32.saturating_mul(p))
+	}
+	fn set_slash_fraction_bounds() -> Weight {
+		5_000
+	}
+	fn set_parameters() -> Weight {
+		15_000
+	}
+	fn cancel_deferred_slash(s: u32) -> Weight {
+		300_000u32
+			.saturating_add(150_000u32.saturating_mul(s))
+	}
+	fn apply_slash_now(n: u32) -> Weight {
+		700_000u32
+			.saturating_add(250_000u32.saturating_mul(n))
+	}
+	fn payout_nominator(v: u32) -> Weight {
+		550_000u32
+			.saturating_add(90_000u32.saturating_mul(v))
+	}
+	fn payout_validator(n: u32) -> Weight {
+		550_000u32
+			.saturating_add(2_000u32.saturating_mul(n))
+	}
+	fn payout_stakers_all(e: u32) -> Weight {
+		100_000u32
+			.saturating_add(650_000u32.saturating_mul(e))
+	}
+	fn rebond(c: u32) -> Weight {
+		450_000u32
+			.saturating_add(10_000u32.saturating_mul(c))
+	}
+	fn set_history_depth() -> Weight {
+		5_000
+	}
+	fn reap_stash(s: u32) -> Weight {
+		800_000u32
+			.saturating_add(25_000u32.saturating_mul(s))
+	}
+	fn repair_ledger(s: u32) -> Weight {
+		550_000u32
+			.saturating_add(25_000u32.saturating_mul(s))
+	}
+	fn report_offence_unchecked(o: u32) -> Weight {
+		300_000u32
+			.saturating_add(900_000u32.saturating_mul(o))
+	}
+	fn set_next_validators(v: u32) -> Weight {
+		20_000u32
+			.saturating_add(3_000u32.saturating_mul(v))
+	}
+	fn bond_agent() -> Weight {
+		550_000
+	}
+	fn delegate(d: u32) -> Weight {
+		650_000u32
+			.saturating_add(2_000u32.saturating_mul(d))
+	}
+	fn undelegate(d: u32) -> Weight {
+		650_000u32
+			.saturating_add(2_000u32.saturating_mul(d))
+	}
+	fn new_era(v: u32, n: u32, w: u32, e: u32) -> Weight {
+		2_000_000u32
+			.saturating_add(120_000u32.saturating_mul(v))
+			.saturating_add(60_000u32.saturating_mul(n))
+			.saturating_add(40_000u32.saturating_mul(w))
+			.saturating_add(15_000u32.saturating_mul(e))
+	}
+}
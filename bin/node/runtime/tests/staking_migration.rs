@@ -0,0 +1,96 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Run the staking migration against the state of a live chain.
+//!
+//! The state is read from the JSON file at `STAKING_SNAPSHOT`, holding the `result` of a
+//! `state_getPairs` call for the staking storage of the chain, e.g.
+//!
+//! ```text
+//! curl -H "Content-Type: application/json" -d '{"id": 1, "jsonrpc": "2.0",
+//!   "method": "state_getPairs", "params": ["0x5f3e4907f716ac89b6347d15ececedca"]}' \
+//!   http://localhost:9933 | jq .result > staking.json
+//! STAKING_SNAPSHOT=staking.json cargo test -p node-runtime --test staking_migration -- --ignored
+//! ```
+//!
+//! where `0x5f3e4907f716ac89b6347d15ececedca` is the `twox_128` hash of `Staking`. The chain must
+//! use the account and balance types of this runtime.
+
+use codec::Decode;
+use node_primitives::{AccountId, Balance};
+use node_runtime::Staking;
+use pallet_staking::{StakingLedger, UnappliedSlash, ValidatorPrefs};
+use sp_core::{Bytes, hashing::twox_128};
+use sp_runtime::traits::OnRuntimeUpgrade;
+
+fn load_snapshot() -> sp_io::TestExternalities {
+	let path = std::env::var("STAKING_SNAPSHOT")
+		.expect("STAKING_SNAPSHOT must be the path of a state_getPairs result");
+	let file = std::fs::File::open(&path).expect("the snapshot can be opened");
+	let pairs: Vec<(Bytes, Bytes)> = serde_json::from_reader(file).expect("the snapshot is valid");
+
+	let mut storage = sp_runtime::Storage::default();
+	storage.top.extend(pairs.into_iter().map(|(key, value)| (key.0, value.0)));
+	sp_io::TestExternalities::from(storage)
+}
+
+/// The number of values in storage under the prefix of the given staking storage item, and how
+/// many of them decode as `V`.
+fn count_values<V: Decode>(item: &[u8]) -> (usize, usize) {
+	let prefix = [twox_128(b"Staking"), twox_128(item)].concat();
+	let (mut count, mut decodable) = (0, 0);
+	let mut key = prefix.clone();
+	while let Some(next) = sp_io::storage::next_key(&key) {
+		if !next.starts_with(&prefix) {
+			break;
+		}
+		count += 1;
+		let value = sp_io::storage::get(&next).unwrap_or_default();
+		if V::decode(&mut &value[..]).is_ok() {
+			decodable += 1;
+		}
+		key = next;
+	}
+	(count, decodable)
+}
+
+#[test]
+#[ignore]
+fn staking_migration_works_on_live_state() {
+	load_snapshot().execute_with(|| {
+		let (ledger_count, _) = count_values::<()>(b"Ledger");
+		let (unapplied_count, _) = count_values::<()>(b"UnappliedSlashes");
+
+		// The ledgers are migrated over several blocks.
+		<Staking as OnRuntimeUpgrade>::on_runtime_upgrade();
+		while !Staking::is_storage_migrated() {
+			<Staking as OnRuntimeUpgrade>::on_runtime_upgrade();
+		}
+
+		// Every value is in its new format, and none was removed by the translations.
+		let (count, decodable) = count_values::<StakingLedger<AccountId, Balance>>(b"Ledger");
+		assert_eq!((count, decodable), (ledger_count, ledger_count));
+		let (count, decodable) =
+			count_values::<Vec<UnappliedSlash<AccountId, Balance>>>(b"UnappliedSlashes");
+		assert_eq!((count, decodable), (unapplied_count, unapplied_count));
+		let (count, decodable) =
+			count_values::<ValidatorPrefs<AccountId, Balance>>(b"ErasValidatorPrefs");
+		assert_eq!(count, decodable);
+		assert!(Staking::active_era().is_some());
+
+		Staking::do_try_state().unwrap();
+	});
+}
@@ -92,7 +92,7 @@ pub fn config_endowed(
 			validator_count: 3,
 			minimum_validator_count: 0,
 			slash_reward_fraction: Perbill::from_percent(10),
-			invulnerables: vec![alice(), bob(), charlie()],
+			invulnerables: vec![dave(), eve(), ferdie()],
 			.. Default::default()
 		}),
 		pallet_contracts: Some(ContractsConfig {
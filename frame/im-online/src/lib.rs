@@ -86,7 +86,7 @@ use sp_runtime::{
 	},
 };
 use sp_staking::{
-	SessionIndex,
+	SessionIndex, OnLiveness, OnUnresponsiveness,
 	offence::{ReportOffence, Offence, Kind},
 };
 use frame_support::{
@@ -245,6 +245,13 @@ pub trait Trait: frame_system::Trait + pallet_session::historical::Trait {
 			IdentificationTuple<Self>,
 			UnresponsivenessOffence<IdentificationTuple<Self>>,
 		>;
+
+	/// A handler told of the validators which sent a heartbeat during the ending session.
+	type OnLiveness: OnLiveness<Self::ValidatorId>;
+
+	/// A handler told of the validators which did not send a heartbeat during the ending session,
+	/// alongside their offence report.
+	type OnUnresponsiveness: OnUnresponsiveness<Self::ValidatorId>;
 }
 
 decl_event!(
@@ -588,6 +595,12 @@ impl<T: Trait> pallet_session::OneSessionHandler<T::AccountId> for Module<T> {
 		let keys = Keys::<T>::get();
 		let current_validators = <pallet_session::Module<T>>::validators();
 
+		let live = current_validators.iter().enumerate()
+			.filter(|(index, _)| <ReceivedHeartbeats>::contains_key(&session_index, &(*index as u32)))
+			.map(|(_, id)| id.clone())
+			.collect::<Vec<_>>();
+		T::OnLiveness::on_liveness(session_index, &live);
+
 		let offenders = current_validators.into_iter().enumerate()
 			.filter(|(index, id)|
 				!Self::is_online_aux(*index as u32, id)
@@ -606,6 +619,9 @@ impl<T: Trait> pallet_session::OneSessionHandler<T::AccountId> for Module<T> {
 		} else {
 			Self::deposit_event(RawEvent::SomeOffline(offenders.clone()));
 
+			let offline = offenders.iter().map(|(id, _)| id.clone()).collect::<Vec<_>>();
+			T::OnUnresponsiveness::on_unresponsiveness(session_index, &offline);
+
 			let validator_set_count = keys.len() as u32;
 			let offence = UnresponsivenessOffence { session_index, validator_set_count, offenders };
 			if let Err(e) = T::ReportUnresponsiveness::report_offence(vec![], offence) {
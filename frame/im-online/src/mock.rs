@@ -22,7 +22,7 @@ use std::cell::RefCell;
 
 use crate::{Module, Trait};
 use sp_runtime::Perbill;
-use sp_staking::{SessionIndex, offence::{ReportOffence, OffenceError}};
+use sp_staking::{SessionIndex, OnLiveness, OnUnresponsiveness, offence::{ReportOffence, OffenceError}};
 use sp_runtime::testing::{Header, UintAuthorityId, TestXt};
 use sp_runtime::traits::{IdentityLookup, BlakeTwo256, ConvertInto};
 use sp_core::H256;
@@ -85,6 +85,27 @@ impl ReportOffence<u64, IdentificationTuple, Offence> for OffenceHandler {
 	}
 }
 
+thread_local! {
+	pub static LIVENESS: RefCell<Vec<(SessionIndex, Vec<u64>)>> = RefCell::new(vec![]);
+	pub static UNRESPONSIVENESS: RefCell<Vec<(SessionIndex, Vec<u64>)>> = RefCell::new(vec![]);
+}
+
+/// A mock liveness handler.
+pub struct LivenessHandler;
+impl OnLiveness<u64> for LivenessHandler {
+	fn on_liveness(session: SessionIndex, validators: &[u64]) {
+		LIVENESS.with(|l| l.borrow_mut().push((session, validators.to_vec())));
+	}
+}
+
+/// A mock unresponsiveness handler.
+pub struct UnresponsivenessHandler;
+impl OnUnresponsiveness<u64> for UnresponsivenessHandler {
+	fn on_unresponsiveness(session: SessionIndex, validators: &[u64]) {
+		UNRESPONSIVENESS.with(|l| l.borrow_mut().push((session, validators.to_vec())));
+	}
+}
+
 pub fn new_test_ext() -> sp_io::TestExternalities {
 	let t = frame_system::GenesisConfig::default().build_storage::<Runtime>().unwrap();
 	t.into()
@@ -166,6 +187,8 @@ impl Trait for Runtime {
 	type SubmitTransaction = SubmitTransaction;
 	type ReportUnresponsiveness = OffenceHandler;
 	type SessionDuration = Period;
+	type OnLiveness = LivenessHandler;
+	type OnUnresponsiveness = UnresponsivenessHandler;
 }
 
 /// Im Online module.
@@ -169,6 +169,25 @@ fn should_mark_online_validator_when_heartbeat_is_received() {
 	});
 }
 
+#[test]
+fn should_notify_live_and_unresponsive_validators_on_session_end() {
+	new_test_ext().execute_with(|| {
+		advance_session();
+		VALIDATORS.with(|l| *l.borrow_mut() = Some(vec![1, 2, 3]));
+		advance_session();
+		assert_eq!(Session::validators(), vec![1, 2, 3]);
+		LIVENESS.with(|l| l.borrow_mut().clear());
+		UNRESPONSIVENESS.with(|l| l.borrow_mut().clear());
+
+		let _ = heartbeat(1, 2, 0, 1.into()).unwrap();
+		let _ = heartbeat(1, 2, 2, 3.into()).unwrap();
+		advance_session();
+
+		LIVENESS.with(|l| assert_eq!(*l.borrow(), vec![(2, vec![1, 3])]));
+		UNRESPONSIVENESS.with(|l| assert_eq!(*l.borrow(), vec![(2, vec![2])]));
+	});
+}
+
 #[test]
 fn late_heartbeat_should_fail() {
 	new_test_ext().execute_with(|| {
@@ -122,6 +122,7 @@ where
 			&concurrent_offenders,
 			&slash_perbill,
 			offence.session_index(),
+			&O::ID,
 		);
 
 		Ok(())
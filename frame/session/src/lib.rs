@@ -644,7 +644,9 @@ impl<T: Trait> Module<T> {
 	///
 	/// This ensures that the reference counter in system is incremented appropriately and as such
 	/// must accept an account ID, rather than a validator ID.
-	fn do_set_keys(account: &T::AccountId, keys: T::Keys) -> dispatch::DispatchResult {
+	///
+	/// The ownership proof of the keys is left to the caller.
+	pub fn do_set_keys(account: &T::AccountId, keys: T::Keys) -> dispatch::DispatchResult {
 		let who = T::ValidatorIdOf::convert(account.clone())
 			.ok_or(Error::<T>::NoAssociatedValidatorId)?;
 
@@ -703,7 +705,8 @@ impl<T: Trait> Module<T> {
 		Ok(())
 	}
 
-	fn load_keys(v: &T::ValidatorId) -> Option<T::Keys> {
+	/// The keys set by the validator `v`, used from the next session.
+	pub fn load_keys(v: &T::ValidatorId) -> Option<T::Keys> {
 		<NextKeys<T>>::get(DEDUP_KEY_PREFIX, v)
 	}
 
@@ -0,0 +1,109 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Runtime API definition for the staking module.
+//!
+//! This API should be imported and implemented by the runtime,
+//! of a node that wants to query staking information.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::{Codec, HasCompact};
+use sp_runtime::Perbill;
+use sp_std::vec::Vec;
+use pallet_staking::{
+	EraIndex, StakingLedger, Nominations, Exposure, ValidatorPrefs, ClaimablePayout,
+	ValidatorEraPoints, PendingSlash, ExposureOverview, ExposurePage, LedgerCorruption,
+};
+
+sp_api::decl_runtime_apis! {
+	/// The API to query the staking module.
+	///
+	/// Version 2 added the queries of the eras, ledgers, nominations, exposures, validator
+	/// preferences, claimable payouts, reward points, nominators of a validator, pending slashes,
+	/// pages of exposures and the check of ledgers.
+	///
+	/// Version 3 added the part of a bond backed by funds still vesting.
+	///
+	/// Version 4 added the commission payee to the validator preferences.
+	#[api_version(4)]
+	pub trait StakingApi<AccountId, Balance> where
+		AccountId: Codec,
+		Balance: Codec + HasCompact,
+	{
+		/// The planned era, if any.
+		fn current_era() -> Option<EraIndex>;
+
+		/// The era validated by the current validator set, if any.
+		fn active_era() -> Option<EraIndex>;
+
+		/// The ledger of the given stash, if it is bonded.
+		fn ledger(stash: AccountId) -> Option<StakingLedger<AccountId, Balance>>;
+
+		/// Check the staking state of the given stash for inconsistencies, which can be repaired
+		/// with `repair_ledger`.
+		fn check_ledger(stash: AccountId) -> Result<(), LedgerCorruption>;
+
+		/// The nominations of the given stash, if it is a nominator.
+		fn nominations(stash: AccountId) -> Option<Nominations<AccountId>>;
+
+		/// The exposure of the given validator stash in the given era, within the history depth.
+		///
+		/// The exposure is empty if the stash was not elected in that era.
+		fn eras_stakers(era: EraIndex, validator: AccountId) -> Exposure<AccountId, Balance>;
+
+		/// The totals of the exposure of the given validator stash in the given era, with its
+		/// number of nominators and of pages, if it was elected in that era.
+		fn eras_stakers_overview(era: EraIndex, validator: AccountId)
+			-> Option<ExposureOverview<Balance>>;
+
+		/// The given page of the nominators exposed to the given validator stash in the given
+		/// era, if there is such a page.
+		fn eras_stakers_page(era: EraIndex, validator: AccountId, page: u32)
+			-> Option<ExposurePage<AccountId, Balance>>;
+
+		/// The preferences of the given stash, if it is a validator candidate.
+		fn validator_prefs(stash: AccountId) -> Option<ValidatorPrefs<AccountId, Balance>>;
+
+		/// The amount each stash would lose if `validator` committed an offence in the active era
+		/// that slashes `slash_fraction` of its exposure.
+		///
+		/// The validator comes first, followed by its nominators. Stashes that would lose nothing
+		/// are omitted. The state of the chain is not changed.
+		fn simulate_slash(validator: AccountId, slash_fraction: Perbill) -> Vec<(AccountId, Balance)>;
+
+		/// The rewards `stash` has not claimed yet, by era, within the history depth.
+		fn pending_rewards(stash: AccountId) -> Vec<(EraIndex, Balance)>;
+
+		/// The payouts `stash` has not claimed yet, by era, within the history depth, each with
+		/// the arguments of the call that claims it.
+		fn payout_info(stash: AccountId) -> Vec<ClaimablePayout<AccountId, Balance>>;
+
+		/// The reward points of each validator in the eras `from..=to` that are within the
+		/// history depth, by era.
+		fn reward_points(from: EraIndex, to: EraIndex) -> Vec<ValidatorEraPoints<AccountId>>;
+
+		/// The stashes of the nominators targeting the given validator stash.
+		fn nominators_for(validator: AccountId) -> Vec<AccountId>;
+
+		/// The deferred slashes that are not applied yet, by era of report and id.
+		fn pending_slashes() -> Vec<PendingSlash<AccountId, Balance>>;
+
+		/// The part of the bond of the given stash backed by funds still vesting, which are
+		/// burnt first when it is slashed.
+		fn vested_bond(stash: AccountId) -> Balance;
+	}
+}
@@ -0,0 +1,154 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! RPC interface for the staking module.
+
+use std::sync::Arc;
+use codec::{Codec, HasCompact};
+use sp_blockchain::HeaderBackend;
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use sp_runtime::{generic::BlockId, traits::{Block as BlockT, MaybeDisplay, MaybeFromStr}};
+use sp_api::ProvideRuntimeApi;
+use pallet_staking::{EraIndex, ClaimablePayout, ValidatorEraPoints, PendingSlash};
+pub use pallet_staking_rpc_runtime_api::StakingApi as StakingRuntimeApi;
+pub use self::gen_client::Client as StakingClient;
+
+/// Staking RPC methods.
+#[rpc]
+pub trait StakingApi<BlockHash, AccountId, Balance> {
+	/// The payouts `stash` can still claim, by era, with the arguments of the call claiming each
+	/// of them.
+	#[rpc(name = "staking_payoutInfo")]
+	fn payout_info(
+		&self,
+		stash: AccountId,
+		at: Option<BlockHash>,
+	) -> Result<Vec<ClaimablePayout<AccountId, Balance>>>;
+
+	/// The reward points of each validator in the eras `from..=to`, by era, with their share of
+	/// the total points of the era.
+	///
+	/// Eras beyond the history depth are omitted.
+	#[rpc(name = "staking_rewardPoints")]
+	fn reward_points(
+		&self,
+		from: EraIndex,
+		to: EraIndex,
+		at: Option<BlockHash>,
+	) -> Result<Vec<ValidatorEraPoints<AccountId>>>;
+
+	/// The deferred slashes that are not applied yet, with the era they were reported in, their
+	/// id and the era they are applied at.
+	///
+	/// The era and id of a slash are the arguments to cancel it.
+	#[rpc(name = "staking_pendingSlashes")]
+	fn pending_slashes(
+		&self,
+		at: Option<BlockHash>,
+	) -> Result<Vec<PendingSlash<AccountId, Balance>>>;
+}
+
+/// A struct that implements the [`StakingApi`].
+pub struct Staking<C, B> {
+	client: Arc<C>,
+	_marker: std::marker::PhantomData<B>,
+}
+
+impl<C, B> Staking<C, B> {
+	/// Create new `Staking` with the given reference to the client.
+	pub fn new(client: Arc<C>) -> Self {
+		Staking { client, _marker: Default::default() }
+	}
+}
+
+/// Error type of this RPC api.
+pub enum Error {
+	/// The call to runtime failed.
+	RuntimeError,
+}
+
+impl From<Error> for i64 {
+	fn from(e: Error) -> i64 {
+		match e {
+			Error::RuntimeError => 1,
+		}
+	}
+}
+
+impl<C, Block, AccountId, Balance> StakingApi<<Block as BlockT>::Hash, AccountId, Balance>
+	for Staking<C, Block>
+where
+	Block: BlockT,
+	C: Send + Sync + 'static + ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+	C::Api: StakingRuntimeApi<Block, AccountId, Balance>,
+	AccountId: Codec,
+	Balance: Codec + HasCompact + MaybeDisplay + MaybeFromStr,
+{
+	fn payout_info(
+		&self,
+		stash: AccountId,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Vec<ClaimablePayout<AccountId, Balance>>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(||
+			// If the block hash is not supplied assume the best block.
+			self.client.info().best_hash
+		));
+
+		api.payout_info(&at, stash).map_err(|e| RpcError {
+			code: ErrorCode::ServerError(Error::RuntimeError.into()),
+			message: "Unable to query payout info.".into(),
+			data: Some(format!("{:?}", e).into()),
+		})
+	}
+
+	fn reward_points(
+		&self,
+		from: EraIndex,
+		to: EraIndex,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Vec<ValidatorEraPoints<AccountId>>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(||
+			// If the block hash is not supplied assume the best block.
+			self.client.info().best_hash
+		));
+
+		api.reward_points(&at, from, to).map_err(|e| RpcError {
+			code: ErrorCode::ServerError(Error::RuntimeError.into()),
+			message: "Unable to query reward points.".into(),
+			data: Some(format!("{:?}", e).into()),
+		})
+	}
+
+	fn pending_slashes(
+		&self,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Vec<PendingSlash<AccountId, Balance>>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(||
+			// If the block hash is not supplied assume the best block.
+			self.client.info().best_hash
+		));
+
+		api.pending_slashes(&at).map_err(|e| RpcError {
+			code: ErrorCode::ServerError(Error::RuntimeError.into()),
+			message: "Unable to query pending slashes.".into(),
+			data: Some(format!("{:?}", e).into()),
+		})
+	}
+}
@@ -0,0 +1,231 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The asset staked.
+//!
+//! The module only bonds, slashes and rewards through [`StakingCurrency`], which is all it needs
+//! of `T::Currency`. It is implemented for every `LockableCurrency` that is also a
+//! `ReservableCurrency`, such as the balances module, the bonded funds being locked under
+//! `T::LockId` and the delegated funds reserved. A runtime staking another asset, a bridged or a
+//! `pallet-assets` token for example, implements it for that asset instead.
+//!
+//! The ledger of a virtual staker is not backed by a lock at all: its funds are held by another
+//! pallet, the [`StakeKeeper`], which burns them when the staker is slashed.
+//!
+//! Funds still vesting can be bonded as any other, both locks overlapping. [`VestedFunds`] makes
+//! their accounting explicit: they back a bond before the other funds of the stash, as they
+//! cannot be transferred anyway, and a slash burns them first, reducing the vesting schedule by
+//! as much.
+
+use sp_std::fmt::Debug;
+use codec::FullCodec;
+use frame_support::traits::{
+	Currency, LockIdentifier, LockableCurrency, ReservableCurrency, WithdrawReasons, Imbalance,
+};
+use sp_runtime::{
+	DispatchError, DispatchResult, traits::{AtLeast32Bit, MaybeSerializeDeserialize, Zero},
+};
+
+/// The identifier of the staking lock of a runtime with a single instance of the module.
+pub const STAKING_ID: LockIdentifier = *b"staking ";
+
+/// The operations of the staking module on the asset staked.
+pub trait StakingCurrency<AccountId> {
+	/// The balance of an account.
+	type Balance: AtLeast32Bit + FullCodec + Copy + MaybeSerializeDeserialize + Debug + Default;
+
+	/// The imbalance of minting funds, as when rewarding a staker.
+	type PositiveImbalance: Imbalance<Self::Balance, Opposite=Self::NegativeImbalance>;
+
+	/// The imbalance of burning funds, as when slashing a staker.
+	type NegativeImbalance: Imbalance<Self::Balance, Opposite=Self::PositiveImbalance>;
+
+	/// The total amount of the asset in existence.
+	fn total_issuance() -> Self::Balance;
+
+	/// The minimum balance of an account; less than it cannot be bonded.
+	fn minimum_balance() -> Self::Balance;
+
+	/// The total balance of `who`, including the funds it cannot use.
+	fn total_balance(who: &AccountId) -> Self::Balance;
+
+	/// The balance of `who` that can be bonded, including what already is.
+	fn free_balance(who: &AccountId) -> Self::Balance;
+
+	/// Make `amount` of the funds of `who` unusable until unlocked under the lock `id`, replacing
+	/// whatever was locked under it before.
+	fn lock(id: LockIdentifier, who: &AccountId, amount: Self::Balance);
+
+	/// Make all the funds of `who` locked under `id` usable again.
+	fn unlock(id: LockIdentifier, who: &AccountId);
+
+	/// Hold `value` of the funds of `who`, as when delegating them.
+	fn reserve(who: &AccountId, value: Self::Balance) -> DispatchResult;
+
+	/// Release up to `value` of the funds of `who` held by `reserve`, returning the part of
+	/// `value` that was not held.
+	fn unreserve(who: &AccountId, value: Self::Balance) -> Self::Balance;
+
+	/// Burn up to `value` of the funds of `who` held by `reserve`, returning the imbalance and the
+	/// part of `value` that could not be burnt.
+	fn slash_reserved(
+		who: &AccountId,
+		value: Self::Balance,
+	) -> (Self::NegativeImbalance, Self::Balance);
+
+	/// Burn up to `value` of the funds of `who`, returning the imbalance and the part of `value`
+	/// that could not be burnt.
+	fn slash(who: &AccountId, value: Self::Balance) -> (Self::NegativeImbalance, Self::Balance);
+
+	/// Mint `value` into the existing account `who`.
+	fn deposit_into_existing(
+		who: &AccountId,
+		value: Self::Balance,
+	) -> Result<Self::PositiveImbalance, DispatchError>;
+
+	/// Mint `value` into `who`, creating the account if need be.
+	fn deposit_creating(who: &AccountId, value: Self::Balance) -> Self::PositiveImbalance;
+
+	/// Move the funds of a negative imbalance into `who`, creating the account if need be.
+	fn resolve_creating(who: &AccountId, value: Self::NegativeImbalance);
+
+	/// Increase the total issuance by `amount`, returning the funds to be placed somewhere.
+	fn issue(amount: Self::Balance) -> Self::NegativeImbalance;
+
+	/// Set the free balance of `who` to `balance`, for tests and benchmarks.
+	#[cfg(any(feature = "runtime-benchmarks", feature = "testing-utils", test))]
+	fn make_free_balance_be(who: &AccountId, balance: Self::Balance);
+}
+
+impl<AccountId, C> StakingCurrency<AccountId> for C where
+	C: LockableCurrency<AccountId> + ReservableCurrency<AccountId>,
+{
+	type Balance = C::Balance;
+	type PositiveImbalance = C::PositiveImbalance;
+	type NegativeImbalance = C::NegativeImbalance;
+
+	fn total_issuance() -> Self::Balance {
+		<C as Currency<AccountId>>::total_issuance()
+	}
+
+	fn minimum_balance() -> Self::Balance {
+		<C as Currency<AccountId>>::minimum_balance()
+	}
+
+	fn total_balance(who: &AccountId) -> Self::Balance {
+		<C as Currency<AccountId>>::total_balance(who)
+	}
+
+	fn free_balance(who: &AccountId) -> Self::Balance {
+		<C as Currency<AccountId>>::free_balance(who)
+	}
+
+	fn lock(id: LockIdentifier, who: &AccountId, amount: Self::Balance) {
+		C::set_lock(id, who, amount, WithdrawReasons::all());
+	}
+
+	fn unlock(id: LockIdentifier, who: &AccountId) {
+		C::remove_lock(id, who);
+	}
+
+	fn reserve(who: &AccountId, value: Self::Balance) -> DispatchResult {
+		<C as ReservableCurrency<AccountId>>::reserve(who, value)
+	}
+
+	fn unreserve(who: &AccountId, value: Self::Balance) -> Self::Balance {
+		<C as ReservableCurrency<AccountId>>::unreserve(who, value)
+	}
+
+	fn slash_reserved(
+		who: &AccountId,
+		value: Self::Balance,
+	) -> (Self::NegativeImbalance, Self::Balance) {
+		<C as ReservableCurrency<AccountId>>::slash_reserved(who, value)
+	}
+
+	fn slash(who: &AccountId, value: Self::Balance) -> (Self::NegativeImbalance, Self::Balance) {
+		<C as Currency<AccountId>>::slash(who, value)
+	}
+
+	fn deposit_into_existing(
+		who: &AccountId,
+		value: Self::Balance,
+	) -> Result<Self::PositiveImbalance, DispatchError> {
+		<C as Currency<AccountId>>::deposit_into_existing(who, value)
+	}
+
+	fn deposit_creating(who: &AccountId, value: Self::Balance) -> Self::PositiveImbalance {
+		<C as Currency<AccountId>>::deposit_creating(who, value)
+	}
+
+	fn resolve_creating(who: &AccountId, value: Self::NegativeImbalance) {
+		<C as Currency<AccountId>>::resolve_creating(who, value)
+	}
+
+	fn issue(amount: Self::Balance) -> Self::NegativeImbalance {
+		<C as Currency<AccountId>>::issue(amount)
+	}
+
+	#[cfg(any(feature = "runtime-benchmarks", feature = "testing-utils", test))]
+	fn make_free_balance_be(who: &AccountId, balance: Self::Balance) {
+		let _ = <C as Currency<AccountId>>::make_free_balance_be(who, balance);
+	}
+}
+
+/// The funds of accounts still vesting, as known by a vesting module.
+pub trait VestedFunds<AccountId, Balance> {
+	/// The funds of `who` still vesting, which it cannot transfer.
+	fn vesting_balance(who: &AccountId) -> Balance;
+
+	/// Reduce the funds of `who` still vesting by `amount`, after they were slashed.
+	fn burn_vesting(who: &AccountId, amount: Balance);
+}
+
+/// For a chain without vesting.
+impl<AccountId, Balance: Default> VestedFunds<AccountId, Balance> for () {
+	fn vesting_balance(_who: &AccountId) -> Balance {
+		Default::default()
+	}
+
+	fn burn_vesting(_who: &AccountId, _amount: Balance) {}
+}
+
+/// The pallet holding the funds of the virtual stakers it bonded, as a pool or a delegation
+/// pallet.
+///
+/// The module tracks their ledger and exposure as for any staker, but never locks nor moves their
+/// funds but through the keeper.
+pub trait StakeKeeper<AccountId, Balance, NegativeImbalance> {
+	/// The funds held for `stash`, which its ledger cannot exceed.
+	fn backing(stash: &AccountId) -> Balance;
+
+	/// Burn up to `value` of the funds held for `stash` after it was slashed, returning the
+	/// imbalance and the part of `value` that could not be burnt.
+	fn slash(stash: &AccountId, value: Balance) -> (NegativeImbalance, Balance);
+}
+
+/// For a chain without keeper: no stash can be bonded through it.
+impl<AccountId, Balance: Zero, NegativeImbalance: Imbalance<Balance>>
+	StakeKeeper<AccountId, Balance, NegativeImbalance> for ()
+{
+	fn backing(_stash: &AccountId) -> Balance {
+		Zero::zero()
+	}
+
+	fn slash(_stash: &AccountId, value: Balance) -> (NegativeImbalance, Balance) {
+		(NegativeImbalance::zero(), value)
+	}
+}
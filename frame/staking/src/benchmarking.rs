@@ -0,0 +1,434 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Staking pallet benchmarking.
+//!
+//! The components of the benchmarks, as found in the functions of [`WeightInfo`]:
+//! - `a`: the number of stashes with automatic payouts.
+//! - `c`: the number of unlocking chunks of the ledger.
+//! - `d`: the number of delegators of the agent.
+//! - `e`: the number of eras, or of edges from each nominator in `new_era`.
+//! - `i`: the number of invulnerables.
+//! - `n`: the number of nominators (or nomination targets for `nominate`).
+//! - `o`: the number of offenders.
+//! - `p`: the number of points of the reward curve.
+//! - `s`: the number of slashing spans of the stash, or of slashes for `cancel_deferred_slash`.
+//! - `v`: the number of validators.
+//! - `w`: the number of winners of the election.
+
+use super::*;
+
+use frame_system::RawOrigin;
+use frame_benchmarking::{benchmarks, account};
+use sp_runtime::traits::{Bounded, One};
+
+use crate::Module as Staking;
+use crate::testing_utils::*;
+
+const SEED: u32 = 0;
+const BALANCE_FACTOR: u32 = 100;
+const MAX_USER_INDEX: u32 = 1000;
+const MAX_VALIDATORS: u32 = 1000;
+const MAX_NOMINATORS: u32 = 1000;
+const MAX_SLASHES: u32 = 1000;
+
+// Bond an agent to which `d` delegators delegated, returning it.
+fn create_agent<T: Trait>(d: u32) -> Result<T::AccountId, &'static str> {
+	let agent = create_funded_user::<T>("agent", 0, BALANCE_FACTOR);
+	let controller = create_funded_user::<T>("agent_controller", 0, BALANCE_FACTOR);
+	Staking::<T>::bond_agent(
+		RawOrigin::Signed(agent.clone()).into(),
+		T::Lookup::unlookup(controller),
+		RewardDestination::Stash,
+	)?;
+	let amount = T::Currency::minimum_balance() * 10u32.into();
+	for i in 0 .. d {
+		let delegator = create_funded_user::<T>("delegator", i, BALANCE_FACTOR);
+		Staking::<T>::delegate(
+			RawOrigin::Signed(delegator).into(),
+			T::Lookup::unlookup(agent.clone()),
+			amount,
+		)?;
+	}
+	Ok(agent)
+}
+
+// Elect all the validators in a new era and give them a reward to share for it, returning the
+// era.
+fn elect_and_reward<T: Trait>(validators: &[T::AccountId]) -> Result<EraIndex, &'static str> {
+	<ValidatorCount>::put(validators.len() as u32);
+	<MinimumValidatorCount>::put(1);
+	plan_new_era::<T>(SessionIndex::one()).ok_or("the election failed")?;
+	let era = Staking::<T>::current_era().ok_or("no era was planned")?;
+
+	let points = validators.iter().map(|validator| (validator.clone(), 100));
+	EraInfo::<T>::add_reward_points(era, points);
+	let payout = T::Currency::minimum_balance() * 1000u32.into();
+	EraInfo::<T>::set_payout(era, payout, Zero::zero());
+	Ok(era)
+}
+
+// Give `stash` `s` slashing spans, with a record for each of them.
+fn add_slashing_spans<T: Trait>(stash: &T::AccountId, s: u32) {
+	if s == 0 {
+		return
+	}
+	let mut spans = slashing::SlashingSpans::new(0);
+	for i in 0 .. s {
+		if i > 0 {
+			spans.end_span(i);
+		}
+		<SpanSlash<T>>::insert((stash.clone(), i), slashing::SpanRecord::default());
+	}
+	<SlashingSpans<T>>::insert(stash, spans);
+}
+
+// Add `c` unlocking chunks of one unit to the ledger of `controller`, unlocked at era 0.
+fn add_unlocking_chunks<T: Trait>(controller: &T::AccountId, c: u32) -> Result<(), &'static str> {
+	let mut ledger = Staking::<T>::ledger(controller).ok_or("the ledger was not created")?;
+	for _ in 0 .. c {
+		let value = BalanceOf::<T>::one();
+		ledger.unlocking.push(UnlockChunk { value, era: 0 });
+		ledger.total += value;
+	}
+	<Ledger<T>>::insert(controller, ledger);
+	Ok(())
+}
+
+benchmarks! {
+	_ {
+		let u in 1 .. MAX_USER_INDEX => ();
+	}
+
+	bond {
+		let u in ...;
+		let stash = create_funded_user::<T>("stash", u, BALANCE_FACTOR);
+		let controller = create_funded_user::<T>("controller", u, BALANCE_FACTOR);
+		let controller_lookup = T::Lookup::unlookup(controller);
+		let amount = T::Currency::minimum_balance() * 10u32.into();
+	}: _(RawOrigin::Signed(stash), controller_lookup, amount, RewardDestination::Controller)
+
+	bond_extra {
+		let u in ...;
+		let (stash, _) = create_stash_controller::<T>("staker", u, BALANCE_FACTOR)?;
+		let max_additional = T::Currency::minimum_balance() * 10u32.into();
+	}: _(RawOrigin::Signed(stash), max_additional)
+
+	unbond {
+		let u in ...;
+		let (_, controller) = create_stash_controller::<T>("staker", u, BALANCE_FACTOR)?;
+		let amount = T::Currency::minimum_balance() * 10u32.into();
+	}: _(RawOrigin::Signed(controller), amount)
+
+	// Withdraw all the chunks of the ledger, which then gets removed with its slashing spans.
+	withdraw_unbonded {
+		let c in 1 .. T::MaxUnlockingChunks::get();
+		let s in 0 .. MAX_CLEARED_SLASHING_SPANS;
+		let (stash, controller) = create_stash_controller::<T>("staker", 0, BALANCE_FACTOR)?;
+		add_unlocking_chunks::<T>(&controller, c)?;
+		let mut ledger = Staking::<T>::ledger(&controller).ok_or("the ledger was not created")?;
+		ledger.total -= ledger.active;
+		ledger.active = Zero::zero();
+		Staking::<T>::update_ledger(&controller, &ledger);
+		add_slashing_spans::<T>(&stash, s);
+		<CurrentEra>::put(EraIndex::max_value());
+	}: _(RawOrigin::Signed(controller), s)
+
+	validate {
+		let u in ...;
+		let (_, controller) = create_stash_controller::<T>("staker", u, BALANCE_FACTOR)?;
+		set_session_keys::<T>(&controller)?;
+		let prefs = ValidatorPrefs::default();
+	}: _(RawOrigin::Signed(controller), prefs)
+
+	validate_with_keys {
+		let u in ...;
+		let (_, controller) = create_stash_controller::<T>("staker", u, BALANCE_FACTOR)?;
+		let keys = session_keys::<T>(&controller)?;
+	}: _(RawOrigin::Signed(controller), ValidatorPrefs::default(), keys, vec![])
+
+	nominate {
+		let n in 1 .. T::MaxNominations::get();
+		let (_, controller) = create_stash_controller::<T>("staker", 0, BALANCE_FACTOR)?;
+		let targets = create_validators::<T>(n, BALANCE_FACTOR)?.into_iter()
+			.map(|stash| T::Lookup::unlookup(stash))
+			.collect::<Vec<_>>();
+	}: _(RawOrigin::Signed(controller), targets)
+
+	chill {
+		let u in ...;
+		let (_, controller) = create_stash_controller::<T>("staker", u, BALANCE_FACTOR)?;
+		set_session_keys::<T>(&controller)?;
+		Staking::<T>::validate(RawOrigin::Signed(controller.clone()).into(), ValidatorPrefs::default())?;
+	}: _(RawOrigin::Signed(controller))
+
+	set_payee {
+		let u in ...;
+		let (_, controller) = create_stash_controller::<T>("staker", u, BALANCE_FACTOR)?;
+	}: _(RawOrigin::Signed(controller), RewardDestination::Controller)
+
+	set_auto_payout {
+		let a in 0 .. MAX_AUTO_PAYOUT_STASHES as u32 - 1;
+		let (_, controller) = create_stash_controller::<T>("staker", MAX_USER_INDEX, BALANCE_FACTOR)?;
+		let mut stashes: Vec<T::AccountId> = (0 .. a).map(|i| account("auto", i, SEED)).collect();
+		stashes.sort();
+		for stash in &stashes {
+			<HasAutoPayout<T>>::insert(stash, true);
+		}
+		<AutoPayoutStashes<T>>::put(stashes);
+	}: _(RawOrigin::Signed(controller), true)
+
+	set_controller {
+		let u in ...;
+		let (stash, _) = create_stash_controller::<T>("staker", u, BALANCE_FACTOR)?;
+		let new_controller = T::Lookup::unlookup(create_funded_user::<T>("new_controller", u, BALANCE_FACTOR));
+	}: _(RawOrigin::Signed(stash), new_controller)
+
+	set_validator_count {
+		let u in ...;
+	}: _(RawOrigin::Root, u)
+
+	force_no_eras {
+		let u in ...;
+	}: _(RawOrigin::Root)
+
+	force_new_era {
+		let u in ...;
+	}: _(RawOrigin::Root)
+
+	set_invulnerables {
+		let i in 0 .. T::MaxInvulnerables::get();
+		let validators: Vec<T::AccountId> =
+			(0 .. i).map(|i| account("invulnerable", i, SEED)).collect();
+	}: _(RawOrigin::Root, validators)
+
+	add_invulnerable {
+		let i in 0 .. T::MaxInvulnerables::get() - 1;
+		let mut invulnerables: Vec<T::AccountId> =
+			(0 .. i).map(|i| account("invulnerable", i, SEED)).collect();
+		invulnerables.sort();
+		Staking::<T>::put_invulnerables(invulnerables);
+		let who = account("invulnerable", i, SEED);
+	}: _(RawOrigin::Root, who)
+
+	remove_invulnerable {
+		let i in 1 .. T::MaxInvulnerables::get();
+		let mut invulnerables: Vec<T::AccountId> =
+			(0 .. i).map(|i| account("invulnerable", i, SEED)).collect();
+		invulnerables.sort();
+		let who = invulnerables[0].clone();
+		Staking::<T>::put_invulnerables(invulnerables);
+	}: _(RawOrigin::Root, who)
+
+	force_unstake {
+		let s in 0 .. MAX_CLEARED_SLASHING_SPANS;
+		let (stash, _) = create_stash_controller::<T>("staker", 0, BALANCE_FACTOR)?;
+		add_slashing_spans::<T>(&stash, s);
+	}: _(RawOrigin::Root, stash, s)
+
+	force_new_era_always {
+		let u in ...;
+	}: _(RawOrigin::Root)
+
+	set_slash_reward_decay {
+		let u in ...;
+	}: _(RawOrigin::Root, Perbill::from_parts(u))
+
+	set_extra_remainder_share {
+		let u in ...;
+	}: _(RawOrigin::Root, Perbill::from_parts(u))
+
+	set_payout_stake_floor {
+		let u in ...;
+	}: _(RawOrigin::Root, u.into())
+
+	set_max_payout_apr {
+		let u in ...;
+	}: _(RawOrigin::Root, Some(Perbill::from_parts(u)))
+
+	set_reward_curve {
+		let p in 1 .. MAX_REWARD_CURVE_POINTS as u32;
+		let curve = RewardCurvePoints {
+			points: (0 .. p).map(|i| (Perbill::from_parts(i), Perbill::from_percent(1))).collect(),
+			maximum: Perbill::from_percent(10),
+		};
+	}: _(RawOrigin::Root, Some(curve))
+
+	set_slash_fraction_bounds {
+		let u in ...;
+		let bounds = SlashFractionBounds {
+			min: Perbill::from_parts(u),
+			max: Perbill::from_percent(100),
+		};
+	}: _(RawOrigin::Root, Default::default(), Some(bounds))
+
+	set_parameters {
+		let u in ...;
+		let parameters = StakingParameters {
+			slash_defer_duration: Some(0),
+			min_validator_bond: Some(T::Currency::minimum_balance()),
+			min_nominator_bond: Some(T::Currency::minimum_balance()),
+			min_commission: Some(Perbill::from_percent(1)),
+			max_unresponsive_sessions: Some(2),
+			min_active_bond: Some(T::Currency::minimum_balance()),
+		};
+	}: _(RawOrigin::Root, parameters)
+
+	cancel_deferred_slash {
+		let s in 1 .. MAX_SLASHES;
+		let validator: T::AccountId = account("validator", 0, SEED);
+		let slashes = (0 .. s).map(|id| UnappliedSlash {
+			id,
+			validator: validator.clone(),
+			slash_era: 0,
+			own: Zero::zero(),
+			others: vec![],
+			reporters: vec![],
+			payout: Zero::zero(),
+		}).collect::<Vec<_>>();
+		for slash in &slashes {
+			Staking::<T>::count_pending_slash(slash, true);
+		}
+		<UnappliedSlashes<T>>::insert(0, slashes);
+	}: _(RawOrigin::Root, 0, (0 .. s).collect())
+
+	// Apply the slash of a validator and of all its nominators.
+	apply_slash_now {
+		let n in 0 .. MAX_NOMINATORS;
+		let validators = create_validators::<T>(1, BALANCE_FACTOR)?;
+		let nominators = create_nominators::<T>(n, &validators, BALANCE_FACTOR)?;
+		let slash = UnappliedSlash {
+			id: 0,
+			validator: validators[0].clone(),
+			slash_era: 0,
+			own: BalanceOf::<T>::one(),
+			others: nominators.into_iter().map(|(stash, _)| (stash, BalanceOf::<T>::one())).collect(),
+			reporters: vec![],
+			payout: Zero::zero(),
+		};
+		Staking::<T>::count_pending_slash(&slash, true);
+		<UnappliedSlashes<T>>::insert(0, vec![slash]);
+	}: _(RawOrigin::Root, 0, 0)
+
+	// Pay out a nominator exposed to `v` validators.
+	payout_nominator {
+		let v in 1 .. T::MaxNominations::get();
+		let validators = create_validators::<T>(v, BALANCE_FACTOR)?;
+		let (_, controller) = create_nominators::<T>(1, &validators, BALANCE_FACTOR)?.remove(0);
+		let era = elect_and_reward::<T>(&validators)?;
+		let validators = validators.into_iter().map(|stash| (stash, 0)).collect::<Vec<_>>();
+	}: _(RawOrigin::Signed(controller), era, validators)
+
+	// Pay out a validator exposed to `n` nominators.
+	payout_validator {
+		let n in 0 .. MAX_NOMINATORS;
+		let validators = create_validators::<T>(1, BALANCE_FACTOR)?;
+		create_nominators::<T>(n, &validators, BALANCE_FACTOR)?;
+		let era = elect_and_reward::<T>(&validators)?;
+		let controller = Staking::<T>::bonded(&validators[0]).ok_or("the stash is not bonded")?;
+	}: _(RawOrigin::Signed(controller), era)
+
+	// Pay out `e` eras of a validator.
+	payout_stakers_all {
+		let e in 1 .. DEFAULT_HISTORY_DEPTH;
+		let validators = create_validators::<T>(1, BALANCE_FACTOR)?;
+		for _ in 0 .. e {
+			elect_and_reward::<T>(&validators)?;
+		}
+		<ActiveEra<T>>::put(ActiveEraInfo { index: e, start: None });
+		let validator = validators[0].clone();
+	}: _(RawOrigin::Signed(validator.clone()), validator, 0, e - 1, Weight::max_value())
+
+	rebond {
+		let c in 1 .. T::MaxUnlockingChunks::get();
+		let (_, controller) = create_stash_controller::<T>("staker", 0, BALANCE_FACTOR)?;
+		add_unlocking_chunks::<T>(&controller, c)?;
+	}: _(RawOrigin::Signed(controller), BalanceOf::<T>::max_value())
+
+	set_history_depth {
+		let u in ...;
+	}: _(RawOrigin::Root, 0)
+
+	reap_stash {
+		let s in 0 .. MAX_CLEARED_SLASHING_SPANS;
+		let (stash, _) = create_stash_controller::<T>("staker", 0, BALANCE_FACTOR)?;
+		add_slashing_spans::<T>(&stash, s);
+		let _ = T::Currency::make_free_balance_be(&stash, Zero::zero());
+	}: _(RawOrigin::Signed(stash.clone()), stash, s)
+
+	// Repair a stash whose ledger is missing, which is unbonded with its slashing spans.
+	repair_ledger {
+		let s in 0 .. MAX_CLEARED_SLASHING_SPANS;
+		let (stash, controller) = create_stash_controller::<T>("staker", 0, BALANCE_FACTOR)?;
+		add_slashing_spans::<T>(&stash, s);
+		<Ledger<T>>::remove(&controller);
+	}: _(RawOrigin::Root, stash)
+
+	bond_agent {
+		let agent = create_funded_user::<T>("agent", 0, BALANCE_FACTOR);
+		let controller = create_funded_user::<T>("agent_controller", 0, BALANCE_FACTOR);
+		let controller_lookup = T::Lookup::unlookup(controller);
+	}: _(RawOrigin::Signed(agent), controller_lookup, RewardDestination::Stash)
+
+	// Add a delegator to an agent with `d` of them.
+	delegate {
+		let d in 0 .. T::MaxDelegators::get() - 1;
+		let agent = create_agent::<T>(d)?;
+		let delegator = create_funded_user::<T>("delegator", d, BALANCE_FACTOR);
+		let amount = T::Currency::minimum_balance() * 10u32.into();
+	}: _(RawOrigin::Signed(delegator), T::Lookup::unlookup(agent), amount)
+
+	// Take back the funds of the last of the `d` delegators of an agent which unbonded them all.
+	undelegate {
+		let d in 1 .. T::MaxDelegators::get();
+		let agent = create_agent::<T>(d)?;
+		let controller = Staking::<T>::bonded(&agent).ok_or("the agent was not bonded")?;
+		let mut ledger = Staking::<T>::ledger(&controller).ok_or("the ledger was not created")?;
+		ledger.total = Zero::zero();
+		ledger.active = Zero::zero();
+		Staking::<T>::update_ledger(&controller, &ledger);
+		let delegator = crate::testing_utils::account::<T>("delegator", d - 1);
+	}: _(RawOrigin::Signed(delegator), T::Lookup::unlookup(agent), BalanceOf::<T>::max_value())
+
+	// Report an offence of `o` validators elected in the active era.
+	report_offence_unchecked {
+		let o in 1 .. MAX_VALIDATORS;
+		let validators = create_validators::<T>(o, BALANCE_FACTOR)?;
+		<ValidatorCount>::put(o);
+		<MinimumValidatorCount>::put(1);
+		let session = start_active_era::<T>(0);
+	}: _(RawOrigin::Root, validators, Perbill::from_percent(10), session)
+
+	// Install a set of `v` validators for the next era.
+	set_next_validators {
+		let v in 1 .. MAX_VALIDATORS;
+		let validators = create_validators::<T>(v, BALANCE_FACTOR)?;
+	}: _(RawOrigin::Root, validators)
+
+	// Elect `w` of `v` validators, backed by `n` nominators nominating `e` validators each.
+	new_era {
+		let v in 1 .. MAX_VALIDATORS;
+		let n in 1 .. MAX_NOMINATORS;
+		let w in 1 .. MAX_VALIDATORS;
+		let e in 1 .. T::MaxNominations::get();
+		let validators = create_validators::<T>(v, BALANCE_FACTOR)?;
+		create_nominators::<T>(n, &validators[..e.min(v) as usize], BALANCE_FACTOR)?;
+		<ValidatorCount>::put(w);
+		<MinimumValidatorCount>::put(1);
+	}: {
+		Staking::<T>::new_era(SessionIndex::one());
+	}
+}
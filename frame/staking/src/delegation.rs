@@ -0,0 +1,141 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Delegated staking.
+//!
+//! An agent bonds virtually through `bond_agent`: its ledger is not backed by a lock on its own
+//! balance but by the funds delegated to it, which stay reserved in the accounts of its
+//! delegators. Delegating bonds the funds right away; the agent then nominates or validates, and
+//! unbonds and withdraws as any other stash. The funds withdrawn from its ledger can be taken back
+//! by the delegators, each up to what it delegated.
+//!
+//! A slash of the agent is apportioned between its delegators by the funds each delegated, and
+//! burnt from their reserved balance.
+
+use super::*;
+use sp_runtime::DispatchError;
+
+/// The total funds delegated to `agent`.
+pub(crate) fn total_delegated<T: Trait<I>, I: Instance>(agent: &T::AccountId) -> BalanceOf<T, I> {
+	<Module<T, I>>::delegators(agent).iter()
+		.fold(Zero::zero(), |total: BalanceOf<T, I>, &(_, amount)| total.saturating_add(amount))
+}
+
+/// Reserve `value` of the funds of `delegator` and bond them in the ledger of `agent`.
+pub(crate) fn delegate<T: Trait<I>, I: Instance>(
+	delegator: &T::AccountId,
+	agent: &T::AccountId,
+	value: BalanceOf<T, I>,
+) -> DispatchResult {
+	ensure!(
+		<Module<T, I>>::is_virtual_staker(agent) && !<Module<T, I>>::is_kept_staker(agent),
+		Error::<T, I>::NotAgent,
+	);
+	ensure!(delegator != agent, Error::<T, I>::IsAgent);
+	ensure!(value >= T::Currency::minimum_balance(), Error::<T, I>::InsufficientValue);
+
+	let controller = <Module<T, I>>::bonded(agent).ok_or(Error::<T, I>::NotStash)?;
+	let mut ledger = <Module<T, I>>::ledger(&controller).ok_or(Error::<T, I>::NotController)?;
+
+	let mut delegators = <Module<T, I>>::delegators(agent);
+	if let Some(index) = delegators.iter().position(|(who, _)| who == delegator) {
+		delegators[index].1 += value;
+	} else {
+		ensure!(
+			delegators.len() < T::MaxDelegators::get() as usize,
+			Error::<T, I>::TooManyDelegators,
+		);
+		delegators.push((delegator.clone(), value));
+	}
+
+	T::Currency::reserve(delegator, value)?;
+	<Delegators<T, I>>::insert(agent, delegators);
+
+	ledger.total += value;
+	ledger.active += value;
+	<Module<T, I>>::update_ledger(&controller, &ledger);
+	Ok(())
+}
+
+/// Release up to `value` of the funds `delegator` delegated to `agent`, out of those no longer
+/// bonded in the ledger of `agent`. Returns the funds released.
+pub(crate) fn undelegate<T: Trait<I>, I: Instance>(
+	delegator: &T::AccountId,
+	agent: &T::AccountId,
+	value: BalanceOf<T, I>,
+) -> Result<BalanceOf<T, I>, DispatchError> {
+	let mut delegators = <Module<T, I>>::delegators(agent);
+	let index = delegators.iter()
+		.position(|(who, _)| who == delegator)
+		.ok_or(Error::<T, I>::NotDelegator)?;
+
+	let bonded = <Module<T, I>>::bonded(agent)
+		.and_then(|controller| <Module<T, I>>::ledger(&controller))
+		.map_or_else(Zero::zero, |ledger| ledger.total);
+	let unbonded = total_delegated::<T, I>(agent).saturating_sub(bonded);
+	let value = value.min(delegators[index].1).min(unbonded);
+
+	delegators[index].1 -= value;
+	if delegators[index].1.is_zero() {
+		delegators.swap_remove(index);
+	}
+	if delegators.is_empty() {
+		<Delegators<T, I>>::remove(agent);
+	} else {
+		<Delegators<T, I>>::insert(agent, delegators);
+	}
+
+	T::Currency::unreserve(delegator, value);
+	Ok(value)
+}
+
+/// Slash `value` from the funds delegated to `agent`, apportioned between its delegators by the
+/// funds each delegated. Returns the imbalance and the part of `value` that could not be slashed.
+pub(crate) fn slash<T: Trait<I>, I: Instance>(
+	agent: &T::AccountId,
+	value: BalanceOf<T, I>,
+) -> (NegativeImbalanceOf<T, I>, BalanceOf<T, I>) {
+	let mut delegators = <Module<T, I>>::delegators(agent);
+	let mut imbalance = NegativeImbalanceOf::<T, I>::zero();
+
+	// each delegator takes its share of what is left to slash from the funds of the delegators
+	// left, so that the last one takes all that is left and no rounding is lost.
+	let mut total_left = total_delegated::<T, I>(agent);
+	let mut value_left = value.min(total_left);
+	for (delegator, amount) in delegators.iter_mut() {
+		if total_left.is_zero() {
+			break;
+		}
+		let share = (Perbill::from_rational_approximation(*amount, total_left) * value_left)
+			.min(*amount);
+		total_left -= *amount;
+		value_left -= share;
+
+		let (slashed, _) = T::Currency::slash_reserved(delegator, share);
+		*amount -= slashed.peek();
+		imbalance.subsume(slashed);
+	}
+
+	delegators.retain(|(_, amount)| !amount.is_zero());
+	if delegators.is_empty() {
+		<Delegators<T, I>>::remove(agent);
+	} else {
+		<Delegators<T, I>>::insert(agent, delegators);
+	}
+
+	let missing = value.saturating_sub(imbalance.peek());
+	(imbalance, missing)
+}
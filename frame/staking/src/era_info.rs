@@ -0,0 +1,444 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Access to the information kept per era.
+//!
+//! The exposures of the validators of an era are stored in one of three layouts, depending on
+//! when the era was elected:
+//! - `ErasStakers` and `ErasStakersClipped`, before exposures were paged;
+//! - `ErasStakersOverview` and `ErasStakersPaged`, before nominators were indexed;
+//! - `ErasStakersOverview`, `ErasStakersCompact` and `ErasStakersAccounts` since.
+//!
+//! [`EraInfo`] reads all of them and only writes the latest, so that the rest of the module never
+//! touches these storages directly. It also owns the reward, the reward points, the payout
+//! summary, the total stake and the validator preferences of each era, and removes the information
+//! of the eras leaving the history.
+
+use super::*;
+
+/// Reads and writes of the information kept per era.
+pub(crate) struct EraInfo<T, I = DefaultInstance>(sp_std::marker::PhantomData<(T, I)>);
+
+impl<T: Trait<I>, I: Instance> EraInfo<T, I> {
+	/// Whether `validator` was elected in `era`.
+	pub(crate) fn is_elected(era: EraIndex, validator: &T::AccountId) -> bool {
+		<ErasStakersOverview<T, I>>::contains_key(era, validator)
+			|| <ErasStakers<T, I>>::contains_key(era, validator)
+	}
+
+	/// The exposure of `validator` in `era`, with all the nominators exposed to it, or an empty
+	/// exposure if it was not elected in that era.
+	pub(crate) fn full_exposure(
+		era: EraIndex,
+		validator: &T::AccountId,
+	) -> Exposure<T::AccountId, BalanceOf<T, I>> {
+		match <ErasStakersOverview<T, I>>::get(era, validator) {
+			Some(overview) => Self::paged_exposure(era, validator, overview, &mut BTreeMap::new()),
+			None => <ErasStakers<T, I>>::get(era, validator),
+		}
+	}
+
+	/// The exposure of `validator` in `era` with only the `T::MaxNominatorRewardedPerValidator`
+	/// biggest nominators, the ones rewarded. The indices of the nominators passed to
+	/// `payout_nominator` are their indices in this exposure.
+	///
+	/// Only the pages holding the rewarded nominators are read.
+	pub(crate) fn rewarded_exposure(
+		era: EraIndex,
+		validator: &T::AccountId,
+	) -> Exposure<T::AccountId, BalanceOf<T, I>> {
+		match <ErasStakersOverview<T, I>>::get(era, validator) {
+			Some(overview) => {
+				let mut chunks = BTreeMap::new();
+				Exposure {
+					total: overview.total,
+					own: overview.own,
+					others: (0..overview.page_count)
+						.filter_map(|page| Self::resolved_page(era, validator, page, &mut chunks))
+						.flat_map(|page| page.others)
+						.take(T::MaxNominatorRewardedPerValidator::get() as usize)
+						.collect(),
+				}
+			},
+			None => <ErasStakersClipped<T, I>>::get(era, validator),
+		}
+	}
+
+	/// The validators elected in `era` and their exposure, in no particular order.
+	///
+	/// The eras elected before exposures were paged are not covered.
+	pub(crate) fn iter_full_exposures(
+		era: EraIndex,
+	) -> impl Iterator<Item = (T::AccountId, Exposure<T::AccountId, BalanceOf<T, I>>)> {
+		let mut prefix = <ErasStakersOverview<T, I>>::final_prefix().to_vec();
+		prefix.extend(Twox64Concat::hash(&era.encode()));
+		let mut chunks = BTreeMap::new();
+		ConcatMapIterator::new(prefix).map(move |(validator, overview)| {
+			let exposure = Self::paged_exposure(era, &validator, overview, &mut chunks);
+			(validator, exposure)
+		})
+	}
+
+	/// The totals of the exposure of `validator` in `era`, if it was elected in that era.
+	pub(crate) fn overview(
+		era: EraIndex,
+		validator: &T::AccountId,
+	) -> Option<ExposureOverview<BalanceOf<T, I>>> {
+		if let Some(overview) = <ErasStakersOverview<T, I>>::get(era, validator) {
+			return Some(overview);
+		}
+		if !<ErasStakers<T, I>>::contains_key(era, validator) {
+			return None;
+		}
+		let exposure = <ErasStakers<T, I>>::get(era, validator);
+		let nominator_count = exposure.others.len();
+		Some(ExposureOverview {
+			total: exposure.total,
+			own: exposure.own,
+			nominator_count: nominator_count as u32,
+			page_count: ((nominator_count + EXPOSURE_PAGE_SIZE - 1) / EXPOSURE_PAGE_SIZE) as u32,
+		})
+	}
+
+	/// The nominators exposed to `validator` in `era`, from the `page * EXPOSURE_PAGE_SIZE`th
+	/// one, by decreasing stake.
+	///
+	/// Returns `None` past the last page.
+	pub(crate) fn exposure_page(
+		era: EraIndex,
+		validator: &T::AccountId,
+		page: u32,
+	) -> Option<ExposurePage<T::AccountId, BalanceOf<T, I>>> {
+		if <ErasStakersOverview<T, I>>::contains_key(era, validator) {
+			return Self::resolved_page(era, validator, page, &mut BTreeMap::new());
+		}
+
+		// The eras elected before exposures were paged keep the order of their exposure.
+		let others = <ErasStakers<T, I>>::get(era, validator).others.into_iter()
+			.skip((page as usize).saturating_mul(EXPOSURE_PAGE_SIZE))
+			.take(EXPOSURE_PAGE_SIZE)
+			.collect::<Vec<_>>();
+		if others.is_empty() {
+			return None;
+		}
+		let page_total = others.iter()
+			.fold(Zero::zero(), |total: BalanceOf<T, I>, individual| total.saturating_add(individual.value));
+		Some(ExposurePage { page_total, others })
+	}
+
+	/// The preferences of `validator` when it was elected in `era`, or the default preferences if
+	/// it was not.
+	pub(crate) fn validator_prefs(
+		era: EraIndex,
+		validator: &T::AccountId,
+	) -> ValidatorPrefs<T::AccountId, BalanceOf<T, I>> {
+		<ErasValidatorPrefs<T, I>>::get(era, validator)
+	}
+
+	/// The total stake of the validators elected in `era`.
+	pub(crate) fn total_stake(era: EraIndex) -> BalanceOf<T, I> {
+		<ErasTotalStake<T, I>>::get(era)
+	}
+
+	/// Store the total stake and the preferences of the validators elected in `era`.
+	pub(crate) fn set_election(
+		era: EraIndex,
+		total_stake: BalanceOf<T, I>,
+		prefs: impl IntoIterator<
+			Item = (T::AccountId, ValidatorPrefs<T::AccountId, BalanceOf<T, I>>),
+		>,
+	) {
+		<ErasTotalStake<T, I>>::insert(era, total_stake);
+		for (validator, validator_prefs) in prefs {
+			<ErasValidatorPrefs<T, I>>::insert(era, validator, validator_prefs);
+		}
+	}
+
+	/// The reward points earned by the validators in `era`.
+	pub(crate) fn reward_points(era: EraIndex) -> EraRewardPoints<T::AccountId> {
+		<ErasRewardPoints<T, I>>::get(era)
+	}
+
+	/// Add to the reward points earned by the validators in `era`.
+	pub(crate) fn add_reward_points(
+		era: EraIndex,
+		validators_points: impl IntoIterator<Item = (T::AccountId, u32)>,
+	) {
+		<ErasRewardPoints<T, I>>::mutate(era, |era_rewards| {
+			for (validator, points) in validators_points.into_iter() {
+				*era_rewards.individual.entry(validator).or_default() += points;
+				era_rewards.total += points;
+			}
+		});
+	}
+
+	/// The payout of `era` to its stakers, once it ended.
+	pub(crate) fn validator_reward(era: EraIndex) -> Option<BalanceOf<T, I>> {
+		<ErasValidatorReward<T, I>>::get(era)
+	}
+
+	/// Whether the payout of `era` to its stakers is known, the era having ended.
+	pub(crate) fn is_rewarded(era: EraIndex) -> bool {
+		<ErasValidatorReward<T, I>>::contains_key(era)
+	}
+
+	/// Store the payout of the ended `era` to its stakers, none of it paid yet, and the remainder
+	/// minted for the rest of the network.
+	pub(crate) fn set_payout(era: EraIndex, staker_payout: BalanceOf<T, I>, remainder: BalanceOf<T, I>) {
+		<ErasValidatorReward<T, I>>::insert(era, staker_payout);
+		<ErasRemainderReward<T, I>>::insert(era, remainder);
+		<ErasPayoutSummary<T, I>>::insert(era, EraPayoutSummary {
+			total_paid: Zero::zero(),
+			total_unclaimed: staker_payout,
+			validators_paid: 0,
+		});
+	}
+
+	/// The payouts made so far of `era`.
+	pub(crate) fn payout_summary(era: EraIndex) -> EraPayoutSummary<BalanceOf<T, I>> {
+		<ErasPayoutSummary<T, I>>::get(era)
+	}
+
+	/// Account for `amount` of the payout of `era` having been paid out.
+	pub(crate) fn note_reward_paid(era: EraIndex, amount: BalanceOf<T, I>) {
+		<ErasPayoutSummary<T, I>>::mutate(era, |summary| {
+			summary.total_paid = summary.total_paid.saturating_add(amount);
+			summary.total_unclaimed = summary.total_unclaimed.saturating_sub(amount);
+		});
+	}
+
+	/// Account for one more validator of `era` having been paid out.
+	pub(crate) fn note_validator_paid(era: EraIndex) {
+		<ErasPayoutSummary<T, I>>::mutate(era, |summary| summary.validators_paid += 1);
+	}
+
+	/// Store the exposure of `validator` in `era` as its overview and its nominators in pages of
+	/// `EXPOSURE_PAGE_SIZE`, by decreasing stake.
+	pub(crate) fn set_exposure(
+		era: EraIndex,
+		validator: &T::AccountId,
+		exposure: Exposure<T::AccountId, BalanceOf<T, I>>,
+	) {
+		Self::set_exposures(era, sp_std::iter::once((validator.clone(), exposure)));
+	}
+
+	/// Store the exposures of the validators elected in `era`, as by `set_exposure`.
+	///
+	/// The nominators are indexed in `ErasStakersAccounts` once for all the exposures.
+	pub(crate) fn set_exposures(
+		era: EraIndex,
+		exposures: impl IntoIterator<Item = (T::AccountId, Exposure<T::AccountId, BalanceOf<T, I>>)>,
+	) {
+		let mut exposed_accounts = ExposedAccounts::<T, I>::load(era);
+		for (validator, mut exposure) in exposures {
+			exposure.others.sort_by(|a, b| b.value.cmp(&a.value));
+			let pages = exposure.others.chunks(EXPOSURE_PAGE_SIZE)
+				.map(|others| CompactExposurePage {
+					page_total: others.iter().fold(Zero::zero(), |total: BalanceOf<T, I>, individual|
+						total.saturating_add(individual.value)
+					),
+					others: others.iter()
+						.map(|individual| CompactIndividualExposure {
+							index: exposed_accounts.index_of(&individual.who),
+							value: individual.value,
+						})
+						.collect(),
+				})
+				.collect::<Vec<_>>();
+
+			<ErasStakersOverview<T, I>>::insert(era, &validator, ExposureOverview {
+				total: exposure.total,
+				own: exposure.own,
+				nominator_count: exposure.others.len() as u32,
+				page_count: pages.len() as u32,
+			});
+			for (index, page) in pages.into_iter().enumerate() {
+				<ErasStakersCompact<T, I>>::insert(era, (validator.clone(), index as u32), page);
+			}
+		}
+		exposed_accounts.store();
+	}
+
+	/// Remove the information of `era`, which left the history.
+	///
+	/// The information held per validator or staker is only queued for removal in `ErasToPrune`,
+	/// see [`Self::prune`].
+	pub(crate) fn clear(era: EraIndex) {
+		ErasToPrune::<I>::mutate(|eras| eras.push(era));
+		<ErasValidatorReward<T, I>>::remove(era);
+		<ErasRemainderReward<T, I>>::remove(era);
+		<ErasPayoutSummary<T, I>>::remove(era);
+		<ErasRewardPoints<T, I>>::remove(era);
+		<ErasTotalStake<T, I>>::remove(era);
+		<ErasTotalIssuance<T, I>>::remove(era);
+		<ErasStart<T, I>>::remove(era);
+		ErasDuration::<I>::remove(era);
+		<ErasTotalSlashed<T, I>>::remove(era);
+		ErasStartSessionIndex::<I>::remove(era);
+	}
+
+	/// Remove the information of the eras in `ErasToPrune` held per validator or staker, oldest era
+	/// first, removing at most `keys_left` keys and deducting them from it.
+	///
+	/// An era leaves the queue once all of its keys are removed.
+	pub(crate) fn prune(keys_left: &mut u32) {
+		let mut eras = ErasToPrune::<I>::get();
+		if eras.is_empty() {
+			return
+		}
+
+		while let Some(&era) = eras.first() {
+			let era_key = Twox64Concat::hash(&era.encode());
+			let prefixes = [
+				<ErasStakers<T, I>>::final_prefix(),
+				<ErasStakersClipped<T, I>>::final_prefix(),
+				<ErasStakersOverview<T, I>>::final_prefix(),
+				<ErasStakersPaged<T, I>>::final_prefix(),
+				<ErasStakersCompact<T, I>>::final_prefix(),
+				<ErasStakersAccounts<T, I>>::final_prefix(),
+				<ErasValidatorPrefs<T, I>>::final_prefix(),
+				<ErasSlashes<T, I>>::final_prefix(),
+			];
+			for prefix in prefixes.iter() {
+				let removed = kill_prefix_limited(&[&prefix[..], &era_key[..]].concat(), *keys_left);
+				*keys_left -= removed;
+				if *keys_left == 0 {
+					break
+				}
+			}
+			if *keys_left == 0 {
+				break
+			}
+			eras.remove(0);
+		}
+
+		ErasToPrune::<I>::put(eras);
+	}
+
+	/// The exposure of `validator` in `era` from its overview and its pages.
+	///
+	/// The chunks of `ErasStakersAccounts` read are kept in `chunks`.
+	fn paged_exposure(
+		era: EraIndex,
+		validator: &T::AccountId,
+		overview: ExposureOverview<BalanceOf<T, I>>,
+		chunks: &mut BTreeMap<u32, Vec<T::AccountId>>,
+	) -> Exposure<T::AccountId, BalanceOf<T, I>> {
+		Exposure {
+			total: overview.total,
+			own: overview.own,
+			others: (0..overview.page_count)
+				.filter_map(|page| Self::resolved_page(era, validator, page, chunks))
+				.flat_map(|page| page.others)
+				.collect(),
+		}
+	}
+
+	/// The page `page` of the nominators exposed to `validator` in `era`, with their stashes
+	/// resolved from `ErasStakersAccounts`, or read from `ErasStakersPaged` for the eras elected
+	/// before nominators were indexed.
+	///
+	/// The chunks of `ErasStakersAccounts` read are kept in `chunks`.
+	fn resolved_page(
+		era: EraIndex,
+		validator: &T::AccountId,
+		page: u32,
+		chunks: &mut BTreeMap<u32, Vec<T::AccountId>>,
+	) -> Option<ExposurePage<T::AccountId, BalanceOf<T, I>>> {
+		let compact = match <ErasStakersCompact<T, I>>::get(era, (validator.clone(), page)) {
+			Some(compact) => compact,
+			None => return <ErasStakersPaged<T, I>>::get(era, (validator.clone(), page)),
+		};
+		let others = compact.others.into_iter()
+			.filter_map(|individual| {
+				let chunk = individual.index / EXPOSURE_PAGE_SIZE as u32;
+				let accounts = chunks.entry(chunk)
+					.or_insert_with(|| <ErasStakersAccounts<T, I>>::get(era, chunk));
+				accounts.get(individual.index as usize % EXPOSURE_PAGE_SIZE)
+					.map(|who| IndividualExposure { who: who.clone(), value: individual.value })
+			})
+			.collect();
+		Some(ExposurePage { page_total: compact.page_total, others })
+	}
+}
+
+/// The nominators exposed in an era, as stored in `ErasStakersAccounts`, indexed as they are
+/// first exposed.
+struct ExposedAccounts<T: Trait<I>, I: Instance> {
+	era: EraIndex,
+	indices: BTreeMap<T::AccountId, u32>,
+	accounts: Vec<T::AccountId>,
+	/// The number of accounts already in storage.
+	stored: usize,
+	_marker: sp_std::marker::PhantomData<I>,
+}
+
+impl<T: Trait<I>, I: Instance> ExposedAccounts<T, I> {
+	/// The nominators already exposed in `era`.
+	fn load(era: EraIndex) -> Self {
+		let mut accounts = Vec::new();
+		for chunk in 0u32.. {
+			let chunk_accounts = <ErasStakersAccounts<T, I>>::get(era, chunk);
+			let is_full = chunk_accounts.len() == EXPOSURE_PAGE_SIZE;
+			accounts.extend(chunk_accounts);
+			if !is_full {
+				break
+			}
+		}
+		let indices = accounts.iter()
+			.enumerate()
+			.map(|(index, who)| (who.clone(), index as u32))
+			.collect();
+		let stored = accounts.len();
+		ExposedAccounts { era, indices, accounts, stored, _marker: sp_std::marker::PhantomData }
+	}
+
+	/// The index of `who`, which is given the next index if it was not exposed yet.
+	fn index_of(&mut self, who: &T::AccountId) -> u32 {
+		let accounts = &mut self.accounts;
+		*self.indices.entry(who.clone()).or_insert_with(|| {
+			accounts.push(who.clone());
+			(accounts.len() - 1) as u32
+		})
+	}
+
+	/// Store the chunks holding the nominators indexed since they were loaded.
+	fn store(self) {
+		let first_chunk = self.stored / EXPOSURE_PAGE_SIZE;
+		let chunks = self.accounts.chunks(EXPOSURE_PAGE_SIZE).enumerate().skip(first_chunk);
+		for (chunk, accounts) in chunks {
+			<ErasStakersAccounts<T, I>>::insert(self.era, chunk as u32, accounts.to_vec());
+		}
+	}
+}
+
+/// Remove at most `limit` keys starting with `prefix` from storage, returning how many were
+/// removed.
+fn kill_prefix_limited(prefix: &[u8], limit: u32) -> u32 {
+	let mut removed = 0;
+	let mut key = prefix.to_vec();
+	while removed < limit {
+		match sp_io::storage::next_key(&key).filter(|next| next.starts_with(prefix)) {
+			Some(next) => {
+				sp_io::storage::clear(&next);
+				removed += 1;
+				key = next;
+			},
+			None => break,
+		}
+	}
+	removed
+}
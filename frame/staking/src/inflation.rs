@@ -18,8 +18,17 @@
 //! the total payout for the era given the era duration and the staking rate in NPoS.
 //! The staking rate in NPoS is the total amount of tokens staked by nominators and validators,
 //! divided by the total token supply.
+//!
+//! It also provides [`FixedPayout`] and [`FixedInflation`], two `EraPayout` schedules that do not
+//! depend on the staking rate.
 
-use sp_runtime::{Perbill, PerThing, traits::AtLeast32Bit, curve::PiecewiseLinear};
+use sp_std::marker::PhantomData;
+use sp_runtime::{Perbill, PerThing, traits::{AtLeast32Bit, Zero}, curve::PiecewiseLinear};
+use frame_support::traits::Get;
+use crate::EraPayout;
+
+/// Milliseconds per year for the Julian year (365.25 days).
+const MILLISECONDS_PER_YEAR: u64 = 1000 * 3600 * 24 * 36525 / 100;
 
 /// The total payout to all validators (and their nominators) per era.
 ///
@@ -28,14 +37,11 @@ use sp_runtime::{Perbill, PerThing, traits::AtLeast32Bit, curve::PiecewiseLinear
 ///
 /// `era_duration` is expressed in millisecond.
 pub fn compute_total_payout<N>(
-	yearly_inflation: &PiecewiseLinear<'static>,
+	yearly_inflation: &PiecewiseLinear<'_>,
 	npos_token_staked: N,
 	total_tokens: N,
 	era_duration: u64
 ) -> (N, N) where N: AtLeast32Bit + Clone {
-	// Milliseconds per year for the Julian year (365.25 days).
-	const MILLISECONDS_PER_YEAR: u64 = 1000 * 3600 * 24 * 36525 / 100;
-
 	let portion = Perbill::from_rational_approximation(era_duration as u64, MILLISECONDS_PER_YEAR);
 	let payout = portion * yearly_inflation.calculate_for_fraction_times_denominator(
 		npos_token_staked,
@@ -45,6 +51,43 @@ pub fn compute_total_payout<N>(
 	(payout, maximum)
 }
 
+/// The highest payout to stakers of an era such that the yearly return on `npos_token_staked`
+/// does not exceed `max_apr`.
+///
+/// `era_duration` is expressed in millisecond.
+pub fn compute_max_payout<N>(max_apr: Perbill, npos_token_staked: N, era_duration: u64) -> N
+	where N: AtLeast32Bit + Clone
+{
+	let portion = Perbill::from_rational_approximation(era_duration, MILLISECONDS_PER_YEAR);
+	portion * (max_apr * npos_token_staked)
+}
+
+/// An `EraPayout` paying the same amount `P` to stakers every era, whatever its duration.
+pub struct FixedPayout<P>(PhantomData<P>);
+
+impl<N, P> EraPayout<N> for FixedPayout<P> where
+	N: AtLeast32Bit + Clone,
+	P: Get<N>,
+{
+	fn era_payout(_total_staked: N, _total_issuance: N, _era_duration: u64) -> (N, N) {
+		(P::get(), Zero::zero())
+	}
+}
+
+/// An `EraPayout` paying stakers the yearly fraction `I` of the total issuance, pro rata of the
+/// era duration and whatever the staking rate.
+pub struct FixedInflation<I>(PhantomData<I>);
+
+impl<N, I> EraPayout<N> for FixedInflation<I> where
+	N: AtLeast32Bit + Clone,
+	I: Get<Perbill>,
+{
+	fn era_payout(_total_staked: N, total_issuance: N, era_duration: u64) -> (N, N) {
+		let portion = Perbill::from_rational_approximation(era_duration, MILLISECONDS_PER_YEAR);
+		(portion * (I::get() * total_issuance), Zero::zero())
+	}
+}
+
 #[cfg(test)]
 mod test {
 	use sp_runtime::curve::PiecewiseLinear;
@@ -100,4 +143,34 @@ mod test {
 			57_038_500_000_000_000_000_000
 		);
 	}
+
+	frame_support::parameter_types! {
+		pub const PerEra: u64 = 1_000;
+		pub const Yearly: sp_runtime::Perbill = sp_runtime::Perbill::from_percent(10);
+	}
+
+	#[test]
+	fn max_payout_follows_apr() {
+		use super::{compute_max_payout, MILLISECONDS_PER_YEAR};
+		use sp_runtime::Perbill;
+
+		let year = MILLISECONDS_PER_YEAR;
+		assert_eq!(compute_max_payout(Perbill::from_percent(20), 50_000u64, year), 10_000);
+		assert_eq!(compute_max_payout(Perbill::from_percent(20), 50_000u64, year / 4), 2_500);
+		assert_eq!(compute_max_payout(Perbill::zero(), 50_000u64, year), 0);
+	}
+
+	#[test]
+	fn fixed_schedules_ignore_staking_rate() {
+		use crate::EraPayout;
+		use super::{FixedPayout, FixedInflation, MILLISECONDS_PER_YEAR};
+
+		assert_eq!(FixedPayout::<PerEra>::era_payout(0, 100_000u64, 1), (1_000, 0));
+		assert_eq!(FixedPayout::<PerEra>::era_payout(50_000, 100_000u64, 1), (1_000, 0));
+
+		let year = MILLISECONDS_PER_YEAR;
+		assert_eq!(FixedInflation::<Yearly>::era_payout(0, 100_000u64, year), (10_000, 0));
+		assert_eq!(FixedInflation::<Yearly>::era_payout(90_000, 100_000u64, year), (10_000, 0));
+		assert_eq!(FixedInflation::<Yearly>::era_payout(0, 100_000u64, year / 2), (5_000, 0));
+	}
 }
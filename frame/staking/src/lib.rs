@@ -85,7 +85,9 @@
 //! by nominators and their votes.
 //!
 //! An account can become a validator candidate via the
-//! [`validate`](./enum.Call.html#variant.validate) call.
+//! [`validate`](./enum.Call.html#variant.validate) call, once it has set its session keys, or via
+//! the [`validate_with_keys`](./enum.Call.html#variant.validate_with_keys) call setting them at the
+//! same time. A candidate without keys could be elected but could not author.
 //!
 //! #### Nomination
 //!
@@ -172,9 +174,11 @@
 //! ### Reward Calculation
 //!
 //! Validators and nominators are rewarded at the end of each era. The total reward of an era is
-//! calculated using the era duration and the staking rate (the total amount of tokens staked by
-//! nominators and validators, divided by the total token supply). It aims to incentivize toward a
-//! defined staking rate. The full specification can be found
+//! decided by `T::EraPayout`, from the era duration, the total amount of tokens staked and the
+//! total token supply. With [`ConvertCurve`](./struct.ConvertCurve.html) it follows an NPoS reward
+//! curve which aims to incentivize toward a defined staking rate (the total amount of tokens
+//! staked by nominators and validators, divided by the total token supply). The full
+//! specification can be found
 //! [here](https://research.web3.foundation/en/latest/polkadot/Token%20Economics.html#inflation-model).
 //!
 //! Total reward is split among validators and their nominators depending on the number of points
@@ -184,7 +188,11 @@
 //!
 //! [`Module`](./struct.Module.html) implements
 //! [`pallet_authorship::EventHandler`](../pallet_authorship/trait.EventHandler.html) to add reward points
-//! to block producer and block producer of referenced uncles.
+//! to block producer and block producer of referenced uncles. It also implements
+//! [`sp_staking::OnLiveness`](../sp_staking/trait.OnLiveness.html) to add reward points to
+//! validators which proved to be online during a session, e.g. through im-online heartbeats, and
+//! [`sp_staking::OnFinalityParticipation`](../sp_staking/trait.OnFinalityParticipation.html) for
+//! a finality gadget to reward the validators that voted in each of its rounds.
 //!
 //! The validator and its nominator split their reward as following:
 //!
@@ -207,6 +215,31 @@
 //! - Stash account, not increasing the staked value.
 //! - Stash account, also increasing the staked value.
 //!
+//! Validators can additionally have their commission paid to a distinct account, the
+//! [`commission_payee`](./struct.ValidatorPrefs.html#structfield.commission_payee) of their
+//! preferences, while the reward for their own stake follows their reward destination. A
+//! commission too small to create the account of the payee is paid along with the rest instead.
+//!
+//! Rewards are not paid unless claimed. A controller can instead opt its stash in to automatic
+//! payouts with [`set_auto_payout`](enum.Call.html#variant.set_auto_payout): the end of each block
+//! then pays out the unclaimed eras of the registered stashes in turn, at most
+//! [`MaxAutoPayoutsPerBlock`](./struct.Module.html#associatedconstant.MaxAutoPayoutsPerBlock)
+//! of them per block.
+//!
+//! The exposures, validator preferences and slashes of an era leaving the history, and the
+//! slashing span records of a removed stash beyond the latest
+//! [`MaxClearedSlashingSpans`](./struct.Module.html#associatedconstant.MaxClearedSlashingSpans),
+//! are likewise removed over the following blocks, at most
+//! [`MaxPrunedKeysPerBlock`](./struct.Module.html#associatedconstant.MaxPrunedKeysPerBlock) keys
+//! per block. The weight of this work is reserved in every block, as a single budget spent on the
+//! payouts first.
+//!
+//! The storage migrations going through every validator, nominator or ledger are spread over
+//! several blocks too, at most
+//! [`MaxMigratedValuesPerBlock`](./struct.Module.html#associatedconstant.MaxMigratedValuesPerBlock)
+//! values per block. Until the storage is migrated, the dispatchables reading ledgers, the history
+//! depth or the deferred slashes fail with `MigrationInProgress` and no new era is planned.
+//!
 //! ### Additional Fund Management Operations
 //!
 //! Any funds already placed into stash can be the target of the following operations:
@@ -220,9 +253,18 @@
 //!
 //! Note that there is a limitation to the number of fund-chunks that can be scheduled to be
 //! unlocked in the future via [`unbond`](enum.Call.html#variant.unbond). In case this maximum
-//! (`MAX_UNLOCKING_CHUNKS`) is reached, the bonded account _must_ first wait until a successful
+//! (`T::MaxUnlockingChunks`) is reached, the bonded account _must_ first wait until a successful
 //! call to `withdraw_unbonded` to remove some of the chunks.
 //!
+//! ### Delegated Staking
+//!
+//! An agent bonded with [`bond_agent`](./enum.Call.html#variant.bond_agent) stakes the funds
+//! delegated to it with [`delegate`](./enum.Call.html#variant.delegate) rather than its own. The
+//! funds stay reserved in the accounts of the delegators, are slashed along with the agent in
+//! proportion to what each delegated, and can be taken back with
+//! [`undelegate`](./enum.Call.html#variant.undelegate) once the agent withdrew them from its
+//! ledger.
+//!
 //! ### Election Algorithm
 //!
 //! The current election algorithm is implemented based on Phragmén.
@@ -235,6 +277,19 @@
 //! values until the total difference among votes of a particular nominator are less than a
 //! threshold.
 //!
+//! A validator can limit the stake it is backed by with the
+//! [`max_total_stake`](./struct.ValidatorPrefs.html#structfield.max_total_stake) of its
+//! preferences. Once elected, the nominations above it are moved onto the other elected targets of
+//! the same nominators, if they have room for them.
+//!
+//! ### Instances
+//!
+//! The module is instantiable, so that a runtime can stake several assets, each in an instance
+//! with its own storage, under the prefix of the instance. The instances staking the same currency
+//! must lock it under different [`LockId`](./trait.Trait.html#associatedtype.LockId)s. The
+//! implementations of [`SessionInterface`] and [`SessionKeysProvider`] for the runtime are for the
+//! default instance, whose storage prefix is the one of the releases before instances.
+//!
 //! ## GenesisConfig
 //!
 //! The Staking module depends on the [`GenesisConfig`](./struct.GenesisConfig.html).
@@ -255,43 +310,65 @@ mod mock;
 mod tests;
 mod slashing;
 mod migration;
+mod era_info;
+mod delegation;
+pub mod asset;
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
+#[cfg(any(feature = "runtime-benchmarks", feature = "testing-utils", test))]
+pub mod testing_utils;
 
 pub mod inflation;
 
-use sp_std::{prelude::*, result, collections::btree_map::BTreeMap};
+use sp_std::{
+	prelude::*, result, borrow::Borrow,
+	collections::{btree_map::BTreeMap, btree_set::BTreeSet},
+};
 use codec::{HasCompact, Encode, Decode};
 use frame_support::{
-	decl_module, decl_event, decl_storage, ensure, decl_error,
-	weights::SimpleDispatchInfo,
+	decl_module, decl_event, decl_storage, ensure, decl_error, StorageHasher, Twox64Concat, Parameter,
+	weights::{SimpleDispatchInfo, Weight, FunctionOf, DispatchClass},
 	dispatch::DispatchResult,
-	traits::{
-		Currency, LockIdentifier, LockableCurrency,
-		WithdrawReasons, OnUnbalanced, Imbalance, Get, Time
-	}
+	traits::{OnUnbalanced, Imbalance, Get, Time, LockIdentifier},
 };
 use pallet_session::historical::SessionManager;
 use sp_runtime::{
-	Perbill, PerThing, RuntimeDebug,
+	Perbill, PerThing, RuntimeDebug, DispatchError,
 	curve::PiecewiseLinear,
 	traits::{
 		Convert, Zero, StaticLookup, CheckedSub, Saturating, SaturatedConversion,
-		AtLeast32Bit, EnsureOrigin,
+		AtLeast32Bit, EnsureOrigin, OpaqueKeys,
 	}
 };
 use sp_staking::{
-	SessionIndex,
-	offence::{OnOffenceHandler, OffenceDetails, Offence, ReportOffence, OffenceError},
+	SessionIndex, OnLiveness, OnUnresponsiveness, OnFinalityParticipation,
+	offence::{OnOffenceHandler, OffenceDetails, Offence, ReportOffence, OffenceError, Kind},
 };
 #[cfg(feature = "std")]
 use sp_runtime::{Serialize, Deserialize};
 use frame_system::{self as system, ensure_signed, ensure_root};
 
-use sp_phragmen::ExtendedBalance;
+use sp_phragmen::{ExtendedBalance, SupportMap};
+use era_info::EraInfo;
 
 const DEFAULT_MINIMUM_VALIDATOR_COUNT: u32 = 4;
-const MAX_NOMINATIONS: usize = 16;
-const MAX_UNLOCKING_CHUNKS: usize = 32;
-const STAKING_ID: LockIdentifier = *b"staking ";
+const DEFAULT_HISTORY_DEPTH: u32 = 84;
+const MAX_AUTO_PAYOUT_STASHES: usize = 256;
+const MAX_REWARD_CURVE_POINTS: usize = 64;
+/// The kind of the offences reported by `report_offence_unchecked`.
+pub const UNCHECKED_OFFENCE_KIND: Kind = *b"staking:reported";
+/// The number of slashing spans of a stash whose records are removed at once when it is reaped,
+/// the records of its older spans being removed over the following blocks.
+pub const MAX_CLEARED_SLASHING_SPANS: u32 = 32;
+/// The number of nominators in each page of an exposure, as returned by `eras_stakers_page`.
+pub const EXPOSURE_PAGE_SIZE: usize = 256;
+/// The weight of looking up and making the automatic payout of a stash as a validator.
+const AUTO_PAYOUT_WEIGHT: Weight = 500_000;
+/// The weight of reading the rewarded exposure of a validator, for the automatic payout of a
+/// stash as a nominator.
+const AUTO_PAYOUT_EXPOSURE_WEIGHT: Weight = 20_000;
+const PRUNING_KEY_WEIGHT: Weight = 10_000;
+const MIGRATED_VALUE_WEIGHT: Weight = 10_000;
 
 /// Counter for the number of eras that have passed.
 pub type EraIndex = u32;
@@ -299,6 +376,9 @@ pub type EraIndex = u32;
 /// Counter for the number of "reward" points earned by a given validator.
 pub type RewardPoint = u32;
 
+/// Identifier of a deferred slash, unique among all slashes ever deferred.
+pub type SlashId = u32;
+
 /// Information regarding the active era (era in used in session).
 #[derive(Encode, Decode, RuntimeDebug)]
 pub struct ActiveEraInfo<Moment> {
@@ -311,6 +391,20 @@ pub struct ActiveEraInfo<Moment> {
 	start: Option<Moment>,
 }
 
+/// The payouts made for an era so far.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, Default, RuntimeDebug)]
+pub struct EraPayoutSummary<Balance: HasCompact> {
+	/// The part of the stakers' era payout paid out.
+	#[codec(compact)]
+	pub total_paid: Balance,
+	/// The part of the stakers' era payout left to be claimed.
+	#[codec(compact)]
+	pub total_unclaimed: Balance,
+	/// The number of validators who have been paid out.
+	#[codec(compact)]
+	pub validators_paid: u32,
+}
+
 /// Reward points of an era. Used to split era total payout between validators.
 ///
 /// This points will be used to reward validators and their respective nominators.
@@ -322,6 +416,42 @@ pub struct EraRewardPoints<AccountId: Ord> {
 	individual: BTreeMap<AccountId, RewardPoint>,
 }
 
+/// The reward points a validator earned in an era.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct ValidatorEraPoints<AccountId> {
+	/// The era the points were earned in.
+	pub era: EraIndex,
+	/// The stash of the validator.
+	pub validator: AccountId,
+	/// The points earned by the validator.
+	pub points: RewardPoint,
+	/// The share of the total points of the era earned by the validator.
+	pub share: Perbill,
+}
+
+/// The call claiming a payout, with its arguments.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub enum PayoutCall<AccountId> {
+	/// `payout_validator(era)`, made by the controller of the validator.
+	Validator(EraIndex),
+	/// `payout_nominator(era, validators)`, made by the controller of the nominator.
+	Nominator(EraIndex, Vec<(AccountId, u32)>),
+}
+
+/// A payout a stash can claim.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct ClaimablePayout<AccountId, Balance> {
+	/// The era the payout is for.
+	pub era: EraIndex,
+	/// The amount the stash is paid.
+	pub amount: Balance,
+	/// The call claiming the payout.
+	pub call: PayoutCall<AccountId>,
+}
+
 /// Indicates the initial status of the staker.
 #[derive(RuntimeDebug)]
 #[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
@@ -353,17 +483,25 @@ impl Default for RewardDestination {
 
 /// Preference of what happens regarding validation.
 #[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug)]
-pub struct ValidatorPrefs {
+pub struct ValidatorPrefs<AccountId, Balance> {
 	/// Reward that validator takes up-front; only the rest is split between themselves and
 	/// nominators.
 	#[codec(compact)]
 	pub commission: Perbill,
+	/// The highest total stake the validator accepts to be backed by, if any. The election moves
+	/// the nominations above it onto the other targets of the nominators.
+	pub max_total_stake: Option<Balance>,
+	/// The account the commission is paid to, if not along with the rest of the reward of the
+	/// validator. Like the commission, it applies from the era the validator is elected with it.
+	pub commission_payee: Option<AccountId>,
 }
 
-impl Default for ValidatorPrefs {
+impl<AccountId, Balance> Default for ValidatorPrefs<AccountId, Balance> {
 	fn default() -> Self {
 		ValidatorPrefs {
 			commission: Default::default(),
+			max_total_stake: None,
+			commission_payee: None,
 		}
 	}
 }
@@ -379,6 +517,22 @@ pub struct UnlockChunk<Balance: HasCompact> {
 	era: EraIndex,
 }
 
+/// An inconsistency in the staking state of a stash, as found by `check_ledger`.
+#[derive(PartialEq, Eq, Clone, Copy, Encode, Decode, RuntimeDebug)]
+pub enum LedgerCorruption {
+	/// The stash is bonded but its controller has no ledger.
+	MissingLedger,
+	/// The stash is bonded but the ledger of its controller belongs to another stash.
+	ForeignLedger,
+	/// The total of the ledger is not its active balance plus its unlocking chunks.
+	TotalMismatch,
+	/// The total of the ledger is above the funds backing it: the free balance of the stash, or
+	/// the funds delegated to it for an agent.
+	TotalAboveBalance,
+	/// The stash is not bonded but still has a payee, preferences or nominations.
+	Dangling,
+}
+
 /// The ledger of a (bonded) stash.
 #[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug)]
 pub struct StakingLedger<AccountId, Balance: HasCompact> {
@@ -543,29 +697,194 @@ pub struct Exposure<AccountId, Balance: HasCompact> {
 	pub others: Vec<IndividualExposure<AccountId, Balance>>,
 }
 
+/// The totals of the exposure of a validator, without the nominators exposed.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, Default, RuntimeDebug)]
+pub struct ExposureOverview<Balance: HasCompact> {
+	/// The total balance backing this validator.
+	#[codec(compact)]
+	pub total: Balance,
+	/// The validator's own stash that is exposed.
+	#[codec(compact)]
+	pub own: Balance,
+	/// The number of nominators exposed.
+	pub nominator_count: u32,
+	/// The number of pages of `EXPOSURE_PAGE_SIZE` nominators the exposure is split into.
+	pub page_count: u32,
+}
+
+/// A page of the nominators exposed to a validator.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, Default, RuntimeDebug)]
+pub struct ExposurePage<AccountId, Balance: HasCompact> {
+	/// The total balance exposed by the nominators of the page.
+	#[codec(compact)]
+	pub page_total: Balance,
+	/// The portions of nominators stashes that are exposed.
+	pub others: Vec<IndividualExposure<AccountId, Balance>>,
+}
+
+/// The exposure of a nominator, referencing its stash by its index in the accounts exposed in the
+/// era, see `ErasStakersAccounts`.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, Default, RuntimeDebug)]
+pub struct CompactIndividualExposure<Balance: HasCompact> {
+	/// The index of the stash of the nominator in the accounts exposed in the era.
+	#[codec(compact)]
+	pub index: u32,
+	/// Amount of funds exposed.
+	#[codec(compact)]
+	pub value: Balance,
+}
+
+/// A page of the nominators exposed to a validator, as stored in `ErasStakersCompact`.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, Default, RuntimeDebug)]
+pub struct CompactExposurePage<Balance: HasCompact> {
+	/// The total balance exposed by the nominators of the page.
+	#[codec(compact)]
+	pub page_total: Balance,
+	/// The portions of nominators stashes that are exposed.
+	pub others: Vec<CompactIndividualExposure<Balance>>,
+}
+
 /// A pending slash record. The value of the slash has been computed but not applied yet,
 /// rather deferred for several eras.
 #[derive(Encode, Decode, Default, RuntimeDebug)]
 pub struct UnappliedSlash<AccountId, Balance: HasCompact> {
+	/// The identifier of the slash. It does not change while the slash is pending.
+	pub id: SlashId,
 	/// The stash ID of the offending validator.
-	validator: AccountId,
+	pub validator: AccountId,
+	/// The era in which the offence was committed.
+	pub slash_era: EraIndex,
 	/// The validator's own slash.
-	own: Balance,
+	pub own: Balance,
 	/// All other slashed stakers and amounts.
-	others: Vec<(AccountId, Balance)>,
+	pub others: Vec<(AccountId, Balance)>,
 	/// Reporters of the offence; bounty payout recipients.
-	reporters: Vec<AccountId>,
+	pub reporters: Vec<AccountId>,
 	/// The amount of payout.
-	payout: Balance,
+	pub payout: Balance,
+}
+
+/// An offence reported while the storage is migrated, whose slashes are computed once it is.
+#[derive(Encode, Decode, RuntimeDebug)]
+pub struct QueuedOffence<AccountId, Balance: HasCompact> {
+	/// The offenders, with their exposure in the era of the offence.
+	pub offenders: Vec<OffenceDetails<AccountId, (AccountId, Exposure<AccountId, Balance>)>>,
+	/// The fraction of the stake slashed of each offender.
+	pub slash_fraction: Vec<Perbill>,
+	/// The session in which the offence was committed.
+	pub slash_session: SessionIndex,
+	/// The kind of the offence.
+	pub kind: Kind,
+}
+
+/// A deferred slash that is not applied yet, as reported to governance.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct PendingSlash<AccountId, Balance> {
+	/// The era the slash was reported in, which together with `id` identifies the slash.
+	pub era: EraIndex,
+	/// The identifier of the slash.
+	pub id: SlashId,
+	/// The stash ID of the offending validator.
+	pub validator: AccountId,
+	/// The validator's own slash.
+	pub own: Balance,
+	/// All other slashed stakers and amounts.
+	pub others: Vec<(AccountId, Balance)>,
+	/// The amount paid to the reporters.
+	pub payout: Balance,
+	/// The era at the start of which the slash is applied, unless it is canceled before.
+	pub apply_at: EraIndex,
+}
+
+/// Governance-set bounds on the slash fraction of one kind of offence.
+#[derive(PartialEq, Eq, Clone, Copy, Encode, Decode, Default, RuntimeDebug)]
+pub struct SlashFractionBounds {
+	/// The lowest fraction slashed for the offence.
+	pub min: Perbill,
+	/// The highest fraction slashed for the offence.
+	pub max: Perbill,
+}
+
+impl SlashFractionBounds {
+	/// Clamp the fraction computed by the offence module into these bounds.
+	pub fn clamp(&self, fraction: Perbill) -> Perbill {
+		fraction.max(self.min).min(self.max)
+	}
+}
+
+/// The kind of a call of this module, as given by `Call::kind`, for the proxy and call filters of
+/// a runtime.
+#[derive(PartialEq, Eq, Clone, Copy, Encode, Decode, RuntimeDebug)]
+pub enum CallKind {
+	/// Bonds, unbonds or withdraws funds, or changes who controls them or where rewards go.
+	FundsMoving,
+	/// Changes the role of a staker or pays out rewards, without moving funds.
+	NonTransfer,
+	/// Restricted to root or `T::SlashCancelOrigin`.
+	Governance,
+}
+
+/// Governance-set parameters of the module, changed without a runtime upgrade.
+///
+/// A parameter left to `None` takes its default: the value given by `Trait` for those overriding
+/// it, no bound for the others.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, Default, RuntimeDebug)]
+pub struct StakingParameters<Balance> {
+	/// Overrides `T::SlashDeferDuration`. It must be less than the bonding duration.
+	pub slash_defer_duration: Option<EraIndex>,
+	/// The lowest active bond to declare the intention to validate.
+	pub min_validator_bond: Option<Balance>,
+	/// The lowest active bond to nominate.
+	pub min_nominator_bond: Option<Balance>,
+	/// The lowest commission a validator candidate can set.
+	pub min_commission: Option<Perbill>,
+	/// Overrides `T::MaxUnresponsiveSessions`.
+	pub max_unresponsive_sessions: Option<SessionIndex>,
+	/// The lowest active bond of a staker, below which it is dust. It must be at least the
+	/// existential deposit, which it defaults to.
+	pub min_active_bond: Option<Balance>,
+}
+
+/// A reward curve set by governance, used in place of `T::EraPayout` while present.
+///
+/// It is the owned counterpart of a `PiecewiseLinear`, giving the yearly inflation as a function
+/// of the staking rate.
+#[derive(PartialEq, Eq, Clone, Encode, Decode, Default, RuntimeDebug)]
+pub struct RewardCurvePoints {
+	/// The points of the curve, in strictly increasing order of abscissa.
+	pub points: Vec<(Perbill, Perbill)>,
+	/// The maximum yearly inflation, above any ordinate of the points.
+	pub maximum: Perbill,
+}
+
+impl RewardCurvePoints {
+	/// Whether the curve is well formed: not empty, bounded by `MAX_REWARD_CURVE_POINTS`, with
+	/// strictly increasing abscissas and no ordinate above the maximum.
+	pub fn is_valid(&self) -> bool {
+		!self.points.is_empty()
+			&& self.points.len() <= MAX_REWARD_CURVE_POINTS
+			&& self.points.windows(2).all(|w| w[0].0 < w[1].0)
+			&& self.points.iter().all(|p| p.1 <= self.maximum)
+	}
+
+	/// The curve as a `PiecewiseLinear`.
+	pub fn as_piecewise_linear(&self) -> PiecewiseLinear<'_> {
+		PiecewiseLinear { points: &self.points, maximum: self.maximum }
+	}
 }
 
-pub type BalanceOf<T> =
-	<<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::Balance;
-type PositiveImbalanceOf<T> =
-	<<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::PositiveImbalance;
-type NegativeImbalanceOf<T> =
-	<<T as Trait>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::NegativeImbalance;
-type MomentOf<T> = <<T as Trait>::Time as Time>::Moment;
+pub type BalanceOf<T, I = DefaultInstance> =
+	<<T as Trait<I>>::Currency as asset::StakingCurrency<<T as frame_system::Trait>::AccountId>>::Balance;
+type PositiveImbalanceOf<T, I = DefaultInstance> =
+	<<T as Trait<I>>::Currency as asset::StakingCurrency<<T as frame_system::Trait>::AccountId>>::PositiveImbalance;
+type NegativeImbalanceOf<T, I = DefaultInstance> =
+	<<T as Trait<I>>::Currency as asset::StakingCurrency<<T as frame_system::Trait>::AccountId>>::NegativeImbalance;
+type MomentOf<T, I = DefaultInstance> = <<T as Trait<I>>::Time as Time>::Moment;
+pub type SessionKeysOf<T, I = DefaultInstance> =
+	<<T as Trait<I>>::SessionKeysProvider as SessionKeysProvider<
+		<T as frame_system::Trait>::AccountId,
+	>>::Keys;
 
 /// Means for interacting with a specialized version of the `session` trait.
 ///
@@ -606,9 +925,269 @@ impl<T: Trait> SessionInterface<<T as frame_system::Trait>::AccountId> for T whe
 	}
 }
 
-pub trait Trait: frame_system::Trait {
+/// Means for reading and setting the session keys of validators, so that a stash cannot declare
+/// the intention to validate without being able to author once elected.
+pub trait SessionKeysProvider<AccountId> {
+	/// The session keys of a validator.
+	type Keys: Parameter;
+
+	/// Whether the validator `stash` has set session keys.
+	fn has_keys(stash: &AccountId) -> bool;
+
+	/// Set the session keys of the validator controlled by `controller`, `proof` proving their
+	/// ownership.
+	fn set_keys(controller: &AccountId, keys: Self::Keys, proof: &[u8]) -> DispatchResult;
+}
+
+/// For a chain without session keys: no stash is refused.
+impl<AccountId> SessionKeysProvider<AccountId> for () {
+	type Keys = ();
+
+	fn has_keys(_stash: &AccountId) -> bool {
+		true
+	}
+
+	fn set_keys(_controller: &AccountId, _keys: (), _proof: &[u8]) -> DispatchResult {
+		Ok(())
+	}
+}
+
+impl<T: Trait> SessionKeysProvider<<T as frame_system::Trait>::AccountId> for T where
+	T: pallet_session::Trait<ValidatorId = <T as frame_system::Trait>::AccountId>,
+{
+	type Keys = <T as pallet_session::Trait>::Keys;
+
+	fn has_keys(stash: &<T as frame_system::Trait>::AccountId) -> bool {
+		<pallet_session::Module<T>>::load_keys(stash).is_some()
+	}
+
+	fn set_keys(
+		controller: &<T as frame_system::Trait>::AccountId,
+		keys: Self::Keys,
+		proof: &[u8],
+	) -> DispatchResult {
+		ensure!(keys.ownership_proof_is_valid(proof), pallet_session::Error::<T>::InvalidProof);
+		<pallet_session::Module<T>>::do_set_keys(controller, keys)
+	}
+}
+
+/// The monetary policy deciding how much is paid out at the end of each era.
+pub trait EraPayout<Balance> {
+	/// Determine the payout for this era.
+	///
+	/// Returns the amount to be paid to stakers in this era, as well as whatever else should be
+	/// minted for the rest of the network. `era_duration` is expressed in milliseconds.
+	fn era_payout(
+		total_staked: Balance,
+		total_issuance: Balance,
+		era_duration: u64,
+	) -> (Balance, Balance);
+}
+
+impl<Balance: Default> EraPayout<Balance> for () {
+	fn era_payout(
+		_total_staked: Balance,
+		_total_issuance: Balance,
+		_era_duration: u64,
+	) -> (Balance, Balance) {
+		(Default::default(), Default::default())
+	}
+}
+
+/// An `EraPayout` implementation following an NPoS reward curve.
+///
+/// Stakers are paid according to the curve, the rest is the difference between the maximum
+/// inflation of the curve and the staker payout.
+pub struct ConvertCurve<T>(sp_std::marker::PhantomData<T>);
+
+impl<Balance, T> EraPayout<Balance> for ConvertCurve<T> where
+	Balance: AtLeast32Bit + Clone,
+	T: Get<&'static PiecewiseLinear<'static>>,
+{
+	fn era_payout(
+		total_staked: Balance,
+		total_issuance: Balance,
+		era_duration: u64,
+	) -> (Balance, Balance) {
+		let (validator_payout, max_payout) = inflation::compute_total_payout(
+			T::get(),
+			total_staked,
+			total_issuance,
+			era_duration,
+		);
+		let rest = max_payout.saturating_sub(validator_payout.clone());
+		(validator_payout, rest)
+	}
+}
+
+/// A handler notified of the changes to the stake and the role of stakers, such as a list of
+/// stakers ranked by stake kept outside of this module.
+///
+/// Each method is given the values before and after the change. Declaring the intention to
+/// validate drops the nominations of a stash, and nominating drops its intention to validate,
+/// without a separate notification.
+pub trait OnStakingUpdate<AccountId, Balance> {
+	/// The active stake of `stash` went from `prev_active`, zero if it was just bonded, to
+	/// `active`.
+	fn on_stake_update(_stash: &AccountId, _prev_active: Balance, _active: Balance) {}
+
+	/// `stash` declared the intention to validate with `prefs`, `prev_prefs` being its previous
+	/// preferences if it already was a validator candidate.
+	fn on_validator_update(
+		_stash: &AccountId,
+		_prev_prefs: Option<&ValidatorPrefs<AccountId, Balance>>,
+		_prefs: &ValidatorPrefs<AccountId, Balance>,
+	) {}
+
+	/// `stash` nominates `targets`, `prev_targets` being its previous targets if it already was
+	/// a nominator.
+	fn on_nominator_update(
+		_stash: &AccountId,
+		_prev_targets: Option<&[AccountId]>,
+		_targets: &[AccountId],
+	) {}
+
+	/// `stash` was chilled, neither validating nor nominating anymore.
+	fn on_chill(_stash: &AccountId) {}
+
+	/// `stash` was unbonded entirely and its staking information removed.
+	fn on_unstake(_stash: &AccountId) {}
+}
+
+impl<AccountId, Balance> OnStakingUpdate<AccountId, Balance> for () {}
+
+/// The policy deciding which stashes may be validator candidates, such as those with a verified
+/// identity or on a whitelist.
+pub trait CandidateFilter<AccountId, Balance> {
+	/// Whether `stash`, with `active` funds bonded, may declare the intention to validate and be
+	/// elected.
+	fn is_allowed(stash: &AccountId, active: Balance) -> bool;
+}
+
+impl<AccountId, Balance> CandidateFilter<AccountId, Balance> for () {
+	fn is_allowed(_stash: &AccountId, _active: Balance) -> bool {
+		true
+	}
+}
+
+/// The policy deciding which stashes may stake at all, such as those not on a deny-list.
+pub trait BondingRestriction<AccountId> {
+	/// Whether `stash` may bond funds, have funds delegated to it or delegate its own, and
+	/// nominate.
+	fn is_allowed(stash: &AccountId) -> bool;
+}
+
+impl<AccountId> BondingRestriction<AccountId> for () {
+	fn is_allowed(_stash: &AccountId) -> bool {
+		true
+	}
+}
+
+/// The weights of the dispatchables of this module, as functions of the components they were
+/// benchmarked with.
+///
+/// The `()` implementation gives the weights used before benchmarking, independent of the
+/// components. See the `benchmarking` module for the meaning of each component.
+pub trait WeightInfo {
+	fn bond() -> Weight;
+	fn bond_extra() -> Weight;
+	fn unbond() -> Weight;
+	fn withdraw_unbonded(c: u32, s: u32) -> Weight;
+	fn validate() -> Weight;
+	fn validate_with_keys() -> Weight;
+	fn nominate(n: u32) -> Weight;
+	fn chill() -> Weight;
+	fn set_payee() -> Weight;
+	fn set_auto_payout(a: u32) -> Weight;
+	fn set_controller() -> Weight;
+	fn set_validator_count() -> Weight;
+	fn force_no_eras() -> Weight;
+	fn force_new_era() -> Weight;
+	fn set_invulnerables(i: u32) -> Weight;
+	fn add_invulnerable(i: u32) -> Weight;
+	fn remove_invulnerable(i: u32) -> Weight;
+	fn force_unstake(s: u32) -> Weight;
+	fn force_new_era_always() -> Weight;
+	fn set_slash_reward_decay() -> Weight;
+	fn set_extra_remainder_share() -> Weight;
+	fn set_payout_stake_floor() -> Weight;
+	fn set_max_payout_apr() -> Weight;
+	fn set_reward_curve(p: u32) -> Weight;
+	fn set_slash_fraction_bounds() -> Weight;
+	fn set_parameters() -> Weight;
+	fn cancel_deferred_slash(s: u32) -> Weight;
+	fn apply_slash_now(n: u32) -> Weight;
+	fn payout_nominator(v: u32) -> Weight;
+	fn payout_validator(n: u32) -> Weight;
+	fn payout_stakers_all(e: u32) -> Weight;
+	fn rebond(c: u32) -> Weight;
+	fn set_history_depth() -> Weight;
+	fn reap_stash(s: u32) -> Weight;
+	fn repair_ledger(s: u32) -> Weight;
+	fn report_offence_unchecked(o: u32) -> Weight;
+	fn set_next_validators(v: u32) -> Weight;
+	fn bond_agent() -> Weight;
+	fn delegate(d: u32) -> Weight;
+	fn undelegate(d: u32) -> Weight;
+	fn new_era(v: u32, n: u32, w: u32, e: u32) -> Weight;
+}
+
+impl WeightInfo for () {
+	fn bond() -> Weight { 500_000 }
+	fn bond_extra() -> Weight { 500_000 }
+	fn unbond() -> Weight { 400_000 }
+	fn withdraw_unbonded(_c: u32, _s: u32) -> Weight { 400_000 }
+	fn validate() -> Weight { 750_000 }
+	fn validate_with_keys() -> Weight { 1_000_000 }
+	fn nominate(_n: u32) -> Weight { 750_000 }
+	fn chill() -> Weight { 500_000 }
+	fn set_payee() -> Weight { 500_000 }
+	fn set_auto_payout(_a: u32) -> Weight { 500_000 }
+	fn set_controller() -> Weight { 750_000 }
+	fn set_validator_count() -> Weight { 5_000 }
+	fn force_no_eras() -> Weight { 5_000 }
+	fn force_new_era() -> Weight { 5_000 }
+	fn set_invulnerables(_i: u32) -> Weight { 5_000 }
+	fn add_invulnerable(_i: u32) -> Weight { 10_000 }
+	fn remove_invulnerable(_i: u32) -> Weight { 10_000 }
+	fn force_unstake(_s: u32) -> Weight { 10_000 }
+	fn force_new_era_always() -> Weight { 5_000 }
+	fn set_slash_reward_decay() -> Weight { 5_000 }
+	fn set_extra_remainder_share() -> Weight { 5_000 }
+	fn set_payout_stake_floor() -> Weight { 5_000 }
+	fn set_max_payout_apr() -> Weight { 5_000 }
+	fn set_reward_curve(_p: u32) -> Weight { 5_000 }
+	fn set_slash_fraction_bounds() -> Weight { 5_000 }
+	fn set_parameters() -> Weight { 5_000 }
+	fn cancel_deferred_slash(_s: u32) -> Weight { 1_000_000 }
+	fn apply_slash_now(_n: u32) -> Weight { 1_000_000 }
+	fn payout_nominator(_v: u32) -> Weight { 500_000 }
+	fn payout_validator(_n: u32) -> Weight { 500_000 }
+	fn payout_stakers_all(e: u32) -> Weight { 500_000u32.saturating_mul(e) }
+	fn rebond(_c: u32) -> Weight { 500_000 }
+	fn set_history_depth() -> Weight { 5_000 }
+	fn reap_stash(_s: u32) -> Weight { 10_000 }
+	fn repair_ledger(_s: u32) -> Weight { 500_000 }
+	fn report_offence_unchecked(o: u32) -> Weight { 1_000_000u32.saturating_mul(o.max(1)) }
+	fn set_next_validators(_v: u32) -> Weight { 50_000 }
+	fn bond_agent() -> Weight { 500_000 }
+	fn delegate(d: u32) -> Weight { 500_000u32.saturating_add(1_000u32.saturating_mul(d)) }
+	fn undelegate(d: u32) -> Weight { 500_000u32.saturating_add(1_000u32.saturating_mul(d)) }
+	fn new_era(v: u32, n: u32, _w: u32, _e: u32) -> Weight {
+		10_000u32.saturating_mul(v.saturating_add(n))
+	}
+}
+
+pub trait Trait<I: Instance = DefaultInstance>: frame_system::Trait {
 	/// The staking balance.
-	type Currency: LockableCurrency<Self::AccountId, Moment=Self::BlockNumber>;
+	///
+	/// Implemented by any `LockableCurrency` that is also a `ReservableCurrency`; see the
+	/// [`asset`] module to stake another asset.
+	type Currency: asset::StakingCurrency<Self::AccountId>;
+
+	/// The identifier of the lock on the bonded funds of the stashes, which must differ between
+	/// the instances of the module staking the same currency.
+	type LockId: Get<LockIdentifier>;
 
 	/// Time used for computing era duration.
 	///
@@ -621,23 +1200,31 @@ pub trait Trait: frame_system::Trait {
 	/// TODO: #1377
 	/// The backward convert should be removed as the new Phragmen API returns ratio.
 	/// The post-processing needs it but will be moved to off-chain. TODO: #2908
-	type CurrencyToVote: Convert<BalanceOf<Self>, u64> + Convert<u128, BalanceOf<Self>>;
+	type CurrencyToVote: Convert<BalanceOf<Self, I>, u64> + Convert<u128, BalanceOf<Self, I>>;
 
 	/// Tokens have been minted and are unused for validator-reward.
-	type RewardRemainder: OnUnbalanced<NegativeImbalanceOf<Self>>;
+	type RewardRemainder: OnUnbalanced<NegativeImbalanceOf<Self, I>>;
+
+	/// Handler for the era payout left unclaimed when the era is removed from history; the
+	/// amount is minted for it.
+	type UnclaimedRewards: OnUnbalanced<NegativeImbalanceOf<Self, I>>;
 
 	/// The overarching event type.
-	type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
+	type Event: From<Event<Self, I>> + Into<<Self as frame_system::Trait>::Event>;
 
 	/// Handler for the unbalanced reduction when slashing a staker.
-	type Slash: OnUnbalanced<NegativeImbalanceOf<Self>>;
+	type Slash: OnUnbalanced<NegativeImbalanceOf<Self, I>>;
 
 	/// Handler for the unbalanced increment when rewarding a staker.
-	type Reward: OnUnbalanced<PositiveImbalanceOf<Self>>;
+	type Reward: OnUnbalanced<PositiveImbalanceOf<Self, I>>;
 
 	/// Number of sessions per era.
 	type SessionsPerEra: Get<SessionIndex>;
 
+	/// The minimum time an era lasts, in the unit of `T::Time`, on top of `SessionsPerEra`: the
+	/// next era is not planned before the active one has lasted that long. Zero for no minimum.
+	type MinimumEraDuration: Get<<Self::Time as Time>::Moment>;
+
 	/// Number of eras that staked funds must remain bonded for.
 	type BondingDuration: Get<EraIndex>;
 
@@ -649,17 +1236,114 @@ pub trait Trait: frame_system::Trait {
 	/// The origin which can cancel a deferred slash. Root can always do this.
 	type SlashCancelOrigin: EnsureOrigin<Self::Origin>;
 
+	/// The origin which can report an offence with `report_offence_unchecked`, for evidence
+	/// arriving out-of-band. Root can always do this.
+	type ReportOffenceOrigin: EnsureOrigin<Self::Origin>;
+
+	/// Number of eras whose historical sessions are kept, counted as the bonding duration is.
+	///
+	/// Set it to `BondingDuration` to keep them for as long as offences can be reported. A chain
+	/// auditing past eras can keep more, the extra eras staying in `BondedEras` but no longer
+	/// being slashable. A light chain can keep fewer, offences committed in the sessions pruned
+	/// then no longer being provable.
+	type HistoricalErasKept: Get<EraIndex>;
+
+	/// Number of eras a shallower history depth set by governance is delayed by, for the rewards
+	/// of the eras leaving the history to be claimed meanwhile.
+	type HistoryShrinkDelay: Get<EraIndex>;
+
 	/// Interface for interacting with a session module.
 	type SessionInterface: self::SessionInterface<Self::AccountId>;
 
-	/// The NPoS reward curve to use.
-	type RewardCurve: Get<&'static PiecewiseLinear<'static>>;
+	/// The session keys of validators, which must be set before declaring the intention to
+	/// validate. `()` does not require any.
+	type SessionKeysProvider: self::SessionKeysProvider<Self::AccountId>;
+
+	/// The payout for validators and the system for the current era.
+	///
+	/// See [`ConvertCurve`] for the NPoS reward curve.
+	type EraPayout: EraPayout<BalanceOf<Self, I>>;
+
+	/// The stashes allowed to validate, checked on `validate` and again at each election. `()`
+	/// allows all of them.
+	type CandidateFilter: CandidateFilter<Self::AccountId, BalanceOf<Self, I>>;
+
+	/// The stashes allowed to stake, checked on `bond`, `bond_agent`, `bond_extra`, `nominate`
+	/// and `delegate`, for both the delegator and the agent, and by `virtual_bond` and
+	/// `virtual_bond_extra`. `()` allows all of them.
+	type BondingRestriction: BondingRestriction<Self::AccountId>;
+
+	/// The funds of stashes still vesting, see [`asset::VestedFunds`].
+	type VestedFunds: asset::VestedFunds<Self::AccountId, BalanceOf<Self, I>>;
+
+	/// The pallet holding the funds of the stashes bonded with `virtual_bond`, see
+	/// [`asset::StakeKeeper`].
+	type StakeKeeper:
+		asset::StakeKeeper<Self::AccountId, BalanceOf<Self, I>, NegativeImbalanceOf<Self, I>>;
+
+	/// Handler notified of the changes to the stake and the role of stakers.
+	type OnStakingUpdate: OnStakingUpdate<Self::AccountId, BalanceOf<Self, I>>;
 
 	/// The maximum number of nominator rewarded for each validator.
 	///
 	/// For each validator only the `$MaxNominatorRewardedPerValidator` biggest stakers can claim
 	/// their reward. This used to limit the i/o cost for the nominator payout.
 	type MaxNominatorRewardedPerValidator: Get<u32>;
+
+	/// The maximum number of nominators exposed to each validator.
+	///
+	/// For each elected validator only the `$MaxNominatorExposedPerValidator` biggest stakers are
+	/// exposed to it. The stake of the others backs no validator for the era, so that they are
+	/// neither rewarded nor slashed for it.
+	type MaxNominatorExposedPerValidator: Get<u32>;
+
+	/// The maximum number of targets a nominator can nominate. Further targets are ignored.
+	type MaxNominations: Get<u32>;
+
+	/// The maximum number of chunks a ledger can be unlocking at once, see [`Call::unbond`].
+	type MaxUnlockingChunks: Get<u32>;
+
+	/// The maximum number of invulnerable validators.
+	type MaxInvulnerables: Get<u32>;
+
+	/// The reward points granted to a validator for each session it proved to be online in, see
+	/// the [`OnLiveness`] implementation.
+	type LivenessPoints: Get<RewardPoint>;
+
+	/// The number of consecutive sessions a validator can be flagged unresponsive in before it is
+	/// chilled, see the [`OnUnresponsiveness`] implementation. Zero never chills.
+	type MaxUnresponsiveSessions: Get<SessionIndex>;
+
+	/// The reward points granted to a validator for each finality round it voted in, see the
+	/// [`OnFinalityParticipation`] implementation.
+	type FinalityPoints: Get<RewardPoint>;
+
+	/// The reward points granted to the producer of a (non-uncle) block.
+	type AuthorPoints: Get<RewardPoint>;
+
+	/// The reward points granted to a block producer for each previously unreferenced uncle it
+	/// references.
+	type UncleReferencePoints: Get<RewardPoint>;
+
+	/// The reward points granted to the producer of each referenced uncle block.
+	type UnclePoints: Get<RewardPoint>;
+
+	/// The maximum number of automatic payouts made at the end of a block.
+	type MaxAutoPayoutsPerBlock: Get<u32>;
+
+	/// The maximum number of storage keys of the eras left the history and of the slashing spans
+	/// of removed stashes deleted at the end of a block.
+	type MaxPrunedKeysPerBlock: Get<u32>;
+
+	/// The maximum number of values of a map migrated in a block, for the storage migrations
+	/// spanning several blocks.
+	type MaxMigratedValuesPerBlock: Get<u32>;
+
+	/// The maximum number of delegators of an agent.
+	type MaxDelegators: Get<u32>;
+
+	/// Weight information for the dispatchables of this module.
+	type WeightInfo: WeightInfo;
 }
 
 /// Mode of era-forcing.
@@ -680,6 +1364,42 @@ impl Default for Forcing {
 	fn default() -> Self { Forcing::NotForcing }
 }
 
+/// How the validator set of an era was chosen.
+#[derive(Copy, Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub enum ElectionCompute {
+	/// Elected on-chain from the candidates and their nominators.
+	OnChain,
+	/// Installed by governance with `set_next_validators`, backed by their own stake only.
+	Governance,
+}
+
+/// Why the election of an era failed, the validator set staying as it was.
+#[derive(Copy, Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug)]
+pub enum ElectionFailure {
+	/// Fewer candidates than the minimum validator count are allowed to validate.
+	NotEnoughCandidates,
+	/// Fewer candidates than the minimum validator count are backed by any stake.
+	NotEnoughWinners,
+}
+
+/// Why a stash stopped validating or nominating.
+#[derive(Copy, Clone, PartialEq, Eq, Encode, Decode, RuntimeDebug)]
+pub enum ChillReason {
+	/// Its controller called `chill`.
+	Requested,
+	/// It misbehaved in its current slashing span.
+	Slashed,
+	/// It was flagged unresponsive for too many consecutive sessions.
+	Unresponsive,
+	/// It was unbonded for good, or its staking state repaired.
+	Unstaked,
+	/// It unbonded below the minimum bond of its role.
+	BelowMinimumBond,
+	/// It started nominating instead of validating, or validating instead of nominating.
+	RoleChanged,
+}
+
 // A value placed in storage that represents the current version of the Staking storage.
 // This value is used by the `on_runtime_upgrade` logic to determine whether we run
 // storage migration logic. This should match directly with the semantic versions of the Rust crate.
@@ -687,6 +1407,18 @@ impl Default for Forcing {
 enum Releases {
 	V1_0_0,
 	V2_0_0,
+	V3_0_0,
+	V4_0_0,
+	V5_0_0,
+	V6_0_0,
+	V7_0_0,
+	V8_0_0,
+	V9_0_0,
+	V10_0_0,
+	V11_0_0,
+	V12_0_0,
+	V13_0_0,
+	V14_0_0,
 }
 
 impl Default for Releases {
@@ -695,8 +1427,53 @@ impl Default for Releases {
 	}
 }
 
+/// Iterator over the entries of a map hashed with `twox_64_concat`, whose keys are decoded from
+/// the end of their final storage key.
+///
+/// Entries that can't be decoded are skipped.
+struct ConcatMapIterator<K, V> {
+	prefix: Vec<u8>,
+	previous_key: Vec<u8>,
+	_phantom: sp_std::marker::PhantomData<(K, V)>,
+}
+
+impl<K, V> ConcatMapIterator<K, V> {
+	/// Iterate over the map whose final prefix is `prefix`.
+	///
+	/// For the second key of a double map, `prefix` is the final prefix of the double map followed
+	/// by the hashed first key.
+	fn new(prefix: Vec<u8>) -> Self {
+		ConcatMapIterator {
+			previous_key: prefix.clone(),
+			prefix,
+			_phantom: Default::default(),
+		}
+	}
+}
+
+impl<K: Decode, V: Decode> Iterator for ConcatMapIterator<K, V> {
+	type Item = (K, V);
+
+	fn next(&mut self) -> Option<(K, V)> {
+		loop {
+			let next_key = sp_io::storage::next_key(&self.previous_key)
+				.filter(|key| key.starts_with(&self.prefix))?;
+			self.previous_key = next_key;
+
+			// The `twox_64` hash of the key is 8 bytes long.
+			let key = self.previous_key.get(self.prefix.len() + 8..)
+				.and_then(|mut raw_key| K::decode(&mut raw_key).ok());
+			let value = frame_support::storage::unhashed::get::<V>(&self.previous_key);
+			match (key, value) {
+				(Some(key), Some(value)) => return Some((key, value)),
+				_ => frame_support::print("ERROR: undecodable entry in a staking map, skipped."),
+			}
+		}
+	}
+}
+
 decl_storage! {
-	trait Store for Module<T: Trait> as Staking {
+	trait Store for Module<T: Trait<I>, I: Instance = DefaultInstance> as Staking {
 		/// Number of era to keep in history.
 		///
 		/// Information is kept for eras in `[current_era - history_depth; current_era]
@@ -704,7 +1481,13 @@ decl_storage! {
 		/// Must be more than the number of era delayed by session otherwise.
 		/// i.e. active era must always be in history.
 		/// i.e. `active_era > current_era - history_depth` must be guaranteed.
-		HistoryDepth get(fn history_depth) config(): u32 = 84;
+		HistoryDepth get(fn history_depth) config(): u32 = DEFAULT_HISTORY_DEPTH;
+
+		/// The history depth set by governance and not reached yet, with the era from whose start
+		/// it applies.
+		///
+		/// A deeper history grows by one era at each new era, a shallower one is applied at once.
+		PendingHistoryDepth get(fn pending_history_depth): Option<(u32, EraIndex)>;
 
 		/// The ideal number of staking participants.
 		pub ValidatorCount get(fn validator_count) config(): u32;
@@ -716,26 +1499,84 @@ decl_storage! {
 		/// Any validators that may never be slashed or forcibly kicked. It's a Vec since they're
 		/// easy to initialize and the performance hit is minimal (we expect no more than four
 		/// invulnerables) and restricted to testnets.
-		pub Invulnerables get(fn invulnerables) config(): Vec<T::AccountId>;
+		///
+		/// The Vec is kept sorted, without duplicates, and holds at most `T::MaxInvulnerables`
+		/// accounts.
+		pub Invulnerables get(fn invulnerables) config() build(|config: &GenesisConfig<T, I>| {
+			let mut invulnerables = config.invulnerables.clone();
+			invulnerables.sort();
+			invulnerables.dedup();
+			invulnerables
+		}): Vec<T::AccountId>;
+
+		/// Whether an account is in `Invulnerables`, to check it without reading the Vec.
+		IsInvulnerable build(|config: &GenesisConfig<T, I>| {
+			config.invulnerables.iter().map(|stash| (stash.clone(), true)).collect::<Vec<_>>()
+		}): map hasher(twox_64_concat) T::AccountId => bool;
 
 		/// Map from all locked "stash" accounts to the controller account.
-		pub Bonded get(fn bonded): map hasher(blake2_256) T::AccountId => Option<T::AccountId>;
+		pub Bonded get(fn bonded): map hasher(blake2_128_concat) T::AccountId => Option<T::AccountId>;
 
 		/// Map from all (unlocked) "controller" accounts to the info regarding the staking.
 		pub Ledger get(fn ledger):
-			map hasher(blake2_256) T::AccountId
-			=> Option<StakingLedger<T::AccountId, BalanceOf<T>>>;
+			map hasher(blake2_128_concat) T::AccountId
+			=> Option<StakingLedger<T::AccountId, BalanceOf<T, I>>>;
+
+		/// The active balance of the ledger of each bonded stash, as in `Ledger`, so that it is
+		/// read without decoding the whole ledger.
+		pub ActiveBonded get(fn active_bonded):
+			map hasher(blake2_128_concat) T::AccountId => BalanceOf<T, I>;
+
+		/// The stashes bonded virtually, as agents: their ledger is backed by the funds delegated
+		/// to them rather than by a lock on their own balance.
+		pub VirtualStakers get(fn is_virtual_staker): map hasher(twox_64_concat) T::AccountId => bool;
+
+		/// The virtual stakers bonded with `virtual_bond`, whose funds are held by `T::StakeKeeper`
+		/// rather than delegated to them.
+		pub KeptStakers get(fn is_kept_staker): map hasher(twox_64_concat) T::AccountId => bool;
+
+		/// The delegators of each agent, with the funds each delegated. The funds are reserved in
+		/// the account of the delegator until taken back.
+		pub Delegators get(fn delegators):
+			map hasher(twox_64_concat) T::AccountId => Vec<(T::AccountId, BalanceOf<T, I>)>;
 
 		/// Where the reward payment should be made. Keyed by stash.
-		pub Payee get(fn payee): map hasher(blake2_256) T::AccountId => RewardDestination;
+		pub Payee get(fn payee): map hasher(blake2_128_concat) T::AccountId => RewardDestination;
+
+		/// The stashes whose rewards are paid out automatically, one era at a time, with the weight
+		/// left at the end of blocks.
+		///
+		/// The Vec is kept sorted, without duplicates, and holds at most `MAX_AUTO_PAYOUT_STASHES`
+		/// accounts.
+		pub AutoPayoutStashes get(fn auto_payout_stashes): Vec<T::AccountId>;
+
+		/// Whether a stash is in `AutoPayoutStashes`, to check it without reading the Vec.
+		HasAutoPayout: map hasher(twox_64_concat) T::AccountId => bool;
+
+		/// The index in `AutoPayoutStashes` of the next stash to be paid out automatically.
+		AutoPayoutCursor: u32;
 
 		/// The map from (wannabe) validator stash key to the preferences of that validator.
 		pub Validators get(fn validators):
-			linked_map hasher(blake2_256) T::AccountId => ValidatorPrefs;
+			map hasher(twox_64_concat) T::AccountId
+			=> ValidatorPrefs<T::AccountId, BalanceOf<T, I>>;
 
 		/// The map from nominator stash key to the set of stash keys of all validators to nominate.
 		pub Nominators get(fn nominators):
-			linked_map hasher(blake2_256) T::AccountId => Option<Nominations<T::AccountId>>;
+			map hasher(twox_64_concat) T::AccountId => Option<Nominations<T::AccountId>>;
+
+		/// The map from validator stash key to the stash keys of the nominators targeting it.
+		///
+		/// This is the reverse of the targets in `Nominators`, with each Vec kept sorted and
+		/// without duplicates.
+		pub NominatorsFor get(fn nominators_for):
+			map hasher(blake2_128_concat) T::AccountId => Vec<T::AccountId>;
+
+		/// The number of entries in `Validators`.
+		pub CounterForValidators get(fn counter_for_validators): u32;
+
+		/// The number of entries in `Nominators`.
+		pub CounterForNominators get(fn counter_for_nominators): u32;
 
 		/// The current era index.
 		///
@@ -747,23 +1588,25 @@ decl_storage! {
 		///
 		/// The active era is the era currently rewarded.
 		/// Validator set of this era must be equal to `SessionInterface::validators`.
-		pub ActiveEra get(fn active_era): Option<ActiveEraInfo<MomentOf<T>>>;
+		pub ActiveEra get(fn active_era): Option<ActiveEraInfo<MomentOf<T, I>>>;
 
 		/// The session index at which the era start for the last `HISTORY_DEPTH` eras
 		pub ErasStartSessionIndex get(fn eras_start_session_index):
-			map hasher(blake2_256) EraIndex => Option<SessionIndex>;
+			map hasher(twox_64_concat) EraIndex => Option<SessionIndex>;
 
-		/// Exposure of validator at era.
+		/// DEPRECATED: exposure of validator at era, for the eras elected before exposures were
+		/// paged in `ErasStakersOverview` and `ErasStakersPaged`.
 		///
 		/// This is keyed first by the era index to allow bulk deletion and then the stash account.
 		///
 		/// Is it removed after `HISTORY_DEPTH` eras.
 		/// If stakers hasn't been set or has been removed then empty exposure is returned.
-		pub ErasStakers get(fn eras_stakers):
+		pub ErasStakers:
 			double_map hasher(twox_64_concat) EraIndex, hasher(twox_64_concat) T::AccountId
-			=> Exposure<T::AccountId, BalanceOf<T>>;
+			=> Exposure<T::AccountId, BalanceOf<T, I>>;
 
-		/// Clipped Exposure of validator at era.
+		/// DEPRECATED: clipped exposure of validator at era, for the eras elected before exposures
+		/// were paged in `ErasStakersOverview` and `ErasStakersPaged`.
 		///
 		/// This is similar to [`ErasStakers`] but number of nominators exposed is reduce to the
 		/// `T::MaxNominatorRewardedPerValidator` biggest stakers.
@@ -773,9 +1616,53 @@ decl_storage! {
 		///
 		/// Is it removed after `HISTORY_DEPTH` eras.
 		/// If stakers hasn't been set or has been removed then empty exposure is returned.
-		pub ErasStakersClipped get(fn eras_stakers_clipped):
+		pub ErasStakersClipped:
+			double_map hasher(twox_64_concat) EraIndex, hasher(twox_64_concat) T::AccountId
+			=> Exposure<T::AccountId, BalanceOf<T, I>>;
+
+		/// The totals of the exposure of validator at era, without the nominators exposed.
+		///
+		/// This is keyed first by the era index to allow bulk deletion and then the stash account.
+		///
+		/// Is it removed after `HISTORY_DEPTH` eras.
+		pub ErasStakersOverview:
 			double_map hasher(twox_64_concat) EraIndex, hasher(twox_64_concat) T::AccountId
-			=> Exposure<T::AccountId, BalanceOf<T>>;
+			=> Option<ExposureOverview<BalanceOf<T, I>>>;
+
+		/// DEPRECATED: the nominators exposed to validator at era, for the eras elected before
+		/// nominators were indexed in `ErasStakersCompact`.
+		///
+		/// This is keyed first by the era index to allow bulk deletion and then the stash account
+		/// and the page index.
+		///
+		/// Is it removed after `HISTORY_DEPTH` eras.
+		pub ErasStakersPaged:
+			double_map hasher(twox_64_concat) EraIndex, hasher(twox_64_concat) (T::AccountId, u32)
+			=> Option<ExposurePage<T::AccountId, BalanceOf<T, I>>>;
+
+		/// The nominators exposed to validator at era, in pages of `EXPOSURE_PAGE_SIZE` sorted by
+		/// decreasing stake, so that the rewarded nominators come first. The nominators are
+		/// referenced by their index in `ErasStakersAccounts`.
+		///
+		/// This is keyed first by the era index to allow bulk deletion and then the stash account
+		/// and the page index.
+		///
+		/// Is it removed after `HISTORY_DEPTH` eras.
+		pub ErasStakersCompact:
+			double_map hasher(twox_64_concat) EraIndex, hasher(twox_64_concat) (T::AccountId, u32)
+			=> Option<CompactExposurePage<BalanceOf<T, I>>>;
+
+		/// The nominators exposed in era, each stored once however many validators it is exposed
+		/// to. The nominator of index `i` is the `i % EXPOSURE_PAGE_SIZE`th account of the chunk
+		/// `i / EXPOSURE_PAGE_SIZE`; only the last chunk holds less than `EXPOSURE_PAGE_SIZE`
+		/// accounts.
+		///
+		/// This is keyed first by the era index to allow bulk deletion and then the chunk index.
+		///
+		/// Is it removed after `HISTORY_DEPTH` eras.
+		pub ErasStakersAccounts:
+			double_map hasher(twox_64_concat) EraIndex, hasher(twox_64_concat) u32
+			=> Vec<T::AccountId>;
 
 		/// Similarly to `ErasStakers` this holds the preferences of validators.
 		///
@@ -785,23 +1672,71 @@ decl_storage! {
 		// If prefs hasn't been set or has been removed then 0 commission is returned.
 		pub ErasValidatorPrefs get(fn eras_validator_prefs):
 			double_map hasher(twox_64_concat) EraIndex, hasher(twox_64_concat) T::AccountId
-			=> ValidatorPrefs;
+			=> ValidatorPrefs<T::AccountId, BalanceOf<T, I>>;
 
 		/// The total validator era payout for the last `HISTORY_DEPTH` eras.
 		///
 		/// Eras that haven't finished yet or has been removed doesn't have reward.
 		pub ErasValidatorReward get(fn eras_validator_reward):
-			map hasher(blake2_256) EraIndex => Option<BalanceOf<T>>;
+			map hasher(twox_64_concat) EraIndex => Option<BalanceOf<T, I>>;
+
+		/// The amount minted for the rest of the network (see `T::RewardRemainder`) at the end of
+		/// each of the last `HISTORY_DEPTH` eras.
+		pub ErasRemainderReward get(fn eras_remainder_reward):
+			map hasher(twox_64_concat) EraIndex => Option<BalanceOf<T, I>>;
+
+		/// A summary of the payouts made so far for each of the last `HISTORY_DEPTH` eras.
+		///
+		/// Eras that haven't finished yet or has been removed have an empty summary.
+		pub ErasPayoutSummary get(fn eras_payout_summary):
+			map hasher(twox_64_concat) EraIndex => EraPayoutSummary<BalanceOf<T, I>>;
 
 		/// Rewards for the last `HISTORY_DEPTH` eras.
 		/// If reward hasn't been set or has been removed then 0 reward is returned.
 		pub ErasRewardPoints get(fn eras_reward_points):
-			map hasher(blake2_256) EraIndex => EraRewardPoints<T::AccountId>;
+			map hasher(twox_64_concat) EraIndex => EraRewardPoints<T::AccountId>;
 
 		/// The total amount staked for the last `HISTORY_DEPTH` eras.
 		/// If total hasn't been set or has been removed then 0 stake is returned.
 		pub ErasTotalStake get(fn eras_total_stake):
-			map hasher(blake2_256) EraIndex => BalanceOf<T>;
+			map hasher(twox_64_concat) EraIndex => BalanceOf<T, I>;
+
+		/// The total issuance of `T::Currency` at the start of each of the last `HISTORY_DEPTH`
+		/// eras, from which their payout is computed.
+		pub ErasTotalIssuance get(fn eras_total_issuance):
+			map hasher(twox_64_concat) EraIndex => Option<BalanceOf<T, I>>;
+
+		/// The time at which each of the last `HISTORY_DEPTH` eras started, set in the first block
+		/// of the era.
+		pub ErasStart get(fn eras_start):
+			map hasher(twox_64_concat) EraIndex => Option<MomentOf<T, I>>;
+
+		/// The duration of each of the last `HISTORY_DEPTH` eras, in milliseconds.
+		///
+		/// Eras that haven't finished yet or has been removed doesn't have a duration.
+		pub ErasDuration get(fn eras_duration):
+			map hasher(twox_64_concat) EraIndex => Option<u64>;
+
+		/// The value slashed from each stash for offences committed in the given era.
+		///
+		/// This is keyed first by the era index to allow bulk deletion and then the stash account.
+		///
+		/// Is it removed after `HISTORY_DEPTH` eras.
+		/// If no slash has been applied or it has been removed then 0 is returned.
+		pub ErasSlashes get(fn eras_slashes):
+			double_map hasher(twox_64_concat) EraIndex, hasher(twox_64_concat) T::AccountId
+			=> BalanceOf<T, I>;
+
+		/// The total value slashed while the given era was active, for the last `HISTORY_DEPTH`
+		/// eras.
+		///
+		/// Deferred slashes count towards the era in which they are applied.
+		pub ErasTotalSlashed get(fn eras_total_slashed):
+			map hasher(twox_64_concat) EraIndex => BalanceOf<T, I>;
+
+		/// The eras left the history whose exposures, validator preferences and slashes are still
+		/// being removed, oldest first.
+		pub ErasToPrune get(fn eras_to_prune): Vec<EraIndex>;
 
 		/// True if the next session change will be a new era regardless of index.
 		pub ForceEra get(fn force_era) config(): Forcing;
@@ -811,30 +1746,78 @@ decl_storage! {
 		/// The rest of the slashed value is handled by the `Slash`.
 		pub SlashRewardFraction get(fn slash_reward_fraction) config(): Perbill;
 
+		/// The portion of the reporters' share that is lost for each era that passes between an
+		/// offence and its report.
+		///
+		/// A report made in the era of the offence receives the full `SlashRewardFraction`.
+		pub SlashRewardDecay get(fn slash_reward_decay) config(): Perbill;
+
+		/// The portion of the stakers' era payout which is diverted to `T::RewardRemainder`.
+		pub ExtraRemainderShare get(fn extra_remainder_share) config(): Perbill;
+
+		/// The highest yearly return stakers are paid on the total stake, if any.
+		///
+		/// Any era payout above it is diverted to `T::RewardRemainder`.
+		pub MaxPayoutApr get(fn max_payout_apr): Option<Perbill>;
+
+		/// The total stake under which the stakers' era payout is reduced, in proportion of the
+		/// stake missing to reach it.
+		pub PayoutStakeFloor get(fn payout_stake_floor) config(): BalanceOf<T, I>;
+
+		/// The reward curve used to compute the era payout instead of `T::EraPayout`, if any.
+		pub RewardCurveOverride get(fn reward_curve_override): Option<RewardCurvePoints>;
+
+		/// Bounds applied to the slash fraction reported for each kind of offence.
+		///
+		/// Offences of a kind without an entry are slashed as reported.
+		pub SlashFractionBoundsOf get(fn slash_fraction_bounds):
+			map hasher(blake2_256) Kind => Option<SlashFractionBounds>;
+
+		/// The parameters set by governance, see `set_parameters`.
+		pub Parameters get(fn parameters): StakingParameters<BalanceOf<T, I>>;
+
+		/// The validator set installed by governance for the next era planned, in place of the
+		/// election, see `set_next_validators`.
+		pub NextValidators get(fn next_validators): Option<Vec<T::AccountId>>;
+
+		/// The validators flagged unresponsive in the last session they were reported in, with
+		/// that session and the number of consecutive sessions up to it they were flagged in.
+		pub UnresponsiveSessions get(fn unresponsive_sessions):
+			map hasher(twox_64_concat) T::AccountId => Option<(SessionIndex, SessionIndex)>;
+
 		/// The amount of currency given to reporters of a slash event which was
 		/// canceled by extraordinary circumstances (e.g. governance).
-		pub CanceledSlashPayout get(fn canceled_payout) config(): BalanceOf<T>;
+		pub CanceledSlashPayout get(fn canceled_payout) config(): BalanceOf<T, I>;
+
+		/// All unapplied slashes that are queued for later, keyed by the era they were reported in.
+		pub UnappliedSlashes get(fn unapplied_slashes):
+			map hasher(twox_64_concat) EraIndex => Vec<UnappliedSlash<T::AccountId, BalanceOf<T, I>>>;
+
+		/// The number of slashes in `UnappliedSlashes` that would hit each stash, either as the
+		/// offending validator or as one of its nominators.
+		PendingSlashCount get(fn pending_slash_count):
+			map hasher(twox_64_concat) T::AccountId => u32;
 
-		/// All unapplied slashes that are queued for later.
-		pub UnappliedSlashes:
-			map hasher(blake2_256) EraIndex => Vec<UnappliedSlash<T::AccountId, BalanceOf<T>>>;
+		/// The id given to the next deferred slash.
+		NextSlashId get(fn next_slash_id): SlashId;
 
 		/// A mapping from still-bonded eras to the first session index of that era.
 		///
 		/// Must contains information for eras for the range:
-		/// `[active_era - bounding_duration; active_era]`
+		/// `[active_era - bounding_duration; active_era]`, and for the older eras whose historical
+		/// sessions are kept, see `HistoricalErasKept`.
 		BondedEras: Vec<(EraIndex, SessionIndex)>;
 
 		/// All slashing events on validators, mapped by era to the highest slash proportion
 		/// and slash value of the era.
 		ValidatorSlashInEra:
-			double_map hasher(blake2_256) EraIndex, hasher(twox_128) T::AccountId
-			=> Option<(Perbill, BalanceOf<T>)>;
+			double_map hasher(twox_64_concat) EraIndex, hasher(twox_128) T::AccountId
+			=> Option<(Perbill, BalanceOf<T, I>)>;
 
 		/// All slashing events on nominators, mapped by era to the highest slash value of the era.
 		NominatorSlashInEra:
-			double_map hasher(blake2_256) EraIndex, hasher(twox_128) T::AccountId
-			=> Option<BalanceOf<T>>;
+			double_map hasher(twox_64_concat) EraIndex, hasher(twox_128) T::AccountId
+			=> Option<BalanceOf<T, I>>;
 
 		/// Slashing spans for stash accounts.
 		SlashingSpans: map hasher(blake2_256) T::AccountId => Option<slashing::SlashingSpans>;
@@ -843,65 +1826,185 @@ decl_storage! {
 		/// as well as how much reward has been paid out.
 		SpanSlash:
 			map hasher(blake2_256) (T::AccountId, slashing::SpanIndex)
-			=> slashing::SpanRecord<BalanceOf<T>>;
+			=> slashing::SpanRecord<BalanceOf<T, I>>;
+
+		/// The slashing span records of removed stashes left to delete, as the range of span
+		/// indices `start..end` of the records of each stash. The stashes are cleared one after the
+		/// other from the first key of the map, which is removed once cleared.
+		SpanRecordsToClear:
+			map hasher(blake2_128_concat) T::AccountId
+			=> Option<(slashing::SpanIndex, slashing::SpanIndex)>;
 
 		/// The earliest era for which we have a pending, unapplied slash.
 		EarliestUnappliedSlash: Option<EraIndex>;
 
 		/// Storage version of the pallet.
 		///
-		/// This is set to v2.0.0 for new networks.
-		StorageVersion build(|_: &GenesisConfig<T>| Releases::V2_0_0): Releases;
+		/// This is set to v14.0.0 for new networks.
+		StorageVersion build(|_: &GenesisConfig<T, I>| Releases::V14_0_0): Releases;
+
+		/// The raw key of the last value migrated by the ongoing step of the storage migration,
+		/// if it spans several blocks.
+		MigrationCursor: Option<Vec<u8>>;
+
+		/// The offences reported while the storage is migrated, in the order they were reported.
+		/// They are slashed once it is, as neither the ledgers nor the slashes recorded in the
+		/// past eras may be found before.
+		OffencesQueuedWhileMigrating:
+			Vec<QueuedOffence<T::AccountId, BalanceOf<T, I>>>;
 	}
 	add_extra_genesis {
 		config(stakers):
-			Vec<(T::AccountId, T::AccountId, BalanceOf<T>, StakerStatus<T::AccountId>)>;
-		build(|config: &GenesisConfig<T>| {
-			for &(ref stash, ref controller, balance, ref status) in &config.stakers {
-				assert!(
-					T::Currency::free_balance(&stash) >= balance,
+			Vec<(T::AccountId, T::AccountId, BalanceOf<T, I>, StakerStatus<T::AccountId>)>;
+		// The numbers of validators and nominators to generate on top of `stakers`, for local
+		// testnets and benchmarks. The generated validators are candidates bonding less than the
+		// validators of `stakers`, which the generated nominators nominate, so that only the latter
+		// are elected.
+		config(dev_stakers): Option<(u32, u32)>;
+		build(|config: &GenesisConfig<T, I>| {
+			let validators = config.stakers.iter()
+				.filter_map(|(stash, _, _, status)| match status {
+					StakerStatus::Validator => Some(stash),
+					_ => None,
+				})
+				.collect::<BTreeSet<_>>();
+			assert!(
+				config.validator_count >= config.minimum_validator_count,
+				"Validator count is below the minimum validator count."
+			);
+			assert!(
+				<Module<T, I>>::invulnerables().len() <= T::MaxInvulnerables::get() as usize,
+				"Too many invulnerables, see `MaxInvulnerables`."
+			);
+			assert!(
+				config.invulnerables.iter().all(|stash| validators.contains(&stash)),
+				"Invulnerable is not a validator at genesis."
+			);
+
+			let mut controllers = BTreeSet::new();
+			for &(ref stash, ref controller, balance, ref status) in &config.stakers {
+				assert!(
+					T::Currency::free_balance(&stash) >= balance,
 					"Stash does not have enough balance to bond."
 				);
-				let _ = <Module<T>>::bond(
+				assert!(
+					balance >= <Module<T, I>>::minimum_active_bond(),
+					"Stash cannot bond less than the minimum active bond."
+				);
+				assert!(controllers.insert(controller), "Controller is used by several stakers.");
+				<Module<T, I>>::bond(
 					T::Origin::from(Some(stash.clone()).into()),
 					T::Lookup::unlookup(controller.clone()),
 					balance,
 					RewardDestination::Staked,
-				);
-				let _ = match status {
+				).expect("Stash could not be bonded.");
+				match status {
+					// the session keys of the validators at genesis are only set after, by the
+					// genesis of the session module.
 					StakerStatus::Validator => {
-						<Module<T>>::validate(
-							T::Origin::from(Some(controller.clone()).into()),
-							Default::default(),
-						)
+						<Module<T, I>>::do_validate(stash, balance, Default::default())
+							.expect("Stash could not validate.");
 					},
 					StakerStatus::Nominator(votes) => {
-						<Module<T>>::nominate(
+						assert!(
+							votes.iter().all(|target| validators.contains(&target)),
+							"Stash nominates an account which is not a validator at genesis."
+						);
+						<Module<T, I>>::nominate(
 							T::Origin::from(Some(controller.clone()).into()),
 							votes.iter().map(|l| T::Lookup::unlookup(l.clone())).collect(),
-						)
-					}, _ => Ok(())
-				};
+						).expect("Stash could not nominate.");
+					},
+					StakerStatus::Idle => {},
+				}
+			}
+
+			if let Some((generated_validators, nominators)) = config.dev_stakers {
+				let validator_count = config.validator_count as usize;
+				assert!(
+					generated_validators == 0 || validator_count <= validators.len(),
+					"Generated validators would be elected, the validator count is above the \
+					number of genesis validators."
+				);
+				let validators = validators.into_iter().cloned().collect::<Vec<_>>();
+				<Module<T, I>>::generate_dev_stakers(generated_validators, nominators, &validators);
 			}
 		});
 	}
 }
 
 decl_event!(
-	pub enum Event<T> where Balance = BalanceOf<T>, <T as frame_system::Trait>::AccountId {
+	pub enum Event<T, I: Instance = DefaultInstance> where
+		Balance = BalanceOf<T, I>,
+		<T as frame_system::Trait>::AccountId,
+	{
 		/// The staker has been rewarded by this amount. AccountId is controller account.
+		///
+		/// Deprecated in favour of `Rewarded`, which is deposited alongside it. It will be removed
+		/// in the next release.
 		Reward(AccountId, Balance),
+		/// The staker has been rewarded by this amount for the given era, paid to the given
+		/// destination. AccountId is the stash account.
+		Rewarded(AccountId, EraIndex, RewardDestination, Balance),
+		/// A validator commission has been paid to this account, its commission payee.
+		CommissionReward(AccountId, Balance),
+		/// A payout of the era through the validator (stash) is starting: the payout of the
+		/// validator itself if no page is given, else of a nominator in the given page of its
+		/// exposure. The `CommissionReward` and `Rewarded` events of the payout follow.
+		PayoutStarted(EraIndex, AccountId, Option<u32>),
+		/// The payouts of the validator (stash) for the eras between the first and the second
+		/// index (inclusive) have been made, the number given having been paid out.
+		ErasPaidOut(AccountId, EraIndex, EraIndex, u32),
+		/// The era was skipped by the payouts of the validator (stash) for several eras, as it
+		/// could not be paid out for the given reason.
+		PayoutSkipped(AccountId, EraIndex, DispatchError),
+		/// The payout of the stash for the era has been made automatically.
+		AutoPaidOut(AccountId, EraIndex),
+		/// The era was removed from history with this part of its payout unclaimed, which was
+		/// handed to `T::UnclaimedRewards`.
+		UnclaimedRewardsSwept(EraIndex, Balance),
 		/// One validator (and its nominators) has been slashed by the given amount.
 		Slash(AccountId, Balance),
+		/// An offence of the validator (stash) has been reported, to be slashed by the given
+		/// fraction of its exposure in the given era, unless the slash is cancelled.
+		SlashReported(AccountId, Perbill, EraIndex),
 		/// An old slashing report from a prior era was discarded because it could
 		/// not be processed.
 		OldSlashingReportDiscarded(SessionIndex),
+		/// An account was added to the invulnerables.
+		InvulnerableAdded(AccountId),
+		/// An account was removed from the invulnerables.
+		InvulnerableRemoved(AccountId),
+		/// The era has ended with this total value slashed from stakers during it.
+		EraSlashed(EraIndex, Balance),
+		/// The era payout has been set; the first balance is the stakers' payout, the second the
+		/// remainder minted for the rest of the network.
+		EraPaid(EraIndex, Balance, Balance),
+		/// The total stake of the era is below `PayoutStakeFloor`: the stakers' payout has been
+		/// reduced by the second balance, the first being the total stake.
+		PayoutThrottled(EraIndex, Balance, Balance),
+		/// The staking state of the stash has been repaired by governance.
+		LedgerRepaired(AccountId),
+		/// The validator (stash) was flagged unresponsive for the given number of consecutive
+		/// sessions.
+		Unresponsive(AccountId, SessionIndex),
+		/// The validator set of the era has been chosen, as given.
+		StakingElection(EraIndex, ElectionCompute),
+		/// The election of the era failed for the given reason: the validator set stays as it
+		/// was.
+		StakingElectionFailed(EraIndex, ElectionFailure),
+		/// The mode of era-forcing has changed to the given one.
+		ForceEra(Forcing),
+		/// The stash stopped validating or nominating, for the given reason.
+		Chilled(AccountId, ChillReason),
+		/// The validator (stash) has set its preferences, as given.
+		ValidatorPrefsSet(AccountId, ValidatorPrefs<AccountId, Balance>),
 	}
 );
 
 decl_error! {
 	/// Error for the staking module.
-	pub enum Error for Module<T: Trait> {
+	pub enum Error for Module<T: Trait<I>, I: Instance> {
 		/// Not a controller account.
 		NotController,
 		/// Not a stash account.
@@ -914,9 +2017,9 @@ decl_error! {
 		EmptyTargets,
 		/// Duplicate index.
 		DuplicateIndex,
-		/// Slash record index out of bounds.
+		/// No pending slash record with the given id.
 		InvalidSlashIndex,
-		/// Can not bond with value less than minimum balance.
+		/// Can not bond with value less than the minimum active bond.
 		InsufficientValue,
 		/// Can not schedule more unlock chunks.
 		NoMoreChunks,
@@ -928,33 +2031,189 @@ decl_error! {
 		InvalidEraToReward,
 		/// Invalid number of nominations.
 		InvalidNumberOfNominations,
+		/// Too many invulnerables, see `T::MaxInvulnerables`.
+		TooManyInvulnerables,
+		/// Account is already invulnerable.
+		AlreadyInvulnerable,
+		/// Account is not invulnerable.
+		NotInvulnerable,
+		/// The stash has a deferred slash that is not applied yet.
+		PendingSlash,
+		/// The lower slash fraction bound is above the upper one.
+		InvalidSlashFractionBounds,
+		/// Too many stashes have automatic payouts enabled.
+		TooManyAutoPayouts,
+		/// The reward curve is empty, too long, unordered or above its maximum.
+		InvalidRewardCurve,
+		/// The slash defer duration of the parameters is not less than the bonding duration, or
+		/// their minimum active bond is below the existential deposit.
+		InvalidParameters,
+		/// The active bond is below the minimum of the parameters for the role.
+		InsufficientBond,
+		/// The commission is below the minimum of the parameters.
+		CommissionTooLow,
+		/// The session of the offence is not in an era that can still be slashed.
+		OffenceNotSlashable,
+		/// The offender was not elected in the era of the offence.
+		OffenderNotElected,
+		/// The staking state of the stash is consistent, there is nothing to repair.
+		LedgerNotCorrupted,
+		/// The number of slashing spans given is below the number cleared with the stash.
+		IncorrectSlashingSpans,
+		/// The storage of this module is being migrated over several blocks.
+		MigrationInProgress,
+		/// The account is not an agent bonded for its delegators.
+		NotAgent,
+		/// Not available to an agent, whose ledger is backed by the funds delegated to it.
+		IsAgent,
+		/// The agent has too many delegators, see `T::MaxDelegators`.
+		TooManyDelegators,
+		/// The account has not delegated to this agent.
+		NotDelegator,
+		/// The stash is not allowed to validate by `T::CandidateFilter`.
+		CandidateNotAllowed,
+		/// The stash has not set session keys, see `validate_with_keys`.
+		NoSessionKeys,
+		/// The validator set is empty or has duplicates.
+		InvalidValidatorSet,
+		/// The funds held by `T::StakeKeeper` for the stash do not cover its bond.
+		InsufficientBacking,
+		/// The stash is not allowed to stake by `T::BondingRestriction`.
+		StakingNotAllowed,
+		/// The stash was not an elected validator in the era.
+		NotElected,
+		/// The weight limit given does not allow to pay out a single era.
+		PayoutWeightTooLow,
+		/// The slashing span records of the stash, removed before, are still being cleared.
+		SpanRecordsNotCleared,
 	}
 }
 
 decl_module! {
-	pub struct Module<T: Trait> for enum Call where origin: T::Origin {
+	pub struct Module<T: Trait<I>, I: Instance = DefaultInstance> for enum Call where origin: T::Origin {
 		/// Number of sessions per era.
 		const SessionsPerEra: SessionIndex = T::SessionsPerEra::get();
 
+		/// The minimum time an era lasts, on top of the number of sessions per era.
+		const MinimumEraDuration: MomentOf<T, I> = T::MinimumEraDuration::get();
+
 		/// Number of eras that staked funds must remain bonded for.
 		const BondingDuration: EraIndex = T::BondingDuration::get();
 
-		type Error = Error<T>;
+		/// Number of eras that slashes are deferred by, after computation.
+		const SlashDeferDuration: EraIndex = T::SlashDeferDuration::get();
+
+		/// Number of eras whose historical sessions are kept.
+		const HistoricalErasKept: EraIndex = T::HistoricalErasKept::get();
+
+		/// Number of eras a shallower history depth is delayed by.
+		const HistoryShrinkDelay: EraIndex = T::HistoryShrinkDelay::get();
+
+		/// The maximum number of nominators rewarded for each validator.
+		const MaxNominatorRewardedPerValidator: u32 = T::MaxNominatorRewardedPerValidator::get();
+
+		/// The maximum number of nominators exposed to each validator.
+		const MaxNominatorExposedPerValidator: u32 = T::MaxNominatorExposedPerValidator::get();
+
+		/// The maximum number of targets a nominator can nominate.
+		const MaxNominations: u32 = T::MaxNominations::get();
+
+		/// The maximum number of chunks a ledger can be unlocking at once.
+		const MaxUnlockingChunks: u32 = T::MaxUnlockingChunks::get();
+
+		/// The maximum number of stashes with automatic payouts enabled.
+		const MaxAutoPayoutStashes: u32 = MAX_AUTO_PAYOUT_STASHES as u32;
+
+		/// The maximum number of points of a reward curve set by governance.
+		const MaxRewardCurvePoints: u32 = MAX_REWARD_CURVE_POINTS as u32;
+
+		/// The maximum number of slashing spans whose records are removed with a stash.
+		const MaxClearedSlashingSpans: u32 = MAX_CLEARED_SLASHING_SPANS;
+
+		/// The number of nominators in each page of an exposure.
+		const ExposurePageSize: u32 = EXPOSURE_PAGE_SIZE as u32;
+
+		/// The history depth used until one is set in genesis or by governance.
+		const DefaultHistoryDepth: u32 = DEFAULT_HISTORY_DEPTH;
+
+		/// The minimum validator count used until one is set in genesis or by governance.
+		const DefaultMinimumValidatorCount: u32 = DEFAULT_MINIMUM_VALIDATOR_COUNT;
+
+		/// The maximum number of invulnerable validators.
+		const MaxInvulnerables: u32 = T::MaxInvulnerables::get();
+
+		/// The reward points granted to a validator for each session it proved to be online in.
+		const LivenessPoints: RewardPoint = T::LivenessPoints::get();
+
+		/// The number of consecutive sessions a validator can be flagged unresponsive in before
+		/// it is chilled, unless overridden by the parameters.
+		const MaxUnresponsiveSessions: SessionIndex = T::MaxUnresponsiveSessions::get();
+
+		/// The reward points granted to a validator for each finality round it voted in.
+		const FinalityPoints: RewardPoint = T::FinalityPoints::get();
+
+		/// The reward points granted to the producer of a (non-uncle) block.
+		const AuthorPoints: RewardPoint = T::AuthorPoints::get();
+
+		/// The reward points granted to a block producer for each uncle it references.
+		const UncleReferencePoints: RewardPoint = T::UncleReferencePoints::get();
+
+		/// The reward points granted to the producer of each referenced uncle block.
+		const UnclePoints: RewardPoint = T::UnclePoints::get();
+
+		/// The maximum number of automatic payouts made at the end of a block.
+		const MaxAutoPayoutsPerBlock: u32 = T::MaxAutoPayoutsPerBlock::get();
+
+		/// The maximum number of storage keys of the eras left the history and of the slashing
+		/// spans of removed stashes deleted at the end of a block.
+		const MaxPrunedKeysPerBlock: u32 = T::MaxPrunedKeysPerBlock::get();
+
+		/// The maximum number of values of a map migrated in a block, for the storage migrations
+		/// spanning several blocks.
+		const MaxMigratedValuesPerBlock: u32 = T::MaxMigratedValuesPerBlock::get();
+
+		/// The maximum number of delegators of an agent.
+		const MaxDelegators: u32 = T::MaxDelegators::get();
+
+		type Error = Error<T, I>;
 
 		fn deposit_event() = default;
 
 		fn on_runtime_upgrade() {
-			migration::on_runtime_upgrade::<T>();
+			migration::on_runtime_upgrade::<T, I>();
 		}
 
+		#[weight = SimpleDispatchInfo::FixedNormal(Module::<T, I>::finalize_weight())]
 		fn on_finalize() {
 			// Set the start of the first era.
 			if let Some(mut active_era) = Self::active_era() {
 				if active_era.start.is_none() {
-					active_era.start = Some(T::Time::now());
-					<ActiveEra<T>>::put(active_era);
+					let now = T::Time::now();
+					active_era.start = Some(now);
+					<ErasStart<T, I>>::insert(active_era.index, now);
+					<ActiveEra<T, I>>::put(active_era);
+				}
+			}
+
+			let mut weight_left = Self::finalize_weight();
+
+			// Keep migrating the storage, if needed, before touching any ledger.
+			if !migration::is_migrated::<I>() {
+				let values = (weight_left / MIGRATED_VALUE_WEIGHT)
+					.min(T::MaxMigratedValuesPerBlock::get());
+				weight_left -= values * MIGRATED_VALUE_WEIGHT;
+				if !migration::migrate::<T, I>(values) {
+					return
 				}
+				Self::apply_slashes_queued_while_migrating();
 			}
+
+			Self::process_auto_payouts(&mut weight_left);
+
+			let mut keys_left = (weight_left / PRUNING_KEY_WEIGHT)
+				.min(T::MaxPrunedKeysPerBlock::get());
+			EraInfo::<T, I>::prune(&mut keys_left);
+			slashing::clear_deferred_span_records::<T, I>(&mut keys_left);
 		}
 
 		/// Take the origin account as a stash and lock up `value` of its balance. `controller` will
@@ -972,33 +2231,40 @@ decl_module! {
 		/// NOTE: Two of the storage writes (`Self::bonded`, `Self::payee`) are _never_ cleaned unless
 		/// the `origin` falls below _existential deposit_ and gets removed as dust.
 		/// # </weight>
-		#[weight = SimpleDispatchInfo::FixedNormal(500_000)]
+		#[weight = SimpleDispatchInfo::FixedNormal(T::WeightInfo::bond())]
 		fn bond(origin,
 			controller: <T::Lookup as StaticLookup>::Source,
-			#[compact] value: BalanceOf<T>,
+			#[compact] value: BalanceOf<T, I>,
 			payee: RewardDestination
 		) {
 			let stash = ensure_signed(origin)?;
+			Self::ensure_migrated()?;
+			ensure!(T::BondingRestriction::is_allowed(&stash), Error::<T, I>::StakingNotAllowed);
 
-			if <Bonded<T>>::contains_key(&stash) {
-				Err(Error::<T>::AlreadyBonded)?
+			if <Bonded<T, I>>::contains_key(&stash) {
+				Err(Error::<T, I>::AlreadyBonded)?
 			}
+			ensure!(!<Delegators<T, I>>::contains_key(&stash), Error::<T, I>::IsAgent);
+			ensure!(
+				!<SpanRecordsToClear<T, I>>::contains_key(&stash),
+				Error::<T, I>::SpanRecordsNotCleared,
+			);
 
 			let controller = T::Lookup::lookup(controller)?;
 
-			if <Ledger<T>>::contains_key(&controller) {
-				Err(Error::<T>::AlreadyPaired)?
+			if <Ledger<T, I>>::contains_key(&controller) {
+				Err(Error::<T, I>::AlreadyPaired)?
 			}
 
 			// reject a bond which is considered to be _dust_.
-			if value < T::Currency::minimum_balance() {
-				Err(Error::<T>::InsufficientValue)?
+			if value < Self::minimum_active_bond() {
+				Err(Error::<T, I>::InsufficientValue)?
 			}
 
 			// You're auto-bonded forever, here. We might improve this by only bonding when
 			// you actually validate/nominate and remove once you unbond __everything__.
-			<Bonded<T>>::insert(&stash, &controller);
-			<Payee<T>>::insert(&stash, payee);
+			<Bonded<T, I>>::insert(&stash, &controller);
+			<Payee<T, I>>::insert(&stash, payee);
 
 			system::Module::<T>::inc_ref(&stash);
 
@@ -1028,12 +2294,15 @@ decl_module! {
 		/// - O(1).
 		/// - One DB entry.
 		/// # </weight>
-		#[weight = SimpleDispatchInfo::FixedNormal(500_000)]
-		fn bond_extra(origin, #[compact] max_additional: BalanceOf<T>) {
+		#[weight = SimpleDispatchInfo::FixedNormal(T::WeightInfo::bond_extra())]
+		fn bond_extra(origin, #[compact] max_additional: BalanceOf<T, I>) {
 			let stash = ensure_signed(origin)?;
+			Self::ensure_migrated()?;
 
-			let controller = Self::bonded(&stash).ok_or(Error::<T>::NotStash)?;
-			let mut ledger = Self::ledger(&controller).ok_or(Error::<T>::NotController)?;
+			let controller = Self::bonded(&stash).ok_or(Error::<T, I>::NotStash)?;
+			let mut ledger = Self::ledger(&controller).ok_or(Error::<T, I>::NotController)?;
+			ensure!(!Self::is_virtual_staker(&stash), Error::<T, I>::IsAgent);
+			ensure!(T::BondingRestriction::is_allowed(&stash), Error::<T, I>::StakingNotAllowed);
 
 			let stash_balance = T::Currency::free_balance(&stash);
 
@@ -1047,12 +2316,13 @@ decl_module! {
 
 		/// Schedule a portion of the stash to be unlocked ready for transfer out after the bond
 		/// period ends. If this leaves an amount actively bonded less than
-		/// T::Currency::minimum_balance(), then it is increased to the full amount.
+		/// `minimum_active_bond`, then it is increased to the full amount. If it leaves less than
+		/// the minimum bond of the role of the stash, the stash is chilled.
 		///
 		/// Once the unlock period is done, you can call `withdraw_unbonded` to actually move
 		/// the funds out of management ready for transfer.
 		///
-		/// No more than a limited number of unlocking chunks (see `MAX_UNLOCKING_CHUNKS`)
+		/// No more than a limited number of unlocking chunks (see `T::MaxUnlockingChunks`)
 		/// can co-exists at the same time. In that case, [`Call::withdraw_unbonded`] need
 		/// to be called first to remove some of the chunks (if possible).
 		///
@@ -1063,19 +2333,20 @@ decl_module! {
 		/// # <weight>
 		/// - Independent of the arguments. Limited but potentially exploitable complexity.
 		/// - Contains a limited number of reads.
-		/// - Each call (requires the remainder of the bonded balance to be above `minimum_balance`)
-		///   will cause a new entry to be inserted into a vector (`Ledger.unlocking`) kept in storage.
-		///   The only way to clean the aforementioned storage item is also user-controlled via
-		///   `withdraw_unbonded`.
+		/// - Each call (requires the remainder of the bonded balance to be above
+		///   `minimum_active_bond`) will cause a new entry to be inserted into a vector
+		///   (`Ledger.unlocking`) kept in storage. The only way to clean the aforementioned storage
+		///   item is also user-controlled via `withdraw_unbonded`.
 		/// - One DB entry.
 		/// </weight>
-		#[weight = SimpleDispatchInfo::FixedNormal(400_000)]
-		fn unbond(origin, #[compact] value: BalanceOf<T>) {
+		#[weight = SimpleDispatchInfo::FixedNormal(T::WeightInfo::unbond())]
+		fn unbond(origin, #[compact] value: BalanceOf<T, I>) {
 			let controller = ensure_signed(origin)?;
-			let mut ledger = Self::ledger(&controller).ok_or(Error::<T>::NotController)?;
+			Self::ensure_migrated()?;
+			let mut ledger = Self::ledger(&controller).ok_or(Error::<T, I>::NotController)?;
 			ensure!(
-				ledger.unlocking.len() < MAX_UNLOCKING_CHUNKS,
-				Error::<T>::NoMoreChunks,
+				ledger.unlocking.len() < T::MaxUnlockingChunks::get() as usize,
+				Error::<T, I>::NoMoreChunks,
 			);
 
 			let mut value = value.min(ledger.active);
@@ -1084,7 +2355,7 @@ decl_module! {
 				ledger.active -= value;
 
 				// Avoid there being a dust balance left in the staking system.
-				if ledger.active < T::Currency::minimum_balance() {
+				if ledger.active < Self::minimum_active_bond() {
 					value += ledger.active;
 					ledger.active = Zero::zero();
 				}
@@ -1093,6 +2364,18 @@ decl_module! {
 				let era = Self::current_era().unwrap_or(0) + T::BondingDuration::get();
 				ledger.unlocking.push(UnlockChunk { value, era });
 				Self::update_ledger(&controller, &ledger);
+
+				let parameters = Self::parameters();
+				let min_bond = if <Validators<T, I>>::contains_key(&ledger.stash) {
+					parameters.min_validator_bond
+				} else if <Nominators<T, I>>::contains_key(&ledger.stash) {
+					parameters.min_nominator_bond
+				} else {
+					None
+				};
+				if min_bond.map_or(false, |min| ledger.active < min) {
+					Self::chill_stash(&ledger.stash, ChillReason::BelowMinimumBond);
+				}
 			}
 		}
 
@@ -1111,11 +2394,27 @@ decl_module! {
 		///  indirectly user-controlled. See [`unbond`] for more detail.
 		/// - Contains a limited number of reads, yet the size of which could be large based on `ledger`.
 		/// - Writes are limited to the `origin` account key.
+		/// - Weighed for `T::MaxUnlockingChunks` chunks, as their number does not follow from the
+		///   arguments.
+		/// - If the stash is removed, the records of at most `MAX_CLEARED_SLASHING_SPANS` of its
+		///   slashing spans are removed, the others over the following blocks.
 		/// # </weight>
-		#[weight = SimpleDispatchInfo::FixedNormal(400_000)]
-		fn withdraw_unbonded(origin) {
+		///
+		/// - `num_slashing_spans`: The number of slashing spans of the stash, up to
+		///   `MAX_CLEARED_SLASHING_SPANS`. It is only checked if the stash is removed.
+		#[weight = FunctionOf(
+			|args: (&u32,)| T::WeightInfo::withdraw_unbonded(
+				T::MaxUnlockingChunks::get(),
+				(*args.0).min(MAX_CLEARED_SLASHING_SPANS),
+			),
+			DispatchClass::Normal,
+			true
+		)]
+		fn withdraw_unbonded(origin, num_slashing_spans: u32) {
 			let controller = ensure_signed(origin)?;
-			let mut ledger = Self::ledger(&controller).ok_or(Error::<T>::NotController)?;
+			Self::ensure_migrated()?;
+			let mut ledger = Self::ledger(&controller).ok_or(Error::<T, I>::NotController)?;
+			ensure!(!Self::has_pending_slash(&ledger.stash), Error::<T, I>::PendingSlash);
 			if let Some(current_era) = Self::current_era() {
 				ledger = ledger.consolidate_unlocked(current_era)
 			}
@@ -1126,9 +2425,9 @@ decl_module! {
 				// left. We can now safely remove this.
 				let stash = ledger.stash;
 				// remove all staking-related information.
-				Self::kill_stash(&stash)?;
+				Self::kill_stash(&stash, num_slashing_spans)?;
 				// remove the lock.
-				T::Currency::remove_lock(STAKING_ID, &stash);
+				T::Currency::unlock(T::LockId::get(), &stash);
 			} else {
 				// This was the consequence of a partial unbond. just update the ledger and move on.
 				Self::update_ledger(&controller, &ledger);
@@ -1137,7 +2436,9 @@ decl_module! {
 
 		/// Declare the desire to validate for the origin controller.
 		///
-		/// Effects will be felt at the beginning of the next era.
+		/// Effects will be felt at the beginning of the next era. The stash must be allowed by
+		/// `T::CandidateFilter`, and must have set session keys. Its active bond and commission
+		/// must be at least the minimums of `Parameters`.
 		///
 		/// The dispatch origin for this call must be _Signed_ by the controller, not the stash.
 		///
@@ -1146,34 +2447,46 @@ decl_module! {
 		/// - Contains a limited number of reads.
 		/// - Writes are limited to the `origin` account key.
 		/// # </weight>
-		#[weight = SimpleDispatchInfo::FixedNormal(750_000)]
-		fn validate(origin, prefs: ValidatorPrefs) {
+		#[weight = SimpleDispatchInfo::FixedNormal(T::WeightInfo::validate())]
+		fn validate(origin, prefs: ValidatorPrefs<T::AccountId, BalanceOf<T, I>>) {
 			let controller = ensure_signed(origin)?;
-			let ledger = Self::ledger(&controller).ok_or(Error::<T>::NotController)?;
-			let stash = &ledger.stash;
-			<Nominators<T>>::remove(stash);
-			<Validators<T>>::insert(stash, prefs);
+			Self::ensure_migrated()?;
+			let ledger = Self::ledger(&controller).ok_or(Error::<T, I>::NotController)?;
+			ensure!(T::SessionKeysProvider::has_keys(&ledger.stash), Error::<T, I>::NoSessionKeys);
+			Self::do_validate(&ledger.stash, ledger.active, prefs)?;
 		}
 
 		/// Declare the desire to nominate `targets` for the origin controller.
 		///
-		/// Effects will be felt at the beginning of the next era.
+		/// Effects will be felt at the beginning of the next era. The active bond must be at least
+		/// the minimum of `Parameters`.
 		///
 		/// The dispatch origin for this call must be _Signed_ by the controller, not the stash.
 		///
 		/// # <weight>
 		/// - The transaction's complexity is proportional to the size of `targets`,
-		/// which is capped at `MAX_NOMINATIONS`.
+		/// which is capped at `T::MaxNominations`.
 		/// - Both the reads and writes follow a similar pattern.
 		/// # </weight>
-		#[weight = SimpleDispatchInfo::FixedNormal(750_000)]
+		#[weight = FunctionOf(
+			|args: (&Vec<<T::Lookup as StaticLookup>::Source>,)|
+				T::WeightInfo::nominate(args.0.len().min(T::MaxNominations::get() as usize) as u32),
+			DispatchClass::Normal,
+			true
+		)]
 		fn nominate(origin, targets: Vec<<T::Lookup as StaticLookup>::Source>) {
 			let controller = ensure_signed(origin)?;
-			let ledger = Self::ledger(&controller).ok_or(Error::<T>::NotController)?;
+			Self::ensure_migrated()?;
+			let ledger = Self::ledger(&controller).ok_or(Error::<T, I>::NotController)?;
 			let stash = &ledger.stash;
-			ensure!(!targets.is_empty(), Error::<T>::EmptyTargets);
+			ensure!(T::BondingRestriction::is_allowed(stash), Error::<T, I>::StakingNotAllowed);
+			ensure!(!targets.is_empty(), Error::<T, I>::EmptyTargets);
+			ensure!(
+				Self::parameters().min_nominator_bond.map_or(true, |min| ledger.active >= min),
+				Error::<T, I>::InsufficientBond,
+			);
 			let targets = targets.into_iter()
-				.take(MAX_NOMINATIONS)
+				.take(T::MaxNominations::get() as usize)
 				.map(|t| T::Lookup::lookup(t))
 				.collect::<result::Result<Vec<T::AccountId>, _>>()?;
 
@@ -1184,8 +2497,11 @@ decl_module! {
 				suppressed: false,
 			};
 
-			<Validators<T>>::remove(stash);
-			<Nominators<T>>::insert(stash, &nominations);
+			if <Validators<T, I>>::contains_key(stash) {
+				Self::deposit_event(RawEvent::Chilled(stash.clone(), ChillReason::RoleChanged));
+			}
+			Self::remove_validator(stash);
+			Self::set_nominations(stash, nominations);
 		}
 
 		/// Declare no desire to either validate or nominate.
@@ -1199,11 +2515,12 @@ decl_module! {
 		/// - Contains one read.
 		/// - Writes are limited to the `origin` account key.
 		/// # </weight>
-		#[weight = SimpleDispatchInfo::FixedNormal(500_000)]
+		#[weight = SimpleDispatchInfo::FixedNormal(T::WeightInfo::chill())]
 		fn chill(origin) {
 			let controller = ensure_signed(origin)?;
-			let ledger = Self::ledger(&controller).ok_or(Error::<T>::NotController)?;
-			Self::chill_stash(&ledger.stash);
+			Self::ensure_migrated()?;
+			let ledger = Self::ledger(&controller).ok_or(Error::<T, I>::NotController)?;
+			Self::chill_stash(&ledger.stash, ChillReason::Requested);
 		}
 
 		/// (Re-)set the payment target for a controller.
@@ -1217,12 +2534,43 @@ decl_module! {
 		/// - Contains a limited number of reads.
 		/// - Writes are limited to the `origin` account key.
 		/// # </weight>
-		#[weight = SimpleDispatchInfo::FixedNormal(500_000)]
+		#[weight = SimpleDispatchInfo::FixedNormal(T::WeightInfo::set_payee())]
 		fn set_payee(origin, payee: RewardDestination) {
 			let controller = ensure_signed(origin)?;
-			let ledger = Self::ledger(&controller).ok_or(Error::<T>::NotController)?;
+			Self::ensure_migrated()?;
+			let ledger = Self::ledger(&controller).ok_or(Error::<T, I>::NotController)?;
 			let stash = &ledger.stash;
-			<Payee<T>>::insert(stash, payee);
+			<Payee<T, I>>::insert(stash, payee);
+		}
+
+		/// Enable or disable the automatic payouts of the stash.
+		///
+		/// When enabled, the unclaimed eras of the stash are paid out one at a time, in turn with
+		/// the other registered stashes, using the weight left at the end of blocks.
+		///
+		/// The dispatch origin for this call must be _Signed_ by the controller, not the stash.
+		///
+		/// # <weight>
+		/// - Time complexity: O(A) where A is the number of stashes with automatic payouts, bounded
+		///   by `MAX_AUTO_PAYOUT_STASHES`.
+		/// - One storage read and one storage write.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(T::WeightInfo::set_auto_payout(MAX_AUTO_PAYOUT_STASHES as u32))]
+		fn set_auto_payout(origin, enabled: bool) {
+			let controller = ensure_signed(origin)?;
+			Self::ensure_migrated()?;
+			let ledger = Self::ledger(&controller).ok_or(Error::<T, I>::NotController)?;
+			let stash = ledger.stash;
+			if <HasAutoPayout<T, I>>::get(&stash) == enabled {
+				return Ok(())
+			}
+			if enabled {
+				ensure!(
+					<AutoPayoutStashes<T, I>>::decode_len().unwrap_or(0) < MAX_AUTO_PAYOUT_STASHES,
+					Error::<T, I>::TooManyAutoPayouts,
+				);
+			}
+			Self::set_auto_payout_of(&stash, enabled);
 		}
 
 		/// (Re-)set the controller of a stash.
@@ -1236,27 +2584,28 @@ decl_module! {
 		/// - Contains a limited number of reads.
 		/// - Writes are limited to the `origin` account key.
 		/// # </weight>
-		#[weight = SimpleDispatchInfo::FixedNormal(750_000)]
+		#[weight = SimpleDispatchInfo::FixedNormal(T::WeightInfo::set_controller())]
 		fn set_controller(origin, controller: <T::Lookup as StaticLookup>::Source) {
 			let stash = ensure_signed(origin)?;
-			let old_controller = Self::bonded(&stash).ok_or(Error::<T>::NotStash)?;
+			Self::ensure_migrated()?;
+			let old_controller = Self::bonded(&stash).ok_or(Error::<T, I>::NotStash)?;
 			let controller = T::Lookup::lookup(controller)?;
-			if <Ledger<T>>::contains_key(&controller) {
-				Err(Error::<T>::AlreadyPaired)?
+			if <Ledger<T, I>>::contains_key(&controller) {
+				Err(Error::<T, I>::AlreadyPaired)?
 			}
 			if controller != old_controller {
-				<Bonded<T>>::insert(&stash, &controller);
-				if let Some(l) = <Ledger<T>>::take(&old_controller) {
-					<Ledger<T>>::insert(&controller, l);
+				<Bonded<T, I>>::insert(&stash, &controller);
+				if let Some(l) = <Ledger<T, I>>::take(&old_controller) {
+					<Ledger<T, I>>::insert(&controller, l);
 				}
 			}
 		}
 
 		/// The ideal number of validators.
-		#[weight = SimpleDispatchInfo::FixedNormal(5_000)]
+		#[weight = SimpleDispatchInfo::FixedNormal(T::WeightInfo::set_validator_count())]
 		fn set_validator_count(origin, #[compact] new: u32) {
 			ensure_root(origin)?;
-			ValidatorCount::put(new);
+			ValidatorCount::<I>::put(new);
 		}
 
 		// ----- Root calls.
@@ -1266,10 +2615,10 @@ decl_module! {
 		/// # <weight>
 		/// - No arguments.
 		/// # </weight>
-		#[weight = SimpleDispatchInfo::FixedNormal(5_000)]
+		#[weight = SimpleDispatchInfo::FixedNormal(T::WeightInfo::force_no_eras())]
 		fn force_no_eras(origin) {
 			ensure_root(origin)?;
-			ForceEra::put(Forcing::ForceNone);
+			Self::set_force_era(Forcing::ForceNone);
 		}
 
 		/// Force there to be a new era at the end of the next session. After this, it will be
@@ -1278,29 +2627,96 @@ decl_module! {
 		/// # <weight>
 		/// - No arguments.
 		/// # </weight>
-		#[weight = SimpleDispatchInfo::FixedNormal(5_000)]
+		#[weight = SimpleDispatchInfo::FixedNormal(T::WeightInfo::force_new_era())]
 		fn force_new_era(origin) {
 			ensure_root(origin)?;
-			ForceEra::put(Forcing::ForceNew);
+			Self::set_force_era(Forcing::ForceNew);
 		}
 
 		/// Set the validators who cannot be slashed (if any).
-		#[weight = SimpleDispatchInfo::FixedNormal(5_000)]
+		///
+		/// No more than `T::MaxInvulnerables` distinct validators can be set.
+		///
+		/// # <weight>
+		/// - Time complexity: O(I) where I is the number of validators given.
+		/// - One storage write.
+		/// # </weight>
+		#[weight = FunctionOf(
+			|args: (&Vec<T::AccountId>,)| T::WeightInfo::set_invulnerables(args.0.len() as u32),
+			DispatchClass::Normal,
+			true
+		)]
 		fn set_invulnerables(origin, validators: Vec<T::AccountId>) {
 			ensure_root(origin)?;
-			<Invulnerables<T>>::put(validators);
+			let mut validators = validators;
+			validators.sort();
+			validators.dedup();
+			ensure!(
+				validators.len() <= T::MaxInvulnerables::get() as usize,
+				Error::<T, I>::TooManyInvulnerables,
+			);
+			Self::put_invulnerables(validators);
+		}
+
+		/// Add a validator to the invulnerables.
+		///
+		/// # <weight>
+		/// - Time complexity: O(I) where I is the number of invulnerables, bounded by
+		///   `T::MaxInvulnerables`.
+		/// - One storage read and one storage write.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(T::WeightInfo::add_invulnerable(T::MaxInvulnerables::get()))]
+		fn add_invulnerable(origin, who: T::AccountId) {
+			ensure_root(origin)?;
+			ensure!(!<IsInvulnerable<T, I>>::get(&who), Error::<T, I>::AlreadyInvulnerable);
+			ensure!(
+				<Invulnerables<T, I>>::decode_len().unwrap_or(0) < T::MaxInvulnerables::get() as usize,
+				Error::<T, I>::TooManyInvulnerables,
+			);
+			<Invulnerables<T, I>>::mutate(|invulnerables| if let Err(index) = invulnerables.binary_search(&who) {
+				invulnerables.insert(index, who.clone());
+			});
+			<IsInvulnerable<T, I>>::insert(&who, true);
+			Self::deposit_event(RawEvent::InvulnerableAdded(who));
+		}
+
+		/// Remove a validator from the invulnerables.
+		///
+		/// # <weight>
+		/// - Time complexity: O(I) where I is the number of invulnerables, bounded by
+		///   `T::MaxInvulnerables`.
+		/// - One storage read and one storage write.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(T::WeightInfo::remove_invulnerable(T::MaxInvulnerables::get()))]
+		fn remove_invulnerable(origin, who: T::AccountId) {
+			ensure_root(origin)?;
+			ensure!(<IsInvulnerable<T, I>>::get(&who), Error::<T, I>::NotInvulnerable);
+			<Invulnerables<T, I>>::mutate(|invulnerables| if let Ok(index) = invulnerables.binary_search(&who) {
+				invulnerables.remove(index);
+			});
+			<IsInvulnerable<T, I>>::remove(&who);
+			Self::deposit_event(RawEvent::InvulnerableRemoved(who));
 		}
 
 		/// Force a current staker to become completely unstaked, immediately.
-		#[weight = SimpleDispatchInfo::FixedNormal(10_000)]
-		fn force_unstake(origin, stash: T::AccountId) {
+		///
+		/// - `num_slashing_spans`: The number of slashing spans of the stash, up to
+		///   `MAX_CLEARED_SLASHING_SPANS`.
+		#[weight = FunctionOf(
+			|args: (&T::AccountId, &u32)|
+				T::WeightInfo::force_unstake((*args.1).min(MAX_CLEARED_SLASHING_SPANS)),
+			DispatchClass::Normal,
+			true
+		)]
+		fn force_unstake(origin, stash: T::AccountId, num_slashing_spans: u32) {
 			ensure_root(origin)?;
+			Self::ensure_migrated()?;
 
 			// remove all staking-related information.
-			Self::kill_stash(&stash)?;
+			Self::kill_stash(&stash, num_slashing_spans)?;
 
 			// remove the lock.
-			T::Currency::remove_lock(STAKING_ID, &stash);
+			T::Currency::unlock(T::LockId::get(), &stash);
 		}
 
 		/// Force there to be a new era at the end of sessions indefinitely.
@@ -1308,44 +2724,186 @@ decl_module! {
 		/// # <weight>
 		/// - One storage write
 		/// # </weight>
-		#[weight = SimpleDispatchInfo::FixedNormal(5_000)]
+		#[weight = SimpleDispatchInfo::FixedNormal(T::WeightInfo::force_new_era_always())]
 		fn force_new_era_always(origin) {
 			ensure_root(origin)?;
-			ForceEra::put(Forcing::ForceAlways);
+			Self::set_force_era(Forcing::ForceAlways);
+		}
+
+		/// Set the portion of the reporters' reward lost for each era an offence goes unreported.
+		///
+		/// # <weight>
+		/// - One storage write.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(T::WeightInfo::set_slash_reward_decay())]
+		fn set_slash_reward_decay(origin, decay: Perbill) {
+			ensure_root(origin)?;
+			SlashRewardDecay::<I>::put(decay);
+		}
+
+		/// Set the portion of the stakers' era payout diverted to the reward remainder.
+		///
+		/// # <weight>
+		/// - One storage write.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(T::WeightInfo::set_extra_remainder_share())]
+		fn set_extra_remainder_share(origin, share: Perbill) {
+			ensure_root(origin)?;
+			ExtraRemainderShare::<I>::put(share);
+		}
+
+		/// Set the total stake under which the stakers' era payout is reduced.
+		///
+		/// # <weight>
+		/// - One storage write.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(T::WeightInfo::set_payout_stake_floor())]
+		fn set_payout_stake_floor(origin, #[compact] floor: BalanceOf<T, I>) {
+			ensure_root(origin)?;
+			<PayoutStakeFloor<T, I>>::put(floor);
+		}
+
+		/// Set or remove the cap on the yearly return paid to stakers.
+		///
+		/// # <weight>
+		/// - One storage write.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(T::WeightInfo::set_max_payout_apr())]
+		fn set_max_payout_apr(origin, max_apr: Option<Perbill>) {
+			ensure_root(origin)?;
+			MaxPayoutApr::<I>::set(max_apr);
+		}
+
+		/// Set or remove the reward curve used in place of `T::EraPayout` to compute era payouts.
+		///
+		/// The curve must have at most `MAX_REWARD_CURVE_POINTS` points, in strictly increasing
+		/// order of abscissa, none of which above its maximum. It applies from the end of the
+		/// active era.
+		///
+		/// # <weight>
+		/// - Time complexity: O(P) where P is the number of points of the curve.
+		/// - One storage write.
+		/// # </weight>
+		#[weight = FunctionOf(
+			|args: (&Option<RewardCurvePoints>,)| T::WeightInfo::set_reward_curve(
+				args.0.as_ref().map_or(0, |curve| curve.points.len().min(MAX_REWARD_CURVE_POINTS) as u32)
+			),
+			DispatchClass::Normal,
+			true
+		)]
+		fn set_reward_curve(origin, curve: Option<RewardCurvePoints>) {
+			ensure_root(origin)?;
+			match curve {
+				Some(curve) => {
+					ensure!(curve.is_valid(), Error::<T, I>::InvalidRewardCurve);
+					RewardCurveOverride::<I>::put(curve);
+				}
+				None => RewardCurveOverride::<I>::kill(),
+			}
+		}
+
+		/// Set or remove the bounds of the slash fraction applied for an offence kind.
+		///
+		/// # <weight>
+		/// - One storage write.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(T::WeightInfo::set_slash_fraction_bounds())]
+		fn set_slash_fraction_bounds(origin, kind: Kind, bounds: Option<SlashFractionBounds>) {
+			ensure_root(origin)?;
+			match bounds {
+				Some(bounds) => {
+					ensure!(bounds.min <= bounds.max, Error::<T, I>::InvalidSlashFractionBounds);
+					SlashFractionBoundsOf::<I>::insert(kind, bounds);
+				}
+				None => SlashFractionBoundsOf::<I>::remove(kind),
+			}
+		}
+
+		/// Set the parameters of the module, replacing all of them.
+		///
+		/// Stakers below the new minimum bonds or commission are left as they are, the minimums
+		/// applying from their next `validate`, `nominate` or `unbond`.
+		///
+		/// # <weight>
+		/// - One storage write.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(T::WeightInfo::set_parameters())]
+		fn set_parameters(origin, parameters: StakingParameters<BalanceOf<T, I>>) {
+			ensure_root(origin)?;
+			ensure!(
+				parameters.slash_defer_duration.map_or(true, |eras| eras < T::BondingDuration::get()),
+				Error::<T, I>::InvalidParameters,
+			);
+			let existential_deposit = T::Currency::minimum_balance();
+			ensure!(
+				parameters.min_active_bond.map_or(true, |min| min >= existential_deposit),
+				Error::<T, I>::InvalidParameters,
+			);
+			<Parameters<T, I>>::put(parameters);
 		}
 
 		/// Cancel enactment of a deferred slash. Can be called by either the root origin or
 		/// the `T::SlashCancelOrigin`.
-		/// passing the era and indices of the slashes for that era to kill.
+		/// passing the era and ids of the slashes for that era to kill.
+		///
+		/// Unlike positions in `UnappliedSlashes`, slash ids do not shift as new slashes are queued.
 		///
 		/// # <weight>
+		/// - Time complexity: O(S) where S is the number of slash ids.
 		/// - One storage write.
 		/// # </weight>
-		#[weight = SimpleDispatchInfo::FixedNormal(1_000_000)]
-		fn cancel_deferred_slash(origin, era: EraIndex, slash_indices: Vec<u32>) {
+		#[weight = FunctionOf(
+			|args: (&EraIndex, &Vec<SlashId>)|
+				T::WeightInfo::cancel_deferred_slash(args.1.len() as u32),
+			DispatchClass::Normal,
+			true
+		)]
+		fn cancel_deferred_slash(origin, era: EraIndex, slash_ids: Vec<SlashId>) {
 			T::SlashCancelOrigin::try_origin(origin)
 				.map(|_| ())
 				.or_else(ensure_root)?;
+			Self::ensure_migrated()?;
+
+			let mut slash_ids = slash_ids;
+			slash_ids.sort_unstable();
+			ensure!(slash_ids.windows(2).all(|w| w[0] != w[1]), Error::<T, I>::DuplicateIndex);
+
+			<Self as Store>::UnappliedSlashes::try_mutate(&era, |unapplied| {
+				for id in slash_ids {
+					let index = unapplied.iter()
+						.position(|slash| slash.id == id)
+						.ok_or(Error::<T, I>::InvalidSlashIndex)?;
+					Self::count_pending_slash(&unapplied.remove(index), false);
+				}
+				Ok::<_, Error<T, I>>(())
+			})?;
+		}
 
-			let mut slash_indices = slash_indices;
-			slash_indices.sort_unstable();
-			let mut unapplied = <Self as Store>::UnappliedSlashes::get(&era);
-
-			for (removed, index) in slash_indices.into_iter().enumerate() {
-				let index = index as usize;
-
-				// if `index` is not duplicate, `removed` must be <= index.
-				ensure!(removed <= index, Error::<T>::DuplicateIndex);
-
-				// all prior removals were from before this index, since the
-				// list is sorted.
-				let index = index - removed;
-				ensure!(index < unapplied.len(), Error::<T>::InvalidSlashIndex);
+		/// Apply a deferred slash right away instead of waiting for `T::SlashDeferDuration`. Can be
+		/// called by either the root origin or the `T::SlashCancelOrigin`.
+		/// passing the era and id of the slash to apply.
+		///
+		/// # <weight>
+		/// - One storage write.
+		/// - Same complexity as a slash applied at the beginning of an era: `O(N)` where `N` is the
+		///   number of nominators exposed to the slashed validator.
+		/// # </weight>
+		#[weight = SimpleDispatchInfo::FixedNormal(T::WeightInfo::apply_slash_now(T::MaxNominatorRewardedPerValidator::get()))]
+		fn apply_slash_now(origin, era: EraIndex, slash_id: SlashId) {
+			T::SlashCancelOrigin::try_origin(origin)
+				.map(|_| ())
+				.or_else(ensure_root)?;
+			Self::ensure_migrated()?;
 
-				unapplied.remove(index);
-			}
+			let slash = <Self as Store>::UnappliedSlashes::try_mutate(&era, |unapplied| {
+				let index = unapplied.iter()
+					.position(|slash| slash.id == slash_id)
+					.ok_or(Error::<T, I>::InvalidSlashIndex)?;
+				Ok::<_, Error<T, I>>(unapplied.remove(index))
+			})?;
 
-			<Self as Store>::UnappliedSlashes::insert(&era, &unapplied);
+			Self::count_pending_slash(&slash, false);
+			slashing::apply_slash::<T, I>(slash);
 		}
 
 		/// Make one nominator's payout for one era.
@@ -1355,7 +2913,7 @@ decl_module! {
 		///   then it indicates an instruction to skip the payout of all previous eras.
 		/// - `validators` is the list of all validators that `who` had exposure to during `era`.
 		///   If it is incomplete, then less than the full reward will be paid out.
-		///   It must not exceed `MAX_NOMINATIONS`.
+		///   It must not exceed `T::MaxNominations`.
 		///
 		/// WARNING: once an era is payed for a validator such validator can't claim the payout of
 		/// previous era.
@@ -1364,18 +2922,24 @@ decl_module! {
 		///
 		/// # <weight>
 		/// - Number of storage read of `O(validators)`; `validators` is the argument of the call,
-		///   and is bounded by `MAX_NOMINATIONS`.
+		///   and is bounded by `T::MaxNominations`.
 		/// - Each storage read is `O(N)` size and decode complexity; `N` is the  maximum
 		///   nominations that can be given to a single validator.
-		/// - Computation complexity: `O(MAX_NOMINATIONS * logN)`; `MAX_NOMINATIONS` is the
+		/// - Computation complexity: `O(T::MaxNominations * logN)`; `T::MaxNominations` is the
 		///   maximum number of validators that may be nominated by a single nominator, it is
 		///   bounded only economically (all nominators are required to place a minimum stake).
 		/// # </weight>
-		#[weight = SimpleDispatchInfo::FixedNormal(500_000)]
+		#[weight = FunctionOf(
+			|args: (&EraIndex, &Vec<(T::AccountId, u32)>)|
+				T::WeightInfo::payout_nominator(args.1.len().min(T::MaxNominations::get() as usize) as u32),
+			DispatchClass::Normal,
+			true
+		)]
 		fn payout_nominator(origin, era: EraIndex, validators: Vec<(T::AccountId, u32)>)
 			-> DispatchResult
 		{
 			let who = ensure_signed(origin)?;
+			Self::ensure_migrated()?;
 			Self::do_payout_nominator(who, era, validators)
 		}
 
@@ -1384,6 +2948,7 @@ decl_module! {
 		/// - `who` is the controller account of the validator to pay out.
 		/// - `era` may not be lower than one following the most recently paid era. If it is higher,
 		///   then it indicates an instruction to skip the payout of all previous eras.
+		/// - The stash must have been elected in `era`.
 		///
 		/// WARNING: once an era is payed for a validator such validator can't claim the payout of
 		/// previous era.
@@ -1394,25 +2959,94 @@ decl_module! {
 		/// - Time complexity: O(1).
 		/// - Contains a limited number of reads and writes.
 		/// # </weight>
-		#[weight = SimpleDispatchInfo::FixedNormal(500_000)]
+		#[weight = SimpleDispatchInfo::FixedNormal(T::WeightInfo::payout_validator(T::MaxNominatorRewardedPerValidator::get()))]
 		fn payout_validator(origin, era: EraIndex) -> DispatchResult {
 			let who = ensure_signed(origin)?;
+			Self::ensure_migrated()?;
 			Self::do_payout_validator(who, era)
 		}
 
+		/// Make one validator's payouts for all its unclaimed eras between `from_era` and `to_era`
+		/// (inclusive), as many of them as `max_weight` allows.
+		///
+		/// - `validator` is the stash account of the validator to pay out.
+		/// - Only the controller of the validator may skip eras by giving a `from_era` higher than
+		///   the first unclaimed era; anyone else can only catch up from it.
+		/// - The eras that can't be paid out, such as those the validator was not elected in, are
+		///   skipped, each reported by `PayoutSkipped`.
+		///
+		/// The range of eras processed and the last era paid out are reported by `ErasPaidOut`; if
+		/// the range ends before `to_era`, the call can be made again to continue.
+		///
+		/// # <weight>
+		/// - Time complexity: O(E) where E is the number of eras from `from_era` to `to_era`, bounded
+		///   by `max_weight`.
+		/// - Contains a limited number of reads and writes per era.
+		/// # </weight>
+		#[weight = FunctionOf(
+			|args: (&T::AccountId, &EraIndex, &EraIndex, &Weight)| {
+				let eras = args.2.saturating_sub(*args.1).saturating_add(1);
+				let eras = Module::<T, I>::payout_eras_within(*args.3, eras).max(1);
+				T::WeightInfo::payout_stakers_all(eras)
+			},
+			DispatchClass::Normal,
+			true
+		)]
+		fn payout_stakers_all(
+			origin,
+			validator: T::AccountId,
+			from_era: EraIndex,
+			to_era: EraIndex,
+			max_weight: Weight,
+		) -> DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::ensure_migrated()?;
+			let controller = Self::bonded(&validator).ok_or(Error::<T, I>::NotStash)?;
+			let ledger = Self::ledger(&controller).ok_or(Error::<T, I>::NotController)?;
+			let active_era = Self::active_era().ok_or(Error::<T, I>::InvalidEraToReward)?.index;
+
+			let first_unclaimed = active_era.saturating_sub(Self::history_depth())
+				.max(ledger.last_reward.map_or(0, |era| era + 1));
+			ensure!(from_era <= first_unclaimed || who == controller, Error::<T, I>::NotController);
+
+			// Only ended eras can be paid out.
+			let from_era = from_era.max(first_unclaimed);
+			let to_era = to_era
+				.min(active_era.checked_sub(1).ok_or(Error::<T, I>::InvalidEraToReward)?);
+			ensure!(from_era <= to_era, Error::<T, I>::InvalidEraToReward);
+			let max_eras = Self::payout_eras_within(max_weight, to_era - from_era + 1);
+			ensure!(max_eras > 0, Error::<T, I>::PayoutWeightTooLow);
+			let to_era = from_era + (max_eras - 1);
+
+			let mut paid = 0u32;
+			for era in from_era..=to_era {
+				// `do_payout_validator` fails before making any change.
+				match Self::do_payout_validator(controller.clone(), era) {
+					Ok(()) => paid += 1,
+					Err(e) => {
+						Self::deposit_event(RawEvent::PayoutSkipped(validator.clone(), era, e));
+					},
+				}
+			}
+
+			Self::deposit_event(RawEvent::ErasPaidOut(validator, from_era, to_era, paid));
+			Ok(())
+		}
+
 		/// Rebond a portion of the stash scheduled to be unlocked.
 		///
 		/// # <weight>
-		/// - Time complexity: O(1). Bounded by `MAX_UNLOCKING_CHUNKS`.
+		/// - Time complexity: O(1). Bounded by `T::MaxUnlockingChunks`.
 		/// - Storage changes: Can't increase storage, only decrease it.
 		/// # </weight>
-		#[weight = SimpleDispatchInfo::FixedNormal(500_000)]
-		fn rebond(origin, #[compact] value: BalanceOf<T>) {
+		#[weight = SimpleDispatchInfo::FixedNormal(T::WeightInfo::rebond(T::MaxUnlockingChunks::get()))]
+		fn rebond(origin, #[compact] value: BalanceOf<T, I>) {
 			let controller = ensure_signed(origin)?;
-			let ledger = Self::ledger(&controller).ok_or(Error::<T>::NotController)?;
+			Self::ensure_migrated()?;
+			let ledger = Self::ledger(&controller).ok_or(Error::<T, I>::NotController)?;
 			ensure!(
 				ledger.unlocking.len() > 0,
-				Error::<T>::NoUnlockChunk,
+				Error::<T, I>::NoUnlockChunk,
 			);
 
 			let ledger = ledger.rebond(value);
@@ -1421,19 +3055,23 @@ decl_module! {
 
 		/// Set history_depth value.
 		///
+		/// Nothing is cleared right away: a deeper history grows by one era at each new era, the
+		/// eras before it being cleared already, and a shallower one is applied at the start of the
+		/// era following the next `T::HistoryShrinkDelay` ones, the rewards of the eras leaving the
+		/// history being claimable until then.
+		///
 		/// Origin must be root.
-		#[weight = SimpleDispatchInfo::FixedOperational(500_000)]
+		#[weight = SimpleDispatchInfo::FixedOperational(T::WeightInfo::set_history_depth())]
 		fn set_history_depth(origin, #[compact] new_history_depth: EraIndex) {
 			ensure_root(origin)?;
-			if let Some(current_era) = Self::current_era() {
-				HistoryDepth::mutate(|history_depth| {
-					let last_kept = current_era.checked_sub(*history_depth).unwrap_or(0);
-					let new_last_kept = current_era.checked_sub(new_history_depth).unwrap_or(0);
-					for era_index in last_kept..new_last_kept {
-						Self::clear_era_information(era_index);
-					}
-					*history_depth = new_history_depth
-				})
+			Self::ensure_migrated()?;
+			if new_history_depth == Self::history_depth() {
+				PendingHistoryDepth::<I>::kill();
+			} else {
+				let from_era = Self::current_era().unwrap_or(0)
+					.saturating_add(T::HistoryShrinkDelay::get())
+					.saturating_add(1);
+				PendingHistoryDepth::<I>::put((new_history_depth, from_era));
 			}
 		}
 
@@ -1444,82 +3082,861 @@ decl_module! {
 		/// This can be called from any origin.
 		///
 		/// - `stash`: The stash account to reap. Its balance must be zero.
-		fn reap_stash(_origin, stash: T::AccountId) {
-			ensure!(T::Currency::total_balance(&stash).is_zero(), Error::<T>::FundedTarget);
-			Self::kill_stash(&stash)?;
-			T::Currency::remove_lock(STAKING_ID, &stash);
+		/// - `num_slashing_spans`: The number of slashing spans of the stash, up to
+		///   `MAX_CLEARED_SLASHING_SPANS`.
+		#[weight = FunctionOf(
+			|args: (&T::AccountId, &u32)|
+				T::WeightInfo::reap_stash((*args.1).min(MAX_CLEARED_SLASHING_SPANS)),
+			DispatchClass::Normal,
+			true
+		)]
+		fn reap_stash(_origin, stash: T::AccountId, num_slashing_spans: u32) {
+			Self::ensure_migrated()?;
+			ensure!(!Self::is_virtual_staker(&stash), Error::<T, I>::IsAgent);
+			ensure!(T::Currency::total_balance(&stash).is_zero(), Error::<T, I>::FundedTarget);
+			Self::kill_stash(&stash, num_slashing_spans)?;
+			T::Currency::unlock(T::LockId::get(), &stash);
+		}
+
+		/// Repair the staking state of a stash found inconsistent by `check_ledger`.
+		///
+		/// - A stash whose ledger is missing or belongs to another stash is unbonded: its staking
+		///   information is removed and its balance unlocked. The other stash is left untouched.
+		/// - The total of a ledger is recomputed from its active balance and unlocking chunks, and
+		///   reduced to the funds backing it if above them (see `ledger_backing`). The lock is set
+		///   to the total.
+		/// - The leftover staking information of a stash that is not bonded is removed, and its
+		///   balance unlocked.
+		///
+		/// The dispatch origin must be Root.
+		#[weight = SimpleDispatchInfo::FixedOperational(T::WeightInfo::repair_ledger(MAX_CLEARED_SLASHING_SPANS))]
+		fn repair_ledger(origin, stash: T::AccountId) {
+			ensure_root(origin)?;
+			Self::ensure_migrated()?;
+			let corruption = Self::check_ledger(&stash)
+				.err()
+				.ok_or(Error::<T, I>::LedgerNotCorrupted)?;
+
+			match corruption {
+				LedgerCorruption::MissingLedger | LedgerCorruption::ForeignLedger => {
+					<Bonded<T, I>>::remove(&stash);
+					Self::clear_stash_info(&stash);
+					system::Module::<T>::dec_ref(&stash);
+					T::Currency::unlock(T::LockId::get(), &stash);
+				},
+				LedgerCorruption::TotalMismatch | LedgerCorruption::TotalAboveBalance => {
+					let controller = Self::bonded(&stash).ok_or(Error::<T, I>::NotStash)?;
+					let mut ledger = Self::ledger(&controller).ok_or(Error::<T, I>::NotController)?;
+					ledger.total = ledger.unlocking.iter()
+						.fold(ledger.active, |total, chunk| total.saturating_add(chunk.value));
+					let backing = Self::ledger_backing(&stash);
+					if ledger.total > backing {
+						let excess = ledger.total - backing;
+						ledger.slash(excess, T::Currency::minimum_balance());
+					}
+					Self::update_ledger(&controller, &ledger);
+				},
+				LedgerCorruption::Dangling => {
+					Self::clear_stash_info(&stash);
+					T::Currency::unlock(T::LockId::get(), &stash);
+				},
+			}
+
+			Self::deposit_event(RawEvent::LedgerRepaired(stash));
+		}
+
+		/// Bond the origin account as an agent, whose ledger is backed by the funds delegated to
+		/// it rather than by its own balance. `controller` will be the account that controls it.
+		///
+		/// The ledger is empty until funds are delegated with `delegate`. Rewards paid to be
+		/// staked are paid into the agent instead, as they are not delegated funds.
+		///
+		/// The dispatch origin for this call must be _Signed_ by the agent account.
+		#[weight = SimpleDispatchInfo::FixedNormal(T::WeightInfo::bond_agent())]
+		fn bond_agent(origin,
+			controller: <T::Lookup as StaticLookup>::Source,
+			payee: RewardDestination
+		) {
+			let agent = ensure_signed(origin)?;
+			Self::ensure_migrated()?;
+			ensure!(T::BondingRestriction::is_allowed(&agent), Error::<T, I>::StakingNotAllowed);
+
+			ensure!(!<Bonded<T, I>>::contains_key(&agent), Error::<T, I>::AlreadyBonded);
+			ensure!(
+				!<SpanRecordsToClear<T, I>>::contains_key(&agent),
+				Error::<T, I>::SpanRecordsNotCleared,
+			);
+			let controller = T::Lookup::lookup(controller)?;
+			ensure!(!<Ledger<T, I>>::contains_key(&controller), Error::<T, I>::AlreadyPaired);
+
+			<Bonded<T, I>>::insert(&agent, &controller);
+			<Payee<T, I>>::insert(&agent, payee);
+			<VirtualStakers<T, I>>::insert(&agent, true);
+
+			system::Module::<T>::inc_ref(&agent);
+
+			let item = StakingLedger {
+				stash: agent,
+				total: Zero::zero(),
+				active: Zero::zero(),
+				unlocking: vec![],
+				last_reward: Self::current_era(),
+			};
+			Self::update_ledger(&controller, &item);
+		}
+
+		/// Delegate `value` of the balance of the origin account to `agent`, bonding it in the
+		/// ledger of the agent. The funds stay reserved in the origin account, and are slashed
+		/// along with the agent.
+		///
+		/// The dispatch origin for this call must be _Signed_ by the delegator.
+		#[weight = SimpleDispatchInfo::FixedNormal(T::WeightInfo::delegate(T::MaxDelegators::get()))]
+		fn delegate(origin, agent: <T::Lookup as StaticLookup>::Source, #[compact] value: BalanceOf<T, I>) {
+			let delegator = ensure_signed(origin)?;
+			Self::ensure_migrated()?;
+			let agent = T::Lookup::lookup(agent)?;
+			ensure!(T::BondingRestriction::is_allowed(&delegator), Error::<T, I>::StakingNotAllowed);
+			ensure!(T::BondingRestriction::is_allowed(&agent), Error::<T, I>::StakingNotAllowed);
+			delegation::delegate::<T, I>(&delegator, &agent, value)?;
+		}
+
+		/// Take back up to `value` of the funds the origin account delegated to `agent`, out of
+		/// those the agent unbonded and withdrew.
+		///
+		/// The dispatch origin for this call must be _Signed_ by the delegator.
+		#[weight = SimpleDispatchInfo::FixedNormal(T::WeightInfo::undelegate(T::MaxDelegators::get()))]
+		fn undelegate(origin, agent: <T::Lookup as StaticLookup>::Source, #[compact] value: BalanceOf<T, I>) {
+			let delegator = ensure_signed(origin)?;
+			Self::ensure_migrated()?;
+			let agent = T::Lookup::lookup(agent)?;
+			delegation::undelegate::<T, I>(&delegator, &agent, value)?;
+		}
+
+		/// Set the session keys of the origin controller, `proof` proving their ownership, and
+		/// declare the desire to validate, as `validate`.
+		///
+		/// The dispatch origin for this call must be _Signed_ by the controller, not the stash.
+		#[weight = SimpleDispatchInfo::FixedNormal(T::WeightInfo::validate_with_keys())]
+		fn validate_with_keys(origin,
+			prefs: ValidatorPrefs<T::AccountId, BalanceOf<T, I>>,
+			keys: SessionKeysOf<T, I>,
+			proof: Vec<u8>
+		) {
+			let controller = ensure_signed(origin)?;
+			Self::ensure_migrated()?;
+			let ledger = Self::ledger(&controller).ok_or(Error::<T, I>::NotController)?;
+			T::SessionKeysProvider::set_keys(&controller, keys, &proof)?;
+			Self::do_validate(&ledger.stash, ledger.active, prefs)?;
+		}
+
+		/// Slash each of the `offenders` and their nominators by `slash_fraction` of their
+		/// exposure, for an offence committed in `session` whose evidence arrived out-of-band.
+		///
+		/// The offence is handled as one reported by the offences module, of the kind
+		/// `UNCHECKED_OFFENCE_KIND`, without reporters. Can be called by either the root origin
+		/// or the `T::ReportOffenceOrigin`.
+		///
+		/// # <weight>
+		/// - Time complexity: O(O) where O is the number of offenders, each with its nominators.
+		/// # </weight>
+		#[weight = FunctionOf(
+			|args: (&Vec<T::AccountId>, &Perbill, &SessionIndex)|
+				T::WeightInfo::report_offence_unchecked(args.0.len() as u32),
+			DispatchClass::Operational,
+			true
+		)]
+		fn report_offence_unchecked(origin,
+			offenders: Vec<T::AccountId>,
+			slash_fraction: Perbill,
+			session: SessionIndex
+		) {
+			T::ReportOffenceOrigin::try_origin(origin)
+				.map(|_| ())
+				.or_else(ensure_root)?;
+			let era = Self::slashable_era_of(session).ok_or(Error::<T, I>::OffenceNotSlashable)?;
+			let details = offenders.into_iter()
+				.map(|stash| {
+					ensure!(EraInfo::<T, I>::is_elected(era, &stash), Error::<T, I>::OffenderNotElected);
+					let exposure = Self::eras_stakers(era, &stash);
+					Ok(OffenceDetails { offender: (stash, exposure), reporters: vec![] })
+				})
+				.collect::<result::Result<Vec<_>, Error<T, I>>>()?;
+			let fractions = vec![slash_fraction; details.len()];
+
+			Self::do_on_offence(&details, &fractions, session, &UNCHECKED_OFFENCE_KIND);
+		}
+
+		/// Install `validators` as the validator set of the next era planned, in place of the
+		/// election, or clear a set installed before with an empty one.
+		///
+		/// Each validator is exposed with its own active stake only, whether or not it is a
+		/// candidate; the nominators back none of them for that era. The set is used once.
+		///
+		/// The dispatch origin must be Root.
+		///
+		/// # <weight>
+		/// - O(V) where V is the number of validators, for the checks.
+		/// - One storage write.
+		/// # </weight>
+		#[weight = FunctionOf(
+			|args: (&Vec<T::AccountId>,)| T::WeightInfo::set_next_validators(args.0.len() as u32),
+			DispatchClass::Operational,
+			true
+		)]
+		fn set_next_validators(origin, validators: Vec<T::AccountId>) {
+			ensure_root(origin)?;
+			if validators.is_empty() {
+				<NextValidators<T, I>>::kill();
+				return Ok(());
+			}
+
+			let mut sorted = validators.clone();
+			sorted.sort();
+			sorted.dedup();
+			ensure!(sorted.len() == validators.len(), Error::<T, I>::InvalidValidatorSet);
+			ensure!(
+				validators.iter().all(|stash| <Bonded<T, I>>::contains_key(stash)),
+				Error::<T, I>::NotStash,
+			);
+
+			<NextValidators<T, I>>::put(validators);
 		}
 	}
 }
 
-impl<T: Trait> Module<T> {
-	// PUBLIC IMMUTABLES
+impl<T: Trait<I>, I: Instance> Call<T, I> {
+	/// The kind of this call.
+	///
+	/// The match is exhaustive so that a new call must be given a kind.
+	pub fn kind(&self) -> CallKind {
+		match self {
+			Call::bond(..)
+				| Call::bond_extra(..)
+				| Call::unbond(..)
+				| Call::withdraw_unbonded(..)
+				| Call::rebond(..)
+				| Call::set_controller(..)
+				| Call::set_payee(..)
+				| Call::bond_agent(..)
+				| Call::delegate(..)
+				| Call::undelegate(..)
+			=> CallKind::FundsMoving,
+			Call::validate(..)
+				| Call::validate_with_keys(..)
+				| Call::nominate(..)
+				| Call::chill(..)
+				| Call::set_auto_payout(..)
+				| Call::payout_nominator(..)
+				| Call::payout_validator(..)
+				| Call::payout_stakers_all(..)
+				| Call::reap_stash(..)
+			=> CallKind::NonTransfer,
+			Call::set_validator_count(..)
+				| Call::force_no_eras(..)
+				| Call::force_new_era(..)
+				| Call::set_invulnerables(..)
+				| Call::add_invulnerable(..)
+				| Call::remove_invulnerable(..)
+				| Call::force_unstake(..)
+				| Call::force_new_era_always(..)
+				| Call::set_slash_reward_decay(..)
+				| Call::set_extra_remainder_share(..)
+				| Call::set_payout_stake_floor(..)
+				| Call::set_max_payout_apr(..)
+				| Call::set_reward_curve(..)
+				| Call::set_slash_fraction_bounds(..)
+				| Call::set_parameters(..)
+				| Call::cancel_deferred_slash(..)
+				| Call::apply_slash_now(..)
+				| Call::set_history_depth(..)
+				| Call::repair_ledger(..)
+				| Call::report_offence_unchecked(..)
+				| Call::set_next_validators(..)
+			=> CallKind::Governance,
+			Call::__PhantomItem(..) => unreachable!("Variant is never constructed"),
+		}
+	}
+
+	/// Whether this call moves funds, see `CallKind::FundsMoving`.
+	pub fn is_funds_moving(&self) -> bool {
+		self.kind() == CallKind::FundsMoving
+	}
+}
+
+impl<T: Trait<I>, I: Instance> Module<T, I> {
+	// PUBLIC IMMUTABLES
+
+	/// The number of eras slashes are deferred by, `T::SlashDeferDuration` unless overridden by
+	/// the parameters.
+	pub fn slash_defer_duration() -> EraIndex {
+		Self::parameters().slash_defer_duration.unwrap_or_else(T::SlashDeferDuration::get)
+	}
+
+	/// The number of consecutive sessions a validator can be unresponsive in before it is
+	/// chilled, `T::MaxUnresponsiveSessions` unless overridden by the parameters.
+	pub fn max_unresponsive_sessions() -> SessionIndex {
+		Self::parameters().max_unresponsive_sessions
+			.unwrap_or_else(T::MaxUnresponsiveSessions::get)
+	}
+
+	/// The lowest active bond of a staker, the existential deposit unless overridden by the
+	/// parameters.
+	pub fn minimum_active_bond() -> BalanceOf<T, I> {
+		Self::parameters().min_active_bond.unwrap_or_else(T::Currency::minimum_balance)
+	}
+
+	/// The total balance that can be slashed from a stash account as of right now.
+	pub fn slashable_balance_of(stash: &T::AccountId) -> BalanceOf<T, I> {
+		Self::active_bonded(stash)
+	}
+
+	/// The part of the bond of `stash` backed by funds still vesting, which back it before the
+	/// other funds of the stash. An agent has none, its bond being backed by delegated funds.
+	pub fn vested_bond(stash: &T::AccountId) -> BalanceOf<T, I> {
+		if Self::is_virtual_staker(stash) {
+			return Zero::zero();
+		}
+		let total = Self::bonded(stash)
+			.and_then(Self::ledger)
+			.map_or_else(Zero::zero, |ledger| ledger.total);
+		<T::VestedFunds as asset::VestedFunds<_, _>>::vesting_balance(stash).min(total)
+	}
+
+	/// The funds backing the ledger of `stash`: the funds held by `T::StakeKeeper` for a stash
+	/// bonded by it, the funds delegated to it for an agent, its free balance otherwise.
+	pub fn ledger_backing(stash: &T::AccountId) -> BalanceOf<T, I> {
+		if Self::is_kept_staker(stash) {
+			<T::StakeKeeper as asset::StakeKeeper<_, _, _>>::backing(stash)
+		} else if Self::is_virtual_staker(stash) {
+			delegation::total_delegated::<T, I>(stash)
+		} else {
+			T::Currency::free_balance(stash)
+		}
+	}
+
+	/// The validator candidates and their preferences, in no particular order.
+	pub fn iter_validators()
+		-> impl Iterator<Item = (T::AccountId, ValidatorPrefs<T::AccountId, BalanceOf<T, I>>)>
+	{
+		ConcatMapIterator::new(<Validators<T, I>>::final_prefix().to_vec())
+	}
+
+	/// The nominators and their nominations, in no particular order.
+	pub fn iter_nominators() -> impl Iterator<Item = (T::AccountId, Nominations<T::AccountId>)> {
+		ConcatMapIterator::new(<Nominators<T, I>>::final_prefix().to_vec())
+	}
+
+	/// Whether the storage is migrated to the latest release. The migrations going through every
+	/// ledger may take several blocks, see `MaxMigratedValuesPerBlock`.
+	pub fn is_storage_migrated() -> bool {
+		migration::is_migrated::<I>()
+	}
+
+	/// Whether a stash account is one of the invulnerables.
+	pub fn is_invulnerable(stash: &T::AccountId) -> bool {
+		<IsInvulnerable<T, I>>::get(stash)
+	}
+
+	/// Ensure the storage is not being migrated, see [`migration::migrate`].
+	fn ensure_migrated() -> DispatchResult {
+		ensure!(Self::is_storage_migrated(), Error::<T, I>::MigrationInProgress);
+		Ok(())
+	}
+
+	/// Whether a deferred slash that is not applied yet would hit the stash account, either as
+	/// the offending validator or as one of its nominators.
+	pub fn has_pending_slash(stash: &T::AccountId) -> bool {
+		Self::pending_slash_count(stash) > 0
+	}
+
+	/// Count `slash` in `PendingSlashCount` for the stashes it would hit if `pending`, else
+	/// uncount it, as it is queued in or taken out of `UnappliedSlashes`.
+	pub(crate) fn count_pending_slash(
+		slash: &UnappliedSlash<T::AccountId, BalanceOf<T, I>>,
+		pending: bool,
+	) {
+		let stashes = sp_std::iter::once(&slash.validator)
+			.chain(slash.others.iter().map(|(who, _)| who));
+		for stash in stashes {
+			<Self as Store>::PendingSlashCount::mutate_exists(stash, |count| {
+				let new_count = if pending {
+					count.unwrap_or(0).saturating_add(1)
+				} else {
+					count.unwrap_or(0).saturating_sub(1)
+				};
+				*count = Some(new_count).filter(|c| *c > 0);
+			});
+		}
+	}
+
+	/// The exposure of `validator` in `era`, with all the nominators exposed to it, or an empty
+	/// exposure if it was not elected in that era.
+	pub fn eras_stakers<V: Borrow<T::AccountId>>(
+		era: EraIndex,
+		validator: V,
+	) -> Exposure<T::AccountId, BalanceOf<T, I>> {
+		EraInfo::<T, I>::full_exposure(era, validator.borrow())
+	}
+
+	/// The validators elected in `era` and their exposure, in no particular order.
+	///
+	/// The eras elected before exposures were paged are not covered.
+	pub fn iter_eras_stakers(
+		era: EraIndex,
+	) -> impl Iterator<Item = (T::AccountId, Exposure<T::AccountId, BalanceOf<T, I>>)> {
+		EraInfo::<T, I>::iter_full_exposures(era)
+	}
+
+	/// The totals of the exposure of `validator` in `era`, if it was elected in that era.
+	pub fn eras_stakers_overview(
+		era: EraIndex,
+		validator: T::AccountId,
+	) -> Option<ExposureOverview<BalanceOf<T, I>>> {
+		EraInfo::<T, I>::overview(era, &validator)
+	}
+
+	/// The nominators exposed to `validator` in `era`, from the `page * EXPOSURE_PAGE_SIZE`th
+	/// one, by decreasing stake.
+	///
+	/// Returns `None` past the last page.
+	pub fn eras_stakers_page(
+		era: EraIndex,
+		validator: T::AccountId,
+		page: u32,
+	) -> Option<ExposurePage<T::AccountId, BalanceOf<T, I>>> {
+		EraInfo::<T, I>::exposure_page(era, &validator, page)
+	}
+
+	/// Check the staking state of `stash` for inconsistencies between `Bonded`, `Ledger`, the
+	/// staker information and the balance of the stash.
+	///
+	/// A stash that is not bonded and has no staker information is consistent.
+	pub fn check_ledger(stash: &T::AccountId) -> Result<(), LedgerCorruption> {
+		let controller = match Self::bonded(stash) {
+			Some(controller) => controller,
+			None => {
+				let dangling = <Payee<T, I>>::contains_key(stash)
+					|| <Validators<T, I>>::contains_key(stash)
+					|| <Nominators<T, I>>::contains_key(stash);
+				return if dangling { Err(LedgerCorruption::Dangling) } else { Ok(()) };
+			},
+		};
+		let ledger = Self::ledger(&controller).ok_or(LedgerCorruption::MissingLedger)?;
+		if &ledger.stash != stash {
+			return Err(LedgerCorruption::ForeignLedger);
+		}
+
+		let total = ledger.unlocking.iter()
+			.fold(ledger.active, |total, chunk| total.saturating_add(chunk.value));
+		if ledger.total != total {
+			return Err(LedgerCorruption::TotalMismatch);
+		}
+		if ledger.total > Self::ledger_backing(stash) {
+			return Err(LedgerCorruption::TotalAboveBalance);
+		}
+
+		Ok(())
+	}
+
+	/// Check the invariants of the staking storage, returning the first one found broken.
+	///
+	/// - Every ledger belongs to a bonded stash whose controller holds it, and there are as many
+	///   ledgers as bonded stashes.
+	/// - The total of every ledger is its active balance plus its unlocking chunks.
+	/// - `ActiveBonded` holds the active balance of every ledger.
+	/// - The total of the ledger of every agent is at most the funds delegated to it.
+	/// - Ledgers, nominations and exposures of the current era are within their bounds.
+	/// - The counters match the number of validator candidates and nominators.
+	/// - `NominatorsFor` indexes exactly the targets of the nominators.
+	/// - `IsInvulnerable` and `HasAutoPayout` hold exactly the accounts of their Vec.
+	/// - `PendingSlashCount` counts exactly the slashes in `UnappliedSlashes` hitting each stash.
+	/// - The total of every exposure of the current era is its own stake plus its nominators'
+	///   stake.
+	///
+	/// The staking lock of every stash is meant to be its ledger total, but the currency does not
+	/// expose the locks: the tests compare them after every session, see
+	/// `mock::check_staking_locks`.
+	#[cfg(any(feature = "try-runtime", test))]
+	pub fn do_try_state() -> Result<(), &'static str> {
+		let mut ledger_count = 0usize;
+		for ledger in <Ledger<T, I>>::iter() {
+			ledger_count += 1;
+			let controller = Self::bonded(&ledger.stash)
+				.ok_or("ledger of a stash that is not bonded")?;
+			if Self::ledger(&controller).map_or(true, |l| l.stash != ledger.stash) {
+				return Err("ledger not held by the controller of its stash");
+			}
+			let total = ledger.unlocking.iter()
+				.fold(ledger.active, |total, chunk| total.saturating_add(chunk.value));
+			if ledger.total != total {
+				return Err("ledger total is not active plus unlocking");
+			}
+			if ledger.unlocking.len() > T::MaxUnlockingChunks::get() as usize {
+				return Err("ledger unlocking more than MaxUnlockingChunks chunks");
+			}
+			if Self::active_bonded(&ledger.stash) != ledger.active {
+				return Err("ActiveBonded does not match the ledger");
+			}
+			if Self::is_virtual_staker(&ledger.stash)
+				&& ledger.total > Self::ledger_backing(&ledger.stash)
+			{
+				return Err("ledger of a virtual staker above the funds backing it");
+			}
+		}
+		if <Bonded<T, I>>::iter().count() != ledger_count {
+			return Err("bonded stashes and ledgers differ in number");
+		}
+
+		if Self::counter_for_validators() as usize != Self::iter_validators().count() {
+			return Err("CounterForValidators does not match Validators");
+		}
+		let mut nominator_count = 0usize;
+		let mut nomination_count = 0usize;
+		for (nominator, nominations) in Self::iter_nominators() {
+			nominator_count += 1;
+			if nominations.targets.len() > T::MaxNominations::get() as usize {
+				return Err("nominations with more than MaxNominations targets");
+			}
+			let mut targets = nominations.targets;
+			targets.sort();
+			targets.dedup();
+			nomination_count += targets.len();
+			let indexed = |target: &T::AccountId|
+				Self::nominators_for(target).binary_search(&nominator).is_ok();
+			if !targets.iter().all(indexed) {
+				return Err("nomination missing from NominatorsFor");
+			}
+		}
+		if Self::counter_for_nominators() as usize != nominator_count {
+			return Err("CounterForNominators does not match Nominators");
+		}
+		let indexed_count = <NominatorsFor<T, I>>::iter()
+			.map(|nominators| nominators.len())
+			.sum::<usize>();
+		if indexed_count != nomination_count {
+			return Err("NominatorsFor holds stale nominations");
+		}
+
+		let invulnerables = Self::invulnerables();
+		if !invulnerables.iter().all(|stash| <IsInvulnerable<T, I>>::get(stash))
+			|| <IsInvulnerable<T, I>>::iter().count() != invulnerables.len()
+		{
+			return Err("IsInvulnerable does not match Invulnerables");
+		}
+		let auto_payout_stashes = Self::auto_payout_stashes();
+		if !auto_payout_stashes.iter().all(|stash| <HasAutoPayout<T, I>>::get(stash))
+			|| <HasAutoPayout<T, I>>::iter().count() != auto_payout_stashes.len()
+		{
+			return Err("HasAutoPayout does not match AutoPayoutStashes");
+		}
+
+		let mut pending_slash_count = BTreeMap::<T::AccountId, u32>::new();
+		for slash in <UnappliedSlashes<T, I>>::iter().flatten() {
+			let stashes = sp_std::iter::once(slash.validator)
+				.chain(slash.others.into_iter().map(|(who, _)| who));
+			for stash in stashes {
+				*pending_slash_count.entry(stash).or_default() += 1;
+			}
+		}
+		let counted = |(stash, count): (&T::AccountId, &u32)|
+			Self::pending_slash_count(stash) == *count;
+		if <PendingSlashCount<T, I>>::iter().count() != pending_slash_count.len()
+			|| !pending_slash_count.iter().all(counted)
+		{
+			return Err("PendingSlashCount does not match UnappliedSlashes");
+		}
+
+		if let Some(current_era) = Self::current_era() {
+			for (_, exposure) in EraInfo::<T, I>::iter_full_exposures(current_era) {
+				let total = exposure.others.iter()
+					.fold(exposure.own, |total, individual| total.saturating_add(individual.value));
+				if exposure.total != total {
+					return Err("exposure total is not own plus others");
+				}
+				if exposure.others.len() > T::MaxNominatorExposedPerValidator::get() as usize {
+					return Err("exposure with more than MaxNominatorExposedPerValidator nominators");
+				}
+			}
+		}
+
+		Ok(())
+	}
+
+	/// The deferred slashes that are not applied yet, by era of report and id.
+	pub fn pending_slashes() -> Vec<PendingSlash<T::AccountId, BalanceOf<T, I>>> {
+		let earliest = match <Self as Store>::EarliestUnappliedSlash::get() {
+			Some(earliest) => earliest,
+			None => return Vec::new(),
+		};
+		let active_era = match Self::active_era() {
+			Some(active_era) => active_era.index,
+			None => return Vec::new(),
+		};
+		let slash_defer_duration = Self::slash_defer_duration();
+
+		(earliest..=active_era).flat_map(|era| {
+			Self::unapplied_slashes(era).into_iter().map(move |slash| PendingSlash {
+				era,
+				id: slash.id,
+				validator: slash.validator,
+				own: slash.own,
+				others: slash.others,
+				payout: slash.payout,
+				apply_at: era.saturating_add(slash_defer_duration).saturating_add(1),
+			})
+		}).collect()
+	}
+
+	/// The amount each stash would lose if `validator` committed an offence in the active era that
+	/// slashes `slash_fraction` of its exposure.
+	///
+	/// The validator comes first, followed by its nominators. Stashes that would lose nothing are
+	/// omitted. Storage is left untouched.
+	pub fn simulate_slash(
+		validator: T::AccountId,
+		slash_fraction: Perbill,
+	) -> Vec<(T::AccountId, BalanceOf<T, I>)> {
+		let active_era = match Self::active_era() {
+			Some(active_era) => active_era.index,
+			None => return Vec::new(),
+		};
+		if Self::is_invulnerable(&validator) {
+			return Vec::new();
+		}
+
+		let exposure = EraInfo::<T, I>::full_exposure(active_era, &validator);
+		slashing::preview_slash::<T, I>(slashing::SlashParams {
+			stash: &validator,
+			slash: slash_fraction,
+			exposure: &exposure,
+			slash_era: active_era,
+			window_start: active_era.saturating_sub(T::BondingDuration::get()),
+			now: active_era,
+			reward_proportion: Self::reporters_reward_fraction(0),
+		})
+			.into_iter()
+			.filter(|(_, value)| !value.is_zero())
+			.collect()
+	}
+
+	/// The preferences of `stash`, if it is a validator candidate.
+	pub fn validator_prefs(
+		stash: T::AccountId,
+	) -> Option<ValidatorPrefs<T::AccountId, BalanceOf<T, I>>> {
+		if <Validators<T, I>>::contains_key(&stash) {
+			Some(Self::validators(stash))
+		} else {
+			None
+		}
+	}
+
+	/// The rewards `stash` can still claim, for each era in the history depth it has not been paid
+	/// out for.
+	///
+	/// The stash is rewarded as a validator in the eras it was elected in, else as a nominator of
+	/// every validator exposing it. Eras with no reward are omitted.
+	pub fn pending_rewards(stash: T::AccountId) -> Vec<(EraIndex, BalanceOf<T, I>)> {
+		Self::claimable_payouts(stash).into_iter()
+			.map(|payout| (payout.era, payout.amount))
+			.collect()
+	}
+
+	/// The payouts `stash` can still claim, as `pending_rewards`, along with the arguments of the
+	/// call claiming each of them.
+	pub fn claimable_payouts(
+		stash: T::AccountId,
+	) -> Vec<ClaimablePayout<T::AccountId, BalanceOf<T, I>>> {
+		let last_reward = match Self::bonded(&stash).and_then(Self::ledger) {
+			Some(ledger) => ledger.last_reward,
+			None => return Vec::new(),
+		};
+		let active_era = match Self::active_era() {
+			Some(active_era) => active_era.index,
+			None => return Vec::new(),
+		};
+		let first_unclaimed = active_era.saturating_sub(Self::history_depth())
+			.max(last_reward.map_or(0, |era| era + 1));
+
+		(first_unclaimed..active_era).filter_map(|era| {
+			let era_payout = EraInfo::<T, I>::validator_reward(era)?;
+			let era_reward_points = EraInfo::<T, I>::reward_points(era);
+
+			let (reward, call) = if EraInfo::<T, I>::is_elected(era, &stash) {
+				let (commission_reward, own_reward) =
+					Self::validator_reward_parts(era, &stash, &era_reward_points);
+				(commission_reward.saturating_add(own_reward), PayoutCall::Validator(era))
+			} else {
+				let validators = Self::nominator_payout_indices(era, &stash, &era_reward_points);
+				let reward = validators.iter()
+					.filter_map(|(validator, index)| Self::nominator_reward_part(
+						era,
+						&stash,
+						validator,
+						*index,
+						&era_reward_points,
+					))
+					.fold(Perbill::zero(), |reward, part| reward.saturating_add(part));
+				(reward, PayoutCall::Nominator(era, validators))
+			};
+
+			let amount = reward * era_payout;
+			if amount.is_zero() {
+				None
+			} else {
+				Some(ClaimablePayout { era, amount, call })
+			}
+		}).collect()
+	}
+
+	/// The reward points of each validator in the eras `from..=to`, by era, in the order of the
+	/// validator stashes.
+	///
+	/// Only the eras from the history depth to the active era are considered.
+	pub fn reward_points(from: EraIndex, to: EraIndex) -> Vec<ValidatorEraPoints<T::AccountId>> {
+		let active_era = match Self::active_era() {
+			Some(active_era) => active_era.index,
+			None => return Vec::new(),
+		};
+		let from = from.max(active_era.saturating_sub(Self::history_depth()));
+		let to = to.min(active_era);
+
+		(from..=to).flat_map(|era| {
+			let era_reward_points = EraInfo::<T, I>::reward_points(era);
+			let total = era_reward_points.total;
+			era_reward_points.individual.into_iter()
+				.map(move |(validator, points)| ValidatorEraPoints {
+					era,
+					validator,
+					points,
+					share: Perbill::from_rational_approximation(points, total),
+				})
+		}).collect()
+	}
+
+	/// The `(validator, nominator_index)` pairs to pass to `payout_nominator` for the nominator
+	/// `stash` to claim its reward for `era`, at most `T::MaxNominations` of them.
+	fn nominator_payout_indices(
+		era: EraIndex,
+		stash: &T::AccountId,
+		era_reward_points: &EraRewardPoints<T::AccountId>,
+	) -> Vec<(T::AccountId, u32)> {
+		era_reward_points.individual.keys()
+			.filter_map(|validator| EraInfo::<T, I>::rewarded_exposure(era, validator).others.iter()
+				.position(|individual| &individual.who == stash)
+				.map(|index| (validator.clone(), index as u32))
+			)
+			.take(T::MaxNominations::get() as usize)
+			.collect()
+	}
+
+	// MUTABLES (DANGEROUS)
+
+	/// Bond `value` of the funds `T::StakeKeeper` holds for `stash`, controlled by `controller`.
+	///
+	/// The stash is bonded virtually: its funds are not locked, the keeper holding them, and only
+	/// the keeper moves them when it is slashed. Its rewards are paid into it, as they are not
+	/// held by the keeper. It is unbonded and withdrawn as any other stash.
+	///
+	/// For the keeper only, there is no dispatchable to call it.
+	pub fn virtual_bond(
+		stash: &T::AccountId,
+		controller: &T::AccountId,
+		value: BalanceOf<T, I>,
+		payee: RewardDestination,
+	) -> DispatchResult {
+		Self::ensure_migrated()?;
+		ensure!(T::BondingRestriction::is_allowed(stash), Error::<T, I>::StakingNotAllowed);
+		ensure!(!<Bonded<T, I>>::contains_key(stash), Error::<T, I>::AlreadyBonded);
+		ensure!(!<SpanRecordsToClear<T, I>>::contains_key(stash), Error::<T, I>::SpanRecordsNotCleared);
+		ensure!(!<Ledger<T, I>>::contains_key(controller), Error::<T, I>::AlreadyPaired);
+		ensure!(value >= Self::minimum_active_bond(), Error::<T, I>::InsufficientValue);
+		ensure!(
+			value <= <T::StakeKeeper as asset::StakeKeeper<_, _, _>>::backing(stash),
+			Error::<T, I>::InsufficientBacking,
+		);
+
+		<Bonded<T, I>>::insert(stash, controller);
+		<Payee<T, I>>::insert(stash, payee);
+		<VirtualStakers<T, I>>::insert(stash, true);
+		<KeptStakers<T, I>>::insert(stash, true);
+
+		system::Module::<T>::inc_ref(stash);
 
-	/// The total balance that can be slashed from a stash account as of right now.
-	pub fn slashable_balance_of(stash: &T::AccountId) -> BalanceOf<T> {
-		Self::bonded(stash).and_then(Self::ledger).map(|l| l.active).unwrap_or_default()
+		let item = StakingLedger {
+			stash: stash.clone(),
+			total: value,
+			active: value,
+			unlocking: vec![],
+			last_reward: Self::current_era(),
+		};
+		Self::update_ledger(controller, &item);
+		Ok(())
 	}
 
-	// MUTABLES (DANGEROUS)
+	/// Bond `extra` more of the funds `T::StakeKeeper` holds for `stash`, bonded by
+	/// `virtual_bond`.
+	///
+	/// For the keeper only, there is no dispatchable to call it.
+	pub fn virtual_bond_extra(stash: &T::AccountId, extra: BalanceOf<T, I>) -> DispatchResult {
+		Self::ensure_migrated()?;
+		ensure!(Self::is_kept_staker(stash), Error::<T, I>::NotStash);
+		ensure!(T::BondingRestriction::is_allowed(stash), Error::<T, I>::StakingNotAllowed);
+		let controller = Self::bonded(stash).ok_or(Error::<T, I>::NotStash)?;
+		let mut ledger = Self::ledger(&controller).ok_or(Error::<T, I>::NotController)?;
+		let total = ledger.total.saturating_add(extra);
+		ensure!(
+			total <= <T::StakeKeeper as asset::StakeKeeper<_, _, _>>::backing(stash),
+			Error::<T, I>::InsufficientBacking,
+		);
+
+		ledger.total = total;
+		ledger.active = ledger.active.saturating_add(extra);
+		Self::update_ledger(&controller, &ledger);
+		Ok(())
+	}
 
 	fn do_payout_nominator(who: T::AccountId, era: EraIndex, validators: Vec<(T::AccountId, u32)>)
 		-> DispatchResult
 	{
-		// validators len must not exceed `MAX_NOMINATIONS` to avoid querying more validator
+		// validators len must not exceed `T::MaxNominations` to avoid querying more validator
 		// exposure than necessary.
-		if validators.len() > MAX_NOMINATIONS {
-			return Err(Error::<T>::InvalidNumberOfNominations.into());
+		if validators.len() > T::MaxNominations::get() as usize {
+			return Err(Error::<T, I>::InvalidNumberOfNominations.into());
 		}
 
 		// Note: if era has no reward to be claimed, era may be future. better not to update
 		// `nominator_ledger.last_reward` in this case.
-		let era_payout = <ErasValidatorReward<T>>::get(&era)
-			.ok_or_else(|| Error::<T>::InvalidEraToReward)?;
+		let era_payout = EraInfo::<T, I>::validator_reward(era)
+			.ok_or_else(|| Error::<T, I>::InvalidEraToReward)?;
 
-		let mut nominator_ledger = <Ledger<T>>::get(&who).ok_or_else(|| Error::<T>::NotController)?;
+		let mut nominator_ledger = <Ledger<T, I>>::get(&who).ok_or_else(|| Error::<T, I>::NotController)?;
 
 		if nominator_ledger.last_reward.map(|last_reward| last_reward >= era).unwrap_or(false) {
-			return Err(Error::<T>::InvalidEraToReward.into());
+			return Err(Error::<T, I>::InvalidEraToReward.into());
 		}
 
 		nominator_ledger.last_reward = Some(era);
-		<Ledger<T>>::insert(&who, &nominator_ledger);
+		<Ledger<T, I>>::insert(&who, &nominator_ledger);
 
 		let mut reward = Perbill::zero();
-		let era_reward_points = <ErasRewardPoints<T>>::get(&era);
+		let era_reward_points = EraInfo::<T, I>::reward_points(era);
 
 		for (validator, nominator_index) in validators.into_iter() {
-			let commission = Self::eras_validator_prefs(&era, &validator).commission;
-			let validator_exposure = <ErasStakersClipped<T>>::get(&era, &validator);
-
-			if let Some(nominator_exposure) = validator_exposure.others
-				.get(nominator_index as usize)
-			{
-				if nominator_exposure.who != nominator_ledger.stash {
-					continue;
-				}
-
-				let nominator_exposure_part = Perbill::from_rational_approximation(
-					nominator_exposure.value,
-					validator_exposure.total,
-				);
-				let validator_point = era_reward_points.individual.get(&validator)
-					.map(|points| *points)
-					.unwrap_or_else(|| Zero::zero());
-				let validator_point_part = Perbill::from_rational_approximation(
-					validator_point,
-					era_reward_points.total,
-				);
-				reward = reward.saturating_add(
-					validator_point_part
-						.saturating_mul(Perbill::one().saturating_sub(commission))
-						.saturating_mul(nominator_exposure_part)
-				);
+			if let Some(part) = Self::nominator_reward_part(
+				era,
+				&nominator_ledger.stash,
+				&validator,
+				nominator_index,
+				&era_reward_points,
+			) {
+				reward = reward.saturating_add(part);
+				let page = nominator_index / EXPOSURE_PAGE_SIZE as u32;
+				Self::deposit_event(RawEvent::PayoutStarted(era, validator, Some(page)));
 			}
 		}
 
 		if let Some(imbalance) = Self::make_payout(&nominator_ledger.stash, reward * era_payout) {
-			Self::deposit_event(RawEvent::Reward(who, imbalance.peek()));
+			Self::deposit_reward_events(who, nominator_ledger.stash, era, imbalance.peek());
 		}
 
 		Ok(())
@@ -1528,69 +3945,323 @@ impl<T: Trait> Module<T> {
 	fn do_payout_validator(who: T::AccountId, era: EraIndex) -> DispatchResult {
 		// Note: if era has no reward to be claimed, era may be future. better not to update
 		// `ledger.last_reward` in this case.
-		let era_payout = <ErasValidatorReward<T>>::get(&era)
-			.ok_or_else(|| Error::<T>::InvalidEraToReward)?;
+		let era_payout = EraInfo::<T, I>::validator_reward(era)
+			.ok_or_else(|| Error::<T, I>::InvalidEraToReward)?;
 
-		let mut ledger = <Ledger<T>>::get(&who).ok_or_else(|| Error::<T>::NotController)?;
+		let mut ledger = <Ledger<T, I>>::get(&who).ok_or_else(|| Error::<T, I>::NotController)?;
 		if ledger.last_reward.map(|last_reward| last_reward >= era).unwrap_or(false) {
-			return Err(Error::<T>::InvalidEraToReward.into());
+			return Err(Error::<T, I>::InvalidEraToReward.into());
 		}
+		ensure!(EraInfo::<T, I>::is_elected(era, &ledger.stash), Error::<T, I>::NotElected);
 
 		ledger.last_reward = Some(era);
-		<Ledger<T>>::insert(&who, &ledger);
+		<Ledger<T, I>>::insert(&who, &ledger);
+		Self::deposit_event(RawEvent::PayoutStarted(era, ledger.stash.clone(), None));
+
+		let era_reward_points = EraInfo::<T, I>::reward_points(era);
+		let (commission_reward, own_reward) =
+			Self::validator_reward_parts(era, &ledger.stash, &era_reward_points);
+
+		let mut total_paid = BalanceOf::<T, I>::zero();
+		let mut reward = commission_reward.saturating_add(own_reward) * era_payout;
+		let prefs = EraInfo::<T, I>::validator_prefs(era, &ledger.stash);
+		if let Some(commission_payee) = prefs.commission_payee {
+			// A commission too small to create the account of the payee is paid along with the
+			// rest of the reward.
+			let commission = commission_reward * era_payout;
+			let imbalance = T::Currency::deposit_creating(&commission_payee, commission);
+			if !imbalance.peek().is_zero() {
+				total_paid = imbalance.peek();
+				reward = own_reward * era_payout;
+				EraInfo::<T, I>::note_reward_paid(era, imbalance.peek());
+				Self::deposit_event(RawEvent::CommissionReward(commission_payee, imbalance.peek()));
+			}
+		}
+
+		if let Some(imbalance) = Self::make_payout(&ledger.stash, reward) {
+			total_paid = total_paid.saturating_add(imbalance.peek());
+			Self::deposit_reward_events(who, ledger.stash, era, imbalance.peek());
+		}
+		if !total_paid.is_zero() {
+			EraInfo::<T, I>::note_validator_paid(era);
+		}
+
+		Ok(())
+	}
+
+	/// The number of eras, at most `eras`, that `payout_stakers_all` can process within
+	/// `max_weight`. No more eras than the history depth can be paid out at once.
+	fn payout_eras_within(max_weight: Weight, eras: EraIndex) -> EraIndex {
+		let (mut low, mut high) = (0, eras.min(Self::history_depth()));
+		while low < high {
+			let middle = high - (high - low) / 2;
+			if T::WeightInfo::payout_stakers_all(middle) <= max_weight {
+				low = middle;
+			} else {
+				high = middle - 1;
+			}
+		}
+		low
+	}
 
-		let era_reward_points = <ErasRewardPoints<T>>::get(&era);
-		let commission = Self::eras_validator_prefs(&era, &ledger.stash).commission;
-		let exposure = <ErasStakers<T>>::get(&era, &ledger.stash);
+	/// The parts of the payout of `era` earned by the validator `stash`: its commission and the
+	/// reward for its own stake.
+	fn validator_reward_parts(
+		era: EraIndex,
+		stash: &T::AccountId,
+		era_reward_points: &EraRewardPoints<T::AccountId>,
+	) -> (Perbill, Perbill) {
+		let commission = EraInfo::<T, I>::validator_prefs(era, stash).commission;
+		let exposure = EraInfo::<T, I>::overview(era, stash).unwrap_or_default();
 
 		let exposure_part = Perbill::from_rational_approximation(
 			exposure.own,
 			exposure.total,
 		);
-		let validator_point = era_reward_points.individual.get(&ledger.stash)
+		let validator_point = era_reward_points.individual.get(stash)
 			.map(|points| *points)
 			.unwrap_or_else(|| Zero::zero());
 		let validator_point_part = Perbill::from_rational_approximation(
 			validator_point,
 			era_reward_points.total,
 		);
-		let reward = validator_point_part.saturating_mul(
-			commission.saturating_add(
-				Perbill::one().saturating_sub(commission).saturating_mul(exposure_part)
-			)
-		);
+		let own_part = Perbill::one().saturating_sub(commission).saturating_mul(exposure_part);
 
-		if let Some(imbalance) = Self::make_payout(&ledger.stash, reward * era_payout) {
-			Self::deposit_event(RawEvent::Reward(who, imbalance.peek()));
+		(validator_point_part.saturating_mul(commission), validator_point_part.saturating_mul(own_part))
+	}
+
+	/// The part of the payout of `era` earned by the nominator `stash` through `validator`, or
+	/// `None` if `stash` is not at `nominator_index` among the rewarded nominators of `validator`.
+	fn nominator_reward_part(
+		era: EraIndex,
+		stash: &T::AccountId,
+		validator: &T::AccountId,
+		nominator_index: u32,
+		era_reward_points: &EraRewardPoints<T::AccountId>,
+	) -> Option<Perbill> {
+		let commission = EraInfo::<T, I>::validator_prefs(era, validator).commission;
+		let validator_exposure = EraInfo::<T, I>::rewarded_exposure(era, validator);
+
+		let nominator_exposure = validator_exposure.others.get(nominator_index as usize)?;
+		if &nominator_exposure.who != stash {
+			return None;
 		}
 
-		Ok(())
+		let nominator_exposure_part = Perbill::from_rational_approximation(
+			nominator_exposure.value,
+			validator_exposure.total,
+		);
+		let validator_point = era_reward_points.individual.get(validator)
+			.map(|points| *points)
+			.unwrap_or_else(|| Zero::zero());
+		let validator_point_part = Perbill::from_rational_approximation(
+			validator_point,
+			era_reward_points.total,
+		);
+		Some(
+			validator_point_part
+				.saturating_mul(Perbill::one().saturating_sub(commission))
+				.saturating_mul(nominator_exposure_part)
+		)
+	}
+
+	/// Note the reward claimed and deposit the events of a reward paid to the staker with the
+	/// given controller and stash.
+	fn deposit_reward_events(
+		controller: T::AccountId,
+		stash: T::AccountId,
+		era: EraIndex,
+		amount: BalanceOf<T, I>,
+	) {
+		let dest = Self::payee(&stash);
+		EraInfo::<T, I>::note_reward_paid(era, amount);
+		Self::deposit_event(RawEvent::Reward(controller, amount));
+		Self::deposit_event(RawEvent::Rewarded(stash, era, dest, amount));
 	}
 
 	/// Update the ledger for a controller. This will also update the stash lock. The lock will
 	/// will lock the entire funds except paying for further transactions.
+	///
+	/// The funds of an agent are not locked, its ledger being backed by delegated funds.
 	fn update_ledger(
 		controller: &T::AccountId,
-		ledger: &StakingLedger<T::AccountId, BalanceOf<T>>
+		ledger: &StakingLedger<T::AccountId, BalanceOf<T, I>>
+	) {
+		if !Self::is_virtual_staker(&ledger.stash) {
+			T::Currency::lock(T::LockId::get(), &ledger.stash, ledger.total);
+		}
+		let prev_active = Self::active_bonded(&ledger.stash);
+		<Ledger<T, I>>::insert(controller, ledger);
+		<ActiveBonded<T, I>>::insert(&ledger.stash, ledger.active);
+		T::OnStakingUpdate::on_stake_update(&ledger.stash, prev_active, ledger.active);
+	}
+
+	/// Chill a stash account, telling why if it was validating or nominating.
+	fn chill_stash(stash: &T::AccountId, reason: ChillReason) {
+		Self::deposit_chilled_if_active(stash, reason);
+		Self::remove_validator(stash);
+		Self::remove_nominations(stash);
+		T::OnStakingUpdate::on_chill(stash);
+	}
+
+	/// Deposit `RawEvent::Chilled` if `stash` is a validator candidate or a nominator, which it is
+	/// about to stop being for `reason`.
+	fn deposit_chilled_if_active(stash: &T::AccountId, reason: ChillReason) {
+		if <Validators<T, I>>::contains_key(stash) || <Nominators<T, I>>::contains_key(stash) {
+			Self::deposit_event(RawEvent::Chilled(stash.clone(), reason));
+		}
+	}
+
+	/// Generate `validators` validator candidates and `nominators` nominators, each bonding funds
+	/// minted for it. Each nominator nominates the `genesis_validators` following the previous
+	/// one's.
+	///
+	/// The generated validators have no session keys to author blocks with, so they bond the
+	/// lowest stake a validator may bond, below the stake of every genesis validator: they are
+	/// only elected if fewer genesis validators than the validator count are candidates.
+	#[cfg(feature = "std")]
+	fn generate_dev_stakers(validators: u32, nominators: u32, genesis_validators: &[T::AccountId]) {
+		assert!(
+			!genesis_validators.is_empty() || nominators == 0,
+			"Generated nominators need genesis validators to nominate."
+		);
+		let stake = T::Currency::minimum_balance().max(1u32.into()).saturating_mul(1_000u32.into());
+		let validator_stake = Self::minimum_active_bond()
+			.max(Self::parameters().min_validator_bond.unwrap_or_else(Zero::zero))
+			.max(1u32.into());
+		assert!(
+			genesis_validators.iter().all(|stash| Self::active_bonded(stash) > validator_stake),
+			"Generated validators would bond as much as a genesis validator."
+		);
+
+		for index in 0..validators {
+			let stash = Self::bond_dev_staker(b"validator", index, validator_stake);
+			Self::do_validate(&stash, validator_stake, Default::default())
+				.expect("Generated validator could not validate.");
+		}
+
+		let targets = genesis_validators.len().min(T::MaxNominations::get() as usize);
+		for index in 0..nominators {
+			let stash = Self::bond_dev_staker(b"nominator", index, stake);
+			let targets = genesis_validators.iter()
+				.cycle()
+				.skip(index as usize % genesis_validators.len())
+				.take(targets)
+				.map(|target| T::Lookup::unlookup(target.clone()))
+				.collect();
+			Self::nominate(T::Origin::from(Some(stash).into()), targets)
+				.expect("Generated nominator could not nominate.");
+		}
+	}
+
+	/// Mint `stake` into an account derived from `role` and `index`, bonding it all with the
+	/// account as its own controller.
+	#[cfg(feature = "std")]
+	fn bond_dev_staker(role: &[u8], index: u32, stake: BalanceOf<T, I>) -> T::AccountId {
+		let seed = (b"staking:dev", role, index).using_encoded(sp_io::hashing::blake2_256);
+		let stash = T::AccountId::decode(&mut sp_runtime::traits::TrailingZeroInput::new(&seed))
+			.expect("infinite length input; no invalid inputs for type; qed");
+		let _ = T::Currency::deposit_creating(&stash, stake);
+		Self::bond(
+			T::Origin::from(Some(stash.clone()).into()),
+			T::Lookup::unlookup(stash.clone()),
+			stake,
+			RewardDestination::Staked,
+		).expect("Generated staker could not be bonded.");
+		stash
+	}
+
+	/// Make `stash`, with `active` funds bonded, a validator candidate with `prefs` if it is
+	/// allowed by `T::CandidateFilter`, dropping its nominations.
+	fn do_validate(
+		stash: &T::AccountId,
+		active: BalanceOf<T, I>,
+		prefs: ValidatorPrefs<T::AccountId, BalanceOf<T, I>>,
+	) -> DispatchResult {
+		ensure!(T::CandidateFilter::is_allowed(stash, active), Error::<T, I>::CandidateNotAllowed);
+		let parameters = Self::parameters();
+		ensure!(
+			parameters.min_validator_bond.map_or(true, |min| active >= min),
+			Error::<T, I>::InsufficientBond,
+		);
+		ensure!(
+			parameters.min_commission.map_or(true, |min| prefs.commission >= min),
+			Error::<T, I>::CommissionTooLow,
+		);
+		if <Nominators<T, I>>::contains_key(stash) {
+			Self::deposit_event(RawEvent::Chilled(stash.clone(), ChillReason::RoleChanged));
+		}
+		Self::remove_nominations(stash);
+		Self::set_validator_prefs(stash, prefs.clone());
+		Self::deposit_event(RawEvent::ValidatorPrefsSet(stash.clone(), prefs));
+		Ok(())
+	}
+
+	/// Set the preferences of the validator candidate `stash`, counting it if it is new.
+	fn set_validator_prefs(
+		stash: &T::AccountId,
+		prefs: ValidatorPrefs<T::AccountId, BalanceOf<T, I>>,
 	) {
-		T::Currency::set_lock(
-			STAKING_ID,
-			&ledger.stash,
-			ledger.total,
-			WithdrawReasons::all(),
+		let prev_prefs = if <Validators<T, I>>::contains_key(stash) {
+			Some(Self::validators(stash))
+		} else {
+			CounterForValidators::<I>::mutate(|count| *count = count.saturating_add(1));
+			None
+		};
+		<Validators<T, I>>::insert(stash, &prefs);
+		T::OnStakingUpdate::on_validator_update(stash, prev_prefs.as_ref(), &prefs);
+	}
+
+	/// Remove `stash` from the validator candidates, if it is one.
+	fn remove_validator(stash: &T::AccountId) {
+		if <Validators<T, I>>::contains_key(stash) {
+			<Validators<T, I>>::remove(stash);
+			CounterForValidators::<I>::mutate(|count| *count = count.saturating_sub(1));
+		}
+	}
+
+	/// Set the nominations of `stash`, updating `NominatorsFor` for the old and new targets.
+	fn set_nominations(stash: &T::AccountId, nominations: Nominations<T::AccountId>) {
+		let prev_targets = Self::nominators(stash).map(|prev| prev.targets);
+		Self::remove_nominations(stash);
+		for target in &nominations.targets {
+			<NominatorsFor<T, I>>::mutate(target, |nominators| {
+				if let Err(index) = nominators.binary_search(stash) {
+					nominators.insert(index, stash.clone());
+				}
+			});
+		}
+		<Nominators<T, I>>::insert(stash, &nominations);
+		CounterForNominators::<I>::mutate(|count| *count = count.saturating_add(1));
+		T::OnStakingUpdate::on_nominator_update(
+			stash,
+			prev_targets.as_ref().map(|targets| &targets[..]),
+			&nominations.targets,
 		);
-		<Ledger<T>>::insert(controller, ledger);
 	}
 
-	/// Chill a stash account.
-	fn chill_stash(stash: &T::AccountId) {
-		<Validators<T>>::remove(stash);
-		<Nominators<T>>::remove(stash);
+	/// Remove the nominations of `stash`, if any, from `Nominators` and `NominatorsFor`.
+	fn remove_nominations(stash: &T::AccountId) {
+		if let Some(nominations) = <Nominators<T, I>>::take(stash) {
+			CounterForNominators::<I>::mutate(|count| *count = count.saturating_sub(1));
+			for target in &nominations.targets {
+				<NominatorsFor<T, I>>::mutate_exists(target, |maybe_nominators| {
+					if let Some(nominators) = maybe_nominators {
+						if let Ok(index) = nominators.binary_search(stash) {
+							nominators.remove(index);
+						}
+						if nominators.is_empty() {
+							*maybe_nominators = None;
+						}
+					}
+				});
+			}
+		}
 	}
 
 	/// Actually make a payment to a staker. This uses the currency's reward function
 	/// to pay the right payee for the given staker account.
-	fn make_payout(stash: &T::AccountId, amount: BalanceOf<T>) -> Option<PositiveImbalanceOf<T>> {
+	fn make_payout(stash: &T::AccountId, amount: BalanceOf<T, I>) -> Option<PositiveImbalanceOf<T, I>> {
 		let dest = Self::payee(stash);
 		match dest {
 			RewardDestination::Controller => Self::bonded(stash)
@@ -1599,6 +4270,9 @@ impl<T: Trait> Module<T> {
 				),
 			RewardDestination::Stash =>
 				T::Currency::deposit_into_existing(stash, amount).ok(),
+			// the ledger of an agent is only backed by delegated funds.
+			RewardDestination::Staked if Self::is_virtual_staker(stash) =>
+				T::Currency::deposit_into_existing(stash, amount).ok(),
 			RewardDestination::Staked => Self::bonded(stash)
 				.and_then(|c| Self::ledger(&c).map(|l| (c, l)))
 				.and_then(|(controller, mut l)| {
@@ -1625,10 +4299,17 @@ impl<T: Trait> Module<T> {
 			let era_length = session_index.checked_sub(current_era_start_session_index)
 				.unwrap_or(0); // Must never happen.
 
-			match ForceEra::get() {
-				Forcing::ForceNew => ForceEra::kill(),
+			// The stakes can't be read until the storage is migrated.
+			if !migration::is_migrated::<I>() {
+				return None
+			}
+
+			match ForceEra::<I>::get() {
+				Forcing::ForceNew => Self::set_force_era(Forcing::NotForcing),
 				Forcing::ForceAlways => (),
-				Forcing::NotForcing if era_length >= T::SessionsPerEra::get() => (),
+				Forcing::NotForcing
+					if era_length >= T::SessionsPerEra::get()
+						&& Self::has_lasted_minimum_era_duration() => (),
 				_ => return None,
 			}
 
@@ -1639,6 +4320,20 @@ impl<T: Trait> Module<T> {
 		}
 	}
 
+	/// Whether the active era has lasted `T::MinimumEraDuration` since it started.
+	///
+	/// An era not started yet, which can only be until the end of its first block, is considered
+	/// to have lasted long enough.
+	fn has_lasted_minimum_era_duration() -> bool {
+		let minimum = T::MinimumEraDuration::get();
+		if minimum.is_zero() {
+			return true
+		}
+		Self::active_era()
+			.and_then(|active_era| active_era.start)
+			.map_or(true, |start| T::Time::now().saturating_sub(start) >= minimum)
+	}
+
 	/// Start a session potentially starting an era.
 	fn start_session(start_session: SessionIndex) {
 		let next_active_era = Self::active_era().map(|e| e.index + 1).unwrap_or(0);
@@ -1673,7 +4368,7 @@ impl<T: Trait> Module<T> {
 	/// * reset `active_era.start`,
 	/// * update `BondedEras` and apply slashes.
 	fn start_era(start_session: SessionIndex) {
-		let active_era = <ActiveEra<T>>::mutate(|active_era| {
+		let active_era = <ActiveEra<T, I>>::mutate(|active_era| {
 			let new_index = active_era.as_ref().map(|info| info.index + 1).unwrap_or(0);
 			*active_era = Some(ActiveEraInfo {
 				index: new_index,
@@ -1682,14 +4377,16 @@ impl<T: Trait> Module<T> {
 			});
 			new_index
 		});
+		<ErasTotalIssuance<T, I>>::insert(active_era, T::Currency::total_issuance());
 
-		let bonding_duration = T::BondingDuration::get();
+		let historical_eras_kept = T::HistoricalErasKept::get();
+		let retention = T::BondingDuration::get().max(historical_eras_kept);
 
-		BondedEras::mutate(|bonded| {
+		BondedEras::<I>::mutate(|bonded| {
 			bonded.push((active_era, start_session));
 
-			if active_era > bonding_duration {
-				let first_kept = active_era - bonding_duration;
+			if active_era > retention {
+				let first_kept = active_era - retention;
 
 				// prune out everything that's from before the first-kept index.
 				let n_to_prune = bonded.iter()
@@ -1698,10 +4395,17 @@ impl<T: Trait> Module<T> {
 
 				// kill slashing metadata.
 				for (pruned_era, _) in bonded.drain(..n_to_prune) {
-					slashing::clear_era_metadata::<T>(pruned_era);
+					slashing::clear_era_metadata::<T, I>(pruned_era);
 				}
+			}
+
+			if active_era > historical_eras_kept {
+				let first_historical = active_era - historical_eras_kept;
+				let first_session = bonded.iter()
+					.find(|&&(era_idx, _)| era_idx >= first_historical)
+					.map(|&(_, first_session)| first_session);
 
-				if let Some(&(_, first_session)) = bonded.first() {
+				if let Some(first_session) = first_session {
 					T::SessionInterface::prune_historical_up_to(first_session);
 				}
 			}
@@ -1711,65 +4415,183 @@ impl<T: Trait> Module<T> {
 	}
 
 	/// Compute payout for era.
-	fn end_era(active_era: ActiveEraInfo<MomentOf<T>>, _session_index: SessionIndex) {
+	fn end_era(active_era: ActiveEraInfo<MomentOf<T, I>>, _session_index: SessionIndex) {
 		// Note: active_era_start can be None if end era is called during genesis config.
 		if let Some(active_era_start) = active_era.start {
 			let now = T::Time::now();
 
-			let era_duration = now - active_era_start;
-			let (total_payout, _max_payout) = inflation::compute_total_payout(
-				&T::RewardCurve::get(),
-				Self::eras_total_stake(&active_era.index),
-				T::Currency::total_issuance(),
-				// Duration of era; more than u64::MAX is rewarded as u64::MAX.
-				era_duration.saturated_into::<u64>(),
-			);
+			// Duration of era; more than u64::MAX is rewarded as u64::MAX.
+			let era_duration = (now - active_era_start).saturated_into::<u64>();
+			ErasDuration::<I>::insert(&active_era.index, era_duration);
+			let total_staked = EraInfo::<T, I>::total_stake(active_era.index);
+			// The issuance at the start of the era, so that the payout does not depend on what was
+			// minted or burnt during the era.
+			let total_issuance = Self::eras_total_issuance(&active_era.index)
+				.unwrap_or_else(T::Currency::total_issuance);
+			let (mut staker_payout, mut remainder) = match Self::reward_curve_override() {
+				Some(curve) => {
+					let (payout, max_payout) = inflation::compute_total_payout(
+						&curve.as_piecewise_linear(),
+						total_staked,
+						total_issuance,
+						era_duration,
+					);
+					(payout, max_payout.saturating_sub(payout))
+				}
+				None => T::EraPayout::era_payout(total_staked, total_issuance, era_duration),
+			};
+			if let Some(max_apr) = Self::max_payout_apr() {
+				let max_payout = inflation::compute_max_payout(max_apr, total_staked, era_duration);
+				let excess = staker_payout.saturating_sub(max_payout);
+				staker_payout -= excess;
+				remainder = remainder.saturating_add(excess);
+			}
+			let stake_floor = Self::payout_stake_floor();
+			if total_staked < stake_floor {
+				// The payout is reduced in proportion of the missing stake and not minted at all.
+				let withheld = staker_payout
+					- Perbill::from_rational_approximation(total_staked, stake_floor) * staker_payout;
+				staker_payout -= withheld;
+				Self::deposit_event(
+					RawEvent::PayoutThrottled(active_era.index, total_staked, withheld),
+				);
+			}
+			let extra_remainder = Self::extra_remainder_share() * staker_payout;
+			let staker_payout = staker_payout.saturating_sub(extra_remainder);
+			let remainder = remainder.saturating_add(extra_remainder);
 
 			// Set ending era reward.
-			<ErasValidatorReward<T>>::insert(&active_era.index, total_payout);
+			EraInfo::<T, I>::set_payout(active_era.index, staker_payout, remainder);
+			Self::deposit_event(RawEvent::EraPaid(active_era.index, staker_payout, remainder));
+			T::RewardRemainder::on_unbalanced(T::Currency::issue(remainder));
+		}
+
+		let total_slashed = Self::eras_total_slashed(&active_era.index);
+		if !total_slashed.is_zero() {
+			Self::deposit_event(RawEvent::EraSlashed(active_era.index, total_slashed));
 		}
 	}
 
 	/// Plan a new era. Return the potential new staking set.
 	fn new_era(start_session_index: SessionIndex) -> Option<Vec<T::AccountId>> {
 		// Increment or set current era.
-		let current_era = CurrentEra::mutate(|s| {
+		let current_era = CurrentEra::<I>::mutate(|s| {
 			*s = Some(s.map(|s| s + 1).unwrap_or(0));
 			s.unwrap()
 		});
-		ErasStartSessionIndex::insert(&current_era, &start_session_index);
+		ErasStartSessionIndex::<I>::insert(&current_era, &start_session_index);
 
 		// Clean old era information.
-		if let Some(old_era) = current_era.checked_sub(Self::history_depth() + 1) {
-			Self::clear_era_information(old_era);
-		}
+		Self::prune_history(current_era);
 
 		// Set staking information for new era.
-		let maybe_new_validators = Self::select_validators(current_era);
+		let (election, compute) = match <NextValidators<T, I>>::take() {
+			Some(validators) => (
+				Ok(Self::install_validators(current_era, validators)),
+				ElectionCompute::Governance,
+			),
+			None => {
+				// The election runs in the `on_initialize` of the session module, whose weight
+				// does not account for it.
+				<system::Module<T>>::register_extra_weight_unchecked(T::WeightInfo::new_era(
+					Self::counter_for_validators(),
+					Self::counter_for_nominators(),
+					Self::validator_count(),
+					T::MaxNominations::get(),
+				));
+				(Self::select_validators(current_era), ElectionCompute::OnChain)
+			}
+		};
+
+		match election {
+			Ok(validators) => {
+				Self::deposit_event(RawEvent::StakingElection(current_era, compute));
+				Some(validators)
+			}
+			Err(failure) => {
+				Self::deposit_event(RawEvent::StakingElectionFailed(current_era, failure));
+				None
+			}
+		}
+	}
+
+	/// Store the staking information of `current_era` for the `validators` set by governance,
+	/// each exposed with its own active stake only, and return them.
+	fn install_validators(
+		current_era: EraIndex,
+		validators: Vec<T::AccountId>,
+	) -> Vec<T::AccountId> {
+		let mut total_staked = BalanceOf::<T, I>::zero();
+		let exposures = validators.iter()
+			.map(|stash| {
+				let own = Self::slashable_balance_of(stash);
+				total_staked = total_staked.saturating_add(own);
+				(stash.clone(), Exposure { own, others: vec![], total: own })
+			})
+			.collect::<Vec<_>>();
+		EraInfo::<T, I>::set_exposures(current_era, exposures);
+
+		EraInfo::<T, I>::set_election(
+			current_era,
+			total_staked,
+			validators.iter().map(|stash| (stash.clone(), Self::validators(stash))),
+		);
+		validators
+	}
 
-		maybe_new_validators
+	/// Clear the information of the eras leaving the history at the start of `current_era`,
+	/// moving the history depth towards the pending one, if any.
+	fn prune_history(current_era: EraIndex) {
+		let history_depth = Self::history_depth();
+		let new_history_depth = match Self::pending_history_depth() {
+			Some((pending, from_era)) if pending > history_depth || current_era >= from_era => {
+				let new_history_depth = pending.min(history_depth + 1);
+				if new_history_depth == pending {
+					PendingHistoryDepth::<I>::kill();
+				}
+				HistoryDepth::<I>::put(new_history_depth);
+				new_history_depth
+			},
+			_ => history_depth,
+		};
+
+		// the eras before `current_era - history_depth - 1` were cleared already.
+		let first_cleared = current_era.checked_sub(history_depth + 1).unwrap_or(0);
+		for era_index in first_cleared..current_era.saturating_sub(new_history_depth) {
+			Self::clear_era_information(era_index);
+		}
 	}
 
-	/// Clear all era information for given era.
+	/// Clear all era information for given era, see [`EraInfo::clear`].
 	fn clear_era_information(era_index: EraIndex) {
-		<ErasStakers<T>>::remove_prefix(era_index);
-		<ErasStakersClipped<T>>::remove_prefix(era_index);
-		<ErasValidatorPrefs<T>>::remove_prefix(era_index);
-		<ErasValidatorReward<T>>::remove(era_index);
-		<ErasRewardPoints<T>>::remove(era_index);
-		<ErasTotalStake<T>>::remove(era_index);
-		ErasStartSessionIndex::remove(era_index);
+		Self::sweep_unclaimed_rewards(era_index);
+		EraInfo::<T, I>::clear(era_index);
+	}
+
+	/// Mint the part of the payout of an era that stakers did not claim for `T::UnclaimedRewards`.
+	fn sweep_unclaimed_rewards(era_index: EraIndex) {
+		let unclaimed = EraInfo::<T, I>::payout_summary(era_index).total_unclaimed;
+		if !unclaimed.is_zero() {
+			T::UnclaimedRewards::on_unbalanced(T::Currency::issue(unclaimed));
+			Self::deposit_event(RawEvent::UnclaimedRewardsSwept(era_index, unclaimed));
+		}
 	}
 
 	/// Apply previously-unapplied slashes on the beginning of a new era, after a delay.
+	///
+	/// Nothing is applied while the storage is migrated, as the ledgers may not be found.
 	fn apply_unapplied_slashes(active_era: EraIndex) {
-		let slash_defer_duration = T::SlashDeferDuration::get();
+		if !migration::is_migrated::<I>() {
+			return
+		}
+		let slash_defer_duration = Self::slash_defer_duration();
 		<Self as Store>::EarliestUnappliedSlash::mutate(|earliest| if let Some(ref mut earliest) = earliest {
 			let keep_from = active_era.saturating_sub(slash_defer_duration);
 			for era in (*earliest)..keep_from {
 				let era_slashes = <Self as Store>::UnappliedSlashes::take(&era);
 				for slash in era_slashes {
-					slashing::apply_slash::<T>(slash);
+					Self::count_pending_slash(&slash, false);
+					slashing::apply_slash::<T, I>(slash);
 				}
 			}
 
@@ -1777,27 +4599,59 @@ impl<T: Trait> Module<T> {
 		})
 	}
 
+	/// Apply the slashes that were kept queued while the storage was migrated, once it is: the
+	/// deferred slashes whose deferral ended, then those of the offences reported meanwhile, as if
+	/// the offences were reported now.
+	fn apply_slashes_queued_while_migrating() {
+		if let Some(active_era) = Self::active_era() {
+			Self::apply_unapplied_slashes(active_era.index);
+		}
+		for offence in <Self as Store>::OffencesQueuedWhileMigrating::take() {
+			Self::do_on_offence(
+				&offence.offenders,
+				&offence.slash_fraction,
+				offence.slash_session,
+				&offence.kind,
+			);
+		}
+	}
+
 	/// Select a new validator set from the assembled stakers and their role preferences, and store
 	/// staking information for the new current era.
 	///
-	/// Fill the storages `ErasStakers`, `ErasStakersClipped`, `ErasValidatorPrefs` and
-	/// `ErasTotalStake` for current era.
+	/// Fill the storages `ErasStakersOverview`, `ErasStakersCompact`, `ErasStakersAccounts`,
+	/// `ErasValidatorPrefs` and `ErasTotalStake` for current era.
 	///
-	/// Returns a set of newly selected _stash_ IDs.
+	/// Returns a set of newly selected _stash_ IDs, or why none could be.
 	///
 	/// Assumes storage is coherent with the declaration.
-	fn select_validators(current_era: EraIndex) -> Option<Vec<T::AccountId>> {
-		let mut all_nominators: Vec<(T::AccountId, Vec<T::AccountId>)> = Vec::new();
-		let mut all_validators_and_prefs = BTreeMap::new();
-		let mut all_validators = Vec::new();
-		for (validator, preference) in <Validators<T>>::enumerate() {
+	fn select_validators(current_era: EraIndex) -> Result<Vec<T::AccountId>, ElectionFailure> {
+		let validator_count = Self::counter_for_validators() as usize;
+		let voter_count = validator_count.saturating_add(Self::counter_for_nominators() as usize);
+		let mut all_nominators: Vec<(T::AccountId, Vec<T::AccountId>)> =
+			Vec::with_capacity(voter_count);
+		let mut all_validators = Vec::with_capacity(validator_count);
+		// The stake of each voter, read once for both the election and the supports.
+		let mut stakes = BTreeMap::new();
+		for (validator, _) in Self::iter_validators() {
+			let stake = Self::slashable_balance_of(&validator);
+			// a candidate may no longer be allowed since it declared the intention to validate,
+			// its identity having been cleared for example. It stays a candidate but is not
+			// elected while it is not allowed.
+			if !T::CandidateFilter::is_allowed(&validator, stake) {
+				continue;
+			}
+			stakes.insert(validator.clone(), stake);
 			let self_vote = (validator.clone(), vec![validator.clone()]);
 			all_nominators.push(self_vote);
-			all_validators_and_prefs.insert(validator.clone(), preference);
 			all_validators.push(validator);
 		}
+		let minimum_validator_count = Self::minimum_validator_count().max(1) as usize;
+		if all_validators.len() < minimum_validator_count {
+			return Err(ElectionFailure::NotEnoughCandidates);
+		}
 
-		let nominator_votes = <Nominators<T>>::enumerate().map(|(nominator, nominations)| {
+		let nominator_votes = Self::iter_nominators().map(|(nominator, nominations)| {
 			let Nominations { submitted_in, mut targets, suppressed: _ } = nominations;
 
 			// Filter out nomination targets which were nominated before the most recent
@@ -1809,16 +4663,20 @@ impl<T: Trait> Module<T> {
 				)
 			});
 
+			stakes.insert(nominator.clone(), Self::slashable_balance_of(&nominator));
 			(nominator, targets)
 		});
 		all_nominators.extend(nominator_votes);
+		let stake_of = |who: &T::AccountId| -> BalanceOf<T, I> {
+			stakes.get(who).cloned().unwrap_or_default()
+		};
 
 		let maybe_phragmen_result = sp_phragmen::elect::<_, _, _, T::CurrencyToVote, Perbill>(
 			Self::validator_count() as usize,
-			Self::minimum_validator_count().max(1) as usize,
+			minimum_validator_count,
 			all_validators,
 			all_nominators,
-			Self::slashable_balance_of,
+			&stake_of,
 		);
 
 		if let Some(phragmen_result) = maybe_phragmen_result {
@@ -1826,23 +4684,38 @@ impl<T: Trait> Module<T> {
 				.map(|(s, _)| s)
 				.collect::<Vec<T::AccountId>>();
 			let assignments = phragmen_result.assignments;
+			// The preferences are only read for the elected validators.
+			let elected_prefs = elected_stashes.iter()
+				.map(|stash| Self::validators(stash))
+				.collect::<Vec<_>>();
 
 			let to_balance = |e: ExtendedBalance|
-				<T::CurrencyToVote as Convert<ExtendedBalance, BalanceOf<T>>>::convert(e);
+				<T::CurrencyToVote as Convert<ExtendedBalance, BalanceOf<T, I>>>::convert(e);
 
-			let supports = sp_phragmen::build_support_map::<_, _, _, T::CurrencyToVote, Perbill>(
+			let mut supports = sp_phragmen::build_support_map::<_, _, _, T::CurrencyToVote, Perbill>(
 				&elected_stashes,
 				&assignments,
-				Self::slashable_balance_of,
+				&stake_of,
 			);
 
+			let to_votes = |b: BalanceOf<T, I>|
+				<T::CurrencyToVote as Convert<BalanceOf<T, I>, u64>>::convert(b) as ExtendedBalance;
+			let caps = elected_stashes.iter()
+				.zip(&elected_prefs)
+				.filter_map(|(stash, prefs)| prefs.max_total_stake
+					.map(|max_total_stake| (stash.clone(), to_votes(max_total_stake)))
+				)
+				.collect::<BTreeMap<_, _>>();
+			Self::cap_supports(&mut supports, &caps);
+
 			// Populate stakers information and figure out the total stake.
-			let mut total_staked = BalanceOf::<T>::zero();
+			let mut total_staked = BalanceOf::<T, I>::zero();
+			let mut exposures = Vec::with_capacity(supports.len());
 			for (c, s) in supports.into_iter() {
 				// build `struct exposure` from `support`
 				let mut others = Vec::new();
-				let mut own: BalanceOf<T> = Zero::zero();
-				let mut total: BalanceOf<T> = Zero::zero();
+				let mut own: BalanceOf<T, I> = Zero::zero();
+				let mut total: BalanceOf<T, I> = Zero::zero();
 				s.voters
 					.into_iter()
 					.map(|(who, value)| (who, to_balance(value)))
@@ -1855,6 +4728,15 @@ impl<T: Trait> Module<T> {
 						total = total.saturating_add(value);
 					});
 
+				// Only the biggest nominators are exposed, see `T::MaxNominatorExposedPerValidator`.
+				let max_exposed = T::MaxNominatorExposedPerValidator::get() as usize;
+				if others.len() > max_exposed {
+					others.sort_unstable_by(|a, b| a.value.cmp(&b.value).reverse());
+					others.truncate(max_exposed);
+					total = others.iter()
+						.fold(own, |total, individual| total.saturating_add(individual.value));
+				}
+
 				total_staked = total_staked.saturating_add(total);
 
 				let exposure = Exposure {
@@ -1866,31 +4748,22 @@ impl<T: Trait> Module<T> {
 					// we simulate it in some tests.
 					total,
 				};
-				<ErasStakers<T>>::insert(&current_era, &c, &exposure);
-
-				let mut exposure_clipped = exposure;
-				let clipped_max_len = T::MaxNominatorRewardedPerValidator::get() as usize;
-				if exposure_clipped.others.len() > clipped_max_len {
-					exposure_clipped.others.sort_unstable_by(|a, b| a.value.cmp(&b.value).reverse());
-					exposure_clipped.others.truncate(clipped_max_len);
-				}
-				<ErasStakersClipped<T>>::insert(&current_era, &c, exposure_clipped);
+				exposures.push((c, exposure));
 			}
+			EraInfo::<T, I>::set_exposures(current_era, exposures);
 
 			// Insert current era staking informations
-			<ErasTotalStake<T>>::insert(&current_era, total_staked);
-			let default_pref = ValidatorPrefs::default();
-			for stash in &elected_stashes {
-				let pref = all_validators_and_prefs.get(stash)
-					.unwrap_or(&default_pref); // Must never happen, but better to be safe.
-				<ErasValidatorPrefs<T>>::insert(&current_era, stash, pref);
-			}
+			EraInfo::<T, I>::set_election(
+				current_era,
+				total_staked,
+				elected_stashes.iter().cloned().zip(elected_prefs),
+			);
 
 			// In order to keep the property required by `n_session_ending`
 			// that we must return the new validator set even if it's the same as the old,
 			// as long as any underlying economic conditions have changed, we don't attempt
 			// to do any optimization where we compare against the prior set.
-			Some(elected_stashes)
+			Ok(elected_stashes)
 		} else {
 			// There were not enough candidates for even our minimal level of functionality.
 			// This is bad.
@@ -1898,32 +4771,228 @@ impl<T: Trait> Module<T> {
 			// and let the chain keep producing blocks until we can decide on a sufficiently
 			// substantial set.
 			// TODO: #2494
-			None
+			Err(ElectionFailure::NotEnoughWinners)
 		}
 	}
 
-	/// Remove all associated data of a stash account from the staking system.
+	/// Bring the support of the validators in `caps` down to their cap, moving the backing of
+	/// their nominators onto the other winners these nominators back, as long as they are below
+	/// their own cap. The largest nominations are moved first; what fits nowhere is left out of the
+	/// supports. The validator's own stake is never moved.
+	fn cap_supports(
+		supports: &mut SupportMap<T::AccountId>,
+		caps: &BTreeMap<T::AccountId, ExtendedBalance>,
+	) {
+		for (capped, cap) in caps.iter() {
+			let (mut excess, mut edges) = match supports.get(capped) {
+				Some(support) if support.total > *cap => (
+					support.total - cap,
+					support.voters.iter()
+						.filter(|(who, _)| who != capped)
+						.cloned()
+						.collect::<Vec<_>>(),
+				),
+				_ => continue,
+			};
+			edges.sort_unstable_by(|a, b| b.1.cmp(&a.1));
+
+			for (nominator, stake) in edges {
+				if excess.is_zero() {
+					break
+				}
+				let taken = stake.min(excess);
+				excess -= taken;
+				if let Some(support) = supports.get_mut(capped) {
+					support.total -= taken;
+					support.voters.iter_mut()
+						.filter(|(who, _)| who == &nominator)
+						.for_each(|(_, stake)| *stake -= taken);
+					support.voters.retain(|(_, stake)| !stake.is_zero());
+				}
+
+				let mut left = taken;
+				let targets = supports.iter()
+					.filter(|(target, support)| *target != capped
+						&& support.voters.iter().any(|(who, _)| who == &nominator)
+					)
+					.map(|(target, _)| target.clone())
+					.collect::<Vec<_>>();
+				for target in targets {
+					if left.is_zero() {
+						break
+					}
+					if let Some(support) = supports.get_mut(&target) {
+						let room = caps.get(&target)
+							.map_or(ExtendedBalance::max_value(), |cap| cap.saturating_sub(support.total));
+						let moved = left.min(room);
+						left -= moved;
+						support.total += moved;
+						support.voters.iter_mut()
+							.filter(|(who, _)| who == &nominator)
+							.for_each(|(_, stake)| *stake += moved);
+					}
+				}
+			}
+		}
+	}
+
+	/// The weight of the work done at the end of a block, which shares a single budget: the
+	/// storage migration if needed, then the automatic payouts, then the pruning of the eras left
+	/// the history and of the slashing spans of removed stashes, each bounded by its parameter.
+	fn finalize_weight() -> Weight {
+		let migration = MIGRATED_VALUE_WEIGHT.saturating_mul(T::MaxMigratedValuesPerBlock::get());
+		let payouts = AUTO_PAYOUT_WEIGHT.saturating_mul(T::MaxAutoPayoutsPerBlock::get());
+		let pruning = PRUNING_KEY_WEIGHT.saturating_mul(T::MaxPrunedKeysPerBlock::get());
+		migration.max(payouts.saturating_add(pruning))
+	}
+
+	/// Make the automatic payouts due in this block, within `weight_left`, deducting their weight
+	/// from it.
 	///
-	/// Assumes storage is upgraded before calling.
+	/// At most `T::MaxAutoPayoutsPerBlock` stashes are visited, continuing from the one after the
+	/// last stash visited in the previous block. A payout heavier than the whole budget of the
+	/// block, as that of a nominator in an era with many rewarded validators, is skipped and left
+	/// to be claimed.
+	fn process_auto_payouts(weight_left: &mut Weight) {
+		let stashes = Self::auto_payout_stashes();
+		if stashes.is_empty() {
+			return
+		}
+
+		let budget = Self::finalize_weight();
+		let mut cursor = AutoPayoutCursor::<I>::get() as usize % stashes.len();
+		let max_payouts = (T::MaxAutoPayoutsPerBlock::get() as usize).min(stashes.len());
+
+		for _ in 0..max_payouts {
+			if *weight_left < AUTO_PAYOUT_WEIGHT {
+				break
+			}
+
+			let stash = &stashes[cursor];
+			let payout = Self::due_auto_payout(stash);
+			let weight = payout.as_ref().map_or(AUTO_PAYOUT_WEIGHT, |(_, _, era_reward_points)| {
+				let validators = era_reward_points.as_ref().map_or(0, |p| p.individual.len());
+				AUTO_PAYOUT_EXPOSURE_WEIGHT.saturating_mul(validators as Weight)
+					.saturating_add(AUTO_PAYOUT_WEIGHT)
+			});
+			if weight > *weight_left && weight <= budget {
+				// left for the next block.
+				break
+			}
+
+			cursor = (cursor + 1) % stashes.len();
+			if weight > *weight_left {
+				*weight_left -= AUTO_PAYOUT_WEIGHT;
+				continue
+			}
+			*weight_left -= weight;
+
+			if let Some((controller, era, era_reward_points)) = payout {
+				let result = match era_reward_points {
+					None => Self::do_payout_validator(controller, era),
+					Some(era_reward_points) => {
+						let validators =
+							Self::nominator_payout_indices(era, stash, &era_reward_points);
+						Self::do_payout_nominator(controller, era, validators)
+					},
+				};
+				if result.is_ok() {
+					Self::deposit_event(RawEvent::AutoPaidOut(stash.clone(), era));
+				}
+			}
+		}
+
+		AutoPayoutCursor::<I>::put(cursor as u32);
+	}
+
+	/// The automatic payout due for the stash, if any: its controller, the first unclaimed era
+	/// with a reward, and the reward points of the era if the stash is paid as a nominator of
+	/// every rewarded validator exposing it rather than as a validator elected in the era.
+	fn due_auto_payout(
+		stash: &T::AccountId,
+	) -> Option<(T::AccountId, EraIndex, Option<EraRewardPoints<T::AccountId>>)> {
+		let controller = Self::bonded(stash)?;
+		let ledger = Self::ledger(&controller)?;
+		let active_era = Self::active_era()?.index;
+
+		let first_unclaimed = active_era.saturating_sub(Self::history_depth())
+			.max(ledger.last_reward.map_or(0, |era| era + 1));
+		let era = (first_unclaimed..active_era)
+			.find(|era| EraInfo::<T, I>::is_rewarded(*era))?;
+
+		if EraInfo::<T, I>::is_elected(era, stash) {
+			Some((controller, era, None))
+		} else {
+			Some((controller, era, Some(EraInfo::<T, I>::reward_points(era))))
+		}
+	}
+
+	/// Remove all associated data of a stash account from the staking system.
 	///
 	/// This is called:
 	/// - after a `withdraw_unbond()` call that frees all of a stash's bonded balance.
 	/// - through `reap_stash()` if the balance has fallen to zero (through slashing).
-	fn kill_stash(stash: &T::AccountId) -> DispatchResult {
-		let controller = Bonded::<T>::take(stash).ok_or(Error::<T>::NotStash)?;
-		<Ledger<T>>::remove(&controller);
-
-		<Payee<T>>::remove(stash);
-		<Validators<T>>::remove(stash);
-		<Nominators<T>>::remove(stash);
+	///
+	/// `num_slashing_spans` must cover the slashing spans whose records are removed at once.
+	fn kill_stash(stash: &T::AccountId, num_slashing_spans: u32) -> DispatchResult {
+		let controller = Self::bonded(stash).ok_or(Error::<T, I>::NotStash)?;
+		ensure!(
+			num_slashing_spans >= slashing::cleared_spans_count::<T, I>(stash),
+			Error::<T, I>::IncorrectSlashingSpans,
+		);
+		<Bonded<T, I>>::remove(stash);
+		<Ledger<T, I>>::remove(&controller);
 
-		slashing::clear_stash_metadata::<T>(stash);
+		Self::clear_stash_info(stash);
 
 		system::Module::<T>::dec_ref(stash);
+		T::OnStakingUpdate::on_unstake(stash);
 
 		Ok(())
 	}
 
+	/// Replace the invulnerables, which must be sorted and without duplicates.
+	fn put_invulnerables(invulnerables: Vec<T::AccountId>) {
+		for stash in Self::invulnerables() {
+			<IsInvulnerable<T, I>>::remove(stash);
+		}
+		for stash in &invulnerables {
+			<IsInvulnerable<T, I>>::insert(stash, true);
+		}
+		<Invulnerables<T, I>>::put(invulnerables);
+	}
+
+	/// Add the stash to `AutoPayoutStashes` or remove it, along with `HasAutoPayout`.
+	fn set_auto_payout_of(stash: &T::AccountId, enabled: bool) {
+		<AutoPayoutStashes<T, I>>::mutate(|stashes| match (stashes.binary_search(stash), enabled) {
+			(Err(index), true) => stashes.insert(index, stash.clone()),
+			(Ok(index), false) => { stashes.remove(index); },
+			_ => (),
+		});
+		if enabled {
+			<HasAutoPayout<T, I>>::insert(stash, true);
+		} else {
+			<HasAutoPayout<T, I>>::remove(stash);
+		}
+	}
+
+	/// Remove the payees, preferences, nominations and slashing metadata of a stash.
+	fn clear_stash_info(stash: &T::AccountId) {
+		<ActiveBonded<T, I>>::remove(stash);
+		<VirtualStakers<T, I>>::remove(stash);
+		<KeptStakers<T, I>>::remove(stash);
+		<Payee<T, I>>::remove(stash);
+		<UnresponsiveSessions<T, I>>::remove(stash);
+		if <HasAutoPayout<T, I>>::get(stash) {
+			Self::set_auto_payout_of(stash, false);
+		}
+		Self::deposit_chilled_if_active(stash, ChillReason::Unstaked);
+		Self::remove_validator(stash);
+		Self::remove_nominations(stash);
+
+		slashing::clear_stash_metadata::<T, I>(stash);
+	}
+
 	/// Add reward points to validators using their stash account ID.
 	///
 	/// Validators are keyed by stash account ID and must be in the current elected set.
@@ -1940,20 +5009,30 @@ impl<T: Trait> Module<T> {
 		validators_points: impl IntoIterator<Item = (T::AccountId, u32)>
 	) {
 		if let Some(active_era) = Self::active_era() {
-			<ErasRewardPoints<T>>::mutate(active_era.index, |era_rewards| {
-				for (validator, points) in validators_points.into_iter() {
-					*era_rewards.individual.entry(validator).or_default() += points;
-					era_rewards.total += points;
-				}
-			});
+			EraInfo::<T, I>::add_reward_points(active_era.index, validators_points);
+		}
+	}
+
+	/// The fraction of a slash paid out to the reporters of an offence that happened `age` eras
+	/// before it was reported.
+	fn reporters_reward_fraction(age: EraIndex) -> Perbill {
+		let decay = Perbill::from_parts(Self::slash_reward_decay().deconstruct().saturating_mul(age));
+		Self::slash_reward_fraction().saturating_mul(Perbill::one().saturating_sub(decay))
+	}
+
+	/// Set the mode of era-forcing, depositing `RawEvent::ForceEra` if it changes.
+	fn set_force_era(mode: Forcing) {
+		if ForceEra::<I>::get() != mode {
+			ForceEra::<I>::put(mode);
+			Self::deposit_event(RawEvent::ForceEra(mode));
 		}
 	}
 
 	/// Ensures that at the end of the current session there will be a new era.
 	fn ensure_new_era() {
-		match ForceEra::get() {
+		match ForceEra::<I>::get() {
 			Forcing::ForceAlways | Forcing::ForceNew => (),
-			_ => ForceEra::put(Forcing::ForceNew),
+			_ => Self::set_force_era(Forcing::ForceNew),
 		}
 	}
 }
@@ -1963,7 +5042,7 @@ impl<T: Trait> Module<T> {
 ///
 /// Once the first new_session is planned, all session must start and then end in order, though
 /// some session can lag in between the newest session planned and the latest session started.
-impl<T: Trait> pallet_session::SessionManager<T::AccountId> for Module<T> {
+impl<T: Trait<I>, I: Instance> pallet_session::SessionManager<T::AccountId> for Module<T, I> {
 	fn new_session(new_index: SessionIndex) -> Option<Vec<T::AccountId>> {
 		Self::new_session(new_index)
 	}
@@ -1977,9 +5056,11 @@ impl<T: Trait> pallet_session::SessionManager<T::AccountId> for Module<T> {
 
 /// This implementation has the same constrains as the implementation of
 /// `pallet_session::SessionManager`.
-impl<T: Trait> SessionManager<T::AccountId, Exposure<T::AccountId, BalanceOf<T>>> for Module<T> {
+impl<T: Trait<I>, I: Instance> SessionManager<T::AccountId, Exposure<T::AccountId, BalanceOf<T, I>>>
+	for Module<T, I>
+{
 	fn new_session(new_index: SessionIndex)
-		-> Option<Vec<(T::AccountId, Exposure<T::AccountId, BalanceOf<T>>)>>
+		-> Option<Vec<(T::AccountId, Exposure<T::AccountId, BalanceOf<T, I>>)>>
 	{
 		<Self as pallet_session::SessionManager<_>>::new_session(new_index).map(|validators| {
 			let current_era = Self::current_era()
@@ -1987,7 +5068,7 @@ impl<T: Trait> SessionManager<T::AccountId, Exposure<T::AccountId, BalanceOf<T>>
 				.unwrap_or(0);
 
 			validators.into_iter().map(|v| {
-				let exposure = Self::eras_stakers(current_era, &v);
+				let exposure = EraInfo::<T, I>::full_exposure(current_era, &v);
 				(v, exposure)
 			}).collect()
 		})
@@ -2001,31 +5082,88 @@ impl<T: Trait> SessionManager<T::AccountId, Exposure<T::AccountId, BalanceOf<T>>
 }
 
 /// Add reward points to block authors:
-/// * 20 points to the block producer for producing a (non-uncle) block in the relay chain,
-/// * 2 points to the block producer for each reference to a previously unreferenced uncle, and
-/// * 1 point to the producer of each referenced uncle block.
-impl<T> pallet_authorship::EventHandler<T::AccountId, T::BlockNumber> for Module<T>
+/// * `T::AuthorPoints` points to the block producer for producing a (non-uncle) block in the
+///   relay chain,
+/// * `T::UncleReferencePoints` points to the block producer for each reference to a previously
+///   unreferenced uncle, and
+/// * `T::UnclePoints` points to the producer of each referenced uncle block.
+impl<T, I: Instance> pallet_authorship::EventHandler<T::AccountId, T::BlockNumber> for Module<T, I>
 	where
-		T: Trait + pallet_authorship::Trait + pallet_session::Trait
+		T: Trait<I> + pallet_authorship::Trait + pallet_session::Trait
 {
 	fn note_author(author: T::AccountId) {
-		Self::reward_by_ids(vec![(author, 20)])
+		Self::reward_by_ids(vec![(author, T::AuthorPoints::get())])
 	}
 	fn note_uncle(author: T::AccountId, _age: T::BlockNumber) {
 		Self::reward_by_ids(vec![
-			(<pallet_authorship::Module<T>>::author(), 2),
-			(author, 1)
+			(<pallet_authorship::Module<T>>::author(), T::UncleReferencePoints::get()),
+			(author, T::UnclePoints::get())
 		])
 	}
 }
 
+/// Add reward points to validators which signalled liveness during a session, e.g. with an
+/// im-online heartbeat:
+/// * `T::LivenessPoints` points to each of them.
+impl<T: Trait<I>, I: Instance> OnLiveness<T::AccountId> for Module<T, I> {
+	fn on_liveness(_session: SessionIndex, validators: &[T::AccountId]) {
+		let points = T::LivenessPoints::get();
+		if points.is_zero() {
+			return
+		}
+		Self::reward_by_ids(validators.iter().map(|v| (v.clone(), points)))
+	}
+}
+
+/// Chill the validators flagged unresponsive during too many consecutive sessions, e.g. without
+/// an im-online heartbeat:
+/// * a validator is chilled once flagged in `max_unresponsive_sessions` consecutive sessions,
+///   and its count starts over whenever it misses a session.
+impl<T: Trait<I>, I: Instance> OnUnresponsiveness<T::AccountId> for Module<T, I> {
+	fn on_unresponsiveness(session: SessionIndex, validators: &[T::AccountId]) {
+		let max_sessions = Self::max_unresponsive_sessions();
+		if max_sessions.is_zero() {
+			return
+		}
+		for stash in validators {
+			if !<Validators<T, I>>::contains_key(stash) {
+				<UnresponsiveSessions<T, I>>::remove(stash);
+				continue
+			}
+			let count = match Self::unresponsive_sessions(stash) {
+				Some((last, count)) if last.saturating_add(1) == session => count.saturating_add(1),
+				_ => 1,
+			};
+			Self::deposit_event(RawEvent::Unresponsive(stash.clone(), count));
+			if count >= max_sessions {
+				<UnresponsiveSessions<T, I>>::remove(stash);
+				Self::chill_stash(stash, ChillReason::Unresponsive);
+			} else {
+				<UnresponsiveSessions<T, I>>::insert(stash, (session, count));
+			}
+		}
+	}
+}
+
+/// Add reward points to validators which voted in a completed round of a finality gadget:
+/// * `T::FinalityPoints` points to each of them.
+impl<T: Trait<I>, I: Instance> OnFinalityParticipation<T::AccountId> for Module<T, I> {
+	fn on_finality_participation(_round: u64, validators: &[T::AccountId]) {
+		let points = T::FinalityPoints::get();
+		if points.is_zero() {
+			return
+		}
+		Self::reward_by_ids(validators.iter().map(|v| (v.clone(), points)))
+	}
+}
+
 /// A `Convert` implementation that finds the stash of the given controller account,
 /// if any.
-pub struct StashOf<T>(sp_std::marker::PhantomData<T>);
+pub struct StashOf<T, I = DefaultInstance>(sp_std::marker::PhantomData<(T, I)>);
 
-impl<T: Trait> Convert<T::AccountId, Option<T::AccountId>> for StashOf<T> {
+impl<T: Trait<I>, I: Instance> Convert<T::AccountId, Option<T::AccountId>> for StashOf<T, I> {
 	fn convert(controller: T::AccountId) -> Option<T::AccountId> {
-		<Module<T>>::ledger(&controller).map(|l| l.stash)
+		<Module<T, I>>::ledger(&controller).map(|l| l.stash)
 	}
 }
 
@@ -2034,14 +5172,14 @@ impl<T: Trait> Convert<T::AccountId, Option<T::AccountId>> for StashOf<T> {
 ///
 /// Active exposure is the exposure of the validator set currently validating, i.e. in
 /// `active_era`. It can differ from the latest planned exposure in `current_era`.
-pub struct ExposureOf<T>(sp_std::marker::PhantomData<T>);
+pub struct ExposureOf<T, I = DefaultInstance>(sp_std::marker::PhantomData<(T, I)>);
 
-impl<T: Trait> Convert<T::AccountId, Option<Exposure<T::AccountId, BalanceOf<T>>>>
-	for ExposureOf<T>
+impl<T: Trait<I>, I: Instance>
+	Convert<T::AccountId, Option<Exposure<T::AccountId, BalanceOf<T, I>>>> for ExposureOf<T, I>
 {
-	fn convert(validator: T::AccountId) -> Option<Exposure<T::AccountId, BalanceOf<T>>> {
-		if let Some(active_era) = <Module<T>>::active_era() {
-			Some(<Module<T>>::eras_stakers(active_era.index, &validator))
+	fn convert(validator: T::AccountId) -> Option<Exposure<T::AccountId, BalanceOf<T, I>>> {
+		if let Some(active_era) = <Module<T, I>>::active_era() {
+			Some(EraInfo::<T, I>::full_exposure(active_era.index, &validator))
 		} else {
 			None
 		}
@@ -2049,11 +5187,14 @@ impl<T: Trait> Convert<T::AccountId, Option<Exposure<T::AccountId, BalanceOf<T>>
 }
 
 /// This is intended to be used with `FilterHistoricalOffences`.
-impl <T: Trait> OnOffenceHandler<T::AccountId, pallet_session::historical::IdentificationTuple<T>> for Module<T> where
+impl<T: Trait<I>, I: Instance>
+	OnOffenceHandler<T::AccountId, pallet_session::historical::IdentificationTuple<T>>
+	for Module<T, I>
+where
 	T: pallet_session::Trait<ValidatorId = <T as frame_system::Trait>::AccountId>,
 	T: pallet_session::historical::Trait<
-		FullIdentification = Exposure<<T as frame_system::Trait>::AccountId, BalanceOf<T>>,
-		FullIdentificationOf = ExposureOf<T>,
+		FullIdentification = Exposure<<T as frame_system::Trait>::AccountId, BalanceOf<T, I>>,
+		FullIdentificationOf = ExposureOf<T, I>,
 	>,
 	T::SessionHandler: pallet_session::SessionHandler<<T as frame_system::Trait>::AccountId>,
 	T::SessionManager: pallet_session::SessionManager<<T as frame_system::Trait>::AccountId>,
@@ -2063,58 +5204,92 @@ impl <T: Trait> OnOffenceHandler<T::AccountId, pallet_session::historical::Ident
 		offenders: &[OffenceDetails<T::AccountId, pallet_session::historical::IdentificationTuple<T>>],
 		slash_fraction: &[Perbill],
 		slash_session: SessionIndex,
+		kind: &Kind,
 	) {
-		let reward_proportion = SlashRewardFraction::get();
+		Self::do_on_offence(offenders, slash_fraction, slash_session, kind)
+	}
+}
 
-		let active_era = {
-			let active_era = Self::active_era();
-			if active_era.is_none() {
-				return
-			}
-			active_era.unwrap().index
-		};
+impl<T: Trait<I>, I: Instance> Module<T, I> {
+	/// The era of `session`, if it can still be slashed: it is the active era or within the
+	/// bonding duration before it.
+	fn slashable_era_of(session: SessionIndex) -> Option<EraIndex> {
+		let active_era = Self::active_era()?.index;
 		let active_era_start_session_index = Self::eras_start_session_index(active_era)
 			.unwrap_or_else(|| {
 				frame_support::print("Error: start_session_index must be set for current_era");
 				0
 			});
 
+		// fast path for active-era report - most likely.
+		// `session` cannot be in a future active era. It must be in `active_era` or before.
+		if session >= active_era_start_session_index {
+			return Some(active_era);
+		}
+
 		let window_start = active_era.saturating_sub(T::BondingDuration::get());
+		// reverse because it's more likely to find reports from recent eras.
+		BondedEras::<I>::get().iter().rev()
+			.find(|&&(_, ref sesh)| sesh <= &session)
+			.map(|&(era, _)| era)
+			.filter(|&era| era >= window_start)
+	}
 
-		// fast path for active-era report - most likely.
-		// `slash_session` cannot be in a future active era. It must be in `active_era` or before.
-		let slash_era = if slash_session >= active_era_start_session_index {
-			active_era
-		} else {
-			let eras = BondedEras::get();
+	/// Slash the `offenders` of an offence of `kind` committed in `slash_session`, each by its
+	/// `slash_fraction`, as reported to `on_offence` or by `report_offence_unchecked`.
+	fn do_on_offence(
+		offenders: &[OffenceDetails<T::AccountId, (T::AccountId, Exposure<T::AccountId, BalanceOf<T, I>>)>],
+		slash_fraction: &[Perbill],
+		slash_session: SessionIndex,
+		kind: &Kind,
+	) {
+		if !migration::is_migrated::<I>() {
+			let offence = QueuedOffence {
+				offenders: offenders.to_vec(),
+				slash_fraction: slash_fraction.to_vec(),
+				slash_session,
+				kind: *kind,
+			};
+			<Self as Store>::OffencesQueuedWhileMigrating::mutate(|queued| queued.push(offence));
+			return
+		}
 
-			// reverse because it's more likely to find reports from recent eras.
-			match eras.iter().rev().filter(|&&(_, ref sesh)| sesh <= &slash_session).next() {
-				None => return, // before bonding period. defensive - should be filtered out.
-				Some(&(ref slash_era, _)) => *slash_era,
-			}
+		let active_era = match Self::active_era() {
+			Some(active_era) => active_era.index,
+			None => return,
+		};
+		let window_start = active_era.saturating_sub(T::BondingDuration::get());
+		let slash_era = match Self::slashable_era_of(slash_session) {
+			Some(slash_era) => slash_era,
+			None => return, // before bonding period. defensive - should be filtered out.
 		};
 
+		// prompt reports earn a larger share of the slash than stale ones.
+		let reward_proportion = Self::reporters_reward_fraction(active_era.saturating_sub(slash_era));
+
 		<Self as Store>::EarliestUnappliedSlash::mutate(|earliest| {
 			if earliest.is_none() {
 				*earliest = Some(active_era)
 			}
 		});
 
-		let slash_defer_duration = T::SlashDeferDuration::get();
+		let slash_defer_duration = Self::slash_defer_duration();
+		let slash_fraction_bounds = Self::slash_fraction_bounds(kind);
 
 		for (details, slash_fraction) in offenders.iter().zip(slash_fraction) {
 			let stash = &details.offender.0;
 			let exposure = &details.offender.1;
 
 			// Skip if the validator is invulnerable.
-			if Self::invulnerables().contains(stash) {
+			if Self::is_invulnerable(stash) {
 				continue
 			}
 
-			let unapplied = slashing::compute_slash::<T>(slashing::SlashParams {
+			let slash = slash_fraction_bounds
+				.map_or(*slash_fraction, |bounds| bounds.clamp(*slash_fraction));
+			let unapplied = slashing::compute_slash::<T, I>(slashing::SlashParams {
 				stash,
-				slash: *slash_fraction,
+				slash,
 				exposure,
 				slash_era,
 				window_start,
@@ -2124,11 +5299,26 @@ impl <T: Trait> OnOffenceHandler<T::AccountId, pallet_session::historical::Ident
 
 			if let Some(mut unapplied) = unapplied {
 				unapplied.reporters = details.reporters.clone();
+				// deferred slashes are applied once the era they were reported in is
+				// `slash_defer_duration` eras old.
+				let apply_era = if slash_defer_duration == 0 {
+					active_era
+				} else {
+					active_era.saturating_add(slash_defer_duration).saturating_add(1)
+				};
+				Self::deposit_event(RawEvent::SlashReported(stash.clone(), slash, apply_era));
 				if slash_defer_duration == 0 {
 					// apply right away.
-					slashing::apply_slash::<T>(unapplied);
+					slashing::apply_slash::<T, I>(unapplied);
 				} else {
+					unapplied.id = NextSlashId::<I>::mutate(|next| {
+						let id = *next;
+						*next = next.wrapping_add(1);
+						id
+					});
+
 					// defer to end of some `slash_defer_duration` from now.
+					Self::count_pending_slash(&unapplied, true);
 					<Self as Store>::UnappliedSlashes::mutate(
 						active_era,
 						move |for_later| for_later.push(unapplied),
@@ -2144,21 +5334,26 @@ pub struct FilterHistoricalOffences<T, R> {
 	_inner: sp_std::marker::PhantomData<(T, R)>,
 }
 
-impl<T, Reporter, Offender, R, O> ReportOffence<Reporter, Offender, O>
-	for FilterHistoricalOffences<Module<T>, R> where
-	T: Trait,
+impl<T, I, Reporter, Offender, R, O> ReportOffence<Reporter, Offender, O>
+	for FilterHistoricalOffences<Module<T, I>, R> where
+	T: Trait<I>,
+	I: Instance,
 	R: ReportOffence<Reporter, Offender, O>,
 	O: Offence<Offender>,
 {
 	fn report_offence(reporters: Vec<Reporter>, offence: O) -> Result<(), OffenceError> {
 		// disallow any slashing from before the current bonding period.
 		let offence_session = offence.session_index();
-		let bonded_eras = BondedEras::get();
+		let window_start = <Module<T, I>>::active_era()
+			.map_or(0, |active_era| active_era.index)
+			.saturating_sub(T::BondingDuration::get());
+		let bonded_eras = BondedEras::<I>::get();
+		let first_bonded = bonded_eras.iter().find(|&&(era, _)| era >= window_start);
 
-		if bonded_eras.first().filter(|(_, start)| offence_session >= *start).is_some() {
+		if first_bonded.filter(|(_, start)| offence_session >= *start).is_some() {
 			R::report_offence(reporters, offence)
 		} else {
-			<Module<T>>::deposit_event(
+			<Module<T, I>>::deposit_event(
 				RawEvent::OldSlashingReportDiscarded(offence_session)
 			);
 			Ok(())
@@ -14,12 +14,18 @@
 // You should have received a copy of the GNU General Public License
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
-/// Deprecated storages used for migration from v1.0.0 to v2.0.0 only.
+/// Deprecated storages and types used for migration to v2.0.0, v3.0.0, v4.0.0, v7.0.0, v12.0.0 and
+/// v14.0.0 only.
+///
+/// The storages are only declared for the default instance of the module, to write the old
+/// values in the tests: the migration reads them by their raw keys under the prefix of the
+/// instance migrated.
 
-use crate::{Trait, BalanceOf, MomentOf, SessionIndex, Exposure, UnlockChunk};
+use crate::{Trait, BalanceOf, MomentOf, SessionIndex, EraIndex, Exposure, UnlockChunk};
 use codec::{Encode, Decode, HasCompact};
 use frame_support::{decl_module, decl_storage};
 use sp_std::prelude::*;
+use sp_runtime::Perbill;
 
 /// Reward points of an era. Used to split era total payout between validators.
 #[derive(Encode, Decode, Default)]
@@ -41,6 +47,21 @@ pub struct OldStakingLedger<AccountId, Balance: HasCompact> {
     pub unlocking: Vec<UnlockChunk<Balance>>,
 }
 
+#[derive(Encode, Decode)]
+pub struct OldUnappliedSlash<AccountId, Balance: HasCompact> {
+    pub validator: AccountId,
+    pub own: Balance,
+    pub others: Vec<(AccountId, Balance)>,
+    pub reporters: Vec<AccountId>,
+    pub payout: Balance,
+}
+
+#[derive(Encode, Decode, Default)]
+pub struct OldValidatorPrefs {
+    #[codec(compact)]
+    pub commission: Perbill,
+}
+
 decl_module! {
     pub struct Module<T: Trait> for enum Call where origin: T::Origin { }
 }
@@ -69,5 +90,78 @@ decl_storage! {
 
         /// Old upgrade flag.
         pub IsUpgraded: bool;
+
+        /// The validator preferences, before they had a `max_total_stake`.
+        pub Validators: linked_map hasher(blake2_256) T::AccountId => OldValidatorPrefs;
+
+        /// The validator preferences of each era, before they had a `max_total_stake`.
+        pub ErasValidatorPrefs:
+            double_map hasher(twox_64_concat) EraIndex, hasher(twox_64_concat) T::AccountId
+            => OldValidatorPrefs;
+    }
+}
+
+/// The validators and nominators as linked maps, in their format of v6.0.0.
+pub mod v6 {
+    use crate::{Trait, BalanceOf, Nominations};
+    use super::v13::ValidatorPrefs;
+    use frame_support::{decl_module, decl_storage};
+
+    decl_module! {
+        pub struct Module<T: Trait> for enum Call where origin: T::Origin { }
+    }
+
+    decl_storage! {
+        pub trait Store for Module<T: Trait> as Staking {
+            pub Validators:
+                linked_map hasher(blake2_256) T::AccountId => ValidatorPrefs<BalanceOf<T>>;
+
+            pub Nominators:
+                linked_map hasher(blake2_256) T::AccountId => Option<Nominations<T::AccountId>>;
+        }
+    }
+}
+
+/// The unapplied slashes in their format from v3.0.0 to v11.0.0, before they had a `slash_era`.
+pub mod v11 {
+    use crate::{Trait, BalanceOf, EraIndex, SlashId};
+    use codec::{Encode, Decode, HasCompact};
+    use frame_support::{decl_module, decl_storage};
+    use sp_std::prelude::*;
+
+    #[derive(Encode, Decode)]
+    pub struct UnappliedSlash<AccountId, Balance: HasCompact> {
+        pub id: SlashId,
+        pub validator: AccountId,
+        pub own: Balance,
+        pub others: Vec<(AccountId, Balance)>,
+        pub reporters: Vec<AccountId>,
+        pub payout: Balance,
+    }
+
+    decl_module! {
+        pub struct Module<T: Trait> for enum Call where origin: T::Origin { }
+    }
+
+    decl_storage! {
+        pub trait Store for Module<T: Trait> as Staking {
+            pub UnappliedSlashes:
+                map hasher(twox_64_concat) EraIndex
+                => Vec<UnappliedSlash<T::AccountId, BalanceOf<T>>>;
+        }
+    }
+}
+
+/// The validator preferences in their format from v4.0.0 to v13.0.0, before they had a
+/// `commission_payee`.
+pub mod v13 {
+    use codec::{Encode, Decode};
+    use sp_runtime::Perbill;
+
+    #[derive(Encode, Decode)]
+    pub struct ValidatorPrefs<Balance> {
+        #[codec(compact)]
+        pub commission: Perbill,
+        pub max_total_stake: Option<Balance>,
     }
 }
@@ -14,13 +14,62 @@
 // You should have received a copy of the GNU General Public License
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
-//! Update storage from v1.0.0 to v2.0.0
+//! Update storage from v1.0.0 up to v14.0.0, one release after the other.
 //!
 //! In old version the staking module has several issue about handling session delay, the
 //! current era was always considered the active one.
 //!
 //! After the migration the current era will still be considered the active one for the era of
 //! the upgrade. And the delay issue will be fixed when planning the next era.
+//!
+//! In v3.0.0 every pending `UnappliedSlash` carries an id, which is assigned to the slashes
+//! already queued at the time of the upgrade.
+//!
+//! In v4.0.0 `ValidatorPrefs` gain a `max_total_stake`, unset for the existing validators.
+//!
+//! In v5.0.0 the nominators of each validator are indexed in `NominatorsFor`.
+//!
+//! In v6.0.0 the validator candidates and nominators are counted.
+//!
+//! In v7.0.0 `Validators` and `Nominators` are plain maps instead of linked maps, keyed with
+//! `twox_64_concat` so that they can still be iterated.
+//!
+//! In v8.0.0 ledgers, nominations and exposures are bounded by the configuration: the nominations
+//! over `MaxNominations` are truncated, and the unlocking chunks over `MaxUnlockingChunks` are
+//! merged into the last one, which unlocks at the latest of their eras. The exposures of past eras
+//! are left as they are until they leave the history.
+//!
+//! In v9.0.0 `Bonded`, `Ledger`, `Payee`, `CommissionPayee` and `NominatorsFor` are keyed with
+//! `blake2_128_concat`, and the maps keyed first by era with `twox_64_concat`, instead of
+//! `blake2_256`. The values of the eras still in the history, or with unapplied slashes, are moved
+//! to their new keys, as well as the slashes recorded in the eras still bonded.
+//!
+//! In v10.0.0 the accounts of `Invulnerables` and `AutoPayoutStashes` are also marked in
+//! `IsInvulnerable` and `HasAutoPayout`.
+//!
+//! In v11.0.0 the active balance of each ledger is also kept in `ActiveBonded`.
+//!
+//! In v12.0.0 every `UnappliedSlash` records the era of its offence. The era is not known for the
+//! slashes already queued at the time of the upgrade, which are given the era they were reported
+//! in.
+//!
+//! In v13.0.0 the slashes queued in `UnappliedSlashes` are counted for each stash they would hit
+//! in `PendingSlashCount`.
+//!
+//! In v14.0.0 `ValidatorPrefs` gain the `commission_payee` of the stash, and `CommissionPayee` is
+//! removed.
+//!
+//! Every step going through a map migrates it over as many blocks as needed, at most
+//! `MaxMigratedValuesPerBlock` values per block, see [`migrate`]: the step to v4.0.0 migrates
+//! `Validators` and then `ErasValidatorPrefs`, the step to v5.0.0 `Nominators`, the steps to
+//! v6.0.0 and v7.0.0 `Validators` and then `Nominators`, the step to v8.0.0 `Nominators` and then
+//! `Ledger`, the step to v9.0.0 `Ledger` and then `NominatorsFor`, the step to v11.0.0 `Ledger`,
+//! and the step to v14.0.0 `Validators` and then `ErasValidatorPrefs`. Until the storage is
+//! migrated, the dispatchables reading ledgers, the history depth or the deferred slashes fail, no
+//! era is planned, and the offences reported are kept in `OffencesQueuedWhileMigrating` and
+//! slashed once it is, as if they were reported then.
+
+// v1.0.0 to v2.0.0:
 // * create:
 //   * ActiveEraStart
 //   * ErasRewardPoints
@@ -38,39 +87,230 @@
 //   * CurrentEraStart
 //   * CurrentEraStartSessionIndex
 //   * CurrentEraPointsEarned
+//
+// v2.0.0 to v3.0.0:
+// * create:
+//   * NextSlashId
+// * translate UnappliedSlashes
+// * sort and deduplicate Invulnerables
+//
+// v3.0.0 to v4.0.0:
+// * translate Validators and ErasValidatorPrefs
+//
+// v4.0.0 to v5.0.0:
+// * create NominatorsFor
+//
+// v5.0.0 to v6.0.0:
+// * create:
+//   * CounterForValidators
+//   * CounterForNominators
+//
+// v6.0.0 to v7.0.0:
+// * move Validators and Nominators out of their linked maps
+//
+// v7.0.0 to v8.0.0:
+// * truncate Nominators to MaxNominations targets
+// * merge the unlocking chunks of Ledger over MaxUnlockingChunks
+//
+// v8.0.0 to v9.0.0:
+// * rehash Bonded, Ledger, Payee, CommissionPayee and NominatorsFor with blake2_128_concat
+// * rehash ErasStartSessionIndex, ErasValidatorReward, ErasRemainderReward, ErasPayoutSummary,
+//   ErasRewardPoints, ErasTotalStake, ErasStart, ErasDuration, ErasTotalSlashed and
+//   UnappliedSlashes with twox_64_concat
+// * rehash the era key of ValidatorSlashInEra and NominatorSlashInEra with twox_64_concat
+//
+// v9.0.0 to v10.0.0:
+// * create:
+//   * IsInvulnerable
+//   * HasAutoPayout
+//
+// v10.0.0 to v11.0.0:
+// * create ActiveBonded
+//
+// v11.0.0 to v12.0.0:
+// * translate UnappliedSlashes
+//
+// v12.0.0 to v13.0.0:
+// * create PendingSlashCount
+
+// v13.0.0 to v14.0.0:
+// * translate Validators
+// * translate ErasValidatorPrefs
+// * removal of CommissionPayee
 
 use super::*;
+use frame_support::{
+	Blake2_128Concat, Blake2_256, Twox128,
+	storage::{
+		generator::Linkage,
+		migration::{StorageIterator, get_storage_value, put_storage_value, take_storage_value},
+	},
+};
 mod deprecated;
 #[cfg(test)]
 mod tests;
 #[cfg(test)]
 mod test_upgrade_from_master_dataset;
 
-pub fn on_runtime_upgrade<T: Trait>() {
-	match StorageVersion::get() {
-		Releases::V2_0_0 => return,
-		Releases::V1_0_0 => upgrade_v1_to_v2::<T>(),
+pub fn on_runtime_upgrade<T: Trait<I>, I: Instance>() {
+	migrate::<T, I>(T::MaxMigratedValuesPerBlock::get());
+}
+
+/// Whether the storage is migrated to the latest release.
+pub fn is_migrated<I: Instance>() -> bool {
+	StorageVersion::<I>::get() == Releases::V14_0_0
+}
+
+/// Migrate the storage towards the latest release, one step after the other, migrating at most
+/// `budget` values of the maps migrated over several blocks. Returns whether the storage is
+/// migrated to the latest release.
+///
+/// A step migrating a map over several blocks keeps the last key it migrated in
+/// `MigrationCursor`, and leaves `StorageVersion` as it is until the whole map is migrated.
+pub fn migrate<T: Trait<I>, I: Instance>(mut budget: u32) -> bool {
+	loop {
+		let step_done = match StorageVersion::<I>::get() {
+			Releases::V14_0_0 => return true,
+			Releases::V13_0_0 => upgrade_v13_to_v14::<T, I>(&mut budget),
+			Releases::V12_0_0 => {
+				upgrade_v12_to_v13::<T, I>();
+				true
+			},
+			Releases::V11_0_0 => {
+				upgrade_v11_to_v12::<T, I>();
+				true
+			},
+			Releases::V10_0_0 => upgrade_v10_to_v11::<T, I>(&mut budget),
+			Releases::V9_0_0 => {
+				upgrade_v9_to_v10::<T, I>();
+				true
+			},
+			Releases::V8_0_0 => upgrade_v8_to_v9::<T, I>(&mut budget),
+			Releases::V7_0_0 => upgrade_v7_to_v8::<T, I>(&mut budget),
+			Releases::V6_0_0 => upgrade_v6_to_v7::<T, I>(&mut budget),
+			Releases::V5_0_0 => upgrade_v5_to_v6::<T, I>(&mut budget),
+			Releases::V4_0_0 => upgrade_v4_to_v5::<T, I>(&mut budget),
+			Releases::V3_0_0 => upgrade_v3_to_v4::<T, I>(&mut budget),
+			Releases::V2_0_0 => {
+				upgrade_v2_to_v3::<T, I>();
+				true
+			},
+			Releases::V1_0_0 => {
+				upgrade_v1_to_v2::<T, I>();
+				true
+			},
+		};
+		if !step_done {
+			return false
+		}
+	}
+}
+
+/// Apply `migrate` to the values of the staking map `item` from the key after `MigrationCursor`,
+/// to at most `budget` of them, deducting them from it. `migrate` is given the hash of the key of
+/// each value in the map, and the value.
+///
+/// Returns whether the end of the map was reached, in which case `MigrationCursor` is cleared.
+fn migrate_values<I: Instance>(
+	item: &[u8],
+	budget: &mut u32,
+	mut migrate: impl FnMut(&[u8], Vec<u8>),
+) -> bool {
+	let prefix = [Twox128::hash(I::PREFIX.as_bytes()), Twox128::hash(item)].concat();
+	let mut key = MigrationCursor::<I>::get().unwrap_or_else(|| prefix.clone());
+	while *budget > 0 {
+		match sp_io::storage::next_key(&key).filter(|next| next.starts_with(&prefix)) {
+			Some(next) => {
+				if let Some(value) = sp_io::storage::get(&next) {
+					migrate(&next[prefix.len()..], value);
+				}
+				*budget -= 1;
+				key = next;
+			},
+			None => {
+				MigrationCursor::<I>::kill();
+				return true
+			},
+		}
+	}
+	MigrationCursor::<I>::put(key);
+	false
+}
+
+/// The raw key of the head of the staking linked map `item`.
+fn linked_head<I: Instance>(item: &[u8]) -> Vec<u8> {
+	[Twox128::hash(I::PREFIX.as_bytes()), Twox128::hash(&[&b"HeadOf"[..], item].concat())].concat()
+}
+
+/// Apply `migrate` to the keys and values of the staking linked map `item`, in the order of its
+/// linkage, from the key in `MigrationCursor` or else the head of the map, to at most `budget` of
+/// them, deducting them from it.
+///
+/// Returns whether the end of the map was reached, in which case `MigrationCursor` is cleared,
+/// else it keeps the encoded key of the next value to migrate.
+fn migrate_linked<I: Instance, K: Encode + Decode, V: Decode>(
+	item: &[u8],
+	budget: &mut u32,
+	mut migrate: impl FnMut(K, V),
+) -> bool {
+	let mut next = MigrationCursor::<I>::get()
+		.or_else(|| sp_io::storage::get(&linked_head::<I>(item)))
+		.and_then(|key| K::decode(&mut &key[..]).ok());
+	loop {
+		let key = match next {
+			Some(key) => key,
+			None => {
+				MigrationCursor::<I>::kill();
+				return true
+			},
+		};
+		if *budget == 0 {
+			MigrationCursor::<I>::put(key.encode());
+			return false
+		}
+		let hash = Blake2_256::hash(&key.encode());
+		next = get_storage_value::<(V, Linkage<K>)>(I::PREFIX.as_bytes(), item, &hash)
+			.and_then(|(value, linkage)| {
+				migrate(key, value);
+				linkage.next
+			});
+		*budget -= 1;
 	}
 }
 
-fn upgrade_v1_to_v2<T: Trait>() {
-	deprecated::IsUpgraded::kill();
+fn upgrade_v1_to_v2<T: Trait<I>, I: Instance>() {
+	// The deprecated storages are only declared for the default instance: they are read and
+	// removed by their raw keys.
+	let module = I::PREFIX.as_bytes();
+	let item_key = |item: &[u8]| [Twox128::hash(module), Twox128::hash(item)].concat();
+	sp_io::storage::clear(&item_key(b"IsUpgraded"));
 
-	let current_era_start_index = deprecated::CurrentEraStartSessionIndex::get();
-	let current_era = <Module<T> as Store>::CurrentEra::get().unwrap_or(0);
-	let current_era_start = deprecated::CurrentEraStart::<T>::get();
-	<Module<T> as Store>::ErasStartSessionIndex::insert(current_era, current_era_start_index);
-	<Module<T> as Store>::ActiveEra::put(ActiveEraInfo {
+	let current_era_start_index = take_storage_value::<SessionIndex>(
+		module,
+		b"CurrentEraStartSessionIndex",
+		&[],
+	).unwrap_or_default();
+	let current_era = <Module<T, I> as Store>::CurrentEra::get().unwrap_or(0);
+	let current_era_start = take_storage_value::<MomentOf<T, I>>(module, b"CurrentEraStart", &[])
+		.unwrap_or_default();
+	<Module<T, I> as Store>::ErasStartSessionIndex::insert(current_era, current_era_start_index);
+	<Module<T, I> as Store>::ActiveEra::put(ActiveEraInfo {
 		index: current_era,
 		start: Some(current_era_start),
 	});
 
-	let current_elected = deprecated::CurrentElected::<T>::get();
-	let mut current_total_stake = <BalanceOf<T>>::zero();
+	let current_elected = take_storage_value::<Vec<T::AccountId>>(module, b"CurrentElected", &[])
+		.unwrap_or_default();
+	let mut current_total_stake = <BalanceOf<T, I>>::zero();
 	for validator in &current_elected {
-		let exposure = deprecated::Stakers::<T>::get(validator);
+		let validator_hash = Blake2_256::hash(&validator.encode());
+		let exposure = get_storage_value::<Exposure<T::AccountId, BalanceOf<T, I>>>(
+			module,
+			b"Stakers",
+			&validator_hash,
+		).unwrap_or_default();
 		current_total_stake += exposure.total;
-		<Module<T> as Store>::ErasStakers::insert(current_era, validator, &exposure);
+		<Module<T, I> as Store>::ErasStakers::insert(current_era, validator, &exposure);
 
 		let mut exposure_clipped = exposure;
 		let clipped_max_len = T::MaxNominatorRewardedPerValidator::get() as usize;
@@ -78,21 +318,31 @@ fn upgrade_v1_to_v2<T: Trait>() {
 			exposure_clipped.others.sort_unstable_by(|a, b| a.value.cmp(&b.value).reverse());
 			exposure_clipped.others.truncate(clipped_max_len);
 		}
-		<Module<T> as Store>::ErasStakersClipped::insert(current_era, validator, exposure_clipped);
+		<Module<T, I> as Store>::ErasStakersClipped::insert(current_era, validator, exposure_clipped);
 
-		let pref = <Module<T> as Store>::Validators::get(validator);
-		<Module<T> as Store>::ErasValidatorPrefs::insert(current_era, validator, pref);
+		// The preferences are translated to the current format by the upgrade to v4.0.0.
+		let pref = get_storage_value::<(deprecated::OldValidatorPrefs, Linkage<T::AccountId>)>(
+			module,
+			b"Validators",
+			&validator_hash,
+		).map(|(pref, _)| pref).unwrap_or_default();
+		let era_validator_hash = [
+			Twox64Concat::hash(&current_era.encode()),
+			Twox64Concat::hash(&validator.encode()),
+		].concat();
+		put_storage_value(module, b"ErasValidatorPrefs", &era_validator_hash, pref);
 	}
-	<Module<T> as Store>::ErasTotalStake::insert(current_era, current_total_stake);
+	<Module<T, I> as Store>::ErasTotalStake::insert(current_era, current_total_stake);
 
-	let points = deprecated::CurrentEraPointsEarned::get();
-	<Module<T> as Store>::ErasRewardPoints::insert(current_era, EraRewardPoints {
+	let points = take_storage_value::<deprecated::EraPoints>(module, b"CurrentEraPointsEarned", &[])
+		.unwrap_or_default();
+	<Module<T, I> as Store>::ErasRewardPoints::insert(current_era, EraRewardPoints {
 		total: points.total,
 		individual: current_elected.iter().cloned().zip(points.individual.iter().cloned()).collect(),
 	});
 
-	let res = <Module<T> as Store>::Ledger::translate_values(
-		|old: deprecated::OldStakingLedger<T::AccountId, BalanceOf<T>>| StakingLedger {
+	let res = <Module<T, I> as Store>::Ledger::translate_values(
+		|old: deprecated::OldStakingLedger<T::AccountId, BalanceOf<T, I>>| StakingLedger {
 			stash: old.stash,
 			total: old.total,
 			active: old.active,
@@ -108,12 +358,489 @@ fn upgrade_v1_to_v2<T: Trait>() {
 
 
 	// Kill old storages
-	deprecated::Stakers::<T>::remove_all();
-	deprecated::SlotStake::<T>::kill();
-	deprecated::CurrentElected::<T>::kill();
-	deprecated::CurrentEraStart::<T>::kill();
-	deprecated::CurrentEraStartSessionIndex::kill();
-	deprecated::CurrentEraPointsEarned::kill();
-
-	StorageVersion::put(Releases::V2_0_0);
+	sp_io::storage::clear_prefix(&item_key(b"Stakers"));
+	sp_io::storage::clear(&item_key(b"SlotStake"));
+
+	StorageVersion::<I>::put(Releases::V2_0_0);
+}
+
+fn upgrade_v2_to_v3<T: Trait<I>, I: Instance>() {
+	let module = I::PREFIX.as_bytes();
+	let old_slashes = StorageIterator::<
+		Vec<deprecated::OldUnappliedSlash<T::AccountId, BalanceOf<T, I>>>
+	>::new(module, b"UnappliedSlashes");
+	for (hash, old) in old_slashes {
+		let slashes = old.into_iter().map(|slash| deprecated::v11::UnappliedSlash {
+			id: NextSlashId::<I>::mutate(|next| {
+				let id = *next;
+				*next = next.wrapping_add(1);
+				id
+			}),
+			validator: slash.validator,
+			own: slash.own,
+			others: slash.others,
+			reporters: slash.reporters,
+			payout: slash.payout,
+		}).collect::<Vec<_>>();
+		put_storage_value(module, b"UnappliedSlashes", &hash, slashes);
+	}
+
+	<Module<T, I> as Store>::Invulnerables::mutate(|invulnerables| {
+		invulnerables.sort();
+		invulnerables.dedup();
+	});
+
+	StorageVersion::<I>::put(Releases::V3_0_0);
+}
+
+fn upgrade_v3_to_v4<T: Trait<I>, I: Instance>(budget: &mut u32) -> bool {
+	fn translate_prefs<Balance>(
+		old: deprecated::OldValidatorPrefs,
+	) -> deprecated::v13::ValidatorPrefs<Balance> {
+		deprecated::v13::ValidatorPrefs { commission: old.commission, max_total_stake: None }
+	}
+
+	// `ErasValidatorPrefs` is migrated once all validators are.
+	let era_prefs = [
+		Twox128::hash(I::PREFIX.as_bytes()),
+		Twox128::hash(b"ErasValidatorPrefs"),
+	].concat();
+	let migrating_era_prefs = MigrationCursor::<I>::get()
+		.map_or(false, |cursor| cursor.starts_with(&era_prefs));
+
+	// The validators are still in their linked map: each value is kept with its linkage.
+	let done = migrating_era_prefs || migrate_values::<I>(b"Validators", budget, |hash, value| {
+		match <(deprecated::OldValidatorPrefs, Linkage<T::AccountId>)>::decode(&mut &value[..]) {
+			Ok((old, linkage)) => put_storage_value(
+				I::PREFIX.as_bytes(),
+				b"Validators",
+				hash,
+				(translate_prefs::<BalanceOf<T, I>>(old), linkage),
+			),
+			Err(_) => frame_support::print(
+				"Encountered error in migration of Staking::Validators map."
+			),
+		}
+	});
+	if !done {
+		return false
+	}
+
+	let done = migrate_values::<I>(b"ErasValidatorPrefs", budget, |hash, value| {
+		match deprecated::OldValidatorPrefs::decode(&mut &value[..]) {
+			Ok(old) => put_storage_value(
+				I::PREFIX.as_bytes(),
+				b"ErasValidatorPrefs",
+				hash,
+				translate_prefs::<BalanceOf<T, I>>(old),
+			),
+			Err(_) => frame_support::print(
+				"Encountered error in migration of Staking::ErasValidatorPrefs map."
+			),
+		}
+	});
+	if !done {
+		return false
+	}
+
+	StorageVersion::<I>::put(Releases::V4_0_0);
+	true
+}
+
+fn upgrade_v4_to_v5<T: Trait<I>, I: Instance>(budget: &mut u32) -> bool {
+	let done = migrate_linked::<I, _, _>(
+		b"Nominators",
+		budget,
+		|nominator: T::AccountId, nominations: Nominations<T::AccountId>| {
+			for target in nominations.targets {
+				<Module<T, I> as Store>::NominatorsFor::mutate(target, |nominators| {
+					if let Err(index) = nominators.binary_search(&nominator) {
+						nominators.insert(index, nominator.clone());
+					}
+				});
+			}
+		},
+	);
+	if !done {
+		return false
+	}
+
+	StorageVersion::<I>::put(Releases::V5_0_0);
+	true
+}
+
+fn upgrade_v5_to_v6<T: Trait<I>, I: Instance>(budget: &mut u32) -> bool {
+	// The nominators are counted once all validators are.
+	let nominators = [Twox128::hash(I::PREFIX.as_bytes()), Twox128::hash(b"Nominators")].concat();
+	let cursor = MigrationCursor::<I>::get();
+	let counting_nominators = cursor.as_ref()
+		.map_or(false, |cursor| cursor.starts_with(&nominators));
+	if cursor.is_none() {
+		<Module<T, I> as Store>::CounterForValidators::put(0);
+	}
+
+	let done = counting_nominators || migrate_values::<I>(b"Validators", budget, |_, _| {
+		<Module<T, I> as Store>::CounterForValidators::mutate(|count| *count = count.saturating_add(1))
+	});
+	if !done {
+		return false
+	}
+
+	if !counting_nominators {
+		<Module<T, I> as Store>::CounterForNominators::put(0);
+	}
+	let done = migrate_values::<I>(b"Nominators", budget, |_, _| {
+		<Module<T, I> as Store>::CounterForNominators::mutate(|count| *count = count.saturating_add(1))
+	});
+	if !done {
+		return false
+	}
+
+	StorageVersion::<I>::put(Releases::V6_0_0);
+	true
+}
+
+/// Remove the value of `key` from the staking linked map `item`, leaving the linkage of the other
+/// values as it is.
+fn clear_linked<I: Instance, K: Encode>(item: &[u8], key: &K) {
+	let prefix = [Twox128::hash(I::PREFIX.as_bytes()), Twox128::hash(item)].concat();
+	sp_io::storage::clear(&[&prefix[..], &Blake2_256::hash(&key.encode())[..]].concat());
+}
+
+fn upgrade_v6_to_v7<T: Trait<I>, I: Instance>(budget: &mut u32) -> bool {
+	// The head of each linked map is removed once all of its values are moved: the validators are
+	// moved as long as the head of theirs is left.
+	if sp_io::storage::exists(&linked_head::<I>(b"Validators")) {
+		let done = migrate_linked::<I, _, _>(
+			b"Validators",
+			budget,
+			|stash: T::AccountId, prefs: deprecated::v13::ValidatorPrefs<BalanceOf<T, I>>| {
+				clear_linked::<I, _>(b"Validators", &stash);
+				let hash = Twox64Concat::hash(&stash.encode());
+				put_storage_value(I::PREFIX.as_bytes(), b"Validators", &hash, prefs);
+			},
+		);
+		if !done {
+			return false
+		}
+		sp_io::storage::clear(&linked_head::<I>(b"Validators"));
+	}
+
+	let done = migrate_linked::<I, _, _>(
+		b"Nominators",
+		budget,
+		|stash: T::AccountId, nominations: Nominations<T::AccountId>| {
+			clear_linked::<I, _>(b"Nominators", &stash);
+			<Module<T, I> as Store>::Nominators::insert(stash, nominations);
+		},
+	);
+	if !done {
+		return false
+	}
+	sp_io::storage::clear(&linked_head::<I>(b"Nominators"));
+
+	StorageVersion::<I>::put(Releases::V7_0_0);
+	true
+}
+
+fn upgrade_v7_to_v8<T: Trait<I>, I: Instance>(budget: &mut u32) -> bool {
+	// `Ledger` is migrated once all nominations are truncated.
+	let ledger = [Twox128::hash(I::PREFIX.as_bytes()), Twox128::hash(b"Ledger")].concat();
+	let migrating_ledger = MigrationCursor::<I>::get()
+		.map_or(false, |cursor| cursor.starts_with(&ledger));
+
+	// `Nominators` is keyed with `twox_64_concat`: the stash follows the 8 bytes of its hash.
+	let max_nominations = T::MaxNominations::get() as usize;
+	let done = migrating_ledger || migrate_values::<I>(b"Nominators", budget, |hash, value| {
+		let stash = hash.get(8..).and_then(|mut key| T::AccountId::decode(&mut key).ok());
+		let nominations = Nominations::<T::AccountId>::decode(&mut &value[..]).ok();
+		match (stash, nominations) {
+			(Some(stash), Some(mut nominations)) => if nominations.targets.len() > max_nominations {
+				nominations.targets.truncate(max_nominations);
+				Module::<T, I>::set_nominations(&stash, nominations);
+			},
+			_ => frame_support::print("Encountered error in migration of Staking::Nominators map."),
+		}
+	});
+	if !done {
+		return false
+	}
+
+	let max_chunks = (T::MaxUnlockingChunks::get() as usize).max(1);
+	let done = migrate_values::<I>(b"Ledger", budget, |hash, value| {
+		let mut ledger = match StakingLedger::<T::AccountId, BalanceOf<T, I>>::decode(&mut &value[..]) {
+			Ok(ledger) => ledger,
+			Err(_) => {
+				frame_support::print("Encountered error in migration of Staking::Ledger map.");
+				return
+			},
+		};
+		if ledger.unlocking.len() > max_chunks {
+			let merged = ledger.unlocking.split_off(max_chunks - 1).into_iter()
+				.fold(UnlockChunk { value: Zero::zero(), era: 0 }, |merged, chunk| UnlockChunk {
+					value: merged.value.saturating_add(chunk.value),
+					era: merged.era.max(chunk.era),
+				});
+			ledger.unlocking.push(merged);
+			put_storage_value(I::PREFIX.as_bytes(), b"Ledger", hash, ledger);
+		}
+	});
+	if !done {
+		return false
+	}
+
+	StorageVersion::<I>::put(Releases::V8_0_0);
+	true
+}
+
+/// Move the value of the staking map `item` from the key hash `old_hash` to `new_hash`, if any.
+fn move_value<I: Instance>(item: &[u8], old_hash: &[u8], new_hash: &[u8]) {
+	let prefix = [Twox128::hash(I::PREFIX.as_bytes()), Twox128::hash(item)].concat();
+	let old_key = [&prefix[..], old_hash].concat();
+	if let Some(value) = sp_io::storage::get(&old_key) {
+		sp_io::storage::clear(&old_key);
+		sp_io::storage::set(&[&prefix[..], new_hash].concat(), &value);
+	}
+}
+
+/// Move the values of the staking double map `item` under the first key hash `old_hash` to
+/// `new_hash`, keeping their second key hash.
+fn move_prefix<I: Instance>(item: &[u8], old_hash: &[u8], new_hash: &[u8]) {
+	let prefix = [Twox128::hash(I::PREFIX.as_bytes()), Twox128::hash(item)].concat();
+	let old_prefix = [&prefix[..], old_hash].concat();
+	let new_prefix = [&prefix[..], new_hash].concat();
+	let mut key = old_prefix.clone();
+	while let Some(next) = sp_io::storage::next_key(&key)
+		.filter(|next| next.starts_with(&old_prefix))
+	{
+		if let Some(value) = sp_io::storage::get(&next) {
+			sp_io::storage::clear(&next);
+			sp_io::storage::set(&[&new_prefix[..], &next[old_prefix.len()..]].concat(), &value);
+		}
+		key = next;
+	}
+}
+
+fn upgrade_v8_to_v9<T: Trait<I>, I: Instance>(budget: &mut u32) -> bool {
+	// `NominatorsFor` is migrated once all ledgers are.
+	let nominators_for = [
+		Twox128::hash(I::PREFIX.as_bytes()),
+		Twox128::hash(b"NominatorsFor"),
+	].concat();
+	let migrating_nominators_for = MigrationCursor::<I>::get()
+		.map_or(false, |cursor| cursor.starts_with(&nominators_for));
+
+	// The keys of `Bonded` and `Ledger` are found from the stash of each ledger. A ledger is only
+	// moved if its key is the `blake2_256` hash of the controller bonded to its stash, as the keys
+	// of both hashers share the prefix of the map: the ledgers already moved are skipped when met
+	// again.
+	let done = migrating_nominators_for || migrate_values::<I>(b"Ledger", budget, |hash, value| {
+		let ledger = match StakingLedger::<T::AccountId, BalanceOf<T, I>>::decode(&mut &value[..]) {
+			Ok(ledger) => ledger,
+			Err(_) => return,
+		};
+		let stash = ledger.stash.encode();
+		let bonded = get_storage_value::<T::AccountId>(
+			I::PREFIX.as_bytes(),
+			b"Bonded",
+			&Blake2_256::hash(&stash),
+		);
+		let controller = match bonded {
+			Some(controller) => controller.encode(),
+			None => return,
+		};
+		if Blake2_256::hash(&controller)[..] != hash[..] {
+			return
+		}
+
+		move_value::<I>(b"Bonded", &Blake2_256::hash(&stash), &Blake2_128Concat::hash(&stash));
+		move_value::<I>(b"Payee", &Blake2_256::hash(&stash), &Blake2_128Concat::hash(&stash));
+		move_value::<I>(b"CommissionPayee", &Blake2_256::hash(&stash), &Blake2_128Concat::hash(&stash));
+		move_value::<I>(b"Ledger", hash, &Blake2_128Concat::hash(&controller));
+	});
+	if !done {
+		return false
+	}
+
+	// The nominators indexed for a target all nominate it: the key of each Vec is found among the
+	// targets of its first nominator, and the Vecs already moved are skipped in the same way.
+	let done = migrate_values::<I>(b"NominatorsFor", budget, |hash, value| {
+		let first = match Vec::<T::AccountId>::decode(&mut &value[..]).ok()
+			.and_then(|nominators| nominators.into_iter().next())
+		{
+			Some(first) => first,
+			None => return,
+		};
+		let targets = Module::<T, I>::nominators(first).map_or_else(Vec::new, |n| n.targets);
+		if let Some(target) = targets.iter().map(Encode::encode)
+			.find(|target| Blake2_256::hash(target)[..] == hash[..])
+		{
+			move_value::<I>(b"NominatorsFor", hash, &Blake2_128Concat::hash(&target));
+		}
+	});
+	if !done {
+		return false
+	}
+
+	let era_items: [&[u8]; 10] = [
+		b"ErasStartSessionIndex",
+		b"ErasValidatorReward",
+		b"ErasRemainderReward",
+		b"ErasPayoutSummary",
+		b"ErasRewardPoints",
+		b"ErasTotalStake",
+		b"ErasStart",
+		b"ErasDuration",
+		b"ErasTotalSlashed",
+		b"UnappliedSlashes",
+	];
+	let current_era = <Module<T, I> as Store>::CurrentEra::get().unwrap_or(0);
+	let first_era = current_era.saturating_sub(Module::<T, I>::history_depth())
+		.min(<Module<T, I> as Store>::EarliestUnappliedSlash::get().unwrap_or(current_era));
+	for era in first_era..=current_era {
+		let era = era.encode();
+		for item in era_items.iter() {
+			move_value::<I>(item, &Blake2_256::hash(&era), &Twox64Concat::hash(&era));
+		}
+	}
+	// The slashes in an era are recorded as long as it is bonded, which may be longer than the
+	// history.
+	for (era, _) in BondedEras::<I>::get() {
+		let era = era.encode();
+		for item in [&b"ValidatorSlashInEra"[..], &b"NominatorSlashInEra"[..]].iter() {
+			move_prefix::<I>(item, &Blake2_256::hash(&era), &Twox64Concat::hash(&era));
+		}
+	}
+
+	StorageVersion::<I>::put(Releases::V9_0_0);
+	true
+}
+
+fn upgrade_v9_to_v10<T: Trait<I>, I: Instance>() {
+	for stash in <Module<T, I> as Store>::Invulnerables::get() {
+		<Module<T, I> as Store>::IsInvulnerable::insert(stash, true);
+	}
+	for stash in <Module<T, I> as Store>::AutoPayoutStashes::get() {
+		<Module<T, I> as Store>::HasAutoPayout::insert(stash, true);
+	}
+
+	StorageVersion::<I>::put(Releases::V10_0_0);
+}
+
+fn upgrade_v10_to_v11<T: Trait<I>, I: Instance>(budget: &mut u32) -> bool {
+	let done = migrate_values::<I>(b"Ledger", budget, |_, value| {
+		match StakingLedger::<T::AccountId, BalanceOf<T, I>>::decode(&mut &value[..]) {
+			Ok(ledger) => <Module<T, I> as Store>::ActiveBonded::insert(ledger.stash, ledger.active),
+			Err(_) => frame_support::print("Encountered error in migration of Staking::Ledger map."),
+		}
+	});
+	if !done {
+		return false
+	}
+
+	StorageVersion::<I>::put(Releases::V11_0_0);
+	true
+}
+
+fn upgrade_v11_to_v12<T: Trait<I>, I: Instance>() {
+	// The slashes are queued under the active era they were reported in, which are the eras
+	// whose values were moved by the upgrade to v9.0.0.
+	let current_era = <Module<T, I> as Store>::CurrentEra::get().unwrap_or(0);
+	let first_era = current_era.saturating_sub(Module::<T, I>::history_depth())
+		.min(<Module<T, I> as Store>::EarliestUnappliedSlash::get().unwrap_or(current_era));
+	for era in first_era..=current_era {
+		let old: Vec<deprecated::v11::UnappliedSlash<T::AccountId, BalanceOf<T, I>>> =
+			take_storage_value(I::PREFIX.as_bytes(), b"UnappliedSlashes", &Twox64Concat::hash(&era.encode()))
+				.unwrap_or_default();
+		if old.is_empty() {
+			continue
+		}
+		<Module<T, I> as Store>::UnappliedSlashes::insert(
+			era,
+			old.into_iter().map(|slash| UnappliedSlash {
+				id: slash.id,
+				validator: slash.validator,
+				slash_era: era,
+				own: slash.own,
+				others: slash.others,
+				reporters: slash.reporters,
+				payout: slash.payout,
+			}).collect::<Vec<_>>(),
+		);
+	}
+
+	StorageVersion::<I>::put(Releases::V12_0_0);
+}
+
+fn upgrade_v12_to_v13<T: Trait<I>, I: Instance>() {
+	for slash in <Module<T, I> as Store>::UnappliedSlashes::iter().flatten() {
+		Module::<T, I>::count_pending_slash(&slash, true);
+	}
+
+	StorageVersion::<I>::put(Releases::V13_0_0);
+}
+
+fn upgrade_v13_to_v14<T: Trait<I>, I: Instance>(budget: &mut u32) -> bool {
+	// The preferences of a validator, in the current and past eras alike, are given the commission
+	// payee of its stash, the stash being the last key of both maps.
+	fn translate_prefs<T: Trait<I>, I: Instance>(
+		stash: Option<&[u8]>,
+		value: &[u8],
+	) -> Option<ValidatorPrefs<T::AccountId, BalanceOf<T, I>>> {
+		let stash = T::AccountId::decode(&mut stash?).ok()?;
+		let old = deprecated::v13::ValidatorPrefs::<BalanceOf<T, I>>::decode(&mut &value[..]).ok()?;
+		Some(ValidatorPrefs {
+			commission: old.commission,
+			max_total_stake: old.max_total_stake,
+			commission_payee: get_storage_value(
+				I::PREFIX.as_bytes(),
+				b"CommissionPayee",
+				&Blake2_128Concat::hash(&stash.encode()),
+			),
+		})
+	}
+
+	// `ErasValidatorPrefs` is migrated once all validators are.
+	let era_prefs = [
+		Twox128::hash(I::PREFIX.as_bytes()),
+		Twox128::hash(b"ErasValidatorPrefs"),
+	].concat();
+	let migrating_era_prefs = MigrationCursor::<I>::get()
+		.map_or(false, |cursor| cursor.starts_with(&era_prefs));
+
+	// `Validators` is keyed with `twox_64_concat`: the stash follows the 8 bytes of its hash.
+	let done = migrating_era_prefs || migrate_values::<I>(b"Validators", budget, |hash, value| {
+		match translate_prefs::<T, I>(hash.get(8..), &value) {
+			Some(prefs) => put_storage_value(I::PREFIX.as_bytes(), b"Validators", hash, prefs),
+			None => frame_support::print(
+				"Encountered error in migration of Staking::Validators map."
+			),
+		}
+	});
+	if !done {
+		return false
+	}
+
+	// The stash follows the 8 bytes of the hash of the era, the era, and the 8 bytes of its hash.
+	let done = migrate_values::<I>(b"ErasValidatorPrefs", budget, |hash, value| {
+		match translate_prefs::<T, I>(hash.get(20..), &value) {
+			Some(prefs) => {
+				put_storage_value(I::PREFIX.as_bytes(), b"ErasValidatorPrefs", hash, prefs)
+			},
+			None => frame_support::print(
+				"Encountered error in migration of Staking::ErasValidatorPrefs map."
+			),
+		}
+	});
+	if !done {
+		return false
+	}
+
+	sp_io::storage::clear_prefix(&[
+		Twox128::hash(I::PREFIX.as_bytes()),
+		Twox128::hash(b"CommissionPayee"),
+	].concat());
+
+	StorageVersion::<I>::put(Releases::V14_0_0);
+	true
 }
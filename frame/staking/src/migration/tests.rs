@@ -1,9 +1,48 @@
 use crate::*;
 use crate::mock::*;
-use frame_support::storage::migration::*;
-use sp_core::hashing::blake2_256;
+use frame_support::{assert_ok, assert_noop, Blake2_128Concat, storage::migration::*};
+use sp_core::hashing::{blake2_256, twox_128};
 use super::test_upgrade_from_master_dataset;
-use sp_runtime::traits::OnRuntimeUpgrade;
+use sp_runtime::traits::{OnRuntimeUpgrade, OnFinalize};
+use sp_staking::offence::OffenceDetails;
+
+/// Rewrite the validator preferences in storage in their format prior to v4.0.0, dropping those of
+/// past eras.
+fn put_old_validator_prefs() {
+    let validators = Staking::iter_validators().collect::<Vec<_>>();
+    for (stash, _) in &validators {
+        <Staking as Store>::Validators::remove(stash);
+    }
+    for (stash, prefs) in validators {
+        super::deprecated::Validators::<Test>::insert(
+            stash,
+            super::deprecated::OldValidatorPrefs { commission: prefs.commission },
+        );
+    }
+    <Staking as Store>::ErasValidatorPrefs::remove_all();
+}
+
+/// Move the validators to their linked map prior to v7.0.0.
+fn put_linked_validators() {
+    for (stash, prefs) in Staking::iter_validators().collect::<Vec<_>>() {
+        <Staking as Store>::Validators::remove(&stash);
+        super::deprecated::v6::Validators::<Test>::insert(
+            stash,
+            super::deprecated::v13::ValidatorPrefs {
+                commission: prefs.commission,
+                max_total_stake: prefs.max_total_stake,
+            },
+        );
+    }
+}
+
+/// Move the nominators to their linked map prior to v7.0.0.
+fn put_linked_nominators() {
+    for (stash, nominations) in Staking::iter_nominators().collect::<Vec<_>>() {
+        <Staking as Store>::Nominators::remove(&stash);
+        super::deprecated::v6::Nominators::<Test>::insert(stash, nominations);
+    }
+}
 
 #[test]
 fn upgrade_works() {
@@ -43,13 +82,15 @@ fn upgrade_works() {
         put_storage_value::<(u32, Vec<u32>)>(b"Staking", b"CurrentEraPointsEarned", b"", (12, vec![2, 10]));
         <Staking as Store>::ErasStakers::remove_all();
         <Staking as Store>::ErasStakersClipped::remove_all();
+        put_old_validator_prefs();
+        put_linked_nominators();
 
         <Staking as Store>::StorageVersion::put(Releases::V1_0_0);
 
         // Perform upgrade
         Staking::on_runtime_upgrade();
 
-        assert_eq!(<Staking as Store>::StorageVersion::get(), Releases::V2_0_0);
+        assert_eq!(<Staking as Store>::StorageVersion::get(), Releases::V14_0_0);
 
         // Check migration
         assert_eq!(<Staking as Store>::ErasStartSessionIndex::get(3).unwrap(), 5);
@@ -96,6 +137,556 @@ fn upgrade_works() {
     })
 }
 
+#[test]
+fn upgrade_v2_to_v3_assigns_slash_ids() {
+    ExtBuilder::default().build().execute_with(|| {
+        start_era(2);
+        <Staking as Store>::EarliestUnappliedSlash::put(1);
+        let old_slash = |validator| super::deprecated::OldUnappliedSlash::<AccountId, Balance> {
+            validator,
+            own: 10,
+            others: vec![(101, 5)],
+            reporters: vec![1],
+            payout: 1,
+        };
+        put_storage_value(
+            b"Staking", b"UnappliedSlashes", &blake2_256(&1u32.encode()),
+            vec![old_slash(11), old_slash(21)],
+        );
+        put_storage_value(
+            b"Staking", b"UnappliedSlashes", &blake2_256(&2u32.encode()),
+            vec![old_slash(31)],
+        );
+        put_old_validator_prefs();
+        put_linked_nominators();
+
+        <Staking as Store>::StorageVersion::put(Releases::V2_0_0);
+
+        // Perform upgrade
+        Staking::on_runtime_upgrade();
+
+        assert_eq!(<Staking as Store>::StorageVersion::get(), Releases::V14_0_0);
+
+        let mut ids = Staking::unapplied_slashes(1).into_iter()
+            .chain(Staking::unapplied_slashes(2))
+            .map(|slash| {
+                assert!(slash.slash_era == 1 || slash.slash_era == 2);
+                assert_eq!(slash.own, 10);
+                assert_eq!(slash.others, vec![(101, 5)]);
+                assert_eq!(slash.reporters, vec![1]);
+                slash.id
+            })
+            .collect::<Vec<_>>();
+        ids.sort();
+        assert_eq!(ids, vec![0, 1, 2]);
+        assert_eq!(Staking::next_slash_id(), 3);
+    })
+}
+
+#[test]
+fn upgrade_v3_to_v4_translates_validator_prefs() {
+    ExtBuilder::default().build().execute_with(|| {
+        let commission = Perbill::from_percent(7);
+        <Staking as Store>::Validators::insert(11, ValidatorPrefs { commission, ..Default::default() });
+        put_old_validator_prefs();
+        super::deprecated::ErasValidatorPrefs::<Test>::insert(
+            1,
+            11,
+            super::deprecated::OldValidatorPrefs { commission },
+        );
+        put_linked_nominators();
+
+        <Staking as Store>::StorageVersion::put(Releases::V3_0_0);
+
+        // A single validator is migrated in the first block.
+        assert!(!super::migrate::<Test, DefaultInstance>(1));
+        assert_eq!(<Staking as Store>::StorageVersion::get(), Releases::V3_0_0);
+        assert!(<Staking as Store>::MigrationCursor::get().is_some());
+
+        let mut blocks = 1;
+        while !super::migrate::<Test, DefaultInstance>(1) {
+            blocks += 1;
+        }
+        assert!(blocks > 3);
+
+        assert_eq!(<Staking as Store>::StorageVersion::get(), Releases::V14_0_0);
+        assert!(<Staking as Store>::MigrationCursor::get().is_none());
+
+        let prefs = ValidatorPrefs { commission, ..Default::default() };
+        assert_eq!(Staking::validators(11), prefs);
+        assert_eq!(Staking::validators(21), ValidatorPrefs::default());
+        assert_eq!(Staking::iter_validators().count(), 3);
+        assert_eq!(Staking::eras_validator_prefs(1, 11), prefs);
+    })
+}
+
+#[test]
+fn upgrade_v4_to_v5_indexes_nominators() {
+    ExtBuilder::default().build().execute_with(|| {
+        Staking::set_nominations(
+            &103,
+            Nominations { targets: vec![11], submitted_in: 0, suppressed: false },
+        );
+        <Staking as Store>::NominatorsFor::remove_all();
+        put_linked_validators();
+        put_linked_nominators();
+        <Staking as Store>::StorageVersion::put(Releases::V4_0_0);
+
+        // A single nominator is indexed in the first block.
+        assert!(!super::migrate::<Test, DefaultInstance>(1));
+        assert_eq!(<Staking as Store>::StorageVersion::get(), Releases::V4_0_0);
+        assert!(<Staking as Store>::MigrationCursor::get().is_some());
+        let indexed = <Staking as Store>::NominatorsFor::iter()
+            .map(|(_, nominators)| nominators.len())
+            .sum::<usize>();
+        assert!(indexed > 0 && indexed < 3);
+
+        while !super::migrate::<Test, DefaultInstance>(1) {}
+
+        assert_eq!(<Staking as Store>::StorageVersion::get(), Releases::V14_0_0);
+
+        assert_eq!(Staking::nominators_for(11), vec![101, 103]);
+        assert_eq!(Staking::nominators_for(21), vec![101]);
+        assert!(Staking::nominators_for(31).is_empty());
+    })
+}
+
+#[test]
+fn upgrade_v5_to_v6_counts_validators_and_nominators() {
+    ExtBuilder::default().build().execute_with(|| {
+        <Staking as Store>::CounterForValidators::put(7);
+        <Staking as Store>::CounterForNominators::kill();
+        put_linked_validators();
+        put_linked_nominators();
+        <Staking as Store>::StorageVersion::put(Releases::V5_0_0);
+
+        // A single validator is counted in the first block.
+        assert!(!super::migrate::<Test, DefaultInstance>(1));
+        assert_eq!(<Staking as Store>::StorageVersion::get(), Releases::V5_0_0);
+        assert_eq!(Staking::counter_for_validators(), 1);
+
+        while !super::migrate::<Test, DefaultInstance>(1) {}
+
+        assert_eq!(<Staking as Store>::StorageVersion::get(), Releases::V14_0_0);
+        assert_eq!(Staking::counter_for_validators(), 3);
+        assert_eq!(Staking::counter_for_nominators(), 1);
+    })
+}
+
+#[test]
+fn upgrade_v6_to_v7_moves_validators_and_nominators() {
+    ExtBuilder::default().build().execute_with(|| {
+        let commission = Perbill::from_percent(7);
+        <Staking as Store>::Validators::insert(11, ValidatorPrefs { commission, ..Default::default() });
+        let mut validators = Staking::iter_validators().collect::<Vec<_>>();
+        validators.sort_by_key(|&(stash, _)| stash);
+        let nominations = Staking::nominators(101).unwrap();
+        put_linked_validators();
+        put_linked_nominators();
+        assert_eq!(Staking::iter_validators().count(), 0);
+        assert_eq!(Staking::iter_nominators().count(), 0);
+
+        <Staking as Store>::StorageVersion::put(Releases::V6_0_0);
+
+        // A single validator is moved in the first block.
+        assert!(!super::migrate::<Test, DefaultInstance>(1));
+        assert_eq!(<Staking as Store>::StorageVersion::get(), Releases::V6_0_0);
+        assert_eq!(Staking::iter_validators().count(), 1);
+        assert!(super::deprecated::v6::Validators::<Test>::head().is_some());
+
+        while !super::migrate::<Test, DefaultInstance>(1) {}
+
+        assert_eq!(<Staking as Store>::StorageVersion::get(), Releases::V14_0_0);
+
+        // The linked maps are gone, leaving only the entries of the plain maps under their prefix.
+        assert!(super::deprecated::v6::Validators::<Test>::head().is_none());
+        assert!(super::deprecated::v6::Nominators::<Test>::head().is_none());
+        let mut upgraded = Staking::iter_validators().collect::<Vec<_>>();
+        upgraded.sort_by_key(|&(stash, _)| stash);
+        assert_eq!(upgraded, validators);
+        assert_eq!(Staking::iter_nominators().collect::<Vec<_>>(), vec![(101, nominations)]);
+        assert_eq!(Staking::validators(11).commission, commission);
+        assert_eq!(Staking::do_try_state(), Ok(()));
+    })
+}
+
+#[test]
+fn upgrade_v7_to_v8_bounds_nominations_and_ledgers() {
+    ExtBuilder::default().build().execute_with(|| {
+        let max_nominations = <Test as Trait>::MaxNominations::get() as u64;
+        let max_chunks = <Test as Trait>::MaxUnlockingChunks::get() as EraIndex;
+        let targets = (0..max_nominations + 2).map(|i| 1_000 + i).collect::<Vec<_>>();
+        Staking::set_nominations(&101, Nominations { targets: targets.clone(), submitted_in: 0, suppressed: false });
+        let unlocking = (1..=max_chunks + 2)
+            .map(|era| UnlockChunk { value: 1, era })
+            .collect::<Vec<_>>();
+        <Staking as Store>::Ledger::mutate(100, |ledger| {
+            let ledger = ledger.as_mut().unwrap();
+            ledger.active -= unlocking.len() as Balance;
+            ledger.unlocking = unlocking;
+        });
+
+        <Staking as Store>::StorageVersion::put(Releases::V7_0_0);
+
+        // The nominations are truncated before the ledgers are bounded.
+        assert!(!super::migrate::<Test, DefaultInstance>(1));
+        assert_eq!(<Staking as Store>::StorageVersion::get(), Releases::V7_0_0);
+        assert_eq!(Staking::nominators(101).unwrap().targets.len(), max_nominations as usize);
+        assert_eq!(Staking::ledger(100).unwrap().unlocking.len(), max_chunks as usize + 2);
+
+        while !super::migrate::<Test, DefaultInstance>(1) {}
+
+        assert_eq!(<Staking as Store>::StorageVersion::get(), Releases::V14_0_0);
+
+        // The nominations over the limit are dropped, from `NominatorsFor` too.
+        assert_eq!(Staking::nominators(101).unwrap().targets, &targets[..max_nominations as usize]);
+        assert_eq!(Staking::nominators_for(1_000), vec![101]);
+        assert!(Staking::nominators_for(1_000 + max_nominations).is_empty());
+        assert_eq!(Staking::counter_for_nominators(), 1);
+
+        // The chunks over the limit are merged into the last one, unlocking at the latest era.
+        let ledger = Staking::ledger(100).unwrap();
+        assert_eq!(ledger.unlocking.len(), max_chunks as usize);
+        assert_eq!(ledger.unlocking[max_chunks as usize - 2], UnlockChunk { value: 1, era: max_chunks - 1 });
+        assert_eq!(ledger.unlocking.last(), Some(&UnlockChunk { value: 3, era: max_chunks + 2 }));
+        assert_eq!(ledger.total, 500);
+        assert_eq!(Staking::do_try_state(), Ok(()));
+    })
+}
+
+#[test]
+fn upgrade_v8_to_v9_rehashes_accounts_and_eras() {
+    ExtBuilder::default().build().execute_with(|| {
+        start_era(3);
+        let ledger = Staking::ledger(10).unwrap();
+        let total_stake = Staking::eras_total_stake(2);
+        assert!(total_stake > 0);
+
+        // Move the values to their keys prior to v9.0.0.
+        <Staking as Store>::Bonded::remove(11);
+        <Staking as Store>::Ledger::remove(10);
+        <Staking as Store>::Payee::remove(11);
+        <Staking as Store>::ErasTotalStake::remove(2);
+        put_storage_value(b"Staking", b"Bonded", &blake2_256(&11u64.encode()), 10u64);
+        put_storage_value(b"Staking", b"Ledger", &blake2_256(&10u64.encode()), &ledger);
+        put_storage_value(b"Staking", b"Payee", &blake2_256(&11u64.encode()), RewardDestination::Stash);
+        put_storage_value(b"Staking", b"ErasTotalStake", &blake2_256(&2u32.encode()), total_stake);
+        <Staking as Store>::NominatorsFor::remove(11);
+        put_storage_value(b"Staking", b"NominatorsFor", &blake2_256(&11u64.encode()), vec![101u64]);
+        put_storage_value(b"Staking", b"CommissionPayee", &blake2_256(&11u64.encode()), 1337u64);
+        let slash_key = [blake2_256(&2u32.encode()), twox_128(&11u64.encode())].concat();
+        let slash = (Perbill::from_percent(10), 100u64);
+        put_storage_value(b"Staking", b"ValidatorSlashInEra", &slash_key, slash);
+        // Era 0 is out of the history, but still bonded.
+        <Staking as Store>::HistoryDepth::put(1);
+        let old_slash_key = [blake2_256(&0u32.encode()), twox_128(&101u64.encode())].concat();
+        put_storage_value(b"Staking", b"NominatorSlashInEra", &old_slash_key, 50u64);
+        assert_eq!(Staking::bonded(11), None);
+        assert!(Staking::nominators_for(11).is_empty());
+
+        <Staking as Store>::StorageVersion::put(Releases::V8_0_0);
+
+        // Perform upgrade
+        Staking::on_runtime_upgrade();
+
+        assert_eq!(<Staking as Store>::StorageVersion::get(), Releases::V14_0_0);
+        assert_eq!(Staking::bonded(11), Some(10));
+        assert_eq!(Staking::ledger(10), Some(ledger));
+        assert_eq!(Staking::payee(11), RewardDestination::Stash);
+        assert_eq!(Staking::eras_total_stake(2), total_stake);
+        assert_eq!(Staking::nominators_for(11), vec![101]);
+        assert_eq!(Staking::validators(11).commission_payee, Some(1337));
+        assert_eq!(<Staking as Store>::ValidatorSlashInEra::get(2, 11), Some(slash));
+        assert!(!have_storage_value(b"Staking", b"ValidatorSlashInEra", &slash_key));
+        assert_eq!(<Staking as Store>::NominatorSlashInEra::get(0, 101), Some(50));
+        assert!(!have_storage_value(b"Staking", b"NominatorSlashInEra", &old_slash_key));
+        assert!(!have_storage_value(b"Staking", b"Bonded", &blake2_256(&11u64.encode())));
+        assert!(!have_storage_value(b"Staking", b"Ledger", &blake2_256(&10u64.encode())));
+        assert_eq!(Staking::do_try_state(), Ok(()));
+    })
+}
+
+#[test]
+fn ledgers_are_migrated_over_several_blocks() {
+    ExtBuilder::default().build().execute_with(|| {
+        let ledger = Staking::ledger(10).unwrap();
+        <Staking as Store>::Bonded::remove(11);
+        <Staking as Store>::Ledger::remove(10);
+        put_storage_value(b"Staking", b"Bonded", &blake2_256(&11u64.encode()), 10u64);
+        put_storage_value(b"Staking", b"Ledger", &blake2_256(&10u64.encode()), &ledger);
+
+        <Staking as Store>::StorageVersion::put(Releases::V8_0_0);
+
+        // A single ledger is migrated in the first block.
+        assert!(!super::migrate::<Test, DefaultInstance>(1));
+        assert_eq!(<Staking as Store>::StorageVersion::get(), Releases::V8_0_0);
+        assert!(<Staking as Store>::MigrationCursor::get().is_some());
+        assert!(!Staking::is_storage_migrated());
+        assert_noop!(Staking::chill(Origin::signed(20)), Error::<Test, _>::MigrationInProgress);
+        assert_noop!(
+            Staking::set_history_depth(Origin::ROOT, 3),
+            Error::<Test, _>::MigrationInProgress,
+        );
+        assert_noop!(
+            Staking::cancel_deferred_slash(Origin::ROOT, 0, vec![0]),
+            Error::<Test, _>::MigrationInProgress,
+        );
+
+        let mut blocks = 1;
+        while !super::migrate::<Test, DefaultInstance>(1) {
+            blocks += 1;
+        }
+        assert!(blocks > 2);
+
+        assert_eq!(<Staking as Store>::StorageVersion::get(), Releases::V14_0_0);
+        assert!(<Staking as Store>::MigrationCursor::get().is_none());
+        assert_eq!(Staking::bonded(11), Some(10));
+        assert_eq!(Staking::ledger(10), Some(ledger));
+        assert_ok!(Staking::chill(Origin::signed(20)));
+        assert_eq!(Staking::do_try_state(), Ok(()));
+    })
+}
+
+#[test]
+fn offences_reported_while_migrating_are_slashed_once_migrated() {
+    ExtBuilder::default().build().execute_with(|| {
+        start_era(1);
+        let ledger = Staking::ledger(10).unwrap();
+        <Staking as Store>::Bonded::remove(11);
+        <Staking as Store>::Ledger::remove(10);
+        put_storage_value(b"Staking", b"Bonded", &blake2_256(&11u64.encode()), 10u64);
+        put_storage_value(b"Staking", b"Ledger", &blake2_256(&10u64.encode()), &ledger);
+
+        <Staking as Store>::StorageVersion::put(Releases::V8_0_0);
+        assert!(!super::migrate::<Test, DefaultInstance>(1));
+        assert!(<Staking as Store>::MigrationCursor::get().is_some());
+
+        // The slash is not deferred, but the ledger of 11 can't be found yet: the offence is
+        // kept as it is, without computing its slash.
+        assert_eq!(Staking::slash_defer_duration(), 0);
+        on_offence_now(
+            &[OffenceDetails { offender: (11, Staking::eras_stakers(1, 11)), reporters: vec![] }],
+            &[Perbill::from_percent(10)],
+        );
+        assert_eq!(Balances::free_balance(11), 1000);
+        assert_eq!(<Staking as Store>::OffencesQueuedWhileMigrating::get().len(), 1);
+        assert!(Staking::unapplied_slashes(1).is_empty());
+        assert_eq!(<Staking as Store>::ValidatorSlashInEra::get(1, 11), None);
+
+        // The slash is applied in the block the migration ends, once.
+        Staking::on_finalize(System::block_number());
+        assert!(Staking::is_storage_migrated());
+        assert!(<Staking as Store>::OffencesQueuedWhileMigrating::get().is_empty());
+        assert_eq!(Balances::free_balance(11), 900);
+        assert_eq!(Staking::ledger(10).unwrap().active, ledger.active - 100);
+        assert_eq!(Staking::eras_slashes(1, 11), 100);
+        assert!(Staking::unapplied_slashes(1).is_empty());
+
+        Staking::on_finalize(System::block_number());
+        assert_eq!(Balances::free_balance(11), 900);
+        assert_eq!(Staking::do_try_state(), Ok(()));
+    })
+}
+
+#[test]
+fn offences_reported_before_upgrade_v11_to_v12_are_deferred_in_the_new_format() {
+    ExtBuilder::default().slash_defer_duration(2).build().execute_with(|| {
+        start_era(1);
+        // A slash deferred before the upgrade, in its format prior to v12.0.0.
+        super::deprecated::v11::UnappliedSlashes::<Test>::insert(1, vec![
+            super::deprecated::v11::UnappliedSlash {
+                id: 0,
+                validator: 11,
+                own: 10,
+                others: vec![(101, 5)],
+                reporters: vec![1],
+                payout: 1,
+            },
+        ]);
+        <Staking as Store>::NextSlashId::put(1);
+        <Staking as Store>::EarliestUnappliedSlash::put(1);
+
+        <Staking as Store>::StorageVersion::put(Releases::V10_0_0);
+        assert!(!super::migrate::<Test, DefaultInstance>(1));
+
+        // The offence is reported before the slashes are translated to v12.0.0.
+        on_offence_now(
+            &[OffenceDetails { offender: (21, Staking::eras_stakers(1, 21)), reporters: vec![] }],
+            &[Perbill::from_percent(10)],
+        );
+        assert_eq!(<Staking as Store>::OffencesQueuedWhileMigrating::get().len(), 1);
+
+        Staking::on_finalize(System::block_number());
+        assert_eq!(<Staking as Store>::StorageVersion::get(), Releases::V14_0_0);
+
+        // Both slashes are deferred in the era they were reported in, and decode.
+        let slashes = Staking::unapplied_slashes(1);
+        assert_eq!(slashes.len(), 2);
+        assert_eq!((slashes[0].id, slashes[0].validator, slashes[0].slash_era), (0, 11, 1));
+        assert_eq!((slashes[0].own, slashes[0].others.clone()), (10, vec![(101, 5)]));
+        assert_eq!((slashes[1].id, slashes[1].validator, slashes[1].slash_era), (1, 21, 1));
+        assert_eq!(slashes[1].own, 100);
+        assert_eq!(Balances::free_balance(11), 1000);
+        assert_eq!(Balances::free_balance(21), 2000);
+
+        // They are applied once deferred for `slash_defer_duration` eras.
+        start_era(4);
+        assert!(Staking::unapplied_slashes(1).is_empty());
+        assert_eq!(Balances::free_balance(11), 990);
+        assert_eq!(Balances::free_balance(21), 1900);
+    })
+}
+
+#[test]
+fn upgrade_v9_to_v10_marks_invulnerables_and_auto_payouts() {
+    ExtBuilder::default().invulnerables(vec![11]).build().execute_with(|| {
+        assert_ok!(Staking::set_auto_payout(Origin::signed(100), true));
+        <Staking as Store>::IsInvulnerable::remove(11);
+        <Staking as Store>::HasAutoPayout::remove(101);
+        assert!(!Staking::is_invulnerable(&11));
+
+        <Staking as Store>::StorageVersion::put(Releases::V9_0_0);
+
+        // Perform upgrade
+        Staking::on_runtime_upgrade();
+
+        assert_eq!(<Staking as Store>::StorageVersion::get(), Releases::V14_0_0);
+        assert!(Staking::is_invulnerable(&11));
+        assert!(<Staking as Store>::HasAutoPayout::get(101));
+        assert_eq!(Staking::do_try_state(), Ok(()));
+    })
+}
+
+#[test]
+fn upgrade_v10_to_v11_records_active_bonded() {
+    ExtBuilder::default().build().execute_with(|| {
+        assert_ok!(Staking::unbond(Origin::signed(10), 400));
+        <Staking as Store>::ActiveBonded::remove(11);
+        <Staking as Store>::ActiveBonded::remove(21);
+        assert_eq!(Staking::slashable_balance_of(&11), 0);
+
+        <Staking as Store>::StorageVersion::put(Releases::V10_0_0);
+
+        // Perform upgrade
+        Staking::on_runtime_upgrade();
+
+        assert_eq!(<Staking as Store>::StorageVersion::get(), Releases::V14_0_0);
+        assert_eq!(Staking::slashable_balance_of(&11), 600);
+        assert_eq!(Staking::slashable_balance_of(&21), Staking::ledger(20).unwrap().active);
+        assert_eq!(Staking::do_try_state(), Ok(()));
+    })
+}
+
+#[test]
+fn upgrade_v11_to_v12_records_slash_eras() {
+    ExtBuilder::default().build().execute_with(|| {
+        start_era(3);
+        let old_slash = |id, validator| super::deprecated::v11::UnappliedSlash::<
+            AccountId,
+            Balance,
+        > {
+            id,
+            validator,
+            own: 10,
+            others: vec![(101, 5)],
+            reporters: vec![1],
+            payout: 1,
+        };
+        super::deprecated::v11::UnappliedSlashes::<Test>::insert(1, vec![old_slash(0, 11)]);
+        super::deprecated::v11::UnappliedSlashes::<Test>::insert(3, vec![old_slash(1, 21)]);
+        <Staking as Store>::EarliestUnappliedSlash::put(1);
+
+        <Staking as Store>::StorageVersion::put(Releases::V11_0_0);
+
+        // Perform upgrade
+        Staking::on_runtime_upgrade();
+
+        assert_eq!(<Staking as Store>::StorageVersion::get(), Releases::V14_0_0);
+        // The slashes are given the era they were reported in.
+        let slashes = Staking::unapplied_slashes(1);
+        assert_eq!(slashes.len(), 1);
+        assert_eq!((slashes[0].id, slashes[0].validator, slashes[0].slash_era), (0, 11, 1));
+        assert_eq!(slashes[0].others, vec![(101, 5)]);
+        let slashes = Staking::unapplied_slashes(3);
+        assert_eq!(slashes.len(), 1);
+        assert_eq!((slashes[0].id, slashes[0].validator, slashes[0].slash_era), (1, 21, 3));
+    })
+}
+
+#[test]
+fn upgrade_v12_to_v13_counts_pending_slashes() {
+    ExtBuilder::default().slash_defer_duration(2).build().execute_with(|| {
+        start_era(1);
+        on_offence_now(
+            &[
+                OffenceDetails { offender: (11, Staking::eras_stakers(1, 11)), reporters: vec![] },
+                OffenceDetails { offender: (21, Staking::eras_stakers(1, 21)), reporters: vec![] },
+            ],
+            &[Perbill::from_percent(10), Perbill::from_percent(10)],
+        );
+        <Staking as Store>::PendingSlashCount::remove_all();
+        assert!(!Staking::has_pending_slash(&11));
+
+        <Staking as Store>::StorageVersion::put(Releases::V12_0_0);
+
+        // Perform upgrade
+        Staking::on_runtime_upgrade();
+
+        assert_eq!(<Staking as Store>::StorageVersion::get(), Releases::V14_0_0);
+        assert_eq!(Staking::pending_slash_count(11), 1);
+        assert_eq!(Staking::pending_slash_count(21), 1);
+        // 101 nominates both validators.
+        assert_eq!(Staking::pending_slash_count(101), 2);
+        assert_eq!(Staking::do_try_state(), Ok(()));
+
+        // The slashes are uncounted as they are applied.
+        start_era(4);
+        assert!(!Staking::has_pending_slash(&101));
+        assert_eq!(Staking::do_try_state(), Ok(()));
+    })
+}
+
+#[test]
+fn upgrade_v13_to_v14_moves_commission_payee() {
+    ExtBuilder::default().build().execute_with(|| {
+        start_era(1);
+        let commission = Perbill::from_percent(7);
+        let old_prefs = super::deprecated::v13::ValidatorPrefs::<Balance> {
+            commission,
+            max_total_stake: Some(1000),
+        };
+        // Rewrite the preferences of 11 in their format prior to v14.0.0, in the current and
+        // past eras.
+        let prefs_key = Twox64Concat::hash(&11u64.encode());
+        let era_prefs_key = [Twox64Concat::hash(&1u32.encode()), prefs_key.clone()].concat();
+        put_storage_value(b"Staking", b"Validators", &prefs_key, &old_prefs);
+        put_storage_value(b"Staking", b"ErasValidatorPrefs", &era_prefs_key, &old_prefs);
+        let payee_key = Blake2_128Concat::hash(&11u64.encode());
+        put_storage_value(b"Staking", b"CommissionPayee", &payee_key, 1337u64);
+
+        <Staking as Store>::StorageVersion::put(Releases::V13_0_0);
+
+        // A single validator is migrated in the first block.
+        assert!(!super::migrate::<Test, DefaultInstance>(1));
+        assert_eq!(<Staking as Store>::StorageVersion::get(), Releases::V13_0_0);
+
+        while !super::migrate::<Test, DefaultInstance>(1) {}
+
+        assert_eq!(<Staking as Store>::StorageVersion::get(), Releases::V14_0_0);
+        let prefs = ValidatorPrefs {
+            commission,
+            max_total_stake: Some(1000),
+            commission_payee: Some(1337),
+        };
+        assert_eq!(Staking::validators(11), prefs);
+        assert_eq!(Staking::eras_validator_prefs(1, 11), prefs);
+        assert_eq!(Staking::validators(21).commission_payee, None);
+        assert_eq!(Staking::eras_validator_prefs(1, 21).commission_payee, None);
+        assert!(!have_storage_value(b"Staking", b"CommissionPayee", &payee_key));
+        assert_eq!(Staking::do_try_state(), Ok(()));
+    })
+}
+
 // Test that an upgrade from previous test environment works.
 #[test]
 fn test_upgrade_from_master_works() {
@@ -136,7 +727,7 @@ fn test_upgrade_from_master_works() {
                 .unwrap_or((0, vec![]));
 
             Staking::on_runtime_upgrade();
-            assert!(<Staking as Store>::StorageVersion::get() == Releases::V2_0_0);
+            assert!(<Staking as Store>::StorageVersion::get() == Releases::V14_0_0);
 
             // Check ActiveEra and CurrentEra
             let active_era = Staking::active_era().unwrap().index;
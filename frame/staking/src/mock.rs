@@ -21,18 +21,19 @@ use sp_runtime::{Perbill, KeyTypeId};
 use sp_runtime::curve::PiecewiseLinear;
 use sp_runtime::traits::{IdentityLookup, Convert, OpaqueKeys, OnInitialize, OnFinalize, SaturatedConversion};
 use sp_runtime::testing::{Header, UintAuthorityId};
-use sp_staking::{SessionIndex, offence::{OffenceDetails, OnOffenceHandler}};
+use sp_staking::{SessionIndex, offence::{OffenceDetails, OnOffenceHandler, Kind}};
 use sp_core::{H256, crypto::key_types};
 use sp_io;
 use frame_support::{
-	assert_ok, impl_outer_origin, parameter_types, StorageLinkedMap, StorageValue, StorageMap,
-	StorageDoubleMap,
-	traits::{Currency, Get, FindAuthor},
+	assert_ok, impl_outer_origin, impl_outer_event, parameter_types, StorageValue, StorageMap,
+	StorageDoubleMap, StoragePrefixedMap,
+	traits::{Currency, ReservableCurrency, Get, FindAuthor, OnUnbalanced, LockIdentifier},
 	weights::Weight,
 };
 use crate::{
-	EraIndex, GenesisConfig, Module, Trait, StakerStatus, ValidatorPrefs, RewardDestination,
-	Nominators, inflation, SessionInterface, Exposure, ErasStakers, ErasRewardPoints
+	EraIndex, RewardPoint, GenesisConfig, Module, Trait, StakerStatus, ValidatorPrefs, RewardDestination,
+	Nominators, SessionInterface, Exposure, ErasRewardPoints, EraPayout, ConvertCurve, OnStakingUpdate,
+	CandidateFilter, BondingRestriction, Ledger, asset::{VestedFunds, StakeKeeper},
 };
 
 /// The AccountId alias in this test module.
@@ -40,6 +41,9 @@ pub type AccountId = u64;
 pub type BlockNumber = u64;
 pub type Balance = u64;
 
+/// The kind of offence reported by `on_offence_in_era` and `on_offence_now`.
+pub const OFFENCE_KIND: Kind = *b"staking:offence0";
+
 /// Simple structure that exposes how u64 currency can be represented as... u64.
 pub struct CurrencyToVoteHandler;
 impl Convert<u64, u64> for CurrencyToVoteHandler {
@@ -53,6 +57,130 @@ thread_local! {
 	static SESSION: RefCell<(Vec<AccountId>, HashSet<AccountId>)> = RefCell::new(Default::default());
 	static EXISTENTIAL_DEPOSIT: RefCell<u64> = RefCell::new(0);
 	static SLASH_DEFER_DURATION: RefCell<EraIndex> = RefCell::new(0);
+	static MINIMUM_ERA_DURATION: RefCell<u64> = RefCell::new(0);
+	static HISTORICAL_ERAS_KEPT: RefCell<EraIndex> = RefCell::new(0);
+	static HISTORY_SHRINK_DELAY: RefCell<EraIndex> = RefCell::new(0);
+	static STAKING_UPDATES: RefCell<Vec<StakingUpdate>> = RefCell::new(Vec::new());
+	static DISALLOWED_CANDIDATES: RefCell<HashSet<AccountId>> = RefCell::new(HashSet::new());
+	static DENIED_STAKERS: RefCell<HashSet<AccountId>> = RefCell::new(HashSet::new());
+	static VESTING: RefCell<HashMap<AccountId, Balance>> = RefCell::new(HashMap::new());
+}
+
+/// The funds still vesting of each account, as set by `set_vesting`.
+pub struct TestVestedFunds;
+impl VestedFunds<AccountId, Balance> for TestVestedFunds {
+	fn vesting_balance(who: &AccountId) -> Balance {
+		VESTING.with(|v| v.borrow().get(who).cloned().unwrap_or_default())
+	}
+
+	fn burn_vesting(who: &AccountId, amount: Balance) {
+		VESTING.with(|v| {
+			if let Some(vesting) = v.borrow_mut().get_mut(who) {
+				*vesting = vesting.saturating_sub(amount);
+			}
+		});
+	}
+}
+
+pub fn set_vesting(who: AccountId, amount: Balance) {
+	VESTING.with(|v| v.borrow_mut().insert(who, amount));
+}
+
+/// Holds the funds of the stashes it bonds reserved in their own account.
+pub struct TestStakeKeeper;
+impl StakeKeeper<AccountId, Balance, pallet_balances::NegativeImbalance<Test>> for TestStakeKeeper {
+	fn backing(stash: &AccountId) -> Balance {
+		Balances::reserved_balance(stash)
+	}
+
+	fn slash(stash: &AccountId, value: Balance) -> (pallet_balances::NegativeImbalance<Test>, Balance) {
+		Balances::slash_reserved(stash, value)
+	}
+}
+
+/// Allows all the stashes to validate but those passed to `disallow_candidate`.
+pub struct TestCandidateFilter;
+impl CandidateFilter<AccountId, Balance> for TestCandidateFilter {
+	fn is_allowed(stash: &AccountId, _active: Balance) -> bool {
+		DISALLOWED_CANDIDATES.with(|d| !d.borrow().contains(stash))
+	}
+}
+
+pub fn disallow_candidate(stash: AccountId) {
+	DISALLOWED_CANDIDATES.with(|d| d.borrow_mut().insert(stash));
+}
+
+/// Allows all the stashes to stake but those passed to `deny_staker`.
+pub struct TestBondingRestriction;
+impl BondingRestriction<AccountId> for TestBondingRestriction {
+	fn is_allowed(stash: &AccountId) -> bool {
+		DENIED_STAKERS.with(|d| !d.borrow().contains(stash))
+	}
+}
+
+pub fn deny_staker(stash: AccountId) {
+	DENIED_STAKERS.with(|d| d.borrow_mut().insert(stash));
+}
+
+/// A notification received by `OnStakingUpdateRecorder`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum StakingUpdate {
+	Stake(AccountId, Balance, Balance),
+	Validator(AccountId, bool),
+	Nominator(AccountId, Option<Vec<AccountId>>, Vec<AccountId>),
+	Chill(AccountId),
+	Unstake(AccountId),
+}
+
+/// Records the notifications of the staking module, taken by `staking_updates`.
+pub struct OnStakingUpdateRecorder;
+impl OnStakingUpdate<AccountId, Balance> for OnStakingUpdateRecorder {
+	fn on_stake_update(stash: &AccountId, prev_active: Balance, active: Balance) {
+		record(StakingUpdate::Stake(*stash, prev_active, active));
+	}
+
+	fn on_validator_update(
+		stash: &AccountId,
+		prev_prefs: Option<&ValidatorPrefs<AccountId, Balance>>,
+		_prefs: &ValidatorPrefs<AccountId, Balance>,
+	) {
+		record(StakingUpdate::Validator(*stash, prev_prefs.is_some()));
+	}
+
+	fn on_nominator_update(
+		stash: &AccountId,
+		prev_targets: Option<&[AccountId]>,
+		targets: &[AccountId],
+	) {
+		record(StakingUpdate::Nominator(*stash, prev_targets.map(|t| t.to_vec()), targets.to_vec()));
+	}
+
+	fn on_chill(stash: &AccountId) {
+		record(StakingUpdate::Chill(*stash));
+	}
+
+	fn on_unstake(stash: &AccountId) {
+		record(StakingUpdate::Unstake(*stash));
+	}
+}
+
+fn record(update: StakingUpdate) {
+	STAKING_UPDATES.with(|updates| updates.borrow_mut().push(update));
+}
+
+/// Take the notifications recorded since the last call.
+pub fn staking_updates() -> Vec<StakingUpdate> {
+	STAKING_UPDATES.with(|updates| updates.borrow_mut().drain(..).collect())
+}
+
+/// The account which receives the rewards left unclaimed when an era leaves the history.
+pub const UNCLAIMED_REWARDS_ACCOUNT: AccountId = 888;
+
+pub struct UnclaimedRewardsHandler;
+impl OnUnbalanced<pallet_balances::NegativeImbalance<Test>> for UnclaimedRewardsHandler {
+	fn on_nonzero_unbalanced(amount: pallet_balances::NegativeImbalance<Test>) {
+		Balances::resolve_creating(&UNCLAIMED_REWARDS_ACCOUNT, amount);
+	}
 }
 
 pub struct TestSessionHandler;
@@ -99,10 +227,43 @@ impl Get<EraIndex> for SlashDeferDuration {
 	}
 }
 
+pub struct MinimumEraDuration;
+impl Get<u64> for MinimumEraDuration {
+	fn get() -> u64 {
+		MINIMUM_ERA_DURATION.with(|v| *v.borrow())
+	}
+}
+
+pub struct HistoricalErasKept;
+impl Get<EraIndex> for HistoricalErasKept {
+	fn get() -> EraIndex {
+		HISTORICAL_ERAS_KEPT.with(|v| *v.borrow())
+	}
+}
+
+pub struct HistoryShrinkDelay;
+impl Get<EraIndex> for HistoryShrinkDelay {
+	fn get() -> EraIndex {
+		HISTORY_SHRINK_DELAY.with(|v| *v.borrow())
+	}
+}
+
 impl_outer_origin!{
 	pub enum Origin for Test  where system = frame_system {}
 }
 
+use frame_system as system;
+mod staking {
+	pub use crate::Event;
+}
+
+impl_outer_event!{
+	pub enum MetaEvent for Test {
+		system<T>,
+		staking<T>,
+	}
+}
+
 /// Author of block is always 11
 pub struct Author11;
 impl FindAuthor<u64> for Author11 {
@@ -132,7 +293,7 @@ impl frame_system::Trait for Test {
 	type AccountId = AccountId;
 	type Lookup = IdentityLookup<Self::AccountId>;
 	type Header = Header;
-	type Event = ();
+	type Event = MetaEvent;
 	type BlockHashCount = BlockHashCount;
 	type MaximumBlockWeight = MaximumBlockWeight;
 	type AvailableBlockRatio = AvailableBlockRatio;
@@ -200,22 +361,64 @@ parameter_types! {
 	pub const BondingDuration: EraIndex = 3;
 	pub const RewardCurve: &'static PiecewiseLinear<'static> = &I_NPOS;
 	pub const MaxNominatorRewardedPerValidator: u32 = 64;
+	pub const MaxNominatorExposedPerValidator: u32 = 512;
+	pub const MaxNominations: u32 = 16;
+	pub const MaxUnlockingChunks: u32 = 32;
+	pub const MaxInvulnerables: u32 = 4;
+	pub const LivenessPoints: RewardPoint = 5;
+	pub const FinalityPoints: RewardPoint = 3;
+	pub const MaxUnresponsiveSessions: SessionIndex = 3;
+	pub const AuthorPoints: RewardPoint = 20;
+	pub const UncleReferencePoints: RewardPoint = 2;
+	pub const UnclePoints: RewardPoint = 1;
+	pub const MaxAutoPayoutsPerBlock: u32 = 2;
+	pub const MaxPrunedKeysPerBlock: u32 = 16;
+	pub const MaxMigratedValuesPerBlock: u32 = 64;
+	pub const MaxDelegators: u32 = 8;
+	pub const StakingLockId: LockIdentifier = crate::asset::STAKING_ID;
 }
 impl Trait for Test {
 	type Currency = pallet_balances::Module<Self>;
+	type LockId = StakingLockId;
 	type Time = pallet_timestamp::Module<Self>;
 	type CurrencyToVote = CurrencyToVoteHandler;
 	type RewardRemainder = ();
-	type Event = ();
+	type UnclaimedRewards = UnclaimedRewardsHandler;
+	type Event = MetaEvent;
 	type Slash = ();
 	type Reward = ();
 	type SessionsPerEra = SessionsPerEra;
+	type MinimumEraDuration = MinimumEraDuration;
 	type SlashDeferDuration = SlashDeferDuration;
+	type HistoricalErasKept = HistoricalErasKept;
+	type HistoryShrinkDelay = HistoryShrinkDelay;
 	type SlashCancelOrigin = frame_system::EnsureRoot<Self::AccountId>;
+	type ReportOffenceOrigin = frame_system::EnsureRoot<Self::AccountId>;
 	type BondingDuration = BondingDuration;
 	type SessionInterface = Self;
-	type RewardCurve = RewardCurve;
+	type SessionKeysProvider = Self;
+	type EraPayout = ConvertCurve<RewardCurve>;
+	type CandidateFilter = TestCandidateFilter;
+	type BondingRestriction = TestBondingRestriction;
+	type VestedFunds = TestVestedFunds;
+	type StakeKeeper = TestStakeKeeper;
+	type OnStakingUpdate = OnStakingUpdateRecorder;
 	type MaxNominatorRewardedPerValidator = MaxNominatorRewardedPerValidator;
+	type MaxNominatorExposedPerValidator = MaxNominatorExposedPerValidator;
+	type MaxNominations = MaxNominations;
+	type MaxUnlockingChunks = MaxUnlockingChunks;
+	type MaxInvulnerables = MaxInvulnerables;
+	type LivenessPoints = LivenessPoints;
+	type MaxUnresponsiveSessions = MaxUnresponsiveSessions;
+	type FinalityPoints = FinalityPoints;
+	type AuthorPoints = AuthorPoints;
+	type UncleReferencePoints = UncleReferencePoints;
+	type UnclePoints = UnclePoints;
+	type MaxAutoPayoutsPerBlock = MaxAutoPayoutsPerBlock;
+	type MaxPrunedKeysPerBlock = MaxPrunedKeysPerBlock;
+	type MaxMigratedValuesPerBlock = MaxMigratedValuesPerBlock;
+	type MaxDelegators = MaxDelegators;
+	type WeightInfo = ();
 }
 
 pub struct ExtBuilder {
@@ -225,6 +428,9 @@ pub struct ExtBuilder {
 	validator_count: u32,
 	minimum_validator_count: u32,
 	slash_defer_duration: EraIndex,
+	minimum_era_duration: u64,
+	historical_eras_kept: EraIndex,
+	history_shrink_delay: EraIndex,
 	fair: bool,
 	num_validators: Option<u32>,
 	invulnerables: Vec<u64>,
@@ -239,6 +445,9 @@ impl Default for ExtBuilder {
 			validator_count: 2,
 			minimum_validator_count: 0,
 			slash_defer_duration: 0,
+			minimum_era_duration: 0,
+			historical_eras_kept: BondingDuration::get(),
+			history_shrink_delay: 0,
 			fair: true,
 			num_validators: None,
 			invulnerables: vec![],
@@ -271,6 +480,18 @@ impl ExtBuilder {
 		self.slash_defer_duration = eras;
 		self
 	}
+	pub fn minimum_era_duration(mut self, duration: u64) -> Self {
+		self.minimum_era_duration = duration;
+		self
+	}
+	pub fn historical_eras_kept(mut self, eras: EraIndex) -> Self {
+		self.historical_eras_kept = eras;
+		self
+	}
+	pub fn history_shrink_delay(mut self, eras: EraIndex) -> Self {
+		self.history_shrink_delay = eras;
+		self
+	}
 	pub fn fair(mut self, is_fair: bool) -> Self {
 		self.fair = is_fair;
 		self
@@ -286,6 +507,9 @@ impl ExtBuilder {
 	pub fn set_associated_consts(&self) {
 		EXISTENTIAL_DEPOSIT.with(|v| *v.borrow_mut() = self.existential_deposit);
 		SLASH_DEFER_DURATION.with(|v| *v.borrow_mut() = self.slash_defer_duration);
+		MINIMUM_ERA_DURATION.with(|v| *v.borrow_mut() = self.minimum_era_duration);
+		HISTORICAL_ERAS_KEPT.with(|v| *v.borrow_mut() = self.historical_eras_kept);
+		HISTORY_SHRINK_DELAY.with(|v| *v.borrow_mut() = self.history_shrink_delay);
 	}
 	pub fn build(self) -> sp_io::TestExternalities {
 		self.set_associated_consts();
@@ -329,17 +553,25 @@ impl ExtBuilder {
 		} else {
 			StakerStatus::<AccountId>::Idle
 		};
-		let nominated = if self.nominate { vec![11, 21] } else { vec![] };
+		let status_101 = if self.nominate {
+			StakerStatus::<AccountId>::Nominator(vec![11, 21])
+		} else {
+			StakerStatus::<AccountId>::Idle
+		};
+		let stakers = vec![
+			// (stash, controller, staked_amount, status)
+			(11, 10, balance_factor * 1000, StakerStatus::<AccountId>::Validator),
+			(21, 20, stake_21, StakerStatus::<AccountId>::Validator),
+			(31, 30, stake_31, StakerStatus::<AccountId>::Validator),
+			(41, 40, balance_factor * 1000, status_41),
+			// nominator
+			(101, 100, balance_factor * 500, status_101),
+		];
 		let _ = GenesisConfig::<Test>{
-			stakers: vec![
-				// (stash, controller, staked_amount, status)
-				(11, 10, balance_factor * 1000, StakerStatus::<AccountId>::Validator),
-				(21, 20, stake_21, StakerStatus::<AccountId>::Validator),
-				(31, 30, stake_31, StakerStatus::<AccountId>::Validator),
-				(41, 40, balance_factor * 1000, status_41),
-				// nominator
-				(101, 100, balance_factor * 500, StakerStatus::<AccountId>::Nominator(nominated))
-			],
+			// with a higher existential deposit, 31 is left unbonded.
+			stakers: stakers.into_iter()
+				.filter(|&(_, _, stake, _)| stake >= self.existential_deposit)
+				.collect(),
 			validator_count: self.validator_count,
 			minimum_validator_count: self.minimum_validator_count,
 			invulnerables: self.invulnerables,
@@ -368,12 +600,22 @@ pub type Session = pallet_session::Module<Test>;
 pub type Timestamp = pallet_timestamp::Module<Test>;
 pub type Staking = Module<Test>;
 
+/// The events deposited by the staking module so far.
+pub fn staking_events() -> Vec<crate::Event<Test>> {
+	System::events().into_iter()
+		.filter_map(|record| match record.event {
+			MetaEvent::staking(event) => Some(event),
+			_ => None,
+		})
+		.collect()
+}
+
 pub fn check_exposure_all(era: EraIndex) {
-	ErasStakers::<Test>::iter_prefix(era).for_each(check_exposure)
+	Staking::iter_eras_stakers(era).for_each(|(_, exposure)| check_exposure(exposure))
 }
 
 pub fn check_nominator_all(era: EraIndex) {
-	<Nominators<Test>>::enumerate()
+	Staking::iter_nominators()
 		.for_each(|(acc, _)| check_nominator_exposure(era, acc));
 }
 
@@ -390,8 +632,8 @@ pub fn check_exposure(expo: Exposure<AccountId, Balance>) {
 pub fn check_nominator_exposure(era: EraIndex, stash: AccountId) {
 	assert_is_stash(stash);
 	let mut sum = 0;
-	ErasStakers::<Test>::iter_prefix(era)
-		.for_each(|exposure| {
+	Staking::iter_eras_stakers(era)
+		.for_each(|(_, exposure)| {
 			exposure.others.iter()
 				.filter(|i| i.who == stash)
 				.for_each(|i| sum += i.value)
@@ -416,11 +658,36 @@ pub fn assert_ledger_consistent(stash: AccountId) {
 	assert_eq!(real_total, ledger.total);
 }
 
+/// Check that the funds of every stash bonded with its own funds are locked up to its ledger total,
+/// which `do_try_state` can't read through the currency.
+pub fn check_staking_locks() {
+	for ledger in <Ledger<Test>>::iter() {
+		if Staking::is_virtual_staker(&ledger.stash) {
+			continue;
+		}
+		let locked = Balances::locks(&ledger.stash).into_iter()
+			.find(|lock| lock.id == StakingLockId::get())
+			.map_or(0, |lock| lock.amount);
+		assert_eq!(
+			locked,
+			ledger.total,
+			"staking lock of {} is not its ledger total",
+			ledger.stash,
+		);
+	}
+}
+
+/// Set the session keys of the validator controlled by `controller`, named after it.
+pub fn set_session_keys(controller: AccountId) {
+	assert_ok!(Session::set_keys(Origin::signed(controller), UintAuthorityId(controller), vec![]));
+}
+
 pub fn bond_validator(acc: u64, val: u64) {
 	// a = controller
 	// a + 1 = stash
 	let _ = Balances::make_free_balance_be(&(acc + 1), val);
 	assert_ok!(Staking::bond(Origin::signed(acc + 1), acc, val, RewardDestination::Controller));
+	set_session_keys(acc);
 	assert_ok!(Staking::validate(Origin::signed(acc), ValidatorPrefs::default()));
 }
 
@@ -443,6 +710,8 @@ pub fn start_session(session_index: SessionIndex) {
 		System::set_block_number((i + 1).into());
 		Timestamp::set_timestamp(System::block_number() * 1000);
 		Session::on_initialize(System::block_number());
+		Staking::do_try_state().unwrap();
+		check_staking_locks();
 	}
 
 	assert_eq!(Session::current_index(), session_index);
@@ -454,10 +723,10 @@ pub fn start_era(era_index: EraIndex) {
 }
 
 pub fn current_total_payout_for_duration(duration: u64) -> u64 {
-	inflation::compute_total_payout(
-		<Test as Trait>::RewardCurve::get(),
-		Staking::eras_total_stake(Staking::active_era().unwrap().index),
-		Balances::total_issuance(),
+	let active_era = Staking::active_era().unwrap().index;
+	<Test as Trait>::EraPayout::era_payout(
+		Staking::eras_total_stake(active_era),
+		Staking::eras_total_issuance(active_era).unwrap_or_else(Balances::total_issuance),
 		duration,
 	).0
 }
@@ -478,10 +747,10 @@ pub fn on_offence_in_era(
 	slash_fraction: &[Perbill],
 	era: EraIndex,
 ) {
-	let bonded_eras = crate::BondedEras::get();
+	let bonded_eras = <crate::BondedEras>::get();
 	for &(bonded_era, start_session) in bonded_eras.iter() {
 		if bonded_era == era {
-			Staking::on_offence(offenders, slash_fraction, start_session);
+			Staking::on_offence(offenders, slash_fraction, start_session, &OFFENCE_KIND);
 			return
 		} else if bonded_era > era {
 			break
@@ -489,7 +758,12 @@ pub fn on_offence_in_era(
 	}
 
 	if Staking::active_era().unwrap().index == era {
-		Staking::on_offence(offenders, slash_fraction, Staking::eras_start_session_index(era).unwrap());
+		Staking::on_offence(
+			offenders,
+			slash_fraction,
+			Staking::eras_start_session_index(era).unwrap(),
+			&OFFENCE_KIND,
+		);
 	} else {
 		panic!("cannot slash in era {}", era);
 	}
@@ -512,7 +786,7 @@ pub fn make_all_reward_payment(era: EraIndex) {
 	// reward nominators
 	let mut nominator_controllers = HashMap::new();
 	for validator in Staking::eras_reward_points(era).individual.keys() {
-		let validator_exposure = Staking::eras_stakers_clipped(era, validator);
+		let validator_exposure = Staking::eras_stakers_rewarded(era, validator);
 		for (nom_index, nom) in validator_exposure.others.iter().enumerate() {
 			if let Some(nom_ctrl) = Staking::bonded(nom.who) {
 				nominator_controllers.entry(nom_ctrl)
@@ -50,12 +50,13 @@
 
 use super::{
 	EraIndex, Trait, Module, Store, BalanceOf, Exposure, Perbill, SessionInterface,
-	NegativeImbalanceOf, UnappliedSlash,
+	NegativeImbalanceOf, UnappliedSlash, MAX_CLEARED_SLASHING_SPANS, ChillReason, delegation, asset,
+	Instance,
 };
 use sp_runtime::{traits::{Zero, Saturating}, PerThing};
 use frame_support::{
-	StorageMap, StorageDoubleMap,
-	traits::{Currency, OnUnbalanced, Imbalance},
+	StorageMap, StorageDoubleMap, StorageValue, IterableStorageMap,
+	traits::{OnUnbalanced, Imbalance},
 };
 use sp_std::vec::Vec;
 use codec::{Encode, Decode};
@@ -100,7 +101,7 @@ pub struct SlashingSpans {
 impl SlashingSpans {
 	// creates a new record of slashing spans for a stash, starting at the beginning
 	// of the bonding period, relative to now.
-	fn new(window_start: EraIndex) -> Self {
+	pub(crate) fn new(window_start: EraIndex) -> Self {
 		SlashingSpans {
 			span_index: 0,
 			last_start: window_start,
@@ -115,7 +116,7 @@ impl SlashingSpans {
 	// update the slashing spans to reflect the start of a new span at the era after `now`
 	// returns `true` if a new span was started, `false` otherwise. `false` indicates
 	// that internal state is unchanged.
-	fn end_span(&mut self, now: EraIndex) -> bool {
+	pub(crate) fn end_span(&mut self, now: EraIndex) -> bool {
 		let next_start = now + 1;
 		if next_start <= self.last_start { return false }
 
@@ -147,6 +148,11 @@ impl SlashingSpans {
 		self.last_nonzero_slash
 	}
 
+	// find the span of the given era, if covered.
+	fn era_span(&self, era: EraIndex) -> Option<SlashingSpan> {
+		self.iter().find(|span| span.contains_era(era))
+	}
+
 	// prune the slashing spans against a window, whose start era index is given.
 	//
 	// If this returns `Some`, then it includes a range start..end of all the span
@@ -179,6 +185,14 @@ pub(crate) struct SpanRecord<Balance> {
 	paid_out: Balance,
 }
 
+impl<Balance: Saturating + Copy> SpanRecord<Balance> {
+	// the part of a slash above the maximum slash of the span so far, which is the value
+	// actually slashed when the span is slashed for it.
+	fn excess(&self, slash: Balance) -> Balance {
+		slash.saturating_sub(self.slashed)
+	}
+}
+
 impl<Balance> SpanRecord<Balance> {
 	/// The value of stash balance slashed in this span.
 	#[cfg(test)]
@@ -189,13 +203,13 @@ impl<Balance> SpanRecord<Balance> {
 
 /// Parameters for performing a slash.
 #[derive(Clone)]
-pub(crate) struct SlashParams<'a, T: 'a + Trait> {
+pub(crate) struct SlashParams<'a, T: 'a + Trait<I>, I: Instance> {
 	/// The stash account being slashed.
 	pub(crate) stash: &'a T::AccountId,
 	/// The proportion of the slash.
 	pub(crate) slash: Perbill,
 	/// The exposure of the stash and all nominators.
-	pub(crate) exposure: &'a Exposure<T::AccountId, BalanceOf<T>>,
+	pub(crate) exposure: &'a Exposure<T::AccountId, BalanceOf<T, I>>,
 	/// The era where the offence occurred.
 	pub(crate) slash_era: EraIndex,
 	/// The first era in the current bonding period.
@@ -211,10 +225,10 @@ pub(crate) struct SlashParams<'a, T: 'a + Trait> {
 /// record to be applied at some later point. Slashing metadata is updated in storage,
 /// since unapplied records are only rarely intended to be dropped.
 ///
-/// The pending slash record returned does not have initialized reporters. Those have
+/// The pending slash record returned does not have initialized reporters nor id. Those have
 /// to be set at a higher level, if any.
-pub(crate) fn compute_slash<T: Trait>(params: SlashParams<T>)
-	-> Option<UnappliedSlash<T::AccountId, BalanceOf<T>>>
+pub(crate) fn compute_slash<T: Trait<I>, I: Instance>(params: SlashParams<T, I>)
+	-> Option<UnappliedSlash<T::AccountId, BalanceOf<T, I>>>
 {
 	let SlashParams {
 		stash,
@@ -234,24 +248,12 @@ pub(crate) fn compute_slash<T: Trait>(params: SlashParams<T>)
 	if slash * exposure.total == Zero::zero() {
 		// kick out the validator even if they won't be slashed,
 		// as long as the misbehavior is from their most recent slashing span.
-		kick_out_if_recent::<T>(params);
+		kick_out_if_recent::<T, I>(params);
 		return None;
 	}
 
-	let (prior_slash_p, _era_slash) = <Module<T> as Store>::ValidatorSlashInEra::get(
-		&slash_era,
-		stash,
-	).unwrap_or((Perbill::zero(), Zero::zero()));
-
-	// compare slash proportions rather than slash values to avoid issues due to rounding
-	// error.
-	if slash.deconstruct() > prior_slash_p.deconstruct() {
-		<Module<T> as Store>::ValidatorSlashInEra::insert(
-			&slash_era,
-			stash,
-			&(slash, own_slash),
-		);
-	} else {
+	let prior_slash_p = match prior_validator_slash::<T, I>(stash, slash_era, slash) {
+		Some(prior_slash_p) => prior_slash_p,
 		// we slash based on the max in era - this new event is not the max,
 		// so neither the validator or any nominators will need an update.
 		//
@@ -259,12 +261,18 @@ pub(crate) fn compute_slash<T: Trait>(params: SlashParams<T>)
 		// pays out some reward even if the latest report is not max-in-era.
 		// we opt to avoid the nominator lookups and edits and leave more rewards
 		// for more drastic misbehavior.
-		return None;
-	}
+		None => return None,
+	};
+
+	<Module<T, I> as Store>::ValidatorSlashInEra::insert(
+		&slash_era,
+		stash,
+		&(slash, own_slash),
+	);
 
 	// apply slash to validator.
 	{
-		let mut spans = fetch_spans::<T>(
+		let mut spans = fetch_spans::<T, I>(
 			stash,
 			window_start,
 			&mut reward_payout,
@@ -284,21 +292,23 @@ pub(crate) fn compute_slash<T: Trait>(params: SlashParams<T>)
 			// chill the validator - it misbehaved in the current span and should
 			// not continue in the next election. also end the slashing span.
 			spans.end_span(now);
-			<Module<T>>::chill_stash(stash);
+			<Module<T, I>>::chill_stash(stash, ChillReason::Slashed);
 
 			// make sure to disable validator till the end of this session
 			if T::SessionInterface::disable_validator(stash).unwrap_or(false) {
 				// force a new era, to select a new validator set
-				<Module<T>>::ensure_new_era()
+				<Module<T, I>>::ensure_new_era()
 			}
 		}
 	}
 
 	let mut nominators_slashed = Vec::new();
-	reward_payout += slash_nominators::<T>(params, prior_slash_p, &mut nominators_slashed);
+	reward_payout += slash_nominators::<T, I>(params, prior_slash_p, &mut nominators_slashed);
 
 	Some(UnappliedSlash {
+		id: Zero::zero(),
 		validator: stash.clone(),
+		slash_era,
 		own: val_slashed,
 		others: nominators_slashed,
 		reporters: Vec::new(),
@@ -306,15 +316,95 @@ pub(crate) fn compute_slash<T: Trait>(params: SlashParams<T>)
 	})
 }
 
+/// Computes the values `compute_slash` would slash from a validator and its nominators, the
+/// validator first, without updating any slashing metadata nor chilling or disabling anyone.
+///
+/// Stakers whose slash is not increased by the offence are given a zero value.
+pub(crate) fn preview_slash<T: Trait<I>, I: Instance>(params: SlashParams<T, I>)
+	-> Vec<(T::AccountId, BalanceOf<T, I>)>
+{
+	let SlashParams { stash, slash, exposure, slash_era, window_start, .. } = params;
+
+	if slash * exposure.total == Zero::zero() {
+		return Vec::new();
+	}
+	let prior_slash_p = match prior_validator_slash::<T, I>(stash, slash_era, slash) {
+		Some(prior_slash_p) => prior_slash_p,
+		None => return Vec::new(),
+	};
+
+	// the value slashed from a staker whose slash in `slash_era` becomes `era_slash`.
+	let span_slash = |who: &T::AccountId, era_slash: BalanceOf<T, I>| {
+		let spans = <Module<T, I> as Store>::SlashingSpans::get(who)
+			.unwrap_or_else(|| SlashingSpans::new(window_start));
+		spans.era_span(slash_era).map_or_else(Zero::zero, |span| {
+			<Module<T, I> as Store>::SpanSlash::get(&(who.clone(), span.index)).excess(era_slash)
+		})
+	};
+
+	sp_std::iter::once((stash.clone(), span_slash(stash, slash * exposure.own)))
+		.chain(exposure.others.iter().map(|nominator| {
+			let era_slash = nominator_era_slash::<T, I>(
+				&nominator.who,
+				nominator.value,
+				slash_era,
+				slash,
+				prior_slash_p,
+			);
+			(nominator.who.clone(), span_slash(&nominator.who, era_slash))
+		}))
+		.collect()
+}
+
+// the highest slash proportion of the validator in the era so far, if `slash` is above it.
+//
+// slash proportions are compared rather than slash values to avoid issues due to rounding
+// error.
+fn prior_validator_slash<T: Trait<I>, I: Instance>(
+	stash: &T::AccountId,
+	slash_era: EraIndex,
+	slash: Perbill,
+) -> Option<Perbill> {
+	let (prior_slash_p, _era_slash) = <Module<T, I> as Store>::ValidatorSlashInEra::get(
+		&slash_era,
+		stash,
+	).unwrap_or((Perbill::zero(), Zero::zero()));
+
+	if slash.deconstruct() > prior_slash_p.deconstruct() {
+		Some(prior_slash_p)
+	} else {
+		None
+	}
+}
+
+// the era slash of a nominator backing a validator with `value`, once the slash of the
+// validator in the era grows from `prior_slash_p` to `slash`.
+//
+// the era slash of a nominator always grows, if the validator had a new max slash for the era.
+fn nominator_era_slash<T: Trait<I>, I: Instance>(
+	stash: &T::AccountId,
+	value: BalanceOf<T, I>,
+	slash_era: EraIndex,
+	slash: Perbill,
+	prior_slash_p: Perbill,
+) -> BalanceOf<T, I> {
+	let own_slash_prior = prior_slash_p * value;
+	let own_slash_by_validator = slash * value;
+	let own_slash_difference = own_slash_by_validator.saturating_sub(own_slash_prior);
+
+	<Module<T, I> as Store>::NominatorSlashInEra::get(&slash_era, stash)
+		.unwrap_or(Zero::zero()) + own_slash_difference
+}
+
 // doesn't apply any slash, but kicks out the validator if the misbehavior is from
 // the most recent slashing span.
-fn kick_out_if_recent<T: Trait>(
-	params: SlashParams<T>,
+fn kick_out_if_recent<T: Trait<I>, I: Instance>(
+	params: SlashParams<T, I>,
 ) {
 	// these are not updated by era-span or end-span.
 	let mut reward_payout = Zero::zero();
 	let mut val_slashed = Zero::zero();
-	let mut spans = fetch_spans::<T>(
+	let mut spans = fetch_spans::<T, I>(
 		params.stash,
 		params.window_start,
 		&mut reward_payout,
@@ -324,12 +414,12 @@ fn kick_out_if_recent<T: Trait>(
 
 	if spans.era_span(params.slash_era).map(|s| s.index) == Some(spans.span_index()) {
 		spans.end_span(params.now);
-		<Module<T>>::chill_stash(params.stash);
+		<Module<T, I>>::chill_stash(params.stash, ChillReason::Slashed);
 
 		// make sure to disable validator till the end of this session
 		if T::SessionInterface::disable_validator(params.stash).unwrap_or(false) {
 			// force a new era, to select a new validator set
-			<Module<T>>::ensure_new_era()
+			<Module<T, I>>::ensure_new_era()
 		}
 	}
 }
@@ -337,11 +427,11 @@ fn kick_out_if_recent<T: Trait>(
 /// Slash nominators. Accepts general parameters and the prior slash percentage of the validator.
 ///
 /// Returns the amount of reward to pay out.
-fn slash_nominators<T: Trait>(
-	params: SlashParams<T>,
+fn slash_nominators<T: Trait<I>, I: Instance>(
+	params: SlashParams<T, I>,
 	prior_slash_p: Perbill,
-	nominators_slashed: &mut Vec<(T::AccountId, BalanceOf<T>)>,
-) -> BalanceOf<T> {
+	nominators_slashed: &mut Vec<(T::AccountId, BalanceOf<T, I>)>,
+) -> BalanceOf<T, I> {
 	let SlashParams {
 		stash: _,
 		slash,
@@ -359,21 +449,16 @@ fn slash_nominators<T: Trait>(
 		let stash = &nominator.who;
 		let mut nom_slashed = Zero::zero();
 
-		// the era slash of a nominator always grows, if the validator
-		// had a new max slash for the era.
 		let era_slash = {
-			let own_slash_prior = prior_slash_p * nominator.value;
-			let own_slash_by_validator = slash * nominator.value;
-			let own_slash_difference = own_slash_by_validator.saturating_sub(own_slash_prior);
-
-			let mut era_slash = <Module<T> as Store>::NominatorSlashInEra::get(
-				&slash_era,
+			let era_slash = nominator_era_slash::<T, I>(
 				stash,
-			).unwrap_or(Zero::zero());
-
-			era_slash += own_slash_difference;
+				nominator.value,
+				slash_era,
+				slash,
+				prior_slash_p,
+			);
 
-			<Module<T> as Store>::NominatorSlashInEra::insert(
+			<Module<T, I> as Store>::NominatorSlashInEra::insert(
 				&slash_era,
 				stash,
 				&era_slash,
@@ -384,7 +469,7 @@ fn slash_nominators<T: Trait>(
 
 		// compare the era slash against other eras in the same span.
 		{
-			let mut spans = fetch_spans::<T>(
+			let mut spans = fetch_spans::<T, I>(
 				stash,
 				window_start,
 				&mut reward_payout,
@@ -417,28 +502,28 @@ fn slash_nominators<T: Trait>(
 // dropping this struct applies any necessary slashes, which can lead to free balance
 // being 0, and the account being garbage-collected -- a dead account should get no new
 // metadata.
-struct InspectingSpans<'a, T: Trait + 'a> {
+struct InspectingSpans<'a, T: Trait<I> + 'a, I: Instance> {
 	dirty: bool,
 	window_start: EraIndex,
 	stash: &'a T::AccountId,
 	spans: SlashingSpans,
-	paid_out: &'a mut BalanceOf<T>,
-	slash_of: &'a mut BalanceOf<T>,
+	paid_out: &'a mut BalanceOf<T, I>,
+	slash_of: &'a mut BalanceOf<T, I>,
 	reward_proportion: Perbill,
-	_marker: sp_std::marker::PhantomData<T>,
+	_marker: sp_std::marker::PhantomData<(T, I)>,
 }
 
 // fetches the slashing spans record for a stash account, initializing it if necessary.
-fn fetch_spans<'a, T: Trait + 'a>(
+fn fetch_spans<'a, T: Trait<I> + 'a, I: Instance>(
 	stash: &'a T::AccountId,
 	window_start: EraIndex,
-	paid_out: &'a mut BalanceOf<T>,
-	slash_of: &'a mut BalanceOf<T>,
+	paid_out: &'a mut BalanceOf<T, I>,
+	slash_of: &'a mut BalanceOf<T, I>,
 	reward_proportion: Perbill,
-) -> InspectingSpans<'a, T> {
-	let spans = <Module<T> as Store>::SlashingSpans::get(stash).unwrap_or_else(|| {
+) -> InspectingSpans<'a, T, I> {
+	let spans = <Module<T, I> as Store>::SlashingSpans::get(stash).unwrap_or_else(|| {
 		let spans = SlashingSpans::new(window_start);
-		<Module<T> as Store>::SlashingSpans::insert(stash, &spans);
+		<Module<T, I> as Store>::SlashingSpans::insert(stash, &spans);
 		spans
 	});
 
@@ -454,7 +539,7 @@ fn fetch_spans<'a, T: Trait + 'a>(
 	}
 }
 
-impl<'a, T: 'a + Trait> InspectingSpans<'a, T> {
+impl<'a, T: 'a + Trait<I>, I: Instance> InspectingSpans<'a, T, I> {
 	fn span_index(&self) -> SpanIndex {
 		self.spans.span_index
 	}
@@ -466,14 +551,14 @@ impl<'a, T: 'a + Trait> InspectingSpans<'a, T> {
 	// add some value to the slash of the staker.
 	// invariant: the staker is being slashed for non-zero value here
 	// although `amount` may be zero, as it is only a difference.
-	fn add_slash(&mut self, amount: BalanceOf<T>, slash_era: EraIndex) {
+	fn add_slash(&mut self, amount: BalanceOf<T, I>, slash_era: EraIndex) {
 		*self.slash_of += amount;
 		self.spans.last_nonzero_slash = sp_std::cmp::max(self.spans.last_nonzero_slash, slash_era);
 	}
 
 	// find the span index of the given era, if covered.
 	fn era_span(&self, era: EraIndex) -> Option<SlashingSpan> {
-		self.spans.iter().find(|span| span.contains_era(era))
+		self.spans.era_span(era)
 	}
 
 	// compares the slash in an era to the overall current span slash.
@@ -483,16 +568,16 @@ impl<'a, T: 'a + Trait> InspectingSpans<'a, T> {
 	fn compare_and_update_span_slash(
 		&mut self,
 		slash_era: EraIndex,
-		slash: BalanceOf<T>,
+		slash: BalanceOf<T, I>,
 	) -> Option<SpanIndex> {
 		let target_span = self.era_span(slash_era)?;
 		let span_slash_key = (self.stash.clone(), target_span.index);
-		let mut span_record = <Module<T> as Store>::SpanSlash::get(&span_slash_key);
+		let mut span_record = <Module<T, I> as Store>::SpanSlash::get(&span_slash_key);
 		let mut changed = false;
 
 		let reward = if span_record.slashed < slash {
 			// new maximum span slash. apply the difference.
-			let difference = slash - span_record.slashed;
+			let difference = span_record.excess(slash);
 			span_record.slashed = slash;
 
 			// compute reward.
@@ -518,37 +603,47 @@ impl<'a, T: 'a + Trait> InspectingSpans<'a, T> {
 
 		if changed {
 			self.dirty = true;
-			<Module<T> as Store>::SpanSlash::insert(&span_slash_key, &span_record);
+			<Module<T, I> as Store>::SpanSlash::insert(&span_slash_key, &span_record);
 		}
 
 		Some(target_span.index)
 	}
 }
 
-impl<'a, T: 'a + Trait> Drop for InspectingSpans<'a, T> {
+impl<'a, T: 'a + Trait<I>, I: Instance> Drop for InspectingSpans<'a, T, I> {
 	fn drop(&mut self) {
 		// only update on disk if we slashed this account.
 		if !self.dirty { return }
 
 		if let Some((start, end)) = self.spans.prune(self.window_start) {
 			for span_index in start..end {
-				<Module<T> as Store>::SpanSlash::remove(&(self.stash.clone(), span_index));
+				<Module<T, I> as Store>::SpanSlash::remove(&(self.stash.clone(), span_index));
 			}
 		}
 
-		<Module<T> as Store>::SlashingSpans::insert(self.stash, &self.spans);
+		<Module<T, I> as Store>::SlashingSpans::insert(self.stash, &self.spans);
 	}
 }
 
 /// Clear slashing metadata for an obsolete era.
-pub(crate) fn clear_era_metadata<T: Trait>(obsolete_era: EraIndex) {
-	<Module<T> as Store>::ValidatorSlashInEra::remove_prefix(&obsolete_era);
-	<Module<T> as Store>::NominatorSlashInEra::remove_prefix(&obsolete_era);
+pub(crate) fn clear_era_metadata<T: Trait<I>, I: Instance>(obsolete_era: EraIndex) {
+	<Module<T, I> as Store>::ValidatorSlashInEra::remove_prefix(&obsolete_era);
+	<Module<T, I> as Store>::NominatorSlashInEra::remove_prefix(&obsolete_era);
+}
+
+/// The number of slashing spans of a stash whose records are removed at once with its metadata.
+pub(crate) fn cleared_spans_count<T: Trait<I>, I: Instance>(stash: &T::AccountId) -> u32 {
+	<Module<T, I> as Store>::SlashingSpans::get(stash)
+		.map_or(0, |spans| (spans.prior.len() as u32 + 1).min(MAX_CLEARED_SLASHING_SPANS))
 }
 
 /// Clear slashing metadata for a dead account.
-pub(crate) fn clear_stash_metadata<T: Trait>(stash: &T::AccountId) {
-	let spans = match <Module<T> as Store>::SlashingSpans::take(stash) {
+///
+/// The records of at most `MAX_CLEARED_SLASHING_SPANS` spans, the latest ones, are removed at
+/// once, the others are queued in `SpanRecordsToClear`. The stash can't be bonded again until they
+/// are removed, so that the spans of its new bond don't meet them.
+pub(crate) fn clear_stash_metadata<T: Trait<I>, I: Instance>(stash: &T::AccountId) {
+	let spans = match <Module<T, I> as Store>::SlashingSpans::take(stash) {
 		None => return,
 		Some(s) => s,
 	};
@@ -558,34 +653,90 @@ pub(crate) fn clear_stash_metadata<T: Trait>(stash: &T::AccountId) {
 	// this can only happen while the account is staked _if_ they are completely slashed.
 	// in that case, they may re-bond, but it would count again as span 0. Further ancient
 	// slashes would slash into this new bond, since metadata has now been cleared.
-	for span in spans.iter() {
-		<Module<T> as Store>::SpanSlash::remove(&(stash.clone(), span.index));
+	let earliest_span_index = spans.span_index - spans.prior.len() as SpanIndex;
+	let deferred_end = spans.span_index
+		.saturating_add(1)
+		.saturating_sub(MAX_CLEARED_SLASHING_SPANS)
+		.max(earliest_span_index);
+	for span_index in deferred_end..=spans.span_index {
+		<Module<T, I> as Store>::SpanSlash::remove(&(stash.clone(), span_index));
+	}
+
+	if earliest_span_index < deferred_end {
+		// the stash may have been slashed again for an offence in an era it was still exposed in,
+		// after it was removed, and be queued already.
+		<Module<T, I> as Store>::SpanRecordsToClear::mutate(stash, |records| {
+			*records = Some(match *records {
+				Some((start, end)) => (start.min(earliest_span_index), end.max(deferred_end)),
+				None => (earliest_span_index, deferred_end),
+			})
+		});
+	}
+}
+
+/// Remove the slashing span records queued in `SpanRecordsToClear`, removing at most `keys_left`
+/// records and deducting them from it.
+///
+/// The stash first in the map is cleared first; its range is kept up to date until it is cleared,
+/// so that the next call continues where this one stopped.
+pub(crate) fn clear_deferred_span_records<T: Trait<I>, I: Instance>(keys_left: &mut u32) {
+	while *keys_left > 0 {
+		let queued = <Module<T, I> as Store>::SpanRecordsToClear::iter().next();
+		let (stash, (mut start, end)) = match queued {
+			Some(queued) => queued,
+			None => return,
+		};
+
+		while start < end && *keys_left > 0 {
+			<Module<T, I> as Store>::SpanSlash::remove(&(stash.clone(), start));
+			start += 1;
+			*keys_left -= 1;
+		}
+
+		if start < end {
+			<Module<T, I> as Store>::SpanRecordsToClear::insert(&stash, (start, end));
+		} else {
+			<Module<T, I> as Store>::SpanRecordsToClear::remove(&stash);
+		}
 	}
 }
 
 // apply the slash to a stash account, deducting any missing funds from the reward
 // payout, saturating at 0. this is mildly unfair but also an edge-case that
 // can only occur when overlapping locked funds have been slashed.
-fn do_slash<T: Trait>(
+fn do_slash<T: Trait<I>, I: Instance>(
 	stash: &T::AccountId,
-	value: BalanceOf<T>,
-	reward_payout: &mut BalanceOf<T>,
-	slashed_imbalance: &mut NegativeImbalanceOf<T>,
+	value: BalanceOf<T, I>,
+	slash_era: EraIndex,
+	reward_payout: &mut BalanceOf<T, I>,
+	slashed_imbalance: &mut NegativeImbalanceOf<T, I>,
 ) {
-	let controller = match <Module<T>>::bonded(stash) {
+	let controller = match <Module<T, I>>::bonded(stash) {
 		None => return, // defensive: should always exist.
 		Some(c) => c,
 	};
 
-	let mut ledger = match <Module<T>>::ledger(&controller) {
+	let mut ledger = match <Module<T, I>>::ledger(&controller) {
 		Some(ledger) => ledger,
 		None => return, // nothing to do.
 	};
 
+	let vested = <Module<T, I>>::vested_bond(stash);
 	let value = ledger.slash(value, T::Currency::minimum_balance());
 
 	if !value.is_zero() {
-		let (imbalance, missing) = T::Currency::slash(stash, value);
+		let (imbalance, missing) = if <Module<T, I>>::is_kept_staker(stash) {
+			<T::StakeKeeper as asset::StakeKeeper<_, _, _>>::slash(stash, value)
+		} else if <Module<T, I>>::is_virtual_staker(stash) {
+			delegation::slash::<T, I>(stash, value)
+		} else {
+			T::Currency::slash(stash, value)
+		};
+		// the funds still vesting back the bond first, and are burnt first.
+		let vested_slashed = imbalance.peek().min(vested);
+		if !vested_slashed.is_zero() {
+			<T::VestedFunds as asset::VestedFunds<_, _>>::burn_vesting(stash, vested_slashed);
+		}
 		slashed_imbalance.subsume(imbalance);
 
 		if !missing.is_zero() {
@@ -593,44 +744,59 @@ fn do_slash<T: Trait>(
 			*reward_payout = reward_payout.saturating_sub(missing);
 		}
 
-		<Module<T>>::update_ledger(&controller, &ledger);
+		<Module<T, I>>::update_ledger(&controller, &ledger);
+		<Module<T, I> as Store>::ErasSlashes::mutate(
+			slash_era,
+			stash,
+			|total| *total = total.saturating_add(value),
+		);
+		if let Some(active_era) = <Module<T, I>>::active_era() {
+			<Module<T, I> as Store>::ErasTotalSlashed::mutate(
+				active_era.index,
+				|total| *total = total.saturating_add(value),
+			);
+		}
 
 		// trigger the event
-		<Module<T>>::deposit_event(
+		<Module<T, I>>::deposit_event(
 			super::RawEvent::Slash(stash.clone(), value)
 		);
 	}
 }
 
-/// Apply a previously-unapplied slash.
-pub(crate) fn apply_slash<T: Trait>(unapplied_slash: UnappliedSlash<T::AccountId, BalanceOf<T>>) {
-	let mut slashed_imbalance = NegativeImbalanceOf::<T>::zero();
+/// Apply a previously-unapplied slash, recording it in the era the offence was committed in.
+pub(crate) fn apply_slash<T: Trait<I>, I: Instance>(
+	unapplied_slash: UnappliedSlash<T::AccountId, BalanceOf<T, I>>,
+) {
+	let mut slashed_imbalance = NegativeImbalanceOf::<T, I>::zero();
 	let mut reward_payout = unapplied_slash.payout;
 
-	do_slash::<T>(
+	do_slash::<T, I>(
 		&unapplied_slash.validator,
 		unapplied_slash.own,
+		unapplied_slash.slash_era,
 		&mut reward_payout,
 		&mut slashed_imbalance,
 	);
 
 	for &(ref nominator, nominator_slash) in &unapplied_slash.others {
-		do_slash::<T>(
+		do_slash::<T, I>(
 			&nominator,
 			nominator_slash,
+			unapplied_slash.slash_era,
 			&mut reward_payout,
 			&mut slashed_imbalance,
 		);
 	}
 
-	pay_reporters::<T>(reward_payout, slashed_imbalance, &unapplied_slash.reporters);
+	pay_reporters::<T, I>(reward_payout, slashed_imbalance, &unapplied_slash.reporters);
 }
 
 
 /// Apply a reward payout to some reporters, paying the rewards out of the slashed imbalance.
-fn pay_reporters<T: Trait>(
-	reward_payout: BalanceOf<T>,
-	slashed_imbalance: NegativeImbalanceOf<T>,
+fn pay_reporters<T: Trait<I>, I: Instance>(
+	reward_payout: BalanceOf<T, I>,
+	slashed_imbalance: NegativeImbalanceOf<T, I>,
 	reporters: &[T::AccountId],
 ) {
 	if reward_payout.is_zero() || reporters.is_empty() {
@@ -0,0 +1,178 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Testing utilities for the staking module, available with the `testing-utils` feature.
+//!
+//! They set up stakers in bulk, advance sessions and eras, run elections and report offences,
+//! for the tests and benchmarks of this module and of the modules building on it.
+//!
+//! The helpers drive the staking module directly and do not need a session module: a mock using
+//! one should advance its sessions itself instead of calling [`rotate_session`] or
+//! [`start_active_era`], as the session module would otherwise not know about them.
+//!
+//! Balances are given as factors of the minimum balance of `T::Currency`, which must not be zero.
+
+use super::*;
+
+use frame_system::RawOrigin;
+use sp_io::hashing::blake2_256;
+
+/// The account `index` of the accounts named `name`.
+pub fn account<T: Trait>(name: &'static str, index: u32) -> T::AccountId {
+	let entropy = (name, index).using_encoded(blake2_256);
+	T::AccountId::decode(&mut &entropy[..]).unwrap_or_default()
+}
+
+/// Create the account `index` of `name` with a free balance of `balance_factor` times the minimum
+/// balance.
+pub fn create_funded_user<T: Trait>(
+	name: &'static str,
+	index: u32,
+	balance_factor: u32,
+) -> T::AccountId {
+	let user = account::<T>(name, index);
+	let balance = T::Currency::minimum_balance() * balance_factor.into();
+	let _ = T::Currency::make_free_balance_be(&user, balance);
+	user
+}
+
+/// Create a stash and its controller, both funded with `balance_factor` times the minimum balance,
+/// the stash bonding a tenth of it (at least the minimum balance) with its rewards staked.
+///
+/// The stash and the controller are the accounts `2 * index` and `2 * index + 1` of `name`.
+pub fn create_stash_controller<T: Trait>(
+	name: &'static str,
+	index: u32,
+	balance_factor: u32,
+) -> Result<(T::AccountId, T::AccountId), &'static str> {
+	let stash = create_funded_user::<T>(name, 2 * index, balance_factor);
+	let controller = create_funded_user::<T>(name, 2 * index + 1, balance_factor);
+	let controller_lookup = T::Lookup::unlookup(controller.clone());
+	let amount = T::Currency::minimum_balance() * (balance_factor / 10).max(1).into();
+	Module::<T>::bond(
+		RawOrigin::Signed(stash.clone()).into(),
+		controller_lookup,
+		amount,
+		RewardDestination::Staked,
+	)?;
+	Ok((stash, controller))
+}
+
+/// Keys for the validator controlled by `controller`, derived from the account so that they differ
+/// between validators.
+pub fn session_keys<T: Trait>(controller: &T::AccountId) -> Result<SessionKeysOf<T>, &'static str> {
+	let entropy = (0u8 .. 16)
+		.flat_map(|i| (controller, i).using_encoded(blake2_256).to_vec())
+		.collect::<Vec<u8>>();
+	SessionKeysOf::<T>::decode(&mut &entropy[..]).map_err(|_| "session keys cannot be decoded")
+}
+
+/// Set the keys of [`session_keys`] for the validator controlled by `controller`.
+pub fn set_session_keys<T: Trait>(controller: &T::AccountId) -> Result<(), &'static str> {
+	let keys = session_keys::<T>(controller)?;
+	T::SessionKeysProvider::set_keys(controller, keys, &[]).map_err(Into::into)
+}
+
+/// Create `count` validators with the default preferences, as by [`create_stash_controller`] with
+/// the name `"validator"`, returning their stashes.
+pub fn create_validators<T: Trait>(
+	count: u32,
+	balance_factor: u32,
+) -> Result<Vec<T::AccountId>, &'static str> {
+	(0 .. count).map(|i| -> Result<_, &'static str> {
+		let (stash, controller) = create_stash_controller::<T>("validator", i, balance_factor)?;
+		set_session_keys::<T>(&controller)?;
+		Module::<T>::validate(RawOrigin::Signed(controller).into(), ValidatorPrefs::default())?;
+		Ok(stash)
+	}).collect()
+}
+
+/// Create `count` nominators of `targets`, as by [`create_stash_controller`] with the name
+/// `"nominator"`, returning their stashes and controllers.
+///
+/// At most `T::MaxNominations` of the targets are nominated.
+pub fn create_nominators<T: Trait>(
+	count: u32,
+	targets: &[T::AccountId],
+	balance_factor: u32,
+) -> Result<Vec<(T::AccountId, T::AccountId)>, &'static str> {
+	let targets = targets.iter()
+		.map(|stash| T::Lookup::unlookup(stash.clone()))
+		.collect::<Vec<_>>();
+	(0 .. count).map(|i| -> Result<_, &'static str> {
+		let (stash, controller) = create_stash_controller::<T>("nominator", i, balance_factor)?;
+		Module::<T>::nominate(RawOrigin::Signed(controller.clone()).into(), targets.clone())?;
+		Ok((stash, controller))
+	}).collect()
+}
+
+/// Run the election of the era starting at `start_session_index`, making it the current era,
+/// and return the elected validators, if enough candidates were available.
+pub fn plan_new_era<T: Trait>(start_session_index: SessionIndex) -> Option<Vec<T::AccountId>> {
+	Module::<T>::new_era(start_session_index)
+}
+
+/// Rotate the session `session_index` as the session module does: end it, start the next one and
+/// plan the one after it, returning the validators planned for it if an era was planned.
+pub fn rotate_session<T: Trait>(session_index: SessionIndex) -> Option<Vec<T::AccountId>> {
+	Module::<T>::end_session(session_index);
+	Module::<T>::start_session(session_index + 1);
+	Module::<T>::new_session(session_index + 2)
+}
+
+/// Force a new era from the current session `session_index` and rotate sessions until it is
+/// active, returning the session it started at, which is the current session afterwards.
+pub fn start_active_era<T: Trait>(session_index: SessionIndex) -> SessionIndex {
+	<ForceEra>::put(Forcing::ForceNew);
+	rotate_session::<T>(session_index);
+	rotate_session::<T>(session_index + 1);
+	session_index + 2
+}
+
+/// Report an offence of `kind` committed in `era` by each of the `offenders`, slashing them and
+/// their nominators by `slash_fraction` of their exposure in that era.
+///
+/// The era must still be bonded. The slashes are deferred by `T::SlashDeferDuration`.
+pub fn report_offence<T: Trait>(
+	offenders: &[T::AccountId],
+	slash_fraction: Perbill,
+	era: EraIndex,
+	kind: Kind,
+) -> Result<(), &'static str> where
+	T: pallet_session::Trait<ValidatorId = <T as frame_system::Trait>::AccountId>,
+	T: pallet_session::historical::Trait<
+		FullIdentification = Exposure<<T as frame_system::Trait>::AccountId, BalanceOf<T>>,
+		FullIdentificationOf = ExposureOf<T>,
+	>,
+	T::SessionHandler: pallet_session::SessionHandler<<T as frame_system::Trait>::AccountId>,
+	T::SessionManager: pallet_session::SessionManager<<T as frame_system::Trait>::AccountId>,
+	T::ValidatorIdOf: Convert<<T as frame_system::Trait>::AccountId, Option<<T as frame_system::Trait>::AccountId>>,
+{
+	let session = <BondedEras>::get().into_iter()
+		.find(|&(bonded_era, _)| bonded_era == era)
+		.map(|(_, start_session)| start_session)
+		.ok_or("the era is not bonded")?;
+	let details = offenders.iter()
+		.map(|stash| OffenceDetails {
+			offender: (stash.clone(), Module::<T>::eras_stakers(era, stash)),
+			reporters: vec![],
+		})
+		.collect::<Vec<_>>();
+	let fractions = vec![slash_fraction; details.len()];
+
+	<Module<T> as OnOffenceHandler<_, _>>::on_offence(&details, &fractions, session, &kind);
+	Ok(())
+}
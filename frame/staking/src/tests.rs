@@ -18,11 +18,11 @@
 
 use super::*;
 use mock::*;
-use sp_runtime::{assert_eq_error_rate, traits::{OnInitialize, BadOrigin}};
+use sp_runtime::{assert_eq_error_rate, testing::UintAuthorityId, traits::{OnInitialize, OnFinalize, BadOrigin}};
 use sp_staking::offence::OffenceDetails;
 use frame_support::{
 	assert_ok, assert_noop,
-	traits::{Currency, ReservableCurrency},
+	traits::{Currency, ReservableCurrency, LockableCurrency, WithdrawReasons},
 	StorageMap,
 };
 use pallet_balances::Error as BalancesError;
@@ -41,9 +41,9 @@ fn force_unstake_works() {
 			BalancesError::<Test, _>::LiquidityRestrictions
 		);
 		// Force unstake requires root.
-		assert_noop!(Staking::force_unstake(Origin::signed(11), 11), BadOrigin);
+		assert_noop!(Staking::force_unstake(Origin::signed(11), 11, 0), BadOrigin);
 		// We now force them to unstake
-		assert_ok!(Staking::force_unstake(Origin::ROOT, 11));
+		assert_ok!(Staking::force_unstake(Origin::ROOT, 11, 0));
 		// No longer bonded.
 		assert_eq!(Staking::bonded(&11), None);
 		// Transfer works.
@@ -76,10 +76,12 @@ fn basic_setup_works() {
 		assert_eq!(Staking::ledger(&1), None);
 
 		// ValidatorPrefs are default
-		assert_eq!(<Validators<Test>>::enumerate().collect::<Vec<_>>(), vec![
-			(31, ValidatorPrefs::default()),
+		let mut validators = Staking::iter_validators().collect::<Vec<_>>();
+		validators.sort_by_key(|&(stash, _)| stash);
+		assert_eq!(validators, vec![
+			(11, ValidatorPrefs::default()),
 			(21, ValidatorPrefs::default()),
-			(11, ValidatorPrefs::default())
+			(31, ValidatorPrefs::default())
 		]);
 
 		assert_eq!(
@@ -143,7 +145,7 @@ fn change_controller_works() {
 
 		assert_noop!(
 			Staking::validate(Origin::signed(10), ValidatorPrefs::default()),
-			Error::<Test>::NotController,
+			Error::<Test, _>::NotController,
 		);
 		assert_ok!(Staking::validate(Origin::signed(5), ValidatorPrefs::default()));
 	})
@@ -266,6 +268,7 @@ fn staking_should_work() {
 			// add a new candidate for being a validator. account 3 controlled by 4.
 			assert_ok!(Staking::bond(Origin::signed(3), 4, 1500, RewardDestination::Controller));
 			let current_era_at_bond = Staking::current_era();
+			set_session_keys(4);
 			assert_ok!(Staking::validate(Origin::signed(4), ValidatorPrefs::default()));
 
 			// No effects will be seen so far.
@@ -345,8 +348,8 @@ fn less_than_needed_candidates_works() {
 			// But the exposure is updated in a simple way. No external votes exists.
 			// This is purely self-vote.
 			assert!(
-				ErasStakers::<Test>::iter_prefix(Staking::active_era().unwrap().index)
-					.all(|exposure| exposure.others.is_empty())
+				Staking::iter_eras_stakers(Staking::active_era().unwrap().index)
+					.all(|(_, exposure)| exposure.others.is_empty())
 			);
 			check_exposure_all(Staking::active_era().unwrap().index);
 			check_nominator_all(Staking::active_era().unwrap().index);
@@ -365,7 +368,7 @@ fn no_candidate_emergency_condition() {
 		.execute_with(|| {
 			// initial validators
 			assert_eq_uvec!(validator_controllers(), vec![10, 20, 30, 40]);
-			let prefs = ValidatorPrefs { commission: Perbill::one() };
+			let prefs = ValidatorPrefs { commission: Perbill::one(), .. Default::default() };
 			<Staking as crate::Store>::Validators::insert(11, prefs.clone());
 
 			// set the minimum validator count.
@@ -468,7 +471,7 @@ fn nominating_and_rewards_should_work() {
 			// ------ check the staked value of all parties.
 
 			// 30 and 40 are not chosen anymore
-			assert_eq!(ErasStakers::<Test>::iter_prefix(Staking::active_era().unwrap().index).count(), 2);
+			assert_eq!(Staking::iter_eras_stakers(Staking::active_era().unwrap().index).count(), 2);
 			assert_eq!(
 				Staking::eras_stakers(Staking::active_era().unwrap().index, 11),
 				Exposure {
@@ -614,10 +617,10 @@ fn double_staking_should_fail() {
 		// 4 = not used so far, 1 stashed => not allowed.
 		assert_noop!(
 			Staking::bond(Origin::signed(1), 4, arbitrary_value,
-			RewardDestination::default()), Error::<Test>::AlreadyBonded,
+			RewardDestination::default()), Error::<Test, _>::AlreadyBonded,
 		);
 		// 1 = stashed => attempting to nominate should fail.
-		assert_noop!(Staking::nominate(Origin::signed(1), vec![1]), Error::<Test>::NotController);
+		assert_noop!(Staking::nominate(Origin::signed(1), vec![1]), Error::<Test, _>::NotController);
 		// 2 = controller  => nominating should work.
 		assert_ok!(Staking::nominate(Origin::signed(2), vec![1]));
 	});
@@ -639,7 +642,7 @@ fn double_controlling_should_fail() {
 		// 2 = controller, 3 stashed (Note that 2 is reused.) => no-op
 		assert_noop!(
 			Staking::bond(Origin::signed(3), 2, arbitrary_value, RewardDestination::default()),
-			Error::<Test>::AlreadyPaired,
+			Error::<Test, _>::AlreadyPaired,
 		);
 	});
 }
@@ -711,7 +714,7 @@ fn forcing_new_era_works() {
 		assert_eq!(Staking::active_era().unwrap().index, 1);
 
 		// no era change.
-		ForceEra::put(Forcing::ForceNone);
+		<ForceEra>::put(Forcing::ForceNone);
 		start_session(4);
 		assert_eq!(Staking::active_era().unwrap().index, 1);
 		start_session(5);
@@ -723,14 +726,14 @@ fn forcing_new_era_works() {
 
 		// back to normal.
 		// this immediately starts a new session.
-		ForceEra::put(Forcing::NotForcing);
+		<ForceEra>::put(Forcing::NotForcing);
 		start_session(8);
 		assert_eq!(Staking::active_era().unwrap().index, 1); // There is one session delay
 		start_session(9);
 		assert_eq!(Staking::active_era().unwrap().index, 2);
 
 		// forceful change
-		ForceEra::put(Forcing::ForceAlways);
+		<ForceEra>::put(Forcing::ForceAlways);
 		start_session(10);
 		assert_eq!(Staking::active_era().unwrap().index, 2); // There is one session delay
 		start_session(11);
@@ -739,10 +742,10 @@ fn forcing_new_era_works() {
 		assert_eq!(Staking::active_era().unwrap().index, 4);
 
 		// just one forceful change
-		ForceEra::put(Forcing::ForceNew);
+		<ForceEra>::put(Forcing::ForceNew);
 		start_session(13);
 		assert_eq!(Staking::active_era().unwrap().index, 5);
-		assert_eq!(ForceEra::get(), Forcing::NotForcing);
+		assert_eq!(<ForceEra>::get(), Forcing::NotForcing);
 		start_session(14);
 		assert_eq!(Staking::active_era().unwrap().index, 6);
 		start_session(15);
@@ -924,6 +927,7 @@ fn validator_payment_prefs_work() {
 		let commission = Perbill::from_percent(40);
 		<Validators<Test>>::insert(&11, ValidatorPrefs {
 			commission: commission.clone(),
+			.. Default::default()
 		});
 
 		// Reward controller so staked ratio doesn't change.
@@ -958,6 +962,87 @@ fn validator_payment_prefs_work() {
 
 }
 
+#[test]
+fn commission_is_paid_to_commission_payee() {
+	ExtBuilder::default().build().execute_with(|| {
+		let commission = Perbill::from_percent(40);
+		assert_ok!(Staking::validate(Origin::signed(10), ValidatorPrefs {
+			commission,
+			commission_payee: Some(777),
+			.. Default::default()
+		}));
+		assert_eq!(Staking::validators(11).commission_payee, Some(777));
+		<Payee<Test>>::insert(&11, RewardDestination::Controller);
+		<Payee<Test>>::insert(&101, RewardDestination::Controller);
+
+		start_era(1);
+		mock::make_all_reward_payment(0);
+
+		let balance_era_1_10 = Balances::total_balance(&10);
+		let balance_era_1_777 = Balances::total_balance(&777);
+
+		let total_payout_1 = current_total_payout_for_duration(3000);
+		assert!(total_payout_1 > 100); // Test is meaningful if reward something
+		let exposure_1 = Staking::eras_stakers(Staking::active_era().unwrap().index, 11);
+		<Module<Test>>::reward_by_ids(vec![(11, 1)]);
+
+		// Changing the preferences does not change the payee of the commission of era 1.
+		assert_ok!(Staking::validate(Origin::signed(10), ValidatorPrefs {
+			commission,
+			.. Default::default()
+		}));
+
+		start_era(2);
+		mock::make_all_reward_payment(1);
+
+		let taken_cut = commission * total_payout_1;
+		let shared_cut = total_payout_1 - taken_cut;
+		let reward_of_10 = shared_cut * exposure_1.own / exposure_1.total;
+		assert_eq_error_rate!(Balances::total_balance(&10), balance_era_1_10 + reward_of_10, 2);
+		assert_eq_error_rate!(Balances::total_balance(&777), balance_era_1_777 + taken_cut, 2);
+		assert_eq!(Staking::eras_validator_prefs(2, 11).commission_payee, None);
+	});
+}
+
+#[test]
+fn commission_below_existential_deposit_is_paid_to_validator() {
+	ExtBuilder::default().existential_deposit(10).build().execute_with(|| {
+		// A commission of about 5, below the existential deposit of the payee to create.
+		let commission =
+			Perbill::from_rational_approximation(5, current_total_payout_for_duration(3000));
+		assert_ok!(Staking::validate(Origin::signed(10), ValidatorPrefs {
+			commission,
+			commission_payee: Some(777),
+			.. Default::default()
+		}));
+		<Payee<Test>>::insert(&11, RewardDestination::Controller);
+		<Payee<Test>>::insert(&101, RewardDestination::Controller);
+
+		start_era(1);
+		mock::make_all_reward_payment(0);
+
+		let balance_era_1_10 = Balances::total_balance(&10);
+
+		let total_payout_1 = current_total_payout_for_duration(3000);
+		let exposure_1 = Staking::eras_stakers(Staking::active_era().unwrap().index, 11);
+		<Module<Test>>::reward_by_ids(vec![(11, 1)]);
+
+		start_era(2);
+		mock::make_all_reward_payment(1);
+
+		let taken_cut = commission * total_payout_1;
+		assert!(taken_cut > 0 && taken_cut < 10);
+		let shared_cut = total_payout_1 - taken_cut;
+		let reward_of_10 = shared_cut * exposure_1.own / exposure_1.total + taken_cut;
+		assert_eq_error_rate!(Balances::total_balance(&10), balance_era_1_10 + reward_of_10, 2);
+		assert_eq!(Balances::total_balance(&777), 0);
+		assert!(!staking_events().iter().any(|event| match event {
+			RawEvent::CommissionReward(..) => true,
+			_ => false,
+		}));
+	});
+}
+
 #[test]
 fn bond_extra_works() {
 	// Tests that extra `free_balance` in the stash can be added to stake
@@ -1074,7 +1159,7 @@ fn bond_extra_and_withdraw_unbonded_works() {
 		);
 
 		// Attempting to free the balances now will fail. 2 eras need to pass.
-		Staking::withdraw_unbonded(Origin::signed(10)).unwrap();
+		Staking::withdraw_unbonded(Origin::signed(10), 0).unwrap();
 		assert_eq!(Staking::ledger(&10), Some(StakingLedger {
 			stash: 11, total: 1000 + 100, active: 100, unlocking: vec![UnlockChunk{ value: 1000, era: 2 + 3}], last_reward: None }));
 
@@ -1082,14 +1167,14 @@ fn bond_extra_and_withdraw_unbonded_works() {
 		start_era(3);
 
 		// nothing yet
-		Staking::withdraw_unbonded(Origin::signed(10)).unwrap();
+		Staking::withdraw_unbonded(Origin::signed(10), 0).unwrap();
 		assert_eq!(Staking::ledger(&10), Some(StakingLedger {
 			stash: 11, total: 1000 + 100, active: 100, unlocking: vec![UnlockChunk{ value: 1000, era: 2 + 3}], last_reward: None }));
 
 		// trigger next era.
 		start_era(5);
 
-		Staking::withdraw_unbonded(Origin::signed(10)).unwrap();
+		Staking::withdraw_unbonded(Origin::signed(10), 0).unwrap();
 		// Now the value is free and the staking ledger is updated.
 		assert_eq!(Staking::ledger(&10), Some(StakingLedger {
 			stash: 11, total: 100, active: 100, unlocking: vec![], last_reward: None }));
@@ -1100,7 +1185,7 @@ fn bond_extra_and_withdraw_unbonded_works() {
 fn too_many_unbond_calls_should_not_work() {
 	ExtBuilder::default().build().execute_with(|| {
 		// locked at era 0 until 3
-		for _ in 0..MAX_UNLOCKING_CHUNKS-1 {
+		for _ in 0..MaxUnlockingChunks::get() - 1 {
 			assert_ok!(Staking::unbond(Origin::signed(10), 1));
 		}
 
@@ -1109,13 +1194,13 @@ fn too_many_unbond_calls_should_not_work() {
 		// locked at era 1 until 4
 		assert_ok!(Staking::unbond(Origin::signed(10), 1));
 		// can't do more.
-		assert_noop!(Staking::unbond(Origin::signed(10), 1), Error::<Test>::NoMoreChunks);
+		assert_noop!(Staking::unbond(Origin::signed(10), 1), Error::<Test, _>::NoMoreChunks);
 
 		start_era(3);
 
-		assert_noop!(Staking::unbond(Origin::signed(10), 1), Error::<Test>::NoMoreChunks);
+		assert_noop!(Staking::unbond(Origin::signed(10), 1), Error::<Test, _>::NoMoreChunks);
 		// free up.
-		assert_ok!(Staking::withdraw_unbonded(Origin::signed(10)));
+		assert_ok!(Staking::withdraw_unbonded(Origin::signed(10), 0));
 
 		// Can add again.
 		assert_ok!(Staking::unbond(Origin::signed(10), 1));
@@ -1163,7 +1248,7 @@ fn rebond_works() {
 			// Try to rebond some funds. We get an error since no fund is unbonded.
 			assert_noop!(
 				Staking::rebond(Origin::signed(10), 500),
-				Error::<Test>::NoUnlockChunk,
+				Error::<Test, _>::NoUnlockChunk,
 			);
 
 			// Unbond almost all of the funds in stash.
@@ -1390,11 +1475,13 @@ fn reward_to_stake_works() {
 		let _ = Balances::make_free_balance_be(&20, 1000);
 
 		// Bypass logic and change current exposure
-		ErasStakers::<Test>::insert(0, 21, Exposure { total: 69, own: 69, others: vec![] });
+		EraInfo::<Test>::set_exposure(0, &21, Exposure { total: 69, own: 69, others: vec![] });
 
 		// Now lets lower account 20 stake
 		assert_eq!(Staking::eras_stakers(Staking::active_era().unwrap().index, 21).total, 69);
 		<Ledger<Test>>::insert(&20, StakingLedger { stash: 21, total: 69, active: 69, unlocking: vec![], last_reward: None });
+		<ActiveBonded<Test>>::insert(&21, 69);
+		Balances::set_lock(StakingLockId::get(), &21, 69, WithdrawReasons::all());
 
 		// Compute total payout now for whole duration as other parameter won't change
 		let total_payout_0 = current_total_payout_for_duration(3000);
@@ -1466,7 +1553,7 @@ fn on_free_balance_zero_stash_removes_validator() {
 		assert_eq!(Balances::total_balance(&11), 0);
 
 		// Reap the stash
-		assert_ok!(Staking::reap_stash(Origin::NONE, 11));
+		assert_ok!(Staking::reap_stash(Origin::NONE, 11, 0));
 
 		// Check storage items do not exist
 		assert!(!<Ledger<Test>>::contains_key(&10));
@@ -1477,6 +1564,113 @@ fn on_free_balance_zero_stash_removes_validator() {
 	});
 }
 
+#[test]
+fn nominators_for_follows_nominations() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_eq!(Staking::nominators_for(11), vec![101]);
+		assert_eq!(Staking::nominators_for(21), vec![101]);
+
+		// Nominating again replaces the old targets, duplicates are indexed once.
+		assert_ok!(Staking::nominate(Origin::signed(100), vec![21, 31, 31]));
+		assert!(!<NominatorsFor<Test>>::contains_key(11));
+		assert_eq!(Staking::nominators_for(21), vec![101]);
+		assert_eq!(Staking::nominators_for(31), vec![101]);
+
+		bond_nominator(1000, 1000, vec![21]);
+		assert_eq!(Staking::nominators_for(21), vec![101, 1001]);
+
+		// Validating drops the nominations.
+		set_session_keys(100);
+		assert_ok!(Staking::validate(Origin::signed(100), ValidatorPrefs::default()));
+		assert_eq!(Staking::nominators_for(21), vec![1001]);
+		assert!(!<NominatorsFor<Test>>::contains_key(31));
+
+		assert_ok!(Staking::chill(Origin::signed(1000)));
+		assert!(!<NominatorsFor<Test>>::contains_key(21));
+	});
+}
+
+#[test]
+fn counters_follow_validators_and_nominators() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_eq!(Staking::counter_for_validators(), 3);
+		assert_eq!(Staking::counter_for_nominators(), 1);
+
+		// Updating the preferences or nominations of a staker doesn't count it twice.
+		assert_ok!(Staking::validate(Origin::signed(10), ValidatorPrefs::default()));
+		assert_ok!(Staking::nominate(Origin::signed(100), vec![31]));
+		assert_eq!(Staking::counter_for_validators(), 3);
+		assert_eq!(Staking::counter_for_nominators(), 1);
+
+		// Switching roles moves the staker between the counters.
+		assert_ok!(Staking::nominate(Origin::signed(10), vec![21]));
+		assert_eq!(Staking::counter_for_validators(), 2);
+		assert_eq!(Staking::counter_for_nominators(), 2);
+
+		assert_ok!(Staking::chill(Origin::signed(10)));
+		assert_ok!(Staking::chill(Origin::signed(20)));
+		assert_eq!(Staking::counter_for_validators(), 1);
+		assert_eq!(Staking::counter_for_nominators(), 1);
+
+		// Reaping a stash uncounts it.
+		let _ = Balances::slash(&101, u64::max_value());
+		assert_ok!(Staking::reap_stash(Origin::NONE, 101, 0));
+		assert_eq!(Staking::counter_for_nominators(), 0);
+		assert_eq!(Staking::counter_for_validators(), 1);
+	});
+}
+
+#[test]
+fn corrupted_ledgers_are_checked_and_repaired() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_eq!(Staking::check_ledger(&11), Ok(()));
+		assert_eq!(Staking::check_ledger(&5), Ok(()));
+		assert_noop!(Staking::repair_ledger(Origin::ROOT, 11), Error::<Test, _>::LedgerNotCorrupted);
+
+		// The total doesn't match the chunks.
+		let mut ledger = Staking::ledger(&10).unwrap();
+		ledger.total += 10;
+		<Ledger<Test>>::insert(10, &ledger);
+		assert_eq!(Staking::check_ledger(&11), Err(LedgerCorruption::TotalMismatch));
+		assert_noop!(Staking::repair_ledger(Origin::signed(10), 11), BadOrigin);
+		assert_ok!(Staking::repair_ledger(Origin::ROOT, 11));
+		assert_eq!(Staking::check_ledger(&11), Ok(()));
+		assert_eq!(Staking::ledger(&10).unwrap().total, 1000);
+
+		// The total is above the balance of the stash.
+		ledger.total = 2000;
+		ledger.active = 2000;
+		<Ledger<Test>>::insert(10, &ledger);
+		assert_eq!(Staking::check_ledger(&11), Err(LedgerCorruption::TotalAboveBalance));
+		assert_ok!(Staking::repair_ledger(Origin::ROOT, 11));
+		assert_eq!(Staking::check_ledger(&11), Ok(()));
+		assert_eq!(Staking::ledger(&10).unwrap().active, Balances::free_balance(11));
+
+		// Two stashes share a controller, 21 is unbonded.
+		<Bonded<Test>>::insert(21, 10);
+		assert_eq!(Staking::check_ledger(&21), Err(LedgerCorruption::ForeignLedger));
+		assert_ok!(Staking::repair_ledger(Origin::ROOT, 21));
+		assert_eq!(Staking::check_ledger(&21), Ok(()));
+		assert_eq!(Staking::bonded(&21), None);
+		assert!(!<Validators<Test>>::contains_key(21));
+		assert_eq!(Staking::check_ledger(&11), Ok(()));
+		assert_eq!(Staking::ledger(&10).unwrap().stash, 11);
+
+		// The controller of 31 lost its ledger.
+		<Ledger<Test>>::remove(30);
+		assert_eq!(Staking::check_ledger(&31), Err(LedgerCorruption::MissingLedger));
+		assert_ok!(Staking::repair_ledger(Origin::ROOT, 31));
+		assert_eq!(Staking::check_ledger(&31), Ok(()));
+		assert_eq!(Staking::bonded(&31), None);
+
+		// A payee is left without a bond.
+		<Payee<Test>>::insert(5, RewardDestination::Controller);
+		assert_eq!(Staking::check_ledger(&5), Err(LedgerCorruption::Dangling));
+		assert_ok!(Staking::repair_ledger(Origin::ROOT, 5));
+		assert_eq!(Staking::check_ledger(&5), Ok(()));
+	});
+}
+
 #[test]
 fn on_free_balance_zero_stash_removes_nominator() {
 	// Tests that nominator storage items are cleaned up when stash is empty
@@ -1522,7 +1716,7 @@ fn on_free_balance_zero_stash_removes_nominator() {
 		assert_eq!(Balances::total_balance(&11), 0);
 
 		// Reap the stash
-		assert_ok!(Staking::reap_stash(Origin::NONE, 11));
+		assert_ok!(Staking::reap_stash(Origin::NONE, 11, 0));
 
 		// Check storage items do not exist
 		assert!(!<Ledger<Test>>::contains_key(&10));
@@ -1555,6 +1749,7 @@ fn switching_roles() {
 
 		// add a new validator candidate
 		assert_ok!(Staking::bond(Origin::signed(5), 6, 1000, RewardDestination::Controller));
+		set_session_keys(6);
 		assert_ok!(Staking::validate(Origin::signed(6), ValidatorPrefs::default()));
 
 		start_era(1);
@@ -1563,6 +1758,7 @@ fn switching_roles() {
 		assert_eq_uvec!(validator_controllers(), vec![6, 10]);
 
 		// 2 decides to be a validator. Consequences:
+		set_session_keys(2);
 		assert_ok!(Staking::validate(Origin::signed(2), ValidatorPrefs::default()));
 		// new stakes:
 		// 10: 1000 self vote
@@ -1616,7 +1812,7 @@ fn bond_with_no_staked_value() {
 			// Can't bond with 1
 			assert_noop!(
 				Staking::bond(Origin::signed(1), 2, 1, RewardDestination::Controller),
-				Error::<Test>::InsufficientValue,
+				Error::<Test, _>::InsufficientValue,
 			);
 			// bonded with absolute minimum value possible.
 			assert_ok!(Staking::bond(Origin::signed(1), 2, 5, RewardDestination::Controller));
@@ -1640,19 +1836,49 @@ fn bond_with_no_staked_value() {
 			start_era(2);
 
 			// not yet removed.
-			assert_ok!(Staking::withdraw_unbonded(Origin::signed(2)));
+			assert_ok!(Staking::withdraw_unbonded(Origin::signed(2), 0));
 			assert!(Staking::ledger(2).is_some());
 			assert_eq!(Balances::locks(&1)[0].amount, 5);
 
 			start_era(3);
 
 			// poof. Account 1 is removed from the staking system.
-			assert_ok!(Staking::withdraw_unbonded(Origin::signed(2)));
+			assert_ok!(Staking::withdraw_unbonded(Origin::signed(2), 0));
 			assert!(Staking::ledger(2).is_none());
 			assert_eq!(Balances::locks(&1).len(), 0);
 		});
 }
 
+#[test]
+#[should_panic(expected = "staking lock of 11 is not its ledger total")]
+fn staking_lock_below_ledger_total_is_caught() {
+	ExtBuilder::default().build().execute_with(|| {
+		Balances::set_lock(StakingLockId::get(), &11, 500, WithdrawReasons::all());
+		advance_session();
+	});
+}
+
+#[test]
+fn bonded_funds_are_locked_under_the_lock_id() {
+	ExtBuilder::default().build().execute_with(|| {
+		let _ = Balances::make_free_balance_be(&1, 100);
+		assert_ok!(Staking::bond(Origin::signed(1), 2, 50, RewardDestination::Controller));
+		assert_eq!(
+			Balances::locks(&1),
+			vec![pallet_balances::BalanceLock {
+				id: StakingLockId::get(),
+				amount: 50,
+				reasons: pallet_balances::Reasons::All,
+			}],
+		);
+
+		assert_ok!(Staking::unbond(Origin::signed(2), 50));
+		start_era(3);
+		assert_ok!(Staking::withdraw_unbonded(Origin::signed(2), 0));
+		assert!(Balances::locks(&1).is_empty());
+	});
+}
+
 #[test]
 fn bond_with_little_staked_value_bounded() {
 	// Behavior when someone bonds with little staked value.
@@ -1671,6 +1897,7 @@ fn bond_with_little_staked_value_bounded() {
 
 			// Stingy validator.
 			assert_ok!(Staking::bond(Origin::signed(1), 2, 1, RewardDestination::Controller));
+			set_session_keys(2);
 			assert_ok!(Staking::validate(Origin::signed(2), ValidatorPrefs::default()));
 
 			// reward era 0
@@ -1815,8 +2042,7 @@ fn reward_validator_slashing_validator_doesnt_overflow() {
 
 		// Check reward
 		ErasRewardPoints::<Test>::insert(0, reward);
-		ErasStakers::<Test>::insert(0, 11, &exposure);
-		ErasStakersClipped::<Test>::insert(0, 11, exposure);
+		EraInfo::<Test>::set_exposure(0, &11, exposure);
 		ErasValidatorReward::<Test>::insert(0, stake);
 		assert_ok!(Staking::payout_validator(Origin::signed(10), 0));
 		assert_eq!(Balances::total_balance(&11), stake * 2);
@@ -1829,7 +2055,7 @@ fn reward_validator_slashing_validator_doesnt_overflow() {
 		// it is 0.
 		Staking::bond(Origin::signed(2), 20000, stake - 1, RewardDestination::default()).unwrap();
 		// Override exposure of 11
-		ErasStakers::<Test>::insert(0, 11, Exposure {
+		EraInfo::<Test>::set_exposure(0, &11, Exposure {
 			total: stake,
 			own: 1,
 			others: vec![ IndividualExposure { who: 2, value: stake - 1 }]
@@ -1878,6 +2104,83 @@ fn reward_from_authorship_event_handler_works() {
 	})
 }
 
+#[test]
+fn election_respects_max_total_stake() {
+	ExtBuilder::default().build().execute_with(|| {
+		// 101 backs both 11 and 21.
+		let exposure_11 = Staking::eras_stakers(0, 11);
+		let exposure_21 = Staking::eras_stakers(0, 21);
+		assert_eq!(exposure_11.own, 1000);
+		assert_eq!(exposure_21.own, 1000);
+		assert_eq!(exposure_11.others, vec![IndividualExposure { who: 101, value: 125 }]);
+		assert_eq!(exposure_21.others, vec![IndividualExposure { who: 101, value: 375 }]);
+
+		assert_ok!(Staking::validate(Origin::signed(10), ValidatorPrefs {
+			max_total_stake: Some(1100),
+			.. Default::default()
+		}));
+		start_era(1);
+
+		// The nomination above the cap of 11 is moved onto 21.
+		let exposure_11 = Staking::eras_stakers(1, 11);
+		let exposure_21 = Staking::eras_stakers(1, 21);
+		assert_eq!(exposure_11.total, 1100);
+		assert_eq!(exposure_11.others, vec![IndividualExposure { who: 101, value: 100 }]);
+		assert_eq!(exposure_21.total, 1400);
+		assert_eq!(exposure_21.others, vec![IndividualExposure { who: 101, value: 400 }]);
+		check_exposure_all(1);
+		check_nominator_all(1);
+
+		// With no other target to move it onto, the nomination above the cap is left out.
+		assert_ok!(Staking::validate(Origin::signed(20), ValidatorPrefs {
+			max_total_stake: Some(1000),
+			.. Default::default()
+		}));
+		start_era(2);
+
+		assert_eq!(Staking::eras_stakers(2, 11).total, 1100);
+		assert_eq!(Staking::eras_stakers(2, 21).total, 1000);
+		assert!(Staking::eras_stakers(2, 21).others.is_empty());
+		assert_eq!(Staking::eras_total_stake(2), 2100);
+	})
+}
+
+#[test]
+fn reward_from_liveness_handler_works() {
+	ExtBuilder::default().build().execute_with(|| {
+		use sp_staking::OnLiveness;
+
+		<Module<Test>>::on_liveness(0, &[11, 21]);
+		<Module<Test>>::on_liveness(1, &[21]);
+
+		assert_eq!(
+			ErasRewardPoints::<Test>::get(Staking::active_era().unwrap().index),
+			EraRewardPoints {
+				individual: vec![(11, 5), (21, 5 + 5)].into_iter().collect(),
+				total: 15,
+			},
+		);
+	})
+}
+
+#[test]
+fn reward_from_finality_participation_works() {
+	ExtBuilder::default().build().execute_with(|| {
+		use sp_staking::OnFinalityParticipation;
+
+		<Module<Test>>::on_finality_participation(1, &[11, 21]);
+		<Module<Test>>::on_finality_participation(2, &[11]);
+
+		assert_eq!(
+			ErasRewardPoints::<Test>::get(Staking::active_era().unwrap().index),
+			EraRewardPoints {
+				individual: vec![(11, 3 + 3), (21, 3)].into_iter().collect(),
+				total: 9,
+			},
+		);
+	})
+}
+
 #[test]
 fn add_reward_points_fns_works() {
 	ExtBuilder::default().build().execute_with(|| {
@@ -1931,7 +2234,7 @@ fn era_is_always_same_length() {
 		assert_eq!(Staking::eras_start_session_index(Staking::active_era().unwrap().index).unwrap(), SessionsPerEra::get() * 2);
 
 		let session = Session::current_index();
-		ForceEra::put(Forcing::ForceNew);
+		<ForceEra>::put(Forcing::ForceNew);
 		advance_session();
 		advance_session();
 		assert_eq!(Staking::active_era().unwrap().index, 3);
@@ -2179,6 +2482,50 @@ fn subsequent_reports_in_same_span_pay_out_less() {
 	});
 }
 
+#[test]
+fn stale_reports_receive_a_decayed_slice() {
+	// This test verifies that the reporters' slice decays with the age of the offence.
+	ExtBuilder::default().build().execute_with(|| {
+		assert_noop!(
+			Staking::set_slash_reward_decay(Origin::signed(1), Perbill::from_percent(100)),
+			BadOrigin,
+		);
+		assert_ok!(Staking::set_slash_reward_decay(Origin::ROOT, Perbill::from_percent(100)));
+
+		// A report in the era of the offence receives the full slice.
+		let initial_balance = 1125;
+		on_offence_now(
+			&[OffenceDetails {
+				offender: (11, Staking::eras_stakers(0, 11)),
+				reporters: vec![1, 2],
+			}],
+			&[Perbill::from_percent(50)],
+		);
+
+		let reward_each = (initial_balance / 20) / 2 / 2;
+		assert_eq!(Balances::free_balance(1), 10 + reward_each);
+		assert_eq!(Balances::free_balance(2), 20 + reward_each);
+
+		start_era(1);
+
+		// An offence reported one era late is worth nothing to the reporters anymore, but the
+		// offender is still slashed.
+		let balance_21 = Balances::free_balance(21);
+		on_offence_in_era(
+			&[OffenceDetails {
+				offender: (21, Staking::eras_stakers(0, 21)),
+				reporters: vec![1, 2],
+			}],
+			&[Perbill::from_percent(50)],
+			0,
+		);
+
+		assert!(Balances::free_balance(21) < balance_21);
+		assert_eq!(Balances::free_balance(1), 10 + reward_each);
+		assert_eq!(Balances::free_balance(2), 20 + reward_each);
+	});
+}
+
 #[test]
 fn invulnerables_are_not_slashed() {
 	// For invulnerable validators no slashing is performed.
@@ -2223,6 +2570,74 @@ fn invulnerables_are_not_slashed() {
 	});
 }
 
+#[test]
+fn simulate_slash_matches_actual_slash() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_eq!(
+			Staking::simulate_slash(11, Perbill::from_percent(20)),
+			vec![(11, 200), (101, 25)],
+		);
+		assert!(Staking::simulate_slash(11, Perbill::zero()).is_empty());
+
+		// nothing is recorded, nor is the validator chilled.
+		assert!(<Staking as Store>::SlashingSpans::get(&11).is_none());
+		assert!(<Staking as Store>::ValidatorSlashInEra::get(0, 11).is_none());
+		assert!(<Staking as Store>::NominatorSlashInEra::get(0, 101).is_none());
+		assert!(<Validators<Test>>::contains_key(11));
+		assert_eq!(
+			Staking::simulate_slash(11, Perbill::from_percent(20)),
+			vec![(11, 200), (101, 25)],
+		);
+	});
+
+	ExtBuilder::default().build().execute_with(|| {
+		on_offence_now(
+			&[OffenceDetails { offender: (11, Staking::eras_stakers(0, 11)), reporters: vec![] }],
+			&[Perbill::from_percent(20)],
+		);
+		assert_eq!(Balances::free_balance(11), 1000 - 200);
+		assert_eq!(Balances::free_balance(101), 2000 - 25);
+	});
+
+	ExtBuilder::default().invulnerables(vec![11]).build().execute_with(|| {
+		assert!(Staking::simulate_slash(11, Perbill::from_percent(20)).is_empty());
+	});
+}
+
+#[test]
+fn invulnerables_are_a_bounded_sorted_set() {
+	ExtBuilder::default().invulnerables(vec![21, 11, 21]).build().execute_with(|| {
+		// Genesis invulnerables are sorted and deduplicated.
+		assert_eq!(Staking::invulnerables(), vec![11, 21]);
+
+		assert_noop!(Staking::add_invulnerable(Origin::signed(1), 31), BadOrigin);
+		assert_noop!(Staking::add_invulnerable(Origin::ROOT, 11), Error::<Test, _>::AlreadyInvulnerable);
+		assert_ok!(Staking::add_invulnerable(Origin::ROOT, 31));
+		assert_ok!(Staking::add_invulnerable(Origin::ROOT, 1));
+		assert_eq!(Staking::invulnerables(), vec![1, 11, 21, 31]);
+		assert!(Staking::is_invulnerable(&31));
+
+		// `MaxInvulnerables` is 4 in the mock.
+		assert_noop!(Staking::add_invulnerable(Origin::ROOT, 41), Error::<Test, _>::TooManyInvulnerables);
+		assert_noop!(
+			Staking::set_invulnerables(Origin::ROOT, vec![1, 11, 21, 31, 41]),
+			Error::<Test, _>::TooManyInvulnerables,
+		);
+
+		assert_noop!(Staking::remove_invulnerable(Origin::signed(1), 31), BadOrigin);
+		assert_noop!(Staking::remove_invulnerable(Origin::ROOT, 41), Error::<Test, _>::NotInvulnerable);
+		assert_ok!(Staking::remove_invulnerable(Origin::ROOT, 11));
+		assert_eq!(Staking::invulnerables(), vec![1, 21, 31]);
+		assert!(!Staking::is_invulnerable(&11));
+
+		assert_ok!(Staking::set_invulnerables(Origin::ROOT, vec![41, 11, 41]));
+		assert_eq!(Staking::invulnerables(), vec![11, 41]);
+		assert!(Staking::is_invulnerable(&41));
+		assert!(!Staking::is_invulnerable(&21));
+		assert_eq!(Staking::do_try_state(), Ok(()));
+	});
+}
+
 #[test]
 fn dont_slash_if_fraction_is_zero() {
 	// Don't slash if the fraction is zero.
@@ -2329,13 +2744,108 @@ fn garbage_collection_after_slashing() {
 		assert_eq!(Balances::free_balance(11), 0);
 		assert_eq!(Balances::total_balance(&11), 0);
 
-		assert_ok!(Staking::reap_stash(Origin::NONE, 11));
+		assert_noop!(Staking::reap_stash(Origin::NONE, 11, 0), Error::<Test, _>::IncorrectSlashingSpans);
+		assert_ok!(Staking::reap_stash(Origin::NONE, 11, 2));
 
 		assert!(<Staking as crate::Store>::SlashingSpans::get(&11).is_none());
 		assert_eq!(<Staking as crate::Store>::SpanSlash::get(&(11, 0)).amount_slashed(), &0);
 	})
 }
 
+#[test]
+fn historical_sessions_follow_the_retention() {
+	let historical_root = pallet_session::historical::Module::<Test>::historical_root;
+
+	// keeping more eras than the bonding duration, for audit.
+	ExtBuilder::default().historical_eras_kept(5).build().execute_with(|| {
+		start_era(7);
+
+		assert_eq!(
+			<BondedEras>::get().into_iter().map(|(era, _)| era).collect::<Vec<_>>(),
+			vec![2, 3, 4, 5, 6, 7],
+		);
+		assert!(historical_root(5).is_none());
+		assert!(historical_root(6).is_some());
+
+		// the extra eras can no longer be slashed.
+		let balance = Balances::free_balance(11);
+		on_offence_in_era(
+			&[OffenceDetails { offender: (11, Staking::eras_stakers(3, 11)), reporters: vec![] }],
+			&[Perbill::from_percent(10)],
+			3,
+		);
+		assert_eq!(Balances::free_balance(11), balance);
+
+		on_offence_in_era(
+			&[OffenceDetails { offender: (11, Staking::eras_stakers(4, 11)), reporters: vec![] }],
+			&[Perbill::from_percent(10)],
+			4,
+		);
+		assert_eq!(Balances::free_balance(11), balance - 100);
+	});
+
+	// keeping fewer eras than the bonding duration, for a light chain.
+	ExtBuilder::default().historical_eras_kept(1).build().execute_with(|| {
+		start_era(7);
+
+		assert_eq!(
+			<BondedEras>::get().into_iter().map(|(era, _)| era).collect::<Vec<_>>(),
+			vec![4, 5, 6, 7],
+		);
+		assert!(historical_root(17).is_none());
+		assert!(historical_root(18).is_some());
+	});
+}
+
+#[test]
+fn slashing_spans_of_a_reaped_stash_are_cleared_over_blocks() {
+	ExtBuilder::default().build().execute_with(|| {
+		// 11 has more slashing spans than are cleared with it.
+		let mut spans = slashing::SlashingSpans::new(0);
+		for i in 0..40 {
+			if i > 0 {
+				spans.end_span(i);
+			}
+			<Staking as Store>::SpanSlash::insert(&(11, i), slashing::SpanRecord::default());
+		}
+		<Staking as Store>::SlashingSpans::insert(&11, spans);
+		let _ = Balances::slash(&11, u64::max_value());
+
+		assert_noop!(
+			Staking::reap_stash(Origin::NONE, 11, MAX_CLEARED_SLASHING_SPANS - 1),
+			Error::<Test, _>::IncorrectSlashingSpans,
+		);
+		assert_ok!(Staking::reap_stash(Origin::NONE, 11, MAX_CLEARED_SLASHING_SPANS));
+
+		// The records of the latest spans are removed at once, the others are queued.
+		assert!(!<Staking as Store>::SpanSlash::contains_key(&(11, 8)));
+		assert!(<Staking as Store>::SpanSlash::contains_key(&(11, 7)));
+		assert_eq!(<Staking as Store>::SpanRecordsToClear::get(11), Some((0, 8)));
+
+		// 11 can't bond again before its records are removed, as its new spans would meet them.
+		let _ = Balances::make_free_balance_be(&11, 1000);
+		assert_noop!(
+			Staking::bond(Origin::signed(11), 1337, 500, RewardDestination::Stash),
+			Error::<Test, _>::SpanRecordsNotCleared,
+		);
+
+		// The records are removed over several blocks if needed.
+		let mut keys_left = 5;
+		slashing::clear_deferred_span_records::<Test, DefaultInstance>(&mut keys_left);
+		assert_eq!(keys_left, 0);
+		assert_eq!(<Staking as Store>::SpanRecordsToClear::get(11), Some((5, 8)));
+		assert!(!<Staking as Store>::SpanSlash::contains_key(&(11, 4)));
+		assert!(<Staking as Store>::SpanSlash::contains_key(&(11, 5)));
+
+		Staking::on_finalize(System::block_number());
+		assert_eq!(<Staking as Store>::SpanRecordsToClear::get(11), None);
+		for i in 0..40 {
+			assert!(!<Staking as Store>::SpanSlash::contains_key(&(11, i)));
+		}
+		assert_ok!(Staking::bond(Origin::signed(11), 1337, 500, RewardDestination::Stash));
+	})
+}
+
 #[test]
 fn garbage_collection_on_window_pruning() {
 	ExtBuilder::default().build().execute_with(|| {
@@ -2557,6 +3067,8 @@ fn deferred_slashes_are_deferred() {
 			&[Perbill::from_percent(10)],
 		);
 
+		// the slash is reported right away, to be applied in era 4.
+		assert!(staking_events().contains(&RawEvent::SlashReported(11, Perbill::from_percent(10), 4)));
 		assert_eq!(Balances::free_balance(11), 1000);
 		assert_eq!(Balances::free_balance(101), 2000);
 
@@ -2580,418 +3092,2142 @@ fn deferred_slashes_are_deferred() {
 }
 
 #[test]
-fn remove_deferred() {
+fn deferred_slash_can_be_applied_now() {
 	ExtBuilder::default().slash_defer_duration(2).build().execute_with(|| {
 		start_era(1);
 
-		assert_eq!(Balances::free_balance(11), 1000);
-
 		let exposure = Staking::eras_stakers(Staking::active_era().unwrap().index, 11);
-		assert_eq!(Balances::free_balance(101), 2000);
 		let nominated_value = exposure.others.iter().find(|o| o.who == 101).unwrap().value;
 
 		on_offence_now(
-			&[
-				OffenceDetails {
-					offender: (11, exposure.clone()),
-					reporters: vec![],
-				},
-			],
+			&[OffenceDetails { offender: (11, exposure), reporters: vec![] }],
 			&[Perbill::from_percent(10)],
 		);
 
 		assert_eq!(Balances::free_balance(11), 1000);
 		assert_eq!(Balances::free_balance(101), 2000);
+		let id = Staking::unapplied_slashes(1)[0].id;
 
-		start_era(2);
-
-		on_offence_in_era(
-			&[
-				OffenceDetails {
-					offender: (11, exposure.clone()),
-					reporters: vec![],
-				},
-			],
-			&[Perbill::from_percent(15)],
-			1,
+		assert_noop!(Staking::apply_slash_now(Origin::signed(1), 1, id), BadOrigin);
+		assert_noop!(
+			Staking::apply_slash_now(Origin::ROOT, 1, id + 1),
+			Error::<Test, _>::InvalidSlashIndex,
 		);
 
-		Staking::cancel_deferred_slash(Origin::ROOT, 1, vec![0]).unwrap();
-
-		assert_eq!(Balances::free_balance(11), 1000);
-		assert_eq!(Balances::free_balance(101), 2000);
-
-		start_era(3);
-
-		assert_eq!(Balances::free_balance(11), 1000);
-		assert_eq!(Balances::free_balance(101), 2000);
+		assert!(Staking::has_pending_slash(&101));
+		assert_ok!(Staking::apply_slash_now(Origin::ROOT, 1, id));
+		assert!(Staking::unapplied_slashes(1).is_empty());
+		assert!(!Staking::has_pending_slash(&11));
+		assert!(!Staking::has_pending_slash(&101));
+		assert_eq!(Balances::free_balance(11), 900);
+		assert_eq!(Balances::free_balance(101), 2000 - (nominated_value / 10));
 
-		// at the start of era 4, slashes from era 1 are processed,
-		// after being deferred for at least 2 full eras.
+		// The slash is not applied a second time once its deferral ends.
 		start_era(4);
 
-		// the first slash for 10% was cancelled, so no effect.
-		assert_eq!(Balances::free_balance(11), 1000);
-		assert_eq!(Balances::free_balance(101), 2000);
-
-		start_era(5);
-
-		let slash_10 = Perbill::from_percent(10);
-		let slash_15 = Perbill::from_percent(15);
-		let initial_slash = slash_10 * nominated_value;
-
-		let total_slash = slash_15 * nominated_value;
-		let actual_slash = total_slash - initial_slash;
-
-		// 5% slash (15 - 10) processed now.
-		assert_eq!(Balances::free_balance(11), 950);
-		assert_eq!(Balances::free_balance(101), 2000 - actual_slash);
+		assert_eq!(Balances::free_balance(11), 900);
+		assert_eq!(Balances::free_balance(101), 2000 - (nominated_value / 10));
 	})
 }
 
 #[test]
-fn remove_multi_deferred() {
+fn applied_slashes_are_recorded_per_era() {
 	ExtBuilder::default().slash_defer_duration(2).build().execute_with(|| {
 		start_era(1);
 
-		assert_eq!(Balances::free_balance(11), 1000);
-
 		let exposure = Staking::eras_stakers(Staking::active_era().unwrap().index, 11);
-		assert_eq!(Balances::free_balance(101), 2000);
+		let nominated_value = exposure.others.iter().find(|o| o.who == 101).unwrap().value;
 
 		on_offence_now(
-			&[
-				OffenceDetails {
-					offender: (11, exposure.clone()),
-					reporters: vec![],
-				},
-			],
+			&[OffenceDetails { offender: (11, exposure), reporters: vec![] }],
 			&[Perbill::from_percent(10)],
 		);
 
-		on_offence_now(
-			&[
-				OffenceDetails {
-					offender: (21, Staking::eras_stakers(Staking::active_era().unwrap().index, 21)),
-					reporters: vec![],
-				}
-			],
-			&[Perbill::from_percent(10)],
-		);
+		// Nothing is recorded while the slash is deferred.
+		assert!(!<Staking as Store>::ErasSlashes::contains_key(1, 11));
 
-		on_offence_now(
+		start_era(4);
+
+		assert_eq!(Staking::eras_slashes(1, 11), 100);
+		assert_eq!(Staking::eras_slashes(1, 101), nominated_value / 10);
+		assert_eq!(Staking::eras_slashes(1, 21), 0);
+
+		// The history is dropped along with the rest of the era information.
+		start_era(5);
+		Staking::set_history_depth(Origin::ROOT, 3).unwrap();
+		start_era(6);
+		// The slashes are pruned at the end of the block.
+		Staking::on_finalize(System::block_number());
+		assert!(!<Staking as Store>::ErasSlashes::contains_key(1, 11));
+		assert!(!<Staking as Store>::ErasSlashes::contains_key(1, 101));
+	})
+}
+
+#[test]
+fn deferred_slashes_are_recorded_in_the_era_of_the_offence() {
+	ExtBuilder::default().slash_defer_duration(2).build().execute_with(|| {
+		start_era(1);
+		let exposure_11 = Staking::eras_stakers(1, 11);
+		let exposure_21 = Staking::eras_stakers(1, 21);
+		start_era(2);
+
+		// Both offences were committed in era 1, but are reported in era 2.
+		on_offence_in_era(
 			&[
-				OffenceDetails {
-					offender: (11, exposure.clone()),
-					reporters: vec![],
-				},
+				OffenceDetails { offender: (11, exposure_11), reporters: vec![] },
+				OffenceDetails { offender: (21, exposure_21.clone()), reporters: vec![] },
 			],
-			&[Perbill::from_percent(25)],
+			&[Perbill::from_percent(10), Perbill::from_percent(10)],
+			1,
 		);
+		let slashes = Staking::unapplied_slashes(2);
+		assert_eq!(slashes[0].validator, 11);
+		assert!(slashes.iter().all(|slash| slash.slash_era == 1));
 
-		assert_eq!(<Staking as Store>::UnappliedSlashes::get(&1).len(), 3);
-		Staking::cancel_deferred_slash(Origin::ROOT, 1, vec![0, 2]).unwrap();
-
-		let slashes = <Staking as Store>::UnappliedSlashes::get(&1);
-		assert_eq!(slashes.len(), 1);
-		assert_eq!(slashes[0].validator, 21);
+		// One is applied right away, the other once its deferral ends.
+		assert_ok!(Staking::apply_slash_now(Origin::ROOT, 2, slashes[0].id));
+		assert_eq!(Staking::eras_slashes(1, 11), 100);
+		start_era(5);
+		assert_eq!(Staking::eras_slashes(1, 21), exposure_21.own / 10);
+		assert!(!<Staking as Store>::ErasSlashes::contains_key(2, 11));
+		assert!(!<Staking as Store>::ErasSlashes::contains_key(2, 21));
 	})
 }
 
 #[test]
-fn slash_kicks_validators_not_nominators() {
-	ExtBuilder::default().build().execute_with(|| {
+fn slash_totals_are_tracked_per_active_era() {
+	ExtBuilder::default().slash_defer_duration(2).build().execute_with(|| {
 		start_era(1);
 
-		assert_eq!(Balances::free_balance(11), 1000);
-
 		let exposure = Staking::eras_stakers(Staking::active_era().unwrap().index, 11);
-		assert_eq!(Balances::free_balance(101), 2000);
 		let nominated_value = exposure.others.iter().find(|o| o.who == 101).unwrap().value;
 
 		on_offence_now(
-			&[
-				OffenceDetails {
-					offender: (11, exposure.clone()),
-					reporters: vec![],
-				},
-			],
+			&[OffenceDetails { offender: (11, exposure), reporters: vec![] }],
 			&[Perbill::from_percent(10)],
 		);
+		assert_eq!(Staking::eras_total_slashed(1), 0);
 
-		assert_eq!(Balances::free_balance(11), 900);
-		assert_eq!(Balances::free_balance(101), 2000 - (nominated_value / 10));
-
-		// This is the best way to check that the validator was chilled; `get` will
-		// return default value.
-		for (stash, _) in <Staking as Store>::Validators::enumerate() {
-			assert!(stash != 11);
-		}
-
-		let nominations = <Staking as Store>::Nominators::get(&101).unwrap();
+		start_era(4);
 
-		// and make sure that the vote will be ignored even if the validator
-		// re-registers.
-		let last_slash = <Staking as Store>::SlashingSpans::get(&11).unwrap().last_nonzero_slash();
-		assert!(nominations.submitted_in < last_slash);
-	});
+		// The deferred slash counts towards the era it was applied in.
+		assert_eq!(Staking::eras_total_slashed(1), 0);
+		assert_eq!(Staking::eras_total_slashed(4), 100 + nominated_value / 10);
+	})
 }
 
 #[test]
-fn claim_reward_at_the_last_era_and_no_double_claim_and_invalid_claim() {
-	// should check that:
-	// * rewards get paid until history_depth for both validators and nominators
-	// * an invalid era to claim doesn't update last_reward
-	// * double claim of one era fails
-	ExtBuilder::default().nominate(true).build().execute_with(|| {
-		let init_balance_10 = Balances::total_balance(&10);
-		let init_balance_100 = Balances::total_balance(&100);
-
-		let part_for_10 = Perbill::from_rational_approximation::<u32>(1000, 1125);
-		let part_for_100 = Perbill::from_rational_approximation::<u32>(125, 1125);
-
-		// Check state
-		Payee::<Test>::insert(11, RewardDestination::Controller);
-		Payee::<Test>::insert(101, RewardDestination::Controller);
-
-		<Module<Test>>::reward_by_ids(vec![(11, 1)]);
-		// Compute total payout now for whole duration as other parameter won't change
-		let total_payout_0 = current_total_payout_for_duration(3000);
-		assert!(total_payout_0 > 10); // Test is meaningful if reward something
+fn withdraw_unbonded_waits_for_pending_slash() {
+	ExtBuilder::default().slash_defer_duration(2).build().execute_with(|| {
+		// Unlocks at era 3, before the slash below is applied.
+		assert_ok!(Staking::unbond(Origin::signed(10), 500));
 
 		start_era(1);
 
-		<Module<Test>>::reward_by_ids(vec![(11, 1)]);
-		// Change total issuance in order to modify total payout
-		let _ = Balances::deposit_creating(&999, 1_000_000_000);
-		// Compute total payout now for whole duration as other parameter won't change
-		let total_payout_1 = current_total_payout_for_duration(3000);
-		assert!(total_payout_1 > 10); // Test is meaningful if reward something
-		assert!(total_payout_1 != total_payout_0);
-
-		start_era(2);
+		on_offence_now(
+			&[OffenceDetails { offender: (11, Staking::eras_stakers(1, 11)), reporters: vec![] }],
+			&[Perbill::from_percent(10)],
+		);
 
-		<Module<Test>>::reward_by_ids(vec![(11, 1)]);
-		// Change total issuance in order to modify total payout
-		let _ = Balances::deposit_creating(&999, 1_000_000_000);
-		// Compute total payout now for whole duration as other parameter won't change
-		let total_payout_2 = current_total_payout_for_duration(3000);
-		assert!(total_payout_2 > 10); // Test is meaningful if reward something
-		assert!(total_payout_2 != total_payout_0);
-		assert!(total_payout_2 != total_payout_1);
+		start_era(3);
 
-		start_era(Staking::history_depth() + 1);
+		assert!(Staking::has_pending_slash(&11));
+		assert!(Staking::has_pending_slash(&101));
+		assert!(!Staking::has_pending_slash(&21));
+		assert_noop!(Staking::withdraw_unbonded(Origin::signed(10), 0), Error::<Test, _>::PendingSlash);
+		assert_noop!(Staking::withdraw_unbonded(Origin::signed(100), 0), Error::<Test, _>::PendingSlash);
+		assert_ok!(Staking::withdraw_unbonded(Origin::signed(20), 0));
 
-		let active_era = Staking::active_era().unwrap().index;
+		start_era(4);
 
-		// This is the latest planned era in staking, not the active era
-		let current_era = Staking::current_era().unwrap();
+		assert!(!Staking::has_pending_slash(&11));
+		assert!(!Staking::has_pending_slash(&101));
+		assert_eq!(Staking::do_try_state(), Ok(()));
+		assert_ok!(Staking::withdraw_unbonded(Origin::signed(10), 0));
+	})
+}
 
-		// Last kept is 1:
-		assert!(current_era - Staking::history_depth() == 1);
+#[test]
+fn slash_fraction_bounds_clamp_reported_fraction() {
+	ExtBuilder::default().build().execute_with(|| {
+		let bounds = SlashFractionBounds {
+			min: Perbill::from_percent(20),
+			max: Perbill::from_percent(50),
+		};
 		assert_noop!(
-			Staking::payout_validator(Origin::signed(10), 0),
-			// Fail: Era out of history
-			Error::<Test>::InvalidEraToReward
+			Staking::set_slash_fraction_bounds(Origin::signed(10), OFFENCE_KIND, Some(bounds)),
+			BadOrigin,
 		);
-		assert_ok!(Staking::payout_validator(Origin::signed(10), 1));
-		assert_ok!(Staking::payout_validator(Origin::signed(10), 2));
 		assert_noop!(
-			Staking::payout_validator(Origin::signed(10), 2),
-			// Fail: Double claim
-			Error::<Test>::InvalidEraToReward
+			Staking::set_slash_fraction_bounds(
+				Origin::ROOT,
+				OFFENCE_KIND,
+				Some(SlashFractionBounds { min: bounds.max, max: bounds.min }),
+			),
+			Error::<Test, _>::InvalidSlashFractionBounds,
 		);
-		assert_noop!(
-			Staking::payout_validator(Origin::signed(10), active_era),
-			// Fail: Era not finished yet
-			Error::<Test>::InvalidEraToReward
+		assert_ok!(Staking::set_slash_fraction_bounds(Origin::ROOT, OFFENCE_KIND, Some(bounds)));
+
+		// Raised to the lower bound.
+		on_offence_now(
+			&[OffenceDetails { offender: (11, Staking::eras_stakers(0, 11)), reporters: vec![] }],
+			&[Perbill::from_percent(10)],
 		);
+		assert_eq!(Balances::free_balance(11), 800);
 
-		assert_noop!(
-			Staking::payout_nominator(Origin::signed(100), 0, vec![(11, 0)]),
-			// Fail: Era out of history
-			Error::<Test>::InvalidEraToReward
+		// Lowered to the upper bound.
+		on_offence_now(
+			&[OffenceDetails { offender: (21, Staking::eras_stakers(0, 21)), reporters: vec![] }],
+			&[Perbill::from_percent(100)],
 		);
-		assert_ok!(Staking::payout_nominator(Origin::signed(100), 1, vec![(11, 0)]));
-		assert_ok!(Staking::payout_nominator(Origin::signed(100), 2, vec![(11, 0)]));
-		assert_noop!(
-			Staking::payout_nominator(Origin::signed(100), 2, vec![(11, 0)]),
-			// Fail: Double claim
-			Error::<Test>::InvalidEraToReward
+		assert_eq!(Balances::free_balance(21), 2000 - 500);
+
+		assert_ok!(Staking::set_slash_fraction_bounds(Origin::ROOT, OFFENCE_KIND, None));
+		assert_eq!(Staking::slash_fraction_bounds(OFFENCE_KIND), None);
+	})
+}
+
+#[test]
+fn era_payout_is_split_between_stakers_and_remainder() {
+	ExtBuilder::default().build().execute_with(|| {
+		let (staker_payout, remainder) = <Test as Trait>::EraPayout::era_payout(
+			Staking::eras_total_stake(0),
+			Balances::total_issuance(),
+			3 * 1000,
 		);
+		assert!(staker_payout > 10);
+
+		start_era(1);
+
+		assert_eq!(Staking::eras_validator_reward(0), Some(staker_payout));
+		assert_eq!(Staking::eras_remainder_reward(0), Some(remainder));
+
 		assert_noop!(
-			Staking::payout_nominator(Origin::signed(100), active_era, vec![(11, 0)]),
-			// Fail: Era not finished yet
-			Error::<Test>::InvalidEraToReward
+			Staking::set_extra_remainder_share(Origin::signed(10), Perbill::from_percent(50)),
+			BadOrigin,
 		);
-		
-		// Era 0 can't be rewarded anymore and current era can't be rewarded yet
-		// only era 1 and 2 can be rewarded.
+		assert_ok!(Staking::set_extra_remainder_share(Origin::ROOT, Perbill::from_percent(50)));
 
-		assert_eq!(
-			Balances::total_balance(&10),
-			init_balance_10 + part_for_10 * (total_payout_1 + total_payout_2),
-		);
-		assert_eq!(
-			Balances::total_balance(&100),
-			init_balance_100 + part_for_100 * (total_payout_1 + total_payout_2),
+		let (staker_payout, remainder) = <Test as Trait>::EraPayout::era_payout(
+			Staking::eras_total_stake(1),
+			Balances::total_issuance(),
+			3 * 1000,
 		);
-	});
+		let extra = Perbill::from_percent(50) * staker_payout;
+
+		start_era(2);
+
+		assert_eq!(Staking::eras_validator_reward(1), Some(staker_payout - extra));
+		assert_eq!(Staking::eras_remainder_reward(1), Some(remainder + extra));
+	})
 }
 
 #[test]
-fn zero_slash_keeps_nominators() {
+fn era_payout_is_computed_from_issuance_at_era_start() {
 	ExtBuilder::default().build().execute_with(|| {
 		start_era(1);
+		let total_issuance = Balances::total_issuance();
+		assert_eq!(Staking::eras_total_issuance(1), Some(total_issuance));
+		let (staker_payout, remainder) = <Test as Trait>::EraPayout::era_payout(
+			Staking::eras_total_stake(1),
+			total_issuance,
+			3 * 1000,
+		);
 
-		assert_eq!(Balances::free_balance(11), 1000);
+		// Minting during the era does not change its payout.
+		let _ = Balances::deposit_creating(&999, total_issuance);
+		start_era(2);
 
-		let exposure = Staking::eras_stakers(Staking::active_era().unwrap().index, 11);
-		assert_eq!(Balances::free_balance(101), 2000);
+		assert_eq!(Staking::eras_validator_reward(1), Some(staker_payout));
+		assert_eq!(Staking::eras_remainder_reward(1), Some(remainder));
+		assert_eq!(Staking::eras_total_issuance(2), Some(Balances::total_issuance()));
+	})
+}
 
-		on_offence_now(
-			&[
-				OffenceDetails {
-					offender: (11, exposure.clone()),
-					reporters: vec![],
-				},
-			],
-			&[Perbill::from_percent(0)],
+#[test]
+fn era_payout_is_capped_by_max_apr() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_noop!(
+			Staking::set_max_payout_apr(Origin::signed(10), Some(Perbill::zero())),
+			BadOrigin,
 		);
+		assert_ok!(Staking::set_max_payout_apr(Origin::ROOT, Some(Perbill::zero())));
 
-		assert_eq!(Balances::free_balance(11), 1000);
-		assert_eq!(Balances::free_balance(101), 2000);
+		let (staker_payout, remainder) = <Test as Trait>::EraPayout::era_payout(
+			Staking::eras_total_stake(0),
+			Balances::total_issuance(),
+			3 * 1000,
+		);
+		assert!(staker_payout > 10);
 
-		// This is the best way to check that the validator was chilled; `get` will
-		// return default value.
-		for (stash, _) in <Staking as Store>::Validators::enumerate() {
-			assert!(stash != 11);
-		}
+		start_era(1);
 
-		let nominations = <Staking as Store>::Nominators::get(&101).unwrap();
+		// Everything above the cap goes to the remainder.
+		assert_eq!(Staking::eras_validator_reward(0), Some(0));
+		assert_eq!(Staking::eras_remainder_reward(0), Some(staker_payout + remainder));
 
-		// and make sure that the vote will not be ignored, because the slash was
-		// zero.
-		let last_slash = <Staking as Store>::SlashingSpans::get(&11).unwrap().last_nonzero_slash();
-		assert!(nominations.submitted_in >= last_slash);
-	});
+		assert_ok!(Staking::set_max_payout_apr(Origin::ROOT, None));
+		start_era(2);
+
+		assert!(Staking::eras_validator_reward(1).unwrap() > 10);
+	})
 }
 
 #[test]
-fn six_session_delay() {
+fn reward_curve_override_is_used_for_era_payout() {
 	ExtBuilder::default().build().execute_with(|| {
-		use pallet_session::SessionManager;
+		let flat = |inflation| RewardCurvePoints {
+			points: vec![(Perbill::zero(), inflation), (Perbill::one(), inflation)],
+			maximum: Perbill::from_percent(10),
+		};
 
-		let val_set = Session::validators();
-		let init_session = Session::current_index();
-		let init_active_era = Staking::active_era().unwrap().index;
-		// pallet-session is delaying session by one, thus the next session to plan is +2.
-		assert_eq!(<Staking as SessionManager<_>>::new_session(init_session + 2), None);
-		assert_eq!(<Staking as SessionManager<_>>::new_session(init_session + 3), Some(val_set.clone()));
-		assert_eq!(<Staking as SessionManager<_>>::new_session(init_session + 4), None);
-		assert_eq!(<Staking as SessionManager<_>>::new_session(init_session + 5), None);
-		assert_eq!(<Staking as SessionManager<_>>::new_session(init_session + 6), Some(val_set.clone()));
+		assert_noop!(
+			Staking::set_reward_curve(Origin::signed(10), Some(flat(Perbill::zero()))),
+			BadOrigin,
+		);
+		assert_noop!(
+			Staking::set_reward_curve(Origin::ROOT, Some(flat(Perbill::from_percent(20)))),
+			Error::<Test, _>::InvalidRewardCurve,
+		);
+		let unordered = RewardCurvePoints {
+			points: vec![(Perbill::one(), Perbill::zero()), (Perbill::zero(), Perbill::zero())],
+			maximum: Perbill::one(),
+		};
+		assert_noop!(
+			Staking::set_reward_curve(Origin::ROOT, Some(unordered)),
+			Error::<Test, _>::InvalidRewardCurve,
+		);
 
-		<Staking as SessionManager<_>>::end_session(init_session);
-		<Staking as SessionManager<_>>::start_session(init_session + 1);
-		assert_eq!(Staking::active_era().unwrap().index, init_active_era);
-		<Staking as SessionManager<_>>::end_session(init_session + 1);
-		<Staking as SessionManager<_>>::start_session(init_session + 2);
-		assert_eq!(Staking::active_era().unwrap().index, init_active_era);
+		let curve = flat(Perbill::from_percent(10));
+		assert_ok!(Staking::set_reward_curve(Origin::ROOT, Some(curve.clone())));
+		let (staker_payout, _) = inflation::compute_total_payout(
+			&curve.as_piecewise_linear(),
+			Staking::eras_total_stake(0),
+			Balances::total_issuance(),
+			3 * 1000,
+		);
+		assert!(staker_payout > 10);
 
-		// Reward current era
-		Staking::reward_by_ids(vec![(11, 1)]);
+		start_era(1);
 
-		// New active era is triggered here.
-		<Staking as SessionManager<_>>::end_session(init_session + 2);
-		<Staking as SessionManager<_>>::start_session(init_session + 3);
-		assert_eq!(Staking::active_era().unwrap().index, init_active_era + 1);
-		<Staking as SessionManager<_>>::end_session(init_session + 3);
-		<Staking as SessionManager<_>>::start_session(init_session + 4);
-		assert_eq!(Staking::active_era().unwrap().index, init_active_era + 1);
-		<Staking as SessionManager<_>>::end_session(init_session + 4);
-		<Staking as SessionManager<_>>::start_session(init_session + 5);
-		assert_eq!(Staking::active_era().unwrap().index, init_active_era + 1);
+		// The curve is at its maximum, so nothing is left for the remainder.
+		assert_eq!(Staking::eras_validator_reward(0), Some(staker_payout));
+		assert_eq!(Staking::eras_remainder_reward(0), Some(0));
 
-		// Reward current era
-		Staking::reward_by_ids(vec![(21, 2)]);
+		// Back to `T::EraPayout`.
+		assert_ok!(Staking::set_reward_curve(Origin::ROOT, None));
+		let (staker_payout, _) = <Test as Trait>::EraPayout::era_payout(
+			Staking::eras_total_stake(1),
+			Balances::total_issuance(),
+			3 * 1000,
+		);
+		start_era(2);
+		assert_eq!(Staking::eras_validator_reward(1), Some(staker_payout));
+	})
+}
 
-		// New active era is triggered here.
-		<Staking as SessionManager<_>>::end_session(init_session + 5);
-		<Staking as SessionManager<_>>::start_session(init_session + 6);
-		assert_eq!(Staking::active_era().unwrap().index, init_active_era + 2);
+#[test]
+fn era_payout_summary_tracks_payouts() {
+	ExtBuilder::default().build().execute_with(|| {
+		<Module<Test>>::reward_by_ids(vec![(11, 1), (21, 1)]);
+		assert_eq!(Staking::eras_payout_summary(0), Default::default());
+		start_era(1);
 
-		// That reward are correct
-		assert_eq!(Staking::eras_reward_points(init_active_era).total, 1);
-		assert_eq!(Staking::eras_reward_points(init_active_era + 1).total, 2);
-	});
+		let era_payout = Staking::eras_validator_reward(0).unwrap();
+		assert_eq!(Staking::eras_payout_summary(0), EraPayoutSummary {
+			total_paid: 0,
+			total_unclaimed: era_payout,
+			validators_paid: 0,
+		});
+
+		make_all_reward_payment(0);
+		let summary = Staking::eras_payout_summary(0);
+		assert_eq!(summary.validators_paid, 2);
+		assert_eq!(summary.total_paid + summary.total_unclaimed, era_payout);
+		// Only rounding errors are left unclaimed.
+		assert_eq_error_rate!(summary.total_paid, era_payout, 5);
+	})
 }
 
 #[test]
-fn test_max_nominator_rewarded_per_validator_and_cant_steal_someone_else_reward() {
-	// Test:
-	// * If nominator nomination is below the $MaxNominatorRewardedPerValidator other nominator
-	//   then the nominator can't claim its reward
-	// * A nominator can't claim another nominator reward
+fn only_elected_validators_with_a_reward_count_as_paid() {
 	ExtBuilder::default().build().execute_with(|| {
-		for i in 0..=<Test as Trait>::MaxNominatorRewardedPerValidator::get() {
-			let stash = 10_000 + i as u64;
-			let controller = 20_000 + i as u64;
-			let balance = 10_000 + i as u64;
-			Balances::make_free_balance_be(&stash, balance);
-			assert_ok!(
-				Staking::bond(
-					Origin::signed(stash),
-					controller,
-					balance,
-					RewardDestination::Stash
-				)
-			);
-			assert_ok!(Staking::nominate(Origin::signed(controller), vec![11]));
+		<Module<Test>>::reward_by_ids(vec![(11, 1)]);
+		start_era(1);
+		assert!(!EraInfo::<Test>::is_elected(0, &31));
+
+		// 31 is a candidate, but was not elected in era 0.
+		assert_noop!(Staking::payout_validator(Origin::signed(30), 0), Error::<Test, _>::NotElected);
+
+		// 21 was elected, but earned no points.
+		assert_ok!(Staking::payout_validator(Origin::signed(20), 0));
+		assert_eq!(Staking::eras_payout_summary(0).validators_paid, 0);
+
+		assert_ok!(Staking::payout_validator(Origin::signed(10), 0));
+		assert_eq!(Staking::eras_payout_summary(0).validators_paid, 1);
+	})
+}
+
+#[test]
+fn era_payout_is_throttled_below_stake_floor() {
+	ExtBuilder::default().build().execute_with(|| {
+		let total_staked = Staking::eras_total_stake(0);
+		assert_noop!(Staking::set_payout_stake_floor(Origin::signed(10), total_staked), BadOrigin);
+		assert_ok!(Staking::set_payout_stake_floor(Origin::ROOT, 2 * total_staked));
+
+		let (staker_payout, remainder) = <Test as Trait>::EraPayout::era_payout(
+			total_staked,
+			Balances::total_issuance(),
+			3 * 1000,
+		);
+		assert!(staker_payout > 10);
+
+		start_era(1);
+
+		// Half the stake is missing, so is half the payout; the remainder is untouched.
+		assert_eq!(
+			Staking::eras_validator_reward(0),
+			Some(Perbill::from_percent(50) * staker_payout),
+		);
+		assert_eq!(Staking::eras_remainder_reward(0), Some(remainder));
+
+		assert_ok!(Staking::set_payout_stake_floor(Origin::ROOT, total_staked));
+		let (staker_payout, _) = <Test as Trait>::EraPayout::era_payout(
+			Staking::eras_total_stake(1),
+			Balances::total_issuance(),
+			3 * 1000,
+		);
+		start_era(2);
+		assert_eq!(Staking::eras_validator_reward(1), Some(staker_payout));
+	})
+}
+
+#[test]
+fn unclaimed_rewards_are_swept_at_history_expiry() {
+	ExtBuilder::default().build().execute_with(|| {
+		<Module<Test>>::reward_by_ids(vec![(11, 1)]);
+		start_era(1);
+
+		let era_payout = Staking::eras_validator_reward(0).unwrap();
+		assert!(era_payout > 10);
+
+		// Only the validator claims its part.
+		assert_ok!(Staking::payout_validator(Origin::signed(10), 0));
+		let summary = Staking::eras_payout_summary(0);
+		let claimed = summary.total_paid;
+		assert!(claimed > 0 && claimed < era_payout);
+		assert_eq!(summary.total_unclaimed, era_payout - claimed);
+		assert_eq!(summary.validators_paid, 1);
+
+		start_era(4);
+		Staking::set_history_depth(Origin::ROOT, 3).unwrap();
+		start_era(5);
+
+		assert_eq!(Balances::total_balance(&UNCLAIMED_REWARDS_ACCOUNT), era_payout - claimed);
+		assert!(!<Staking as Store>::ErasPayoutSummary::contains_key(0));
+	})
+}
+
+#[test]
+fn payout_stakers_all_catches_up_eras() {
+	ExtBuilder::default().build().execute_with(|| {
+		<Payee<Test>>::insert(&11, RewardDestination::Controller);
+
+		for era in 0..4 {
+			<Module<Test>>::reward_by_ids(vec![(11, 1)]);
+			start_era(era + 1);
 		}
-		mock::start_era(1);
+		assert_eq!(Staking::active_era().unwrap().index, 4);
+		let balance = Balances::total_balance(&10);
+		let max_weight = Weight::max_value();
+
+		// Only the controller may skip eras.
+		assert_noop!(
+			Staking::payout_stakers_all(Origin::signed(1337), 11, 2, 10, max_weight),
+			Error::<Test, _>::NotController,
+		);
+		assert_noop!(
+			Staking::payout_stakers_all(Origin::signed(1337), 10, 0, 10, max_weight),
+			Error::<Test, _>::NotStash,
+		);
+		assert_noop!(
+			Staking::payout_stakers_all(Origin::signed(1337), 11, 0, 10, 0),
+			Error::<Test, _>::PayoutWeightTooLow,
+		);
+
+		// The weight limit allows two eras, the second one is skipped as 11 was not elected.
+		<Staking as Store>::ErasStakersOverview::remove(1, 11);
+		<Staking as Store>::ErasStakers::remove(1, 11);
+		let two_eras = <() as WeightInfo>::payout_stakers_all(2);
+		assert_ok!(Staking::payout_stakers_all(Origin::signed(1337), 11, 0, 10, two_eras));
+		assert_eq!(Staking::ledger(&10).unwrap().last_reward, Some(0));
+		let events = staking_events();
+		let not_elected = DispatchError::from(Error::<Test, _>::NotElected);
+		assert_eq!(events[events.len() - 2], RawEvent::PayoutSkipped(11, 1, not_elected));
+		assert_eq!(events.last(), Some(&RawEvent::ErasPaidOut(11, 0, 1, 1)));
+
+		assert_ok!(Staking::payout_stakers_all(Origin::signed(1337), 11, 0, 10, max_weight));
+		assert_eq!(Staking::ledger(&10).unwrap().last_reward, Some(3));
+		assert!(Balances::total_balance(&10) > balance);
+		let events = staking_events();
+		for era in [0, 2, 3].iter() {
+			assert!(events.contains(&RawEvent::PayoutStarted(*era, 11, None)));
+		}
+		assert!(!events.contains(&RawEvent::PayoutStarted(1, 11, None)));
+		assert_eq!(events.last(), Some(&RawEvent::ErasPaidOut(11, 1, 3, 2)));
+
+		// Nothing is left to be paid out.
+		assert_noop!(
+			Staking::payout_stakers_all(Origin::signed(10), 11, 0, 10, max_weight),
+			Error::<Test, _>::InvalidEraToReward,
+		);
+	})
+}
+
+#[test]
+fn auto_payouts_are_made_within_the_finalize_budget() {
+	use frame_support::weights::WeighBlock;
+
+	ExtBuilder::default().build().execute_with(|| {
+		<Payee<Test>>::insert(&11, RewardDestination::Controller);
+		<Payee<Test>>::insert(&101, RewardDestination::Controller);
+
+		assert_noop!(
+			Staking::set_auto_payout(Origin::signed(1337), true),
+			Error::<Test, _>::NotController,
+		);
+		assert_ok!(Staking::set_auto_payout(Origin::signed(100), true));
+		assert_ok!(Staking::set_auto_payout(Origin::signed(10), true));
+		assert_ok!(Staking::set_auto_payout(Origin::signed(10), true));
+		assert_eq!(Staking::auto_payout_stashes(), vec![11, 101]);
 
 		<Module<Test>>::reward_by_ids(vec![(11, 1)]);
-		// Compute total payout now for whole duration as other parameter won't change
-		let total_payout_0 = current_total_payout_for_duration(3 * 1000);
-		assert!(total_payout_0 > 100); // Test is meaningful if reward something
+		start_era(1);
+		<Module<Test>>::reward_by_ids(vec![(11, 1)]);
+		start_era(2);
+		let balance_10 = Balances::total_balance(&10);
+		let balance_100 = Balances::total_balance(&100);
 
-		mock::start_era(2);
-		mock::make_all_reward_payment(1);
+		// Era 0 was paid out automatically during era 1.
+		assert_eq!(Staking::ledger(&10).unwrap().last_reward, Some(0));
+		assert_eq!(Staking::ledger(&100).unwrap().last_reward, Some(0));
 
-		// nominator 10_000 can't get its reward because exposure is clipped. However it will try
-		// to query other people reward.
-		assert_ok!(Staking::payout_nominator(Origin::signed(20_000), 1, vec![(11, 0)]));
+		// The payouts and the pruning share the weight reserved for the end of the block.
+		assert_eq!(
+			<Staking as WeighBlock<BlockNumber>>::on_finalize(System::block_number()),
+			2 * AUTO_PAYOUT_WEIGHT + 16 * PRUNING_KEY_WEIGHT,
+		);
 
-		// Assert only nominators from 1 to Max are rewarded
-		for i in 0..=<Test as Trait>::MaxNominatorRewardedPerValidator::get() {
-			let stash = 10_000 + i as u64;
-			let balance = 10_000 + i as u64;
-			if stash == 10_000 {
-				assert!(Balances::free_balance(&stash) == balance);
-			} else {
-				assert!(Balances::free_balance(&stash) > balance);
+		// Nothing is paid out without enough weight. The payout of 101 as a nominator also reads
+		// the exposure of 11, so it does not fit in the weight of the payout of 11.
+		let mut weight_left = AUTO_PAYOUT_WEIGHT - 1;
+		Staking::process_auto_payouts(&mut weight_left);
+		assert_eq!(weight_left, AUTO_PAYOUT_WEIGHT - 1);
+		assert_eq!(Staking::ledger(&10).unwrap().last_reward, Some(0));
+		let mut weight_left = AUTO_PAYOUT_WEIGHT;
+		Staking::process_auto_payouts(&mut weight_left);
+		assert_eq!(weight_left, 0);
+		assert_eq!(Staking::ledger(&10).unwrap().last_reward, Some(1));
+		assert_eq!(Staking::ledger(&100).unwrap().last_reward, Some(0));
+
+		Staking::on_finalize(System::block_number());
+		assert_eq!(Staking::ledger(&10).unwrap().last_reward, Some(1));
+		assert_eq!(Staking::ledger(&100).unwrap().last_reward, Some(1));
+		assert!(Balances::total_balance(&10) > balance_10);
+		assert!(Balances::total_balance(&100) > balance_100);
+
+		// Opted-out stashes are left alone.
+		assert_ok!(Staking::set_auto_payout(Origin::signed(100), false));
+		assert_eq!(Staking::auto_payout_stashes(), vec![11]);
+		<Module<Test>>::reward_by_ids(vec![(11, 1)]);
+		start_era(3);
+		Staking::on_finalize(System::block_number());
+		assert_eq!(Staking::ledger(&10).unwrap().last_reward, Some(2));
+		assert_eq!(Staking::ledger(&100).unwrap().last_reward, Some(1));
+	})
+}
+
+#[test]
+fn validator_prefs_are_only_returned_for_candidates() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_eq!(Staking::validator_prefs(11), Some(ValidatorPrefs::default()));
+		assert_eq!(Staking::validator_prefs(101), None);
+
+		assert_ok!(Staking::chill(Origin::signed(10)));
+		assert_eq!(Staking::validator_prefs(11), None);
+	})
+}
+
+#[test]
+fn pending_rewards_match_payouts() {
+	ExtBuilder::default().build().execute_with(|| {
+		<Payee<Test>>::insert(&11, RewardDestination::Controller);
+		<Payee<Test>>::insert(&101, RewardDestination::Controller);
+		assert!(Staking::pending_rewards(11).is_empty());
+
+		<Module<Test>>::reward_by_ids(vec![(11, 1)]);
+		start_era(1);
+		<Module<Test>>::reward_by_ids(vec![(11, 1), (21, 1)]);
+		start_era(2);
+
+		let pending_11 = Staking::pending_rewards(11);
+		let pending_101 = Staking::pending_rewards(101);
+		assert_eq!(pending_11.iter().map(|(era, _)| *era).collect::<Vec<_>>(), vec![0, 1]);
+		assert_eq!(pending_101.iter().map(|(era, _)| *era).collect::<Vec<_>>(), vec![0, 1]);
+		// Not a stash.
+		assert!(Staking::pending_rewards(10).is_empty());
+
+		let balance_10 = Balances::total_balance(&10);
+		let balance_100 = Balances::total_balance(&100);
+		make_all_reward_payment(0);
+		assert_eq!(Balances::total_balance(&10), balance_10 + pending_11[0].1);
+		assert_eq!(Balances::total_balance(&100), balance_100 + pending_101[0].1);
+
+		assert_eq!(Staking::pending_rewards(11), vec![pending_11[1]]);
+		assert_eq!(Staking::pending_rewards(101), vec![pending_101[1]]);
+	})
+}
+
+#[test]
+fn reward_points_are_reported_with_their_share() {
+	ExtBuilder::default().build().execute_with(|| {
+		<Module<Test>>::reward_by_ids(vec![(11, 1)]);
+		start_era(1);
+		<Module<Test>>::reward_by_ids(vec![(11, 1), (21, 3)]);
+		start_era(2);
+
+		assert_eq!(
+			Staking::reward_points(0, 1),
+			vec![
+				ValidatorEraPoints {
+					era: 0,
+					validator: 11,
+					points: 1,
+					share: Perbill::one(),
+				},
+				ValidatorEraPoints {
+					era: 1,
+					validator: 11,
+					points: 1,
+					share: Perbill::from_percent(25),
+				},
+				ValidatorEraPoints {
+					era: 1,
+					validator: 21,
+					points: 3,
+					share: Perbill::from_percent(75),
+				},
+			],
+		);
+		assert_eq!(Staking::reward_points(1, 1).len(), 2);
+		// Nothing is earned in the active era yet, nor beyond it.
+		assert!(Staking::reward_points(2, 10).is_empty());
+	})
+}
+
+#[test]
+fn claimable_payouts_can_be_claimed_with_their_call() {
+	ExtBuilder::default().build().execute_with(|| {
+		<Payee<Test>>::insert(&11, RewardDestination::Controller);
+		<Payee<Test>>::insert(&101, RewardDestination::Controller);
+
+		<Module<Test>>::reward_by_ids(vec![(11, 1)]);
+		start_era(1);
+		<Module<Test>>::reward_by_ids(vec![(11, 1), (21, 1)]);
+		start_era(2);
+
+		let payouts_101 = Staking::claimable_payouts(101);
+		assert_eq!(
+			payouts_101.iter().map(|payout| payout.call.clone()).collect::<Vec<_>>(),
+			vec![
+				PayoutCall::Nominator(0, vec![(11, 0)]),
+				PayoutCall::Nominator(1, vec![(11, 0), (21, 0)]),
+			],
+		);
+		assert_eq!(
+			Staking::claimable_payouts(11).iter().map(|payout| payout.call.clone()).collect::<Vec<_>>(),
+			vec![PayoutCall::Validator(0), PayoutCall::Validator(1)],
+		);
+
+		for payout in payouts_101 {
+			let balance_100 = Balances::total_balance(&100);
+			match payout.call {
+				PayoutCall::Nominator(era, validators) =>
+					assert_ok!(Staking::payout_nominator(Origin::signed(100), era, validators)),
+				PayoutCall::Validator(_) => panic!("101 is a nominator"),
 			}
+			assert_eq!(Balances::total_balance(&100), balance_100 + payout.amount);
 		}
-	});
+		assert!(Staking::claimable_payouts(101).is_empty());
+	})
 }
 
 #[test]
-fn set_history_depth_works() {
+fn exposure_is_queried_by_page() {
 	ExtBuilder::default().build().execute_with(|| {
-		start_era(10);
-		Staking::set_history_depth(Origin::ROOT, 20).unwrap();
-		assert!(<Staking as Store>::ErasTotalStake::contains_key(10 - 4));
-		assert!(<Staking as Store>::ErasTotalStake::contains_key(10 - 5));
-		Staking::set_history_depth(Origin::ROOT, 4).unwrap();
-		assert!(<Staking as Store>::ErasTotalStake::contains_key(10 - 4));
-		assert!(!<Staking as Store>::ErasTotalStake::contains_key(10 - 5));
-		Staking::set_history_depth(Origin::ROOT, 3).unwrap();
-		assert!(!<Staking as Store>::ErasTotalStake::contains_key(10 - 4));
-		assert!(!<Staking as Store>::ErasTotalStake::contains_key(10 - 5));
-		Staking::set_history_depth(Origin::ROOT, 8).unwrap();
-		assert!(!<Staking as Store>::ErasTotalStake::contains_key(10 - 4));
-		assert!(!<Staking as Store>::ErasTotalStake::contains_key(10 - 5));
-	});
+		for i in 0..=EXPOSURE_PAGE_SIZE as u64 {
+			bond_nominator(1000 + i * 2, 100, vec![11]);
+		}
+		start_era(1);
+
+		let exposure = Staking::eras_stakers(1, 11);
+		assert!(exposure.others.len() > EXPOSURE_PAGE_SIZE);
+		assert_eq!(
+			Staking::eras_stakers_overview(1, 11),
+			Some(ExposureOverview {
+				total: exposure.total,
+				own: exposure.own,
+				nominator_count: exposure.others.len() as u32,
+				page_count: 2,
+			}),
+		);
+
+		let first_page = Staking::eras_stakers_page(1, 11, 0).unwrap();
+		let last_page = Staking::eras_stakers_page(1, 11, 1).unwrap();
+		assert_eq!(first_page.others[..], exposure.others[..EXPOSURE_PAGE_SIZE]);
+		assert_eq!(last_page.others[..], exposure.others[EXPOSURE_PAGE_SIZE..]);
+		assert_eq!(first_page.page_total + last_page.page_total, exposure.total - exposure.own);
+		assert_eq!(Staking::eras_stakers_page(1, 11, 2), None);
+
+		// 41 is not elected.
+		assert_eq!(Staking::eras_stakers_overview(1, 41), None);
+		assert_eq!(Staking::eras_stakers_page(1, 41, 0), None);
+	})
+}
+
+#[test]
+fn exposures_of_eras_before_paging_are_still_read() {
+	ExtBuilder::default().build().execute_with(|| {
+		start_era(1);
+		<Module<Test>>::reward_by_ids(vec![(11, 1)]);
+
+		// Bring the exposure of 11 back to its layout before exposures were paged.
+		let exposure = Staking::eras_stakers(1, 11);
+		assert_eq!(exposure.others.len(), 1);
+		<Staking as Store>::ErasStakersOverview::remove(1, 11);
+		<Staking as Store>::ErasStakersCompact::remove(1, (11, 0));
+		<Staking as Store>::ErasStakers::insert(1, 11, &exposure);
+		<Staking as Store>::ErasStakersClipped::insert(1, 11, &exposure);
+
+		assert_eq!(Staking::eras_stakers(1, 11), exposure);
+		assert_eq!(
+			Staking::eras_stakers_overview(1, 11),
+			Some(ExposureOverview { total: exposure.total, own: exposure.own, nominator_count: 1, page_count: 1 }),
+		);
+		assert_eq!(Staking::eras_stakers_page(1, 11, 0).unwrap().others, exposure.others);
+
+		start_era(2);
+		let (balance_11, balance_101) = (Balances::total_balance(&11), Balances::total_balance(&101));
+		make_all_reward_payment(1);
+		assert!(Balances::total_balance(&11) > balance_11);
+		assert!(Balances::total_balance(&101) > balance_101);
+	})
+}
+
+#[test]
+fn election_weight_is_registered() {
+	ExtBuilder::default().build().execute_with(|| {
+		let weight = <() as WeightInfo>::new_era(
+			Staking::counter_for_validators(),
+			Staking::counter_for_nominators(),
+			Staking::validator_count(),
+			<Test as Trait>::MaxNominations::get(),
+		);
+		assert!(weight > 0);
+
+		let weight_before = System::all_extrinsics_weight();
+		assert!(Staking::new_era(1).is_some());
+		assert_eq!(System::all_extrinsics_weight(), weight_before + weight);
+	})
+}
+
+#[test]
+fn exposed_nominators_are_stored_once_per_era() {
+	ExtBuilder::default().build().execute_with(|| {
+		start_era(1);
+
+		// 101 is exposed to both 11 and 21, but its account is only stored once.
+		assert_eq!(<Staking as Store>::ErasStakersAccounts::get(1, 0), vec![101]);
+		let page_11 = <Staking as Store>::ErasStakersCompact::get(1, (11, 0)).unwrap();
+		let page_21 = <Staking as Store>::ErasStakersCompact::get(1, (21, 0)).unwrap();
+		assert_eq!(page_11.others[0].index, 0);
+		assert_eq!(page_21.others[0].index, 0);
+
+		// The indices are resolved when reading the exposures.
+		let exposure = Staking::eras_stakers(1, 11);
+		assert_eq!(exposure.others.len(), 1);
+		assert_eq!(exposure.others[0].who, 101);
+		assert_eq!(exposure.others[0].value, page_11.others[0].value);
+		assert_eq!(Staking::eras_stakers_page(1, 21, 0).unwrap().others[0].who, 101);
+
+		// The pages stored before nominators were indexed are still read.
+		let page = Staking::eras_stakers_page(1, 11, 0).unwrap();
+		<Staking as Store>::ErasStakersCompact::remove(1, (11, 0));
+		<Staking as Store>::ErasStakersPaged::insert(1, (11, 0), &page);
+		assert_eq!(Staking::eras_stakers(1, 11), exposure);
+	})
 }
 
+#[test]
+fn pending_slashes_are_reported_until_applied() {
+	ExtBuilder::default().slash_defer_duration(2).build().execute_with(|| {
+		start_era(1);
+		assert!(Staking::pending_slashes().is_empty());
+
+		let exposure = Staking::eras_stakers(Staking::active_era().unwrap().index, 11);
+		let nominated_value = exposure.others.iter().find(|o| o.who == 101).unwrap().value;
+
+		on_offence_now(
+			&[
+				OffenceDetails {
+					offender: (11, exposure.clone()),
+					reporters: vec![],
+				},
+			],
+			&[Perbill::from_percent(10)],
+		);
+
+		let pending = Staking::pending_slashes();
+		assert_eq!(pending.len(), 1);
+		assert_eq!((pending[0].era, pending[0].id, pending[0].validator), (1, 0, 11));
+		assert_eq!(pending[0].own, 100);
+		assert_eq!(pending[0].others, vec![(101, nominated_value / 10)]);
+		assert_eq!(pending[0].apply_at, 4);
+
+		start_era(3);
+		assert_eq!(Staking::pending_slashes(), pending);
+		assert_eq!(Balances::free_balance(11), 1000);
+
+		start_era(4);
+		assert!(Staking::pending_slashes().is_empty());
+		assert_eq!(Balances::free_balance(11), 900);
+	})
+}
+
+#[test]
+fn remove_deferred() {
+	ExtBuilder::default().slash_defer_duration(2).build().execute_with(|| {
+		start_era(1);
+
+		assert_eq!(Balances::free_balance(11), 1000);
+
+		let exposure = Staking::eras_stakers(Staking::active_era().unwrap().index, 11);
+		assert_eq!(Balances::free_balance(101), 2000);
+		let nominated_value = exposure.others.iter().find(|o| o.who == 101).unwrap().value;
+
+		on_offence_now(
+			&[
+				OffenceDetails {
+					offender: (11, exposure.clone()),
+					reporters: vec![],
+				},
+			],
+			&[Perbill::from_percent(10)],
+		);
+
+		assert_eq!(Balances::free_balance(11), 1000);
+		assert_eq!(Balances::free_balance(101), 2000);
+
+		start_era(2);
+
+		on_offence_in_era(
+			&[
+				OffenceDetails {
+					offender: (11, exposure.clone()),
+					reporters: vec![],
+				},
+			],
+			&[Perbill::from_percent(15)],
+			1,
+		);
+
+		Staking::cancel_deferred_slash(Origin::ROOT, 1, vec![0]).unwrap();
+
+		assert_eq!(Balances::free_balance(11), 1000);
+		assert_eq!(Balances::free_balance(101), 2000);
+
+		start_era(3);
+
+		assert_eq!(Balances::free_balance(11), 1000);
+		assert_eq!(Balances::free_balance(101), 2000);
+
+		// at the start of era 4, slashes from era 1 are processed,
+		// after being deferred for at least 2 full eras.
+		start_era(4);
+
+		// the first slash for 10% was cancelled, so no effect.
+		assert_eq!(Balances::free_balance(11), 1000);
+		assert_eq!(Balances::free_balance(101), 2000);
+
+		start_era(5);
+
+		let slash_10 = Perbill::from_percent(10);
+		let slash_15 = Perbill::from_percent(15);
+		let initial_slash = slash_10 * nominated_value;
+
+		let total_slash = slash_15 * nominated_value;
+		let actual_slash = total_slash - initial_slash;
+
+		// 5% slash (15 - 10) processed now.
+		assert_eq!(Balances::free_balance(11), 950);
+		assert_eq!(Balances::free_balance(101), 2000 - actual_slash);
+	})
+}
+
+#[test]
+fn remove_multi_deferred() {
+	ExtBuilder::default().slash_defer_duration(2).build().execute_with(|| {
+		start_era(1);
+
+		assert_eq!(Balances::free_balance(11), 1000);
+
+		let exposure = Staking::eras_stakers(Staking::active_era().unwrap().index, 11);
+		assert_eq!(Balances::free_balance(101), 2000);
+
+		on_offence_now(
+			&[
+				OffenceDetails {
+					offender: (11, exposure.clone()),
+					reporters: vec![],
+				},
+			],
+			&[Perbill::from_percent(10)],
+		);
+
+		on_offence_now(
+			&[
+				OffenceDetails {
+					offender: (21, Staking::eras_stakers(Staking::active_era().unwrap().index, 21)),
+					reporters: vec![],
+				}
+			],
+			&[Perbill::from_percent(10)],
+		);
+
+		on_offence_now(
+			&[
+				OffenceDetails {
+					offender: (11, exposure.clone()),
+					reporters: vec![],
+				},
+			],
+			&[Perbill::from_percent(25)],
+		);
+
+		assert_eq!(<Staking as Store>::UnappliedSlashes::get(&1).len(), 3);
+		Staking::cancel_deferred_slash(Origin::ROOT, 1, vec![0, 2]).unwrap();
+
+		let slashes = <Staking as Store>::UnappliedSlashes::get(&1);
+		assert_eq!(slashes.len(), 1);
+		assert_eq!(slashes[0].validator, 21);
+	})
+}
+
+#[test]
+fn deferred_slash_ids_are_stable() {
+	ExtBuilder::default().slash_defer_duration(2).build().execute_with(|| {
+		start_era(1);
+
+		let exposure_11 = Staking::eras_stakers(Staking::active_era().unwrap().index, 11);
+		let exposure_21 = Staking::eras_stakers(Staking::active_era().unwrap().index, 21);
+
+		on_offence_now(
+			&[OffenceDetails { offender: (11, exposure_11.clone()), reporters: vec![] }],
+			&[Perbill::from_percent(10)],
+		);
+		on_offence_now(
+			&[OffenceDetails { offender: (21, exposure_21.clone()), reporters: vec![] }],
+			&[Perbill::from_percent(10)],
+		);
+
+		let ids = |era| Staking::unapplied_slashes(era).iter().map(|s| s.id).collect::<Vec<_>>();
+		assert_eq!(ids(1), vec![0, 1]);
+
+		// Removing a slash does not change the id of the others.
+		assert_ok!(Staking::cancel_deferred_slash(Origin::ROOT, 1, vec![0]));
+		assert_eq!(ids(1), vec![1]);
+
+		on_offence_now(
+			&[OffenceDetails { offender: (11, exposure_11.clone()), reporters: vec![] }],
+			&[Perbill::from_percent(25)],
+		);
+		assert_eq!(ids(1), vec![1, 2]);
+
+		// Cancelled, unknown and duplicate ids are rejected.
+		assert_noop!(
+			Staking::cancel_deferred_slash(Origin::ROOT, 1, vec![0]),
+			Error::<Test, _>::InvalidSlashIndex,
+		);
+		assert_noop!(
+			Staking::cancel_deferred_slash(Origin::ROOT, 2, vec![1]),
+			Error::<Test, _>::InvalidSlashIndex,
+		);
+		assert_noop!(
+			Staking::cancel_deferred_slash(Origin::ROOT, 1, vec![2, 2]),
+			Error::<Test, _>::DuplicateIndex,
+		);
+
+		assert_ok!(Staking::cancel_deferred_slash(Origin::ROOT, 1, vec![2]));
+		let slashes = Staking::unapplied_slashes(1);
+		assert_eq!(slashes.len(), 1);
+		assert_eq!((slashes[0].id, slashes[0].validator), (1, 21));
+	})
+}
+
+#[test]
+fn slash_kicks_validators_not_nominators() {
+	ExtBuilder::default().build().execute_with(|| {
+		start_era(1);
+
+		assert_eq!(Balances::free_balance(11), 1000);
+
+		let exposure = Staking::eras_stakers(Staking::active_era().unwrap().index, 11);
+		assert_eq!(Balances::free_balance(101), 2000);
+		let nominated_value = exposure.others.iter().find(|o| o.who == 101).unwrap().value;
+
+		on_offence_now(
+			&[
+				OffenceDetails {
+					offender: (11, exposure.clone()),
+					reporters: vec![],
+				},
+			],
+			&[Perbill::from_percent(10)],
+		);
+
+		assert_eq!(Balances::free_balance(11), 900);
+		assert_eq!(Balances::free_balance(101), 2000 - (nominated_value / 10));
+
+		// This is the best way to check that the validator was chilled; `get` will
+		// return default value.
+		for (stash, _) in Staking::iter_validators() {
+			assert!(stash != 11);
+		}
+
+		let nominations = <Staking as Store>::Nominators::get(&101).unwrap();
+
+		// and make sure that the vote will be ignored even if the validator
+		// re-registers.
+		let last_slash = <Staking as Store>::SlashingSpans::get(&11).unwrap().last_nonzero_slash();
+		assert!(nominations.submitted_in < last_slash);
+	});
+}
+
+#[test]
+fn claim_reward_at_the_last_era_and_no_double_claim_and_invalid_claim() {
+	// should check that:
+	// * rewards get paid until history_depth for both validators and nominators
+	// * an invalid era to claim doesn't update last_reward
+	// * double claim of one era fails
+	ExtBuilder::default().nominate(true).build().execute_with(|| {
+		let init_balance_10 = Balances::total_balance(&10);
+		let init_balance_100 = Balances::total_balance(&100);
+
+		let part_for_10 = Perbill::from_rational_approximation::<u32>(1000, 1125);
+		let part_for_100 = Perbill::from_rational_approximation::<u32>(125, 1125);
+
+		// Check state
+		Payee::<Test>::insert(11, RewardDestination::Controller);
+		Payee::<Test>::insert(101, RewardDestination::Controller);
+
+		<Module<Test>>::reward_by_ids(vec![(11, 1)]);
+		// Compute total payout now for whole duration as other parameter won't change
+		let total_payout_0 = current_total_payout_for_duration(3000);
+		assert!(total_payout_0 > 10); // Test is meaningful if reward something
+
+		start_era(1);
+
+		<Module<Test>>::reward_by_ids(vec![(11, 1)]);
+		// Change total issuance in order to modify total payout
+		let _ = Balances::deposit_creating(&999, 1_000_000_000);
+		// Compute total payout now for whole duration as other parameter won't change
+		let total_payout_1 = current_total_payout_for_duration(3000);
+		assert!(total_payout_1 > 10); // Test is meaningful if reward something
+		assert!(total_payout_1 != total_payout_0);
+
+		start_era(2);
+
+		<Module<Test>>::reward_by_ids(vec![(11, 1)]);
+		// Change total issuance in order to modify total payout
+		let _ = Balances::deposit_creating(&999, 1_000_000_000);
+		// Compute total payout now for whole duration as other parameter won't change
+		let total_payout_2 = current_total_payout_for_duration(3000);
+		assert!(total_payout_2 > 10); // Test is meaningful if reward something
+		assert!(total_payout_2 != total_payout_0);
+		assert!(total_payout_2 != total_payout_1);
+
+		start_era(Staking::history_depth() + 1);
+
+		let active_era = Staking::active_era().unwrap().index;
+
+		// This is the latest planned era in staking, not the active era
+		let current_era = Staking::current_era().unwrap();
+
+		// Last kept is 1:
+		assert!(current_era - Staking::history_depth() == 1);
+		assert_noop!(
+			Staking::payout_validator(Origin::signed(10), 0),
+			// Fail: Era out of history
+			Error::<Test, _>::InvalidEraToReward
+		);
+		assert_ok!(Staking::payout_validator(Origin::signed(10), 1));
+		assert_ok!(Staking::payout_validator(Origin::signed(10), 2));
+		assert_noop!(
+			Staking::payout_validator(Origin::signed(10), 2),
+			// Fail: Double claim
+			Error::<Test, _>::InvalidEraToReward
+		);
+		assert_noop!(
+			Staking::payout_validator(Origin::signed(10), active_era),
+			// Fail: Era not finished yet
+			Error::<Test, _>::InvalidEraToReward
+		);
+
+		assert_noop!(
+			Staking::payout_nominator(Origin::signed(100), 0, vec![(11, 0)]),
+			// Fail: Era out of history
+			Error::<Test, _>::InvalidEraToReward
+		);
+		assert_ok!(Staking::payout_nominator(Origin::signed(100), 1, vec![(11, 0)]));
+		assert_ok!(Staking::payout_nominator(Origin::signed(100), 2, vec![(11, 0)]));
+		assert!(staking_events().contains(&RawEvent::PayoutStarted(2, 11, Some(0))));
+		assert_noop!(
+			Staking::payout_nominator(Origin::signed(100), 2, vec![(11, 0)]),
+			// Fail: Double claim
+			Error::<Test, _>::InvalidEraToReward
+		);
+		assert_noop!(
+			Staking::payout_nominator(Origin::signed(100), active_era, vec![(11, 0)]),
+			// Fail: Era not finished yet
+			Error::<Test, _>::InvalidEraToReward
+		);
+		
+		// Era 0 can't be rewarded anymore and current era can't be rewarded yet
+		// only era 1 and 2 can be rewarded.
+
+		assert_eq!(
+			Balances::total_balance(&10),
+			init_balance_10 + part_for_10 * (total_payout_1 + total_payout_2),
+		);
+		assert_eq!(
+			Balances::total_balance(&100),
+			init_balance_100 + part_for_100 * (total_payout_1 + total_payout_2),
+		);
+	});
+}
+
+#[test]
+fn zero_slash_keeps_nominators() {
+	ExtBuilder::default().build().execute_with(|| {
+		start_era(1);
+
+		assert_eq!(Balances::free_balance(11), 1000);
+
+		let exposure = Staking::eras_stakers(Staking::active_era().unwrap().index, 11);
+		assert_eq!(Balances::free_balance(101), 2000);
+
+		on_offence_now(
+			&[
+				OffenceDetails {
+					offender: (11, exposure.clone()),
+					reporters: vec![],
+				},
+			],
+			&[Perbill::from_percent(0)],
+		);
+
+		assert_eq!(Balances::free_balance(11), 1000);
+		assert_eq!(Balances::free_balance(101), 2000);
+
+		// This is the best way to check that the validator was chilled; `get` will
+		// return default value.
+		for (stash, _) in Staking::iter_validators() {
+			assert!(stash != 11);
+		}
+
+		let nominations = <Staking as Store>::Nominators::get(&101).unwrap();
+
+		// and make sure that the vote will not be ignored, because the slash was
+		// zero.
+		let last_slash = <Staking as Store>::SlashingSpans::get(&11).unwrap().last_nonzero_slash();
+		assert!(nominations.submitted_in >= last_slash);
+	});
+}
+
+#[test]
+fn six_session_delay() {
+	ExtBuilder::default().build().execute_with(|| {
+		use pallet_session::SessionManager;
+
+		let val_set = Session::validators();
+		let init_session = Session::current_index();
+		let init_active_era = Staking::active_era().unwrap().index;
+		// pallet-session is delaying session by one, thus the next session to plan is +2.
+		assert_eq!(<Staking as SessionManager<_>>::new_session(init_session + 2), None);
+		assert_eq!(<Staking as SessionManager<_>>::new_session(init_session + 3), Some(val_set.clone()));
+		assert_eq!(<Staking as SessionManager<_>>::new_session(init_session + 4), None);
+		assert_eq!(<Staking as SessionManager<_>>::new_session(init_session + 5), None);
+		assert_eq!(<Staking as SessionManager<_>>::new_session(init_session + 6), Some(val_set.clone()));
+
+		<Staking as SessionManager<_>>::end_session(init_session);
+		<Staking as SessionManager<_>>::start_session(init_session + 1);
+		assert_eq!(Staking::active_era().unwrap().index, init_active_era);
+		<Staking as SessionManager<_>>::end_session(init_session + 1);
+		<Staking as SessionManager<_>>::start_session(init_session + 2);
+		assert_eq!(Staking::active_era().unwrap().index, init_active_era);
+
+		// Reward current era
+		Staking::reward_by_ids(vec![(11, 1)]);
+
+		// New active era is triggered here.
+		<Staking as SessionManager<_>>::end_session(init_session + 2);
+		<Staking as SessionManager<_>>::start_session(init_session + 3);
+		assert_eq!(Staking::active_era().unwrap().index, init_active_era + 1);
+		<Staking as SessionManager<_>>::end_session(init_session + 3);
+		<Staking as SessionManager<_>>::start_session(init_session + 4);
+		assert_eq!(Staking::active_era().unwrap().index, init_active_era + 1);
+		<Staking as SessionManager<_>>::end_session(init_session + 4);
+		<Staking as SessionManager<_>>::start_session(init_session + 5);
+		assert_eq!(Staking::active_era().unwrap().index, init_active_era + 1);
+
+		// Reward current era
+		Staking::reward_by_ids(vec![(21, 2)]);
+
+		// New active era is triggered here.
+		<Staking as SessionManager<_>>::end_session(init_session + 5);
+		<Staking as SessionManager<_>>::start_session(init_session + 6);
+		assert_eq!(Staking::active_era().unwrap().index, init_active_era + 2);
+
+		// That reward are correct
+		assert_eq!(Staking::eras_reward_points(init_active_era).total, 1);
+		assert_eq!(Staking::eras_reward_points(init_active_era + 1).total, 2);
+	});
+}
+
+#[test]
+fn test_max_nominator_rewarded_per_validator_and_cant_steal_someone_else_reward() {
+	// Test:
+	// * If nominator nomination is below the $MaxNominatorRewardedPerValidator other nominator
+	//   then the nominator can't claim its reward
+	// * A nominator can't claim another nominator reward
+	ExtBuilder::default().build().execute_with(|| {
+		for i in 0..=<Test as Trait>::MaxNominatorRewardedPerValidator::get() {
+			let stash = 10_000 + i as u64;
+			let controller = 20_000 + i as u64;
+			let balance = 10_000 + i as u64;
+			Balances::make_free_balance_be(&stash, balance);
+			assert_ok!(
+				Staking::bond(
+					Origin::signed(stash),
+					controller,
+					balance,
+					RewardDestination::Stash
+				)
+			);
+			assert_ok!(Staking::nominate(Origin::signed(controller), vec![11]));
+		}
+		mock::start_era(1);
+
+		<Module<Test>>::reward_by_ids(vec![(11, 1)]);
+		// Compute total payout now for whole duration as other parameter won't change
+		let total_payout_0 = current_total_payout_for_duration(3 * 1000);
+		assert!(total_payout_0 > 100); // Test is meaningful if reward something
+
+		mock::start_era(2);
+		mock::make_all_reward_payment(1);
+
+		// nominator 10_000 can't get its reward because exposure is clipped. However it will try
+		// to query other people reward.
+		assert_ok!(Staking::payout_nominator(Origin::signed(20_000), 1, vec![(11, 0)]));
+
+		// Assert only nominators from 1 to Max are rewarded
+		for i in 0..=<Test as Trait>::MaxNominatorRewardedPerValidator::get() {
+			let stash = 10_000 + i as u64;
+			let balance = 10_000 + i as u64;
+			if stash == 10_000 {
+				assert!(Balances::free_balance(&stash) == balance);
+			} else {
+				assert!(Balances::free_balance(&stash) > balance);
+			}
+		}
+	});
+}
+
+#[test]
+fn only_max_nominator_exposed_per_validator_are_exposed() {
+	ExtBuilder::default().build().execute_with(|| {
+		let max_exposed = <Test as Trait>::MaxNominatorExposedPerValidator::get();
+		for i in 0..=max_exposed {
+			let stash = 10_000 + i as u64;
+			let controller = 20_000 + i as u64;
+			let balance = 10_000 + i as u64;
+			Balances::make_free_balance_be(&stash, balance);
+			assert_ok!(Staking::bond(Origin::signed(stash), controller, balance, RewardDestination::Stash));
+			assert_ok!(Staking::nominate(Origin::signed(controller), vec![11]));
+		}
+		mock::start_era(1);
+
+		// The smallest nominator is left out of the exposure, and of its total.
+		let exposure = Staking::eras_stakers(1, 11);
+		assert_eq!(exposure.others.len(), max_exposed as usize);
+		assert!(exposure.others.iter().all(|individual| individual.who != 10_000));
+		let total = exposure.others.iter().fold(exposure.own, |total, individual| total + individual.value);
+		assert_eq!(exposure.total, total);
+		assert_eq!(Staking::do_try_state(), Ok(()));
+	});
+}
+
+#[test]
+fn set_history_depth_works() {
+	ExtBuilder::default().build().execute_with(|| {
+		start_era(10);
+		Staking::set_history_depth(Origin::ROOT, 4).unwrap();
+		// Nothing is cleared before the next era.
+		assert_eq!(Staking::history_depth(), DEFAULT_HISTORY_DEPTH);
+		assert!(<Staking as Store>::ErasTotalStake::contains_key(10 - 5));
+
+		start_era(11);
+		assert_eq!(Staking::history_depth(), 4);
+		assert_eq!(Staking::pending_history_depth(), None);
+		assert!(<Staking as Store>::ErasTotalStake::contains_key(11 - 4));
+		assert!(!<Staking as Store>::ErasTotalStake::contains_key(11 - 5));
+
+		// A deeper history grows by one era at a time, as the eras before it are already cleared.
+		Staking::set_history_depth(Origin::ROOT, 6).unwrap();
+		start_era(12);
+		assert_eq!(Staking::history_depth(), 5);
+		assert!(<Staking as Store>::ErasTotalStake::contains_key(12 - 5));
+		start_era(13);
+		assert_eq!(Staking::history_depth(), 6);
+		assert_eq!(Staking::pending_history_depth(), None);
+		assert!(<Staking as Store>::ErasTotalStake::contains_key(13 - 6));
+		assert!(!<Staking as Store>::ErasTotalStake::contains_key(13 - 7));
+
+		// Setting the current depth back cancels the pending one.
+		Staking::set_history_depth(Origin::ROOT, 2).unwrap();
+		Staking::set_history_depth(Origin::ROOT, 6).unwrap();
+		assert_eq!(Staking::pending_history_depth(), None);
+		start_era(14);
+		assert_eq!(Staking::history_depth(), 6);
+		assert!(<Staking as Store>::ErasTotalStake::contains_key(14 - 6));
+	});
+}
+
+#[test]
+fn shallower_history_depth_is_delayed() {
+	ExtBuilder::default().history_shrink_delay(2).build().execute_with(|| {
+		<Module<Test>>::reward_by_ids(vec![(11, 1)]);
+		start_era(5);
+		Staking::set_history_depth(Origin::ROOT, 1).unwrap();
+		assert_eq!(Staking::pending_history_depth(), Some((1, 8)));
+
+		// The rewards of the eras leaving the history can still be claimed meanwhile.
+		start_era(7);
+		assert_eq!(Staking::history_depth(), DEFAULT_HISTORY_DEPTH);
+		assert_ok!(Staking::payout_validator(Origin::signed(10), 0));
+
+		start_era(8);
+		assert_eq!(Staking::history_depth(), 1);
+		assert_eq!(Staking::pending_history_depth(), None);
+		assert!(!<Staking as Store>::ErasTotalStake::contains_key(6));
+		assert!(<Staking as Store>::ErasTotalStake::contains_key(7));
+	});
+}
+
+#[test]
+fn era_information_is_pruned_over_blocks() {
+	ExtBuilder::default().build().execute_with(|| {
+		start_era(4);
+		Staking::set_history_depth(Origin::ROOT, 1).unwrap();
+		// Era 5 is planned at the start of session 14, clearing the eras before era 4.
+		start_session(14);
+
+		// The totals are removed at once, the information per validator is only queued.
+		assert!(!<Staking as Store>::ErasTotalStake::contains_key(0));
+		assert_eq!(Staking::eras_to_prune(), vec![0, 1, 2, 3]);
+		assert!(<Staking as Store>::ErasStakersOverview::contains_key(0, 11));
+
+		// Each block removes at most `MaxPrunedKeysPerBlock` keys.
+		Staking::on_finalize(System::block_number());
+		assert!(!Staking::eras_to_prune().is_empty());
+		assert!(<Staking as Store>::ErasValidatorPrefs::contains_key(3, 11));
+
+		for _ in 0..10 {
+			Staking::on_finalize(System::block_number());
+		}
+		assert!(Staking::eras_to_prune().is_empty());
+		for era in 0..4 {
+			assert!(!<Staking as Store>::ErasStakersOverview::contains_key(era, 11));
+			assert!(!<Staking as Store>::ErasStakersCompact::contains_key(era, (11, 0)));
+			assert!(!<Staking as Store>::ErasStakersAccounts::contains_key(era, 0));
+			assert!(!<Staking as Store>::ErasValidatorPrefs::contains_key(era, 11));
+		}
+		assert!(<Staking as Store>::ErasStakersOverview::contains_key(4, 11));
+		assert!(<Staking as Store>::ErasValidatorPrefs::contains_key(5, 11));
+	});
+}
+
+#[test]
+fn era_start_and_duration_are_recorded() {
+	ExtBuilder::default().build().execute_with(|| {
+		start_era(2);
+
+		// Each session lasts one block of 1000 ms.
+		assert_eq!(Staking::eras_start(0), Some(0));
+		assert_eq!(Staking::eras_duration(0), Some(3000));
+		assert_eq!(Staking::eras_start(1), Some(3000));
+		assert_eq!(Staking::eras_duration(1), Some(3000));
+		// The active era has not started in a block yet.
+		assert_eq!(Staking::eras_start(2), None);
+		assert_eq!(Staking::eras_duration(2), None);
+
+		Staking::set_history_depth(Origin::ROOT, 2).unwrap();
+		// Era 3 is planned at the start of session 8, clearing era 0.
+		start_session(8);
+		assert_eq!(Staking::eras_start(0), None);
+		assert_eq!(Staking::eras_duration(0), None);
+		assert_eq!(Staking::eras_start(1), Some(3000));
+	});
+}
+
+#[test]
+fn ledger_arithmetic_preserves_invariants() {
+	use rand::{Rng, SeedableRng, rngs::StdRng};
+
+	fn unlocking_sum(ledger: &StakingLedger<AccountId, Balance>) -> Balance {
+		ledger.unlocking.iter().map(|chunk| chunk.value).sum()
+	}
+
+	let mut rng = StdRng::seed_from_u64(42);
+	for _ in 0..1_000 {
+		let minimum_balance = rng.gen_range(0, 10);
+		let mut era = 0;
+		let unlocking = (0..rng.gen_range(0, MaxUnlockingChunks::get() as usize))
+			.map(|_| {
+				era += rng.gen_range(0, 3);
+				UnlockChunk { value: rng.gen_range(1, 1_000), era }
+			})
+			.collect::<Vec<_>>();
+		let active = rng.gen_range(0, 10_000);
+		let mut ledger = StakingLedger {
+			stash: 11,
+			total: active + unlocking.iter().map(|chunk| chunk.value).sum::<Balance>(),
+			active,
+			unlocking,
+			last_reward: None,
+		};
+
+		for _ in 0..20 {
+			let pre_total = ledger.total;
+			match rng.gen_range(0, 3) {
+				0 => {
+					let value = rng.gen_range(0, pre_total + 10);
+					let pre_active = ledger.active;
+					let slashed = ledger.slash(value, minimum_balance);
+					assert_eq!(slashed, pre_total - ledger.total);
+					assert!(slashed >= value.min(pre_total));
+					// Drained chunks are removed and slashed funds never leave dust.
+					assert!(ledger.unlocking.iter().all(|chunk| !chunk.value.is_zero()));
+					if value > 0 && pre_active > 0 {
+						assert!(ledger.active.is_zero() || ledger.active > minimum_balance);
+					}
+				},
+				1 => {
+					let pre_unlocking = unlocking_sum(&ledger);
+					let value = rng.gen_range(0, pre_unlocking + 10);
+					let pre_active = ledger.active;
+					ledger = ledger.rebond(value);
+					assert_eq!(ledger.active - pre_active, value.min(pre_unlocking));
+				},
+				_ => {
+					let current_era = rng.gen_range(0, era + 2);
+					ledger = ledger.consolidate_unlocked(current_era);
+					assert!(ledger.unlocking.iter().all(|chunk| chunk.era > current_era));
+				},
+			}
+
+			assert!(ledger.total <= pre_total);
+			assert_eq!(ledger.total, ledger.active + unlocking_sum(&ledger));
+			assert!(ledger.unlocking.windows(2).all(|w| w[0].era <= w[1].era));
+		}
+	}
+}
+
+#[test]
+fn payout_stakers_all_is_weighed_by_its_eras() {
+	use frame_support::weights::GetDispatchInfo;
+
+	let weight = |from_era, to_era, max_weight| {
+		Call::<Test>::payout_stakers_all(11, from_era, to_era, max_weight)
+			.get_dispatch_info()
+			.weight
+	};
+	let eras_weight = |eras| <() as WeightInfo>::payout_stakers_all(eras);
+
+	ExtBuilder::default().build().execute_with(|| {
+		assert_eq!(weight(2, 4, Weight::max_value()), eras_weight(3));
+		assert_eq!(weight(4, 4, Weight::max_value()), eras_weight(1));
+		// An empty range is weighed as a single era, a large one is capped by the weight limit.
+		assert_eq!(weight(4, 2, Weight::max_value()), eras_weight(1));
+		assert_eq!(weight(0, EraIndex::max_value(), eras_weight(5)), eras_weight(5));
+		assert_eq!(weight(0, EraIndex::max_value(), eras_weight(5) + 1), eras_weight(5));
+		assert!(weight(2, 4, Weight::max_value()) < weight(0, 100, Weight::max_value()));
+		// No more eras than the history depth are weighed.
+		assert_eq!(
+			weight(0, EraIndex::max_value(), Weight::max_value()),
+			eras_weight(Staking::history_depth()),
+		);
+		assert_eq!(eras_weight(EraIndex::max_value()), Weight::max_value());
+	})
+}
+
+#[test]
+fn testing_utils_set_up_stakers_eras_and_offences() {
+	ExtBuilder::default().build().execute_with(|| {
+		let validators = testing_utils::create_validators::<Test>(2, 1000).unwrap();
+		let nominators =
+			testing_utils::create_nominators::<Test>(3, &validators[..1], 1000).unwrap();
+		assert!(validators.iter().all(|stash| <Validators<Test>>::contains_key(stash)));
+		assert_eq!(Staking::nominators_for(&validators[0]).len(), 3);
+		assert_eq!(Staking::ledger(&nominators[0].1).unwrap().total, 100);
+
+		<ValidatorCount>::put(5);
+		let session = Session::current_index();
+		assert_eq!(testing_utils::start_active_era::<Test>(session), session + 2);
+		assert_eq!(Staking::active_era().unwrap().index, 1);
+		let exposure = Staking::eras_stakers(1, &validators[0]);
+		assert_eq!(exposure.others.len(), 3);
+
+		testing_utils::report_offence::<Test>(
+			&validators[..1],
+			Perbill::from_percent(10),
+			1,
+			OFFENCE_KIND,
+		).unwrap();
+		assert_eq!(Balances::free_balance(&validators[0]), 1000 - exposure.own / 10);
+		assert_eq!(Balances::free_balance(&validators[1]), 1000);
+		assert!(testing_utils::report_offence::<Test>(&validators, Perbill::zero(), 5, OFFENCE_KIND).is_err());
+	});
+}
+
+#[test]
+fn agent_stakes_the_funds_delegated_to_it() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(Staking::bond_agent(Origin::signed(51), 50, RewardDestination::Stash));
+		assert!(Staking::is_virtual_staker(51));
+		assert_noop!(Staking::delegate(Origin::signed(3), 11, 100), Error::<Test, _>::NotAgent);
+		assert_noop!(Staking::delegate(Origin::signed(51), 51, 100), Error::<Test, _>::IsAgent);
+
+		assert_ok!(Staking::delegate(Origin::signed(3), 51, 300));
+		assert_ok!(Staking::delegate(Origin::signed(4), 51, 200));
+		assert_ok!(Staking::delegate(Origin::signed(4), 51, 200));
+		assert_eq!(Staking::delegators(51), vec![(3, 300), (4, 400)]);
+		assert_eq!(Balances::reserved_balance(3), 300);
+		assert_eq!(Balances::reserved_balance(4), 400);
+		assert_eq!(Staking::ledger(&50).unwrap().total, 700);
+		assert_eq!(Staking::ledger(&50).unwrap().active, 700);
+		assert_eq!(Staking::ledger_backing(&51), 700);
+
+		// the agent cannot bond funds of its own.
+		assert_noop!(Staking::bond_extra(Origin::signed(51), 10), Error::<Test, _>::IsAgent);
+		assert_noop!(
+			Staking::bond(Origin::signed(51), 52, 10, RewardDestination::Stash),
+			Error::<Test, _>::AlreadyBonded,
+		);
+
+		// nothing can be taken back while bonded.
+		assert_ok!(Staking::undelegate(Origin::signed(4), 51, 400));
+		assert_eq!(Balances::reserved_balance(4), 400);
+
+		assert_ok!(Staking::unbond(Origin::signed(50), 500));
+		start_era(1 + BondingDuration::get());
+		assert_ok!(Staking::withdraw_unbonded(Origin::signed(50), 0));
+		assert_eq!(Staking::ledger(&50).unwrap().total, 200);
+
+		// each delegator takes back up to what it delegated.
+		assert_noop!(Staking::undelegate(Origin::signed(1), 51, 10), Error::<Test, _>::NotDelegator);
+		assert_ok!(Staking::undelegate(Origin::signed(4), 51, 1_000));
+		assert_eq!(Balances::reserved_balance(4), 0);
+		assert_ok!(Staking::undelegate(Origin::signed(3), 51, 300));
+		assert_eq!(Balances::reserved_balance(3), 200);
+		assert_eq!(Staking::delegators(51), vec![(3, 200)]);
+	});
+}
+
+#[test]
+fn agent_slash_is_apportioned_between_delegators() {
+	ExtBuilder::default().validator_count(3).build().execute_with(|| {
+		assert_ok!(Staking::bond_agent(Origin::signed(51), 50, RewardDestination::Stash));
+		assert_ok!(Staking::delegate(Origin::signed(3), 51, 300));
+		assert_ok!(Staking::delegate(Origin::signed(4), 51, 400));
+		set_session_keys(50);
+		assert_ok!(Staking::validate(Origin::signed(50), ValidatorPrefs::default()));
+
+		start_era(1);
+		assert!(Session::validators().contains(&51));
+
+		on_offence_now(
+			&[OffenceDetails {
+				offender: (51, Staking::eras_stakers(Staking::active_era().unwrap().index, 51)),
+				reporters: vec![],
+			}],
+			&[Perbill::from_percent(10)],
+		);
+
+		assert_eq!(Staking::ledger(&50).unwrap().total, 630);
+		assert_eq!(Staking::delegators(51), vec![(3, 270), (4, 360)]);
+		assert_eq!(Balances::reserved_balance(3), 270);
+		assert_eq!(Balances::reserved_balance(4), 360);
+		assert_eq!(Balances::free_balance(3), 0);
+	});
+}
+
+#[test]
+fn staking_updates_are_notified() {
+	ExtBuilder::default().build().execute_with(|| {
+		let _ = staking_updates();
+		let _ = Balances::make_free_balance_be(&61, 1000);
+
+		assert_ok!(Staking::bond(Origin::signed(61), 60, 500, RewardDestination::Controller));
+		set_session_keys(60);
+		assert_ok!(Staking::validate(Origin::signed(60), ValidatorPrefs::default()));
+		assert_ok!(Staking::validate(Origin::signed(60), ValidatorPrefs::default()));
+		assert_ok!(Staking::nominate(Origin::signed(60), vec![11]));
+		assert_ok!(Staking::nominate(Origin::signed(60), vec![11, 21]));
+		assert_ok!(Staking::bond_extra(Origin::signed(61), 100));
+		assert_ok!(Staking::chill(Origin::signed(60)));
+		assert_ok!(Staking::unbond(Origin::signed(60), 600));
+		assert_eq!(staking_updates(), vec![
+			StakingUpdate::Stake(61, 0, 500),
+			StakingUpdate::Validator(61, false),
+			StakingUpdate::Validator(61, true),
+			StakingUpdate::Nominator(61, None, vec![11]),
+			StakingUpdate::Nominator(61, Some(vec![11]), vec![11, 21]),
+			StakingUpdate::Stake(61, 500, 600),
+			StakingUpdate::Chill(61),
+			StakingUpdate::Stake(61, 600, 0),
+		]);
+
+		start_era(1 + BondingDuration::get());
+		let _ = staking_updates();
+		assert_ok!(Staking::withdraw_unbonded(Origin::signed(60), 0));
+		assert_eq!(staking_updates(), vec![StakingUpdate::Unstake(61)]);
+	});
+}
+
+#[test]
+fn candidates_must_pass_the_filter() {
+	ExtBuilder::default().build().execute_with(|| {
+		disallow_candidate(21);
+		assert_noop!(
+			Staking::validate(Origin::signed(20), ValidatorPrefs::default()),
+			Error::<Test, _>::CandidateNotAllowed,
+		);
+
+		// 21 declared the intention to validate at genesis, but is no longer elected.
+		start_era(1);
+		assert!(<Validators<Test>>::contains_key(21));
+		assert_eq_uvec!(validator_controllers(), vec![10, 30]);
+	});
+}
+
+#[test]
+fn denied_stashes_cannot_stake() {
+	ExtBuilder::default().build().execute_with(|| {
+		deny_staker(61);
+		let _ = Balances::make_free_balance_be(&61, 1000);
+		assert_noop!(
+			Staking::bond(Origin::signed(61), 60, 500, RewardDestination::Controller),
+			Error::<Test, _>::StakingNotAllowed,
+		);
+		assert_noop!(
+			Staking::bond_agent(Origin::signed(61), 60, RewardDestination::Stash),
+			Error::<Test, _>::StakingNotAllowed,
+		);
+		assert_ok!(Balances::reserve(&61, 500));
+		assert_noop!(
+			Staking::virtual_bond(&61, &60, 500, RewardDestination::Stash),
+			Error::<Test, _>::StakingNotAllowed,
+		);
+
+		// Neither a denied delegator nor a denied agent can be delegated to.
+		assert_ok!(Staking::bond_agent(Origin::signed(51), 50, RewardDestination::Stash));
+		deny_staker(3);
+		assert_noop!(
+			Staking::delegate(Origin::signed(3), 51, 300),
+			Error::<Test, _>::StakingNotAllowed,
+		);
+		assert_ok!(Staking::delegate(Origin::signed(4), 51, 200));
+		deny_staker(51);
+		assert_noop!(
+			Staking::delegate(Origin::signed(4), 51, 200),
+			Error::<Test, _>::StakingNotAllowed,
+		);
+
+		// Stashes bonded before being denied can neither bond more nor nominate.
+		deny_staker(101);
+		let _ = Balances::make_free_balance_be(&101, 3000);
+		assert_noop!(
+			Staking::bond_extra(Origin::signed(101), 100),
+			Error::<Test, _>::StakingNotAllowed,
+		);
+		assert_noop!(
+			Staking::nominate(Origin::signed(100), vec![11]),
+			Error::<Test, _>::StakingNotAllowed,
+		);
+		assert_ok!(Staking::unbond(Origin::signed(100), 100));
+	});
+}
+
+#[test]
+fn validators_must_set_session_keys() {
+	ExtBuilder::default().build().execute_with(|| {
+		let _ = Balances::make_free_balance_be(&61, 1000);
+		assert_ok!(Staking::bond(Origin::signed(61), 60, 500, RewardDestination::Controller));
+		assert_noop!(
+			Staking::validate(Origin::signed(60), ValidatorPrefs::default()),
+			Error::<Test, _>::NoSessionKeys,
+		);
+
+		// keys already owned by another validator are refused.
+		assert_noop!(
+			Staking::validate_with_keys(
+				Origin::signed(60),
+				ValidatorPrefs::default(),
+				UintAuthorityId(11),
+				vec![],
+			),
+			pallet_session::Error::<Test, _>::DuplicatedKey,
+		);
+
+		assert_ok!(Staking::validate_with_keys(
+			Origin::signed(60),
+			ValidatorPrefs::default(),
+			UintAuthorityId(60),
+			vec![],
+		));
+		assert!(<Validators<Test>>::contains_key(61));
+		assert_eq!(Session::load_keys(&61), Some(UintAuthorityId(60)));
+	});
+}
+
+#[test]
+fn parameters_are_set_by_governance() {
+	ExtBuilder::default().build().execute_with(|| {
+		let parameters = StakingParameters {
+			slash_defer_duration: Some(2),
+			min_validator_bond: Some(600),
+			min_nominator_bond: Some(300),
+			min_commission: Some(Perbill::from_percent(5)),
+			max_unresponsive_sessions: Some(2),
+			min_active_bond: None,
+		};
+		assert_noop!(Staking::set_parameters(Origin::signed(10), parameters.clone()), BadOrigin);
+		assert_noop!(
+			Staking::set_parameters(Origin::ROOT, StakingParameters {
+				slash_defer_duration: Some(BondingDuration::get()),
+				..Default::default()
+			}),
+			Error::<Test, _>::InvalidParameters,
+		);
+
+		assert_eq!(Staking::slash_defer_duration(), 0);
+		assert_ok!(Staking::set_parameters(Origin::ROOT, parameters));
+		assert_eq!(Staking::slash_defer_duration(), 2);
+
+		let _ = Balances::make_free_balance_be(&61, 1000);
+		assert_ok!(Staking::bond(Origin::signed(61), 60, 500, RewardDestination::Controller));
+		set_session_keys(60);
+		let prefs = ValidatorPrefs { commission: Perbill::from_percent(5), .. Default::default() };
+		assert_noop!(Staking::validate(Origin::signed(60), prefs.clone()), Error::<Test, _>::InsufficientBond);
+		assert_ok!(Staking::bond_extra(Origin::signed(61), 100));
+		assert_noop!(
+			Staking::validate(Origin::signed(60), ValidatorPrefs::default()),
+			Error::<Test, _>::CommissionTooLow,
+		);
+		assert_ok!(Staking::validate(Origin::signed(60), prefs));
+
+		// Unbonding below the minimum validator bond chills the stash.
+		assert_ok!(Staking::unbond(Origin::signed(60), 400));
+		assert!(!<Validators<Test>>::contains_key(61));
+		assert_eq!(
+			staking_events().last(),
+			Some(&RawEvent::Chilled(61, ChillReason::BelowMinimumBond)),
+		);
+		assert_noop!(Staking::nominate(Origin::signed(60), vec![11]), Error::<Test, _>::InsufficientBond);
+	});
+}
+
+#[test]
+fn minimum_active_bond_is_set_by_governance() {
+	ExtBuilder::default().existential_deposit(10).build().execute_with(|| {
+		assert_eq!(Staking::minimum_active_bond(), 10);
+		assert_noop!(
+			Staking::set_parameters(Origin::ROOT, StakingParameters {
+				min_active_bond: Some(9),
+				..Default::default()
+			}),
+			Error::<Test, _>::InvalidParameters,
+		);
+		assert_ok!(Staking::set_parameters(Origin::ROOT, StakingParameters {
+			min_active_bond: Some(100),
+			..Default::default()
+		}));
+		assert_eq!(Staking::minimum_active_bond(), 100);
+
+		let _ = Balances::make_free_balance_be(&61, 1000);
+		assert_noop!(
+			Staking::bond(Origin::signed(61), 60, 99, RewardDestination::Controller),
+			Error::<Test, _>::InsufficientValue,
+		);
+		assert_ok!(Staking::bond(Origin::signed(61), 60, 150, RewardDestination::Controller));
+
+		// Unbonding below the minimum active bond unbonds all of it.
+		assert_ok!(Staking::unbond(Origin::signed(60), 60));
+		assert_eq!(Staking::ledger(&60).unwrap().active, 0);
+	});
+}
+
+#[test]
+fn calls_are_categorized() {
+	assert!(Call::<Test>::bond(1, 10, RewardDestination::Staked).is_funds_moving());
+	assert!(Call::<Test>::withdraw_unbonded(0).is_funds_moving());
+	assert_eq!(Call::<Test>::set_payee(RewardDestination::Stash).kind(), CallKind::FundsMoving);
+	assert_eq!(Call::<Test>::nominate(vec![11]).kind(), CallKind::NonTransfer);
+	assert_eq!(Call::<Test>::chill().kind(), CallKind::NonTransfer);
+	assert_eq!(Call::<Test>::payout_stakers_all(11, 0, 1, 0).kind(), CallKind::NonTransfer);
+	assert_eq!(Call::<Test>::force_new_era().kind(), CallKind::Governance);
+	assert!(!Call::<Test>::set_validator_count(5).is_funds_moving());
+}
+
+#[test]
+fn slashes_burn_the_vested_part_of_a_bond_first() {
+	ExtBuilder::default().build().execute_with(|| {
+		set_vesting(11, 300);
+		set_vesting(21, 5000);
+		assert_eq!(Staking::vested_bond(&11), 300);
+		// only the bonded funds count.
+		assert_eq!(Staking::vested_bond(&21), Staking::ledger(&20).unwrap().total);
+
+		start_era(1);
+		on_offence_now(
+			&[OffenceDetails {
+				offender: (11, Staking::eras_stakers(Staking::active_era().unwrap().index, 11)),
+				reporters: vec![],
+			}],
+			&[Perbill::from_percent(10)],
+		);
+
+		assert_eq!(Staking::ledger(&10).unwrap().total, 900);
+		assert_eq!(<Test as Trait>::VestedFunds::vesting_balance(&11), 200);
+		assert_eq!(Staking::vested_bond(&11), 200);
+	});
+}
+
+#[test]
+fn governance_can_report_an_offence() {
+	ExtBuilder::default().build().execute_with(|| {
+		start_era(1);
+		let session = Session::current_index();
+		assert_noop!(
+			Staking::report_offence_unchecked(Origin::signed(10), vec![11], Perbill::from_percent(10), session),
+			BadOrigin,
+		);
+		assert_noop!(
+			Staking::report_offence_unchecked(Origin::ROOT, vec![11, 31], Perbill::from_percent(10), session),
+			Error::<Test, _>::OffenderNotElected,
+		);
+
+		assert_ok!(
+			Staking::report_offence_unchecked(Origin::ROOT, vec![11], Perbill::from_percent(10), session)
+		);
+		assert_eq!(Staking::ledger(&10).unwrap().total, 900);
+		assert_eq!(Staking::force_era(), Forcing::ForceNew);
+		assert!(!<Validators<Test>>::contains_key(11));
+	});
+}
+
+#[test]
+fn persistently_unresponsive_validators_are_chilled() {
+	ExtBuilder::default().build().execute_with(|| {
+		use sp_staking::OnUnresponsiveness;
+		assert_eq!(Staking::max_unresponsive_sessions(), 3);
+
+		Staking::on_unresponsiveness(1, &[11, 21]);
+		Staking::on_unresponsiveness(2, &[11]);
+		assert_eq!(Staking::unresponsive_sessions(11), Some((2, 2)));
+		// 11 missed session 3: its count starts over.
+		Staking::on_unresponsiveness(4, &[11, 21]);
+		assert_eq!(Staking::unresponsive_sessions(11), Some((4, 1)));
+		Staking::on_unresponsiveness(5, &[21]);
+		Staking::on_unresponsiveness(6, &[21]);
+		assert!(<Validators<Test>>::contains_key(11));
+		assert!(!<Validators<Test>>::contains_key(21));
+		assert_eq!(Staking::unresponsive_sessions(21), None);
+
+		Staking::on_unresponsiveness(7, &[11]);
+		Staking::on_unresponsiveness(8, &[11]);
+		assert!(<Validators<Test>>::contains_key(11));
+		Staking::on_unresponsiveness(9, &[11]);
+		assert!(!<Validators<Test>>::contains_key(11));
+	});
+}
+
+#[test]
+fn governance_can_install_the_next_validator_set() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_noop!(Staking::set_next_validators(Origin::signed(10), vec![11, 41]), BadOrigin);
+		assert_noop!(
+			Staking::set_next_validators(Origin::ROOT, vec![11, 11]),
+			Error::<Test, _>::InvalidValidatorSet,
+		);
+		assert_noop!(Staking::set_next_validators(Origin::ROOT, vec![11, 1]), Error::<Test, _>::NotStash);
+
+		set_session_keys(40);
+		assert_ok!(Staking::set_next_validators(Origin::ROOT, vec![11, 41]));
+		start_era(1);
+		assert_eq_uvec!(Session::validators(), vec![11, 41]);
+		assert_eq!(
+			Staking::eras_stakers(1, 11),
+			Exposure { own: 1000, total: 1000, others: vec![] },
+		);
+		assert_eq!(Staking::next_validators(), None);
+
+		// the set is used once, the election runs again for the next era.
+		start_era(2);
+		assert_eq_uvec!(Session::validators(), vec![11, 21]);
+	});
+}
+
+#[test]
+fn eras_last_the_minimum_era_duration() {
+	// sessions last one second in the mock: the era needs twice as many of them.
+	ExtBuilder::default().minimum_era_duration(5_000).build().execute_with(|| {
+		start_session(5);
+		assert_eq!(Staking::active_era().unwrap().index, 0);
+
+		start_session(6);
+		assert_eq!(Staking::active_era().unwrap().index, 1);
+		assert_eq!(Staking::eras_start_session_index(1), Some(6));
+	});
+}
+
+#[test]
+fn keeper_bonds_the_funds_it_holds_and_slashes_them() {
+	ExtBuilder::default().build().execute_with(|| {
+		let _ = Balances::make_free_balance_be(&61, 1000);
+		// the keeper holds 500 of the funds of 61.
+		assert_ok!(Balances::reserve(&61, 500));
+		assert_noop!(
+			Staking::virtual_bond(&61, &60, 600, RewardDestination::Stash),
+			Error::<Test, _>::InsufficientBacking,
+		);
+		assert_ok!(Staking::virtual_bond(&61, &60, 400, RewardDestination::Stash));
+		assert!(Staking::is_kept_staker(&61));
+		// nothing is locked.
+		assert_ok!(Balances::transfer(Origin::signed(61), 1, 500));
+		assert_noop!(Staking::bond_extra(Origin::signed(61), 100), Error::<Test, _>::IsAgent);
+		assert_noop!(Staking::virtual_bond_extra(&61, 200), Error::<Test, _>::InsufficientBacking);
+		assert_ok!(Staking::virtual_bond_extra(&61, 100));
+		assert_eq!(Staking::ledger(&60).unwrap().total, 500);
+
+		assert_ok!(Staking::nominate(Origin::signed(60), vec![11]));
+		start_era(1);
+		let exposure = Staking::eras_stakers(Staking::active_era().unwrap().index, 11);
+		assert!(exposure.others.iter().any(|i| i.who == 61 && i.value == 500));
+		on_offence_now(
+			&[OffenceDetails { offender: (11, exposure), reporters: vec![] }],
+			&[Perbill::from_percent(10)],
+		);
+
+		assert_eq!(Staking::ledger(&60).unwrap().total, 450);
+		assert_eq!(Balances::reserved_balance(&61), 450);
+	});
+}
+
+#[test]
+fn chilling_deposits_an_event_with_the_reason() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(Staking::chill(Origin::signed(100)));
+		assert_eq!(
+			staking_events().last(),
+			Some(&RawEvent::Chilled(101, ChillReason::Requested)),
+		);
+		// already chilled: no event.
+		let events = staking_events().len();
+		assert_ok!(Staking::chill(Origin::signed(100)));
+		assert_eq!(staking_events().len(), events);
+
+		on_offence_now(
+			&[OffenceDetails {
+				offender: (11, Staking::eras_stakers(Staking::active_era().unwrap().index, 11)),
+				reporters: vec![],
+			}],
+			&[Perbill::from_percent(10)],
+		);
+		assert!(staking_events().contains(&RawEvent::Chilled(11, ChillReason::Slashed)));
+
+		assert_ok!(Staking::force_unstake(Origin::ROOT, 21, 0));
+		assert!(staking_events().contains(&RawEvent::Chilled(21, ChillReason::Unstaked)));
+	});
+}
+
+#[test]
+fn changing_role_deposits_a_chilled_event() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(Staking::nominate(Origin::signed(30), vec![11]));
+		assert!(staking_events().contains(&RawEvent::Chilled(31, ChillReason::RoleChanged)));
+		assert!(!<Validators<Test>>::contains_key(31));
+
+		set_session_keys(100);
+		assert_ok!(Staking::validate(Origin::signed(100), ValidatorPrefs::default()));
+		assert!(staking_events().contains(&RawEvent::Chilled(101, ChillReason::RoleChanged)));
+		assert!(Staking::nominators(101).is_none());
+
+		// Nominating again as a nominator is no change of role.
+		let events = staking_events().len();
+		assert_ok!(Staking::nominate(Origin::signed(30), vec![21]));
+		assert_eq!(staking_events().len(), events);
+	});
+}
+
+#[test]
+fn validating_deposits_an_event_with_the_prefs() {
+	ExtBuilder::default().build().execute_with(|| {
+		let prefs = ValidatorPrefs { commission: Perbill::from_percent(5), .. Default::default() };
+		assert_ok!(Staking::validate(Origin::signed(10), prefs.clone()));
+		assert_eq!(staking_events().last(), Some(&RawEvent::ValidatorPrefsSet(11, prefs.clone())));
+
+		// setting the same preferences again is reported as well.
+		assert_ok!(Staking::validate(Origin::signed(10), prefs.clone()));
+		let event = RawEvent::ValidatorPrefsSet(11, prefs);
+		assert_eq!(staking_events().iter().filter(|e| **e == event).count(), 2);
+	});
+}
+
+#[test]
+fn election_failure_deposits_an_event_with_the_reason() {
+	ExtBuilder::default().build().execute_with(|| {
+		start_era(1);
+		assert!(staking_events().contains(&RawEvent::StakingElection(1, ElectionCompute::OnChain)));
+
+		<MinimumValidatorCount>::put(5);
+		start_session(6);
+		assert!(staking_events().contains(
+			&RawEvent::StakingElectionFailed(2, ElectionFailure::NotEnoughCandidates),
+		));
+		assert_eq_uvec!(Session::validators(), vec![11, 21]);
+	});
+}
+
+#[test]
+fn era_forcing_changes_deposit_events() {
+	ExtBuilder::default().build().execute_with(|| {
+		assert_ok!(Staking::force_no_eras(Origin::ROOT));
+		assert_eq!(staking_events().last(), Some(&RawEvent::ForceEra(Forcing::ForceNone)));
+		assert_ok!(Staking::force_new_era_always(Origin::ROOT));
+		assert_eq!(staking_events().last(), Some(&RawEvent::ForceEra(Forcing::ForceAlways)));
+
+		// no change, no event.
+		let events = staking_events().len();
+		assert_ok!(Staking::force_new_era_always(Origin::ROOT));
+		assert_eq!(staking_events().len(), events);
+
+		assert_ok!(Staking::force_new_era(Origin::ROOT));
+		advance_session();
+		assert_eq!(Staking::force_era(), Forcing::NotForcing);
+		assert!(staking_events().contains(&RawEvent::ForceEra(Forcing::NotForcing)));
+	});
+}
+
+fn build_genesis(stakers: Vec<(AccountId, AccountId, Balance, StakerStatus<AccountId>)>) {
+	let mut storage = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	let _ = pallet_balances::GenesisConfig::<Test> {
+		balances: vec![(11, 1000), (21, 1000), (31, 1000)],
+	}.assimilate_storage(&mut storage);
+	let _ = GenesisConfig::<Test> { stakers, validator_count: 4, .. Default::default() }
+		.assimilate_storage(&mut storage);
+}
+
+#[test]
+#[should_panic(expected = "Controller is used by several stakers.")]
+fn genesis_stakers_cannot_share_a_controller() {
+	build_genesis(vec![
+		(11, 10, 1000, StakerStatus::Validator),
+		(21, 10, 1000, StakerStatus::Validator),
+	]);
+}
+
+#[test]
+#[should_panic(expected = "Invulnerable is not a validator at genesis.")]
+fn genesis_invulnerables_must_be_genesis_validators() {
+	let mut storage = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	let _ = pallet_balances::GenesisConfig::<Test> {
+		balances: vec![(11, 1000), (21, 1000)],
+	}.assimilate_storage(&mut storage);
+	let _ = GenesisConfig::<Test> {
+		stakers: vec![
+			(11, 10, 1000, StakerStatus::Validator),
+			(21, 20, 1000, StakerStatus::Idle),
+		],
+		validator_count: 4,
+		invulnerables: vec![11, 21],
+		.. Default::default()
+	}.assimilate_storage(&mut storage);
+}
+
+#[test]
+#[should_panic(expected = "Validator count is below the minimum validator count.")]
+fn genesis_validator_count_cannot_be_below_the_minimum() {
+	let mut storage = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	let _ = GenesisConfig::<Test> {
+		validator_count: 2,
+		minimum_validator_count: 3,
+		.. Default::default()
+	}.assimilate_storage(&mut storage);
+}
+
+#[test]
+#[should_panic(expected = "Stash nominates an account which is not a validator at genesis.")]
+fn genesis_nominators_must_nominate_genesis_validators() {
+	build_genesis(vec![
+		(11, 10, 1000, StakerStatus::Validator),
+		(21, 20, 1000, StakerStatus::Idle),
+		(31, 30, 1000, StakerStatus::Nominator(vec![11, 21])),
+	]);
+}
+
+#[test]
+fn dev_stakers_are_generated_at_genesis() {
+	ExtBuilder::default().set_associated_consts();
+	let mut storage = frame_system::GenesisConfig::default().build_storage::<Test>().unwrap();
+	let _ = pallet_balances::GenesisConfig::<Test> {
+		balances: vec![(11, 1000), (21, 1000)],
+	}.assimilate_storage(&mut storage);
+	let _ = GenesisConfig::<Test> {
+		stakers: vec![
+			(11, 10, 1000, StakerStatus::Validator),
+			(21, 20, 1000, StakerStatus::Validator),
+		],
+		dev_stakers: Some((4, 10)),
+		validator_count: 2,
+		.. Default::default()
+	}.assimilate_storage(&mut storage);
+	sp_io::TestExternalities::from(storage).execute_with(|| {
+		// the generated validators are candidates bonding the lowest validator stake.
+		assert_eq!(<Staking as Store>::Bonded::iter().count(), 16);
+		assert_eq!(Staking::counter_for_validators(), 6);
+		assert_eq!(Staking::counter_for_nominators(), 10);
+		assert!(Staking::iter_nominators().all(|(_, nominations)|
+			nominations.targets.iter().all(|target| *target == 11 || *target == 21)
+		));
+		// each generated staker bonds funds minted for it.
+		let validator_stake = Staking::minimum_active_bond();
+		assert_eq!(Balances::total_issuance(), 2 * 1000 + 4 * validator_stake + 10 * 1000);
+
+		// only the genesis validators are elected.
+		let mut elected = Staking::select_validators(1).unwrap();
+		elected.sort();
+		assert_eq!(elected, vec![11, 21]);
+	});
+}
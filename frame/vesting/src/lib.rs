@@ -272,6 +272,17 @@ impl<T: Trait> Module<T> {
 		}
 		Ok(())
 	}
+
+	/// Reduce the funds still vesting of `who` by `amount`, as when they are slashed, the rest
+	/// vesting on the same schedule.
+	pub fn burn_locked(who: &T::AccountId, amount: BalanceOf<T>) {
+		if let Some(mut vesting) = Self::vesting(who) {
+			vesting.locked = vesting.locked.saturating_sub(amount);
+			Vesting::<T>::insert(who, vesting);
+			// it can't fail, the account is vesting.
+			let _ = Self::update_lock(who.clone());
+		}
+	}
 }
 
 impl<T: Trait> VestingSchedule<T::AccountId> for Module<T> where
@@ -752,4 +763,22 @@ mod tests {
 				assert_eq!(user4_free_balance, 256 * 40);
 			});
 	}
+
+	#[test]
+	fn burn_locked_reduces_the_funds_vesting() {
+		ExtBuilder::default()
+			.existential_deposit(10)
+			.build()
+			.execute_with(|| {
+				assert_eq!(Vesting::vesting_balance(&2), Some(200));
+				Vesting::burn_locked(&2, 50);
+				assert_eq!(Vesting::vesting(&2).unwrap().locked, 150);
+				assert_eq!(Vesting::vesting_balance(&2), Some(150));
+
+				// burning all of them completes the vesting.
+				Vesting::burn_locked(&2, 1000);
+				assert_eq!(Vesting::vesting(&2), None);
+				assert_eq!(Vesting::vesting_balance(&2), None);
+			});
+	}
 }
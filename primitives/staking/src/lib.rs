@@ -21,3 +21,34 @@ pub mod offence;
 
 /// Simple index type with which we can count sessions.
 pub type SessionIndex = u32;
+
+/// Something that is notified of the validators which proved to be online during a session.
+pub trait OnLiveness<ValidatorId> {
+	/// Called at the end of `session` with the validators that signalled liveness during it.
+	fn on_liveness(session: SessionIndex, validators: &[ValidatorId]);
+}
+
+impl<ValidatorId> OnLiveness<ValidatorId> for () {
+	fn on_liveness(_session: SessionIndex, _validators: &[ValidatorId]) {}
+}
+
+/// Something that is notified of the validators which were flagged unresponsive in a session.
+pub trait OnUnresponsiveness<ValidatorId> {
+	/// Called at the end of `session` with the validators that did not signal liveness during
+	/// it, if any.
+	fn on_unresponsiveness(session: SessionIndex, validators: &[ValidatorId]);
+}
+
+impl<ValidatorId> OnUnresponsiveness<ValidatorId> for () {
+	fn on_unresponsiveness(_session: SessionIndex, _validators: &[ValidatorId]) {}
+}
+
+/// Something that is notified of the validators which took part in a round of a finality gadget.
+pub trait OnFinalityParticipation<ValidatorId> {
+	/// Called once `round` is completed with the validators that voted in it.
+	fn on_finality_participation(round: u64, validators: &[ValidatorId]);
+}
+
+impl<ValidatorId> OnFinalityParticipation<ValidatorId> for () {
+	fn on_finality_participation(_round: u64, _validators: &[ValidatorId]) {}
+}
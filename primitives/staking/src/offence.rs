@@ -141,11 +141,13 @@ pub trait OnOffenceHandler<Reporter, Offender> {
 	/// according to the `OffenceCount` already. This is of the same length as `offenders.`
 	/// Zero is a valid value for a fraction.
 	///
-	/// The `session` parameter is the session index of the offence.
+	/// The `session` parameter is the session index of the offence and `kind` is the
+	/// [`Offence::ID`] of the reported offence.
 	fn on_offence(
 		offenders: &[OffenceDetails<Reporter, Offender>],
 		slash_fraction: &[Perbill],
 		session: SessionIndex,
+		kind: &Kind,
 	);
 }
 
@@ -154,6 +156,7 @@ impl<Reporter, Offender> OnOffenceHandler<Reporter, Offender> for () {
 		_offenders: &[OffenceDetails<Reporter, Offender>],
 		_slash_fraction: &[Perbill],
 		_session: SessionIndex,
+		_kind: &Kind,
 	) {}
 }
 